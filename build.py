@@ -203,8 +203,8 @@ class M3Env(Env):
             env.install(gen, outdir=env['RUSTLIBS'], input=o)
         return outs
 
-    def build_fs(self, gen, out, dir, blocks, inodes):
-        deps = [BuildPath(self['TOOLDIR'] + '/mkm3fs')]
+    def build_fs(self, gen, out, dir, blocks, inodes, csum_out=None, journal_out=None):
+        deps = [BuildPath(self['TOOLDIR'] + '/m3fstool')]
 
         global bins
         for dirname, dirbins in bins.items():
@@ -227,19 +227,31 @@ class M3Env(Env):
                 dir_env.install_as(gen, dst, src)
             deps += [dst]
 
-        out = BuildPath(self['BUILDDIR'] + '/' + out)
-        gen.add_build(BuildEdge(
-            'mkm3fs',
-            outs=[out],
-            ins=[],
-            deps=deps,
-            vars={
-                'dir': BuildPath.new(self, dir),
-                'blocks': blocks,
-                'inodes': inodes
-            }
-        ))
-        return out
+        # optionally build further images from the same directory with checksums for all blocks
+        # or with a metadata journal (which is only useful for disk images)
+        images = [(out, '')]
+        if csum_out is not None:
+            images.append((csum_out, '-csum'))
+        if journal_out is not None:
+            images.append((journal_out, '-journal'))
+
+        res = []
+        for (img, flags) in images:
+            img = BuildPath(self['BUILDDIR'] + '/' + img)
+            gen.add_build(BuildEdge(
+                'mkfs',
+                outs=[img],
+                ins=[],
+                deps=deps,
+                vars={
+                    'dir': BuildPath.new(self, dir),
+                    'blocks': blocks,
+                    'inodes': inodes,
+                    'flags': flags
+                }
+            ))
+            res.append(img)
+        return res[0]
 
 
 # build basic environment
@@ -360,8 +372,8 @@ if isa == 'riscv':
 # start the generation
 gen = Generator()
 
-gen.add_rule('mkm3fs', Rule(
-    cmd=env['TOOLDIR'] + '/mkm3fs $out $dir $blocks $inodes 0',
+gen.add_rule('mkfs', Rule(
+    cmd=env['TOOLDIR'] + '/m3fstool mkfs $out $dir $blocks $inodes 0 $flags',
     desc='MKFS $out',
 ))
 gen.add_rule('elf2hex', Rule(
@@ -7,14 +7,9 @@
  * GNU General Public License 2. Please see the COPYING-GPL-2 file for details.
  */
 
-#include <fs/internal.h>
-
 #include "exceptions.h"
 #include "tracerecorder.h"
 
-FILE *file;
-m3::SuperBlock sb;
-
 int main(int argc, char **argv) {
     if(argc != 2) {
         std::cerr << "Usage: " << argv[0] << " <name>\n";
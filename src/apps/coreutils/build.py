@@ -1,7 +1,9 @@
 dirs = [
     'hashsum',
+    'lscap',
     'man',
     'netcat',
+    'pmap',
     'rand',
     'readelf',
     'sink',
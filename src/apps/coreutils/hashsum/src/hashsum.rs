@@ -37,11 +37,15 @@ fn open_file(path: &str, flags: OpenFlags, stdfd: Fd) -> Result<FileRef<dyn File
 fn hash(
     sess: &mut HashSession,
     path: &str,
+    key: Option<&str>,
     output_bytes: usize,
     output_file: Option<&mut FileRef<dyn File>>,
 ) -> Result<(), Error> {
     let mut file = open_file(path, OpenFlags::R, STDIN_FILENO)?;
-    sess.reset(sess.algo())?;
+    match key {
+        Some(key) => sess.reset_with_key(sess.algo(), key.as_bytes())?,
+        None => sess.reset(sess.algo())?,
+    }
     file.hash_input(sess, usize::MAX)?;
 
     if let Some(output_file) = output_file {
@@ -64,15 +68,17 @@ pub fn main() -> Result<(), Error> {
         None => {
             print!("Usage: {} <", program);
             let mut sep = "";
-            for algo in HashAlgorithm::ALL.iter() {
+            for algo in HashAlgorithm::ALL.iter().chain(HashAlgorithm::KEYED.iter()) {
                 print!("{}{}", sep, algo.name);
                 sep = "|";
             }
-            println!("> [-O <output-bytes>|-o <output-file>] [files...]");
+            println!("> [-k <key>] [-O <output-bytes>|-o <output-file>] [files...]");
+            println!("  -k: the key for the keyed algorithms (required for them)");
             return Err(Error::new(Code::InvArgs));
         },
     };
 
+    let mut key = None;
     let mut output_file = None;
     let mut output_bytes = algo.output_bytes;
     let mut next;
@@ -80,6 +86,7 @@ pub fn main() -> Result<(), Error> {
     loop {
         next = args.next();
         match next {
+            Some("-k") => key = Some(args.next().expect("Missing argument")),
             Some("-O") => {
                 output_bytes = args
                     .next()
@@ -109,12 +116,27 @@ pub fn main() -> Result<(), Error> {
         return Err(Error::new(Code::InvArgs));
     }
 
+    // keyed algorithms would silently use an empty key otherwise
+    if algo.is_keyed() != key.is_some() {
+        if algo.is_keyed() {
+            println!("Hash algorithm {} requires a key (-k)", algo);
+        }
+        else {
+            println!("Hash algorithm {} does not take a key", algo);
+        }
+        return Err(Error::new(Code::InvArgs));
+    }
+    if key.map(|k| k.len()).unwrap_or(0) > HashAlgorithm::MAX_KEY_BYTES {
+        println!("Key larger than {} bytes", HashAlgorithm::MAX_KEY_BYTES);
+        return Err(Error::new(Code::InvArgs));
+    }
+
     let mut sess = HashSession::new("hash", algo).expect("Failed to get hash session");
 
     let mut res = Ok(());
     next = next.or(Some("-"));
     while let Some(path) = next {
-        if let Err(e) = hash(&mut sess, path, output_bytes, output_file.as_mut()) {
+        if let Err(e) = hash(&mut sess, path, key, output_bytes, output_file.as_mut()) {
             if output_file.as_ref().map(|f| f.fd()) != Some(STDOUT_FILENO) {
                 // Avoid printing to standard output if it is used as output file
                 println!("{}: {}: {}", program, path, e);
@@ -0,0 +1,112 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+#![no_std]
+
+use m3::col::Vec;
+use m3::errors::{Code, Error};
+use m3::io::{STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use m3::kif::{CapType, INVALID_SEL};
+use m3::tiles::{Activity, ChildActivity, OwnActivity, RunningActivity, Tile};
+use m3::time::TimeDuration;
+use m3::{env, println};
+
+fn usage(prog: &str) -> Error {
+    println!("Usage: {} [-m] [-d <ms>] [<program> [<arg>...]]", prog);
+    println!("  Lists the capabilities of the own activity or of <program>.");
+    println!("  -m: list mapping capabilities instead of object capabilities");
+    println!("  -d: the time in milliseconds to let <program> run before listing");
+    Error::new(Code::InvArgs)
+}
+
+fn print_caps(act: &Activity, name: &str, ty: CapType) -> Result<(), Error> {
+    let caps = act.caps(ty)?;
+
+    println!(
+        "Capabilities of {} ({} caps of type {}):",
+        name,
+        caps.len(),
+        ty
+    );
+    println!(
+        "{:>6} {:>6} {:>6} {:>7} {:>12} {:>8}",
+        "Sel", "Count", "Type", "Derived", "Parent", "Children"
+    );
+    for c in caps {
+        if c.parent_sel != INVALID_SEL {
+            println!(
+                "{:>6} {:>6} {:>6} {:>7} {:>5}:{:<6} {:>8}",
+                c.sel, c.count, c.obj, c.derived, c.parent_act, c.parent_sel, c.children
+            );
+        }
+        else {
+            println!(
+                "{:>6} {:>6} {:>6} {:>7} {:>12} {:>8}",
+                c.sel, c.count, c.obj, c.derived, "-", c.children
+            );
+        }
+    }
+    Ok(())
+}
+
+#[no_mangle]
+pub fn main() -> Result<(), Error> {
+    let args: Vec<&str> = env::args().collect();
+
+    let mut ty = CapType::OBJECT;
+    let mut delay = TimeDuration::ZERO;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i] {
+            "-m" => ty = CapType::MAPPING,
+            "-d" if i + 1 < args.len() => {
+                let ms = args[i + 1].parse().map_err(|_| usage(args[0]))?;
+                delay = TimeDuration::from_millis(ms);
+                i += 1;
+            },
+            a if a.starts_with('-') => return Err(usage(args[0])),
+            _ => break,
+        }
+        i += 1;
+    }
+
+    if i == args.len() {
+        return print_caps(Activity::own(), "self", ty);
+    }
+
+    let tile = Tile::get("own|core")?;
+    let mut child = ChildActivity::new(tile, args[i])?;
+    child.add_file(STDIN_FILENO, STDIN_FILENO);
+    child.add_file(STDOUT_FILENO, STDOUT_FILENO);
+    child.add_file(STDERR_FILENO, STDERR_FILENO);
+    child.add_mount("/", "/");
+
+    let run = child.exec(&args[i..])?;
+
+    if delay != TimeDuration::ZERO {
+        OwnActivity::sleep_for(delay)?;
+    }
+
+    // the child might have exited already, in which case there is nothing left to list
+    if let Err(e) = print_caps(run.activity(), args[i], ty) {
+        println!("Unable to list capabilities of {}: {}", args[i], e);
+    }
+
+    let res = run.wait()?;
+    if res != Code::Success {
+        println!("{} terminated with exit code {:?}", args[i], res);
+    }
+    Ok(())
+}
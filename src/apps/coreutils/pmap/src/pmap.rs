@@ -0,0 +1,164 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+#![no_std]
+
+use m3::cfg;
+use m3::col::Vec;
+use m3::errors::{Code, Error};
+use m3::goff;
+use m3::io::{STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use m3::kif::Perm;
+use m3::session::{MapFlags, Pager};
+use m3::tiles::{Activity, ChildActivity, OwnActivity, RunningActivity, Tile};
+use m3::time::TimeDuration;
+use m3::{env, println};
+
+fn usage(prog: &str) -> Error {
+    println!("Usage: {} [-d <ms>] [<program> [<arg>...]]", prog);
+    println!("  Prints the address space of the own activity or of <program>.");
+    println!("  -d: list after <program> ran for the given number of milliseconds");
+    println!("      instead of after it terminated");
+    Error::new(Code::InvArgs)
+}
+
+fn kib(pages: usize) -> usize {
+    pages * cfg::PAGE_SIZE / 1024
+}
+
+fn perm_char(perm: Perm, bit: Perm, c: char) -> char {
+    if perm.contains(bit) {
+        c
+    }
+    else {
+        '-'
+    }
+}
+
+fn print_aspace(pager: &Pager, name: &str) -> Result<(), Error> {
+    let stats = pager.stats()?;
+
+    let mut dss = Vec::new();
+    for i in 0..stats.dataspaces {
+        dss.push(pager.dataspace_info(i)?);
+    }
+    dss.sort_by_key(|ds| ds.virt);
+
+    println!("Address space of {} ({} dataspaces):", name, dss.len());
+    println!(
+        "{:>18} {:>18} {:>9} {:>4} {:>7} {:>4} {:>9} {:>9} {:>9}",
+        "Start", "End", "Size", "Perm", "Flags", "Type", "Resident", "COW", "Swapped"
+    );
+    for ds in &dss {
+        println!(
+            "{:#18x} {:#18x} {:>8}K  {}{}{} {:>7} {:>4} {:>8}K {:>8}K {:>8}K",
+            ds.virt,
+            ds.virt + ds.size - 1,
+            ds.size / 1024,
+            perm_char(ds.perm, Perm::R, 'r'),
+            perm_char(ds.perm, Perm::W, 'w'),
+            perm_char(ds.perm, Perm::X, 'x'),
+            if ds.flags.contains(MapFlags::SHARED) {
+                "shared"
+            }
+            else {
+                "private"
+            },
+            if ds.file { "file" } else { "anon" },
+            kib(ds.resident),
+            kib(ds.cow),
+            kib(ds.swapped),
+        );
+    }
+
+    println!(
+        "Total: {}K virtual, {}K resident ({}K anonymous, {}K file), {}K COW, {}K swapped",
+        stats.virt_size / 1024 as goff,
+        kib(stats.resident),
+        kib(stats.anon),
+        kib(stats.file),
+        kib(stats.cow),
+        kib(stats.swapped),
+    );
+    println!(
+        "Page faults: {} anonymous, {} file, {} COW, {} swap, {} minor",
+        stats.faults.anon,
+        stats.faults.file,
+        stats.faults.cow,
+        stats.faults.swap,
+        stats.faults.minor,
+    );
+    Ok(())
+}
+
+fn print_act(act: &Activity, name: &str) {
+    let res = match act.pager() {
+        Some(pager) => print_aspace(pager, name),
+        None => Err(Error::new(Code::NotSup)),
+    };
+    if let Err(e) = res {
+        println!("Unable to print address space of {}: {}", name, e);
+    }
+}
+
+#[no_mangle]
+pub fn main() -> Result<(), Error> {
+    let args: Vec<&str> = env::args().collect();
+
+    let mut delay = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i] {
+            "-d" if i + 1 < args.len() => {
+                let ms = args[i + 1].parse().map_err(|_| usage(args[0]))?;
+                delay = Some(TimeDuration::from_millis(ms));
+                i += 1;
+            },
+            a if a.starts_with('-') => return Err(usage(args[0])),
+            _ => break,
+        }
+        i += 1;
+    }
+
+    if i == args.len() {
+        print_act(Activity::own(), "self");
+        return Ok(());
+    }
+
+    let tile = Tile::get("own|core")?;
+    let mut child = ChildActivity::new(tile, args[i])?;
+    child.add_file(STDIN_FILENO, STDIN_FILENO);
+    child.add_file(STDOUT_FILENO, STDOUT_FILENO);
+    child.add_file(STDERR_FILENO, STDERR_FILENO);
+    child.add_mount("/", "/");
+
+    let run = child.exec(&args[i..])?;
+
+    // the address space stays intact until we drop the child; thus we can list it afterwards
+    if let Some(delay) = delay {
+        OwnActivity::sleep_for(delay)?;
+        print_act(run.activity(), args[i]);
+    }
+
+    let res = run.wait()?;
+    if res != Code::Success {
+        println!("{} terminated with exit code {:?}", args[i], res);
+    }
+
+    if delay.is_none() {
+        print_act(run.activity(), args[i]);
+    }
+    Ok(())
+}
@@ -29,6 +29,8 @@ int main(int argc, char **argv) {
         exitmsg("Usage: {} <file>..."_cf, argv[0]);
 
     int res;
+    KIF::ActivityStats stats;
+    bool have_stats = true;
     auto start = TimeInstant::now();
     {
         auto tile = Tile::get("own|core");
@@ -41,11 +43,25 @@ int main(int argc, char **argv) {
         child.exec(argc - 1, const_cast<const char **>(argv) + 1);
 
         res = child.wait();
+        try {
+            stats = child.stats();
+        }
+        catch(const Exception &) {
+            // not available if the child did not run on TileMux
+            have_stats = false;
+        }
     }
 
     auto end = TimeInstant::now();
 
     eprintln("Activity ({}) terminated with exit-code {}"_cf, argv[1], res);
     eprintln("Runtime: {}"_cf, end.duration_since(start));
+    if(have_stats) {
+        eprintln("CPU time: {}"_cf, TimeDuration::from_nanos(stats.cpu_time));
+        eprintln("Blocked time: {}"_cf, TimeDuration::from_nanos(stats.blocked_time));
+        eprintln("Context switches: {}"_cf, stats.ctxsws);
+        eprintln("Page faults: {}"_cf, stats.page_faults);
+        eprintln("Messages while descheduled: {}"_cf, stats.desched_msgs);
+    }
     return 0;
 }
@@ -18,16 +18,30 @@
 
 #![no_std]
 
+use m3::env;
 use m3::errors::Error;
 use m3::test::{DefaultWvTester, WvTester};
 use m3::{println, wv_run_suite};
 
+mod tchecksum;
 mod tdisk;
+mod tjournal;
 
 #[no_mangle]
 pub fn main() -> Result<(), Error> {
     let mut tester = DefaultWvTester::default();
-    wv_run_suite!(tester, tdisk::run);
+    // the journal test starts its own m3fs instances on the disk and can therefore not run
+    // together with the other tests. it needs a disk with a journal (e.g., from default-journal.img)
+    if env::args().nth(1) == Some("journal") {
+        wv_run_suite!(tester, tjournal::run);
+    }
+    // the checksum test corrupts the file system image and needs one with checksums
+    else if env::args().nth(1) == Some("csum") {
+        wv_run_suite!(tester, tchecksum::run);
+    }
+    else {
+        wv_run_suite!(tester, tdisk::run);
+    }
     println!("{}", tester);
     Ok(())
 }
@@ -0,0 +1,47 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::com::MemGate;
+use m3::errors::Code;
+use m3::test::WvTester;
+use m3::vec;
+use m3::vfs::VFS;
+use m3::{wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
+
+pub fn run(t: &mut dyn WvTester) {
+    wv_run_test!(t, corrupt_block);
+}
+
+fn corrupt_block(t: &mut dyn WvTester) {
+    let before = wv_assert_ok!(VFS::statfs("/"));
+    wv_assert!(t, before.checksums);
+    wv_assert_eq!(t, before.checksum_errors, 0);
+
+    // overwrite the directory entries of /subdir/subsubdir behind the back of m3fs. m3fs has not
+    // loaded this block yet, because we have not accessed the directory so far.
+    let info = wv_assert_ok!(VFS::stat("/subdir/subsubdir"));
+    let fs_mod = wv_assert_ok!(MemGate::new_bind_bootmod("fs"));
+    let garbage = vec![0xFFu8; info.blocksize as usize];
+    wv_assert_ok!(fs_mod.write(&garbage, info.firstblock as u64 * info.blocksize as u64));
+
+    // searching the directory loads the block, which does not match its checksum anymore
+    wv_assert_err!(
+        t,
+        VFS::stat("/subdir/subsubdir/testfile.txt"),
+        Code::DataCorrupted
+    );
+    let after = wv_assert_ok!(VFS::statfs("/"));
+    wv_assert!(t, after.checksum_errors > before.checksum_errors);
+}
@@ -0,0 +1,178 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::{String, ToString, Vec};
+use m3::errors::{Code, Error};
+use m3::io::Write;
+use m3::test::WvTester;
+use m3::tiles::{
+    ActivityArgs, ChildActivity, OwnActivity, RunningActivity, RunningProgramActivity, Tile,
+};
+use m3::time::TimeDuration;
+use m3::vfs::{read_dir, FileMode, OpenFlags, VFS};
+use m3::{format, println, vec, wv_assert, wv_assert_eq, wv_assert_ok, wv_run_test};
+
+const SERVICE: &str = "m3fs-disk";
+const MOUNT: &str = "/disk/";
+
+// the number of metadata writes after which m3fs crashes; chosen to hit the different phases of a
+// commit (journal, header, home locations, superblock, retirement)
+const CRASH_POINTS: [usize; 8] = [1, 4, 9, 13, 22, 37, 58, 91];
+
+pub fn run(t: &mut dyn WvTester) {
+    wv_run_test!(t, crash_and_replay);
+}
+
+fn start_fs(crash_after: Option<usize>) -> Result<RunningProgramActivity, Error> {
+    let tile = Tile::get("compat|own")?;
+    let act = ChildActivity::new_with(tile, ActivityArgs::new("m3fs"))?;
+
+    let mut args = vec![
+        "/sbin/m3fs".to_string(),
+        "-n".to_string(),
+        SERVICE.to_string(),
+    ];
+    if let Some(n) = crash_after {
+        args.push("-k".to_string());
+        args.push(n.to_string());
+    }
+    args.push("disk".to_string());
+    let act = act.exec(&args)?;
+
+    // wait until the service has been registered
+    loop {
+        match VFS::mount(MOUNT, "m3fs", SERVICE) {
+            Err(e) if e.code() == Code::InvArgs => {
+                OwnActivity::sleep_for(TimeDuration::from_millis(1))?
+            },
+            res => break res.map(|_| act),
+        }
+    }
+}
+
+fn workload(dir: &str) -> Result<(), Error> {
+    VFS::mkdir(dir, FileMode::from_bits(0o755).unwrap())?;
+    for i in 0..16 {
+        let file = format!("{}/f{}", dir, i);
+        let sub = format!("{}/d{}", dir, i);
+
+        let mut f = VFS::open(&file, OpenFlags::W | OpenFlags::CREATE)?;
+        write!(f, "file {}", i)?;
+        drop(f);
+
+        VFS::mkdir(&sub, FileMode::from_bits(0o755).unwrap())?;
+        VFS::rename(&file, &format!("{}/g", sub))?;
+        if i % 2 == 1 {
+            VFS::unlink(&format!("{}/g", sub))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_tree(t: &mut dyn WvTester, dir: &str) {
+    let mut names = Vec::new();
+    for e in wv_assert_ok!(read_dir(dir)) {
+        names.push(e.file_name().to_string());
+    }
+    wv_assert!(t, names.iter().any(|n| n == "."));
+    wv_assert!(t, names.iter().any(|n| n == ".."));
+
+    for name in names.iter().filter(|n| *n != "." && *n != "..") {
+        let path = format!("{}/{}", dir, name);
+        let info = wv_assert_ok!(VFS::stat(&path));
+        if info.mode.is_dir() {
+            check_tree(t, &path);
+        }
+    }
+}
+
+fn check_workload(t: &mut dyn WvTester, dir: &str) {
+    if VFS::stat(dir).is_err() {
+        return;
+    }
+
+    check_tree(t, dir);
+
+    for i in 0..16 {
+        let file = format!("{}/f{}", dir, i);
+        let moved = format!("{}/d{}/g", dir, i);
+        // a rename is atomic: the file can never exist at both places
+        wv_assert!(t, !(VFS::stat(&file).is_ok() && VFS::stat(&moved).is_ok()));
+    }
+}
+
+fn remove_tree(dir: &str) -> Result<(), Error> {
+    let mut names: Vec<String> = Vec::new();
+    for e in read_dir(dir)? {
+        if e.file_name() != "." && e.file_name() != ".." {
+            names.push(e.file_name().to_string());
+        }
+    }
+
+    for name in names {
+        let path = format!("{}/{}", dir, name);
+        if VFS::stat(&path)?.mode.is_dir() {
+            remove_tree(&path)?;
+        }
+        else {
+            VFS::unlink(&path)?;
+        }
+    }
+    VFS::rmdir(dir)
+}
+
+fn has_journal() -> Result<bool, Error> {
+    let _act = start_fs(None)?;
+    let stats = VFS::statfs(MOUNT);
+    VFS::unmount(MOUNT)?;
+    Ok(stats?.journal)
+}
+
+fn crash_and_replay(t: &mut dyn WvTester) {
+    // without a journal, the crashes would simply leave an inconsistent file system behind
+    if !wv_assert_ok!(has_journal()) {
+        println!(
+            "The file system on the disk has no journal; \
+             run this test with M3_HDD=default-journal.img"
+        );
+        wv_assert!(t, false);
+        return;
+    }
+
+    for (i, point) in CRASH_POINTS.iter().enumerate() {
+        let dir = format!("{}j{}", MOUNT, i);
+
+        // run the workload until m3fs crashes in the middle of some operation
+        {
+            let act = wv_assert_ok!(start_fs(Some(*point)));
+            wv_assert!(t, workload(&dir).is_err());
+            wv_assert_ok!(VFS::unmount(MOUNT));
+            wv_assert_eq!(t, act.wait(), Ok(Code::Abort));
+        }
+
+        // mount it again, which replays the journal, and check that everything is consistent
+        {
+            let _act = wv_assert_ok!(start_fs(None));
+            check_workload(t, &dir);
+            if VFS::stat(&dir).is_ok() {
+                wv_assert_ok!(remove_tree(&dir));
+            }
+            // the allocators panic on inconsistent bitmaps, so this checks them as well
+            wv_assert_ok!(workload(&dir));
+            wv_assert_ok!(remove_tree(&dir));
+            wv_assert_ok!(VFS::unmount(MOUNT));
+        }
+    }
+}
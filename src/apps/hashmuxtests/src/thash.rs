@@ -29,6 +29,7 @@ use m3::{goff, println, tmif, util, vec};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, hash_empty);
+    wv_run_test!(t, hash_keyed);
     wv_run_test!(t, hash_mapped_mem);
     wv_run_test!(t, hash_file);
     wv_run_test!(t, seek_then_hash_file);
@@ -88,6 +89,89 @@ fn hash_empty(t: &mut dyn WvTester) {
     );
 }
 
+fn _hash_keyed(
+    t: &mut dyn WvTester,
+    hash: &mut HashSession,
+    algo: &'static HashAlgorithm,
+    key: &[u8],
+    data: &[u8],
+    expected: &[u8],
+) {
+    wv_assert_ok!(hash.reset_with_key(algo, key));
+
+    let mgate = wv_assert_ok!(MemGate::new(data.len(), Perm::RW));
+    wv_assert_ok!(mgate.write(data, 0));
+    wv_assert_ok!(hash.ep().configure(mgate.sel()));
+    wv_assert_ok!(hash.input(0, data.len()));
+
+    let mut buf = vec![0u8; algo.output_bytes];
+    wv_assert_ok!(hash.finish(&mut buf));
+    wv_assert_eq!(t, &buf, expected);
+}
+
+fn hash_keyed(t: &mut dyn WvTester) {
+    let mut hash = wv_assert_ok!(HashSession::new("hash", &HashAlgorithm::SHA3_256));
+
+    // keys are only supported for keyed algorithms and limited in size
+    wv_assert_err!(
+        t,
+        hash.reset_with_key(&HashAlgorithm::SHA3_256, b"key"),
+        Code::InvArgs
+    );
+    wv_assert_err!(
+        t,
+        hash.reset_with_key(
+            &HashAlgorithm::KMAC128,
+            &[0u8; HashAlgorithm::MAX_KEY_BYTES + 1]
+        ),
+        Code::InvArgs
+    );
+
+    // KMAC128: sample #1 of the KMAC samples for NIST SP 800-185. all KMAC256 samples of NIST use
+    // a customization string, which is not supported. thus, the KMAC256 value uses the key and data
+    // of sample #4 with an empty customization string and has been computed with an independent
+    // implementation of SP 800-185.
+    let key: Vec<u8> = (0x40..0x60).collect();
+    _hash_keyed(
+        t,
+        &mut hash,
+        &HashAlgorithm::KMAC128,
+        &key,
+        &[0, 1, 2, 3],
+        &hex!("e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e"),
+    );
+    _hash_keyed(
+        t,
+        &mut hash,
+        &HashAlgorithm::KMAC256,
+        &key,
+        &[0, 1, 2, 3],
+        &hex!(
+            "2ebd1622de2de44174e3477206060d7f64489a639b7545649132317609fa214f4c8ac90630fb4c757fba074b15186fe452ae71b6a1e443bf54059e090c11ae20"
+        ),
+    );
+
+    const FOX: &[u8] = b"The quick brown fox jumps over the lazy dog";
+    _hash_keyed(
+        t,
+        &mut hash,
+        &HashAlgorithm::HMAC_SHA3_256,
+        b"key",
+        FOX,
+        &hex!("8c6e0683409427f8931711b10ca92a506eb1fafa48fadd66d76126f47ac2c333"),
+    );
+    _hash_keyed(
+        t,
+        &mut hash,
+        &HashAlgorithm::HMAC_SHA3_512,
+        b"",
+        FOX,
+        &hex!(
+            "8a904f5e32ddc346a4f84a6dd2d58d59b68efbed54fc4cf8ff021f1082357e0cce70d3a50b3cab5a87c417785874c6ab0a1fc09e4e128ba648074bc020fa4ffc"
+        ),
+    );
+}
+
 fn hash_mapped_mem(t: &mut dyn WvTester) {
     if !Activity::own().tile_desc().has_virtmem() {
         println!("No virtual memory; skipping hash_mapped_mem test");
@@ -15,7 +15,7 @@
 
 use m3::col::ToString;
 use m3::errors::Code;
-use m3::kif::Perm;
+use m3::kif::{Perm, Priority};
 use m3::test::WvTester;
 use m3::{wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
@@ -40,6 +40,7 @@ pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, app_sems);
     wv_run_test!(t, app_serial);
     wv_run_test!(t, app_domains);
+    wv_run_test!(t, app_prios);
 }
 
 fn errors(t: &mut dyn WvTester) {
@@ -435,3 +436,40 @@ fn app_domains(t: &mut dyn WvTester) {
     wv_assert_eq!(t, *cfg.domains()[1].tile(), TileType("boom".to_string()));
     wv_assert_eq!(t, cfg.domains()[1].apps()[0].name(), "zap");
 }
+
+fn app_prios(t: &mut dyn WvTester) {
+    // the top-level app is started by our parent, so that its priority is not checked
+    let cfg = wv_assert_ok!(AppConfig::parse("<app args=\"foo\" prio=\"high\"/>"));
+    wv_assert_eq!(t, cfg.prio(), Some(Priority::HIGH));
+
+    // without attribute, the top-level app runs with the given priority
+    let cfg_str = "<app args=\"foo\"><app args=\"bar\" prio=\"high\"/></app>";
+    wv_assert_err!(t, AppConfig::parse(cfg_str), Code::NoPerm);
+    let cfg = wv_assert_ok!(AppConfig::parse_with(cfg_str, Priority::HIGH));
+    wv_assert_eq!(t, cfg.domains()[0].apps()[0].prio(), Some(Priority::HIGH));
+
+    // children cannot exceed the priority of their parent, also within domains
+    wv_assert_err!(
+        t,
+        AppConfig::parse_with(
+            "<app args=\"foo\" prio=\"low\"><dom><app args=\"bar\" prio=\"normal\"/></dom></app>",
+            Priority::HIGH
+        ),
+        Code::NoPerm
+    );
+
+    // apps without attribute run with normal priority
+    wv_assert_err!(
+        t,
+        AppConfig::parse_with(
+            "<app args=\"foo\"><app args=\"bar\"><app args=\"zap\" prio=\"high\"/></app></app>",
+            Priority::HIGH
+        ),
+        Code::NoPerm
+    );
+    wv_assert_err!(
+        t,
+        AppConfig::parse("<app args=\"foo\" prio=\"medium\"/>"),
+        Code::InvArgs
+    );
+}
@@ -18,6 +18,7 @@
 
 #![no_std]
 
+use m3::env;
 use m3::errors::Error;
 use m3::test::{DefaultWvTester, WvTester};
 use m3::{println, wv_run_suite};
@@ -36,6 +37,7 @@ mod tmemmap;
 mod tmgate;
 mod tnonblock;
 mod tpaging;
+mod tpartition;
 mod tpipe;
 mod trgate;
 mod tsems;
@@ -44,10 +46,19 @@ mod tsgate;
 mod tsrvmsgs;
 mod tsyscalls;
 mod ttreap;
+mod tvterm;
 
 #[no_mangle]
 pub fn main() -> Result<(), Error> {
     let mut tester = DefaultWvTester::default();
+
+    // with a small memory quota and a swap space (see boot/swap.xml), just test the paging
+    if env::args().nth(1) == Some("swap") {
+        wv_run_suite!(tester, tpaging::run);
+        println!("{}", tester);
+        return Ok(());
+    }
+
     wv_run_suite!(tester, tboxlist::run);
     wv_run_suite!(tester, tbufio::run);
     wv_run_suite!(tester, tdir::run);
@@ -61,6 +72,7 @@ pub fn main() -> Result<(), Error> {
     wv_run_suite!(tester, tmgate::run);
     wv_run_suite!(tester, tnonblock::run);
     wv_run_suite!(tester, tpaging::run);
+    wv_run_suite!(tester, tpartition::run);
     wv_run_suite!(tester, tpipe::run);
     wv_run_suite!(tester, trgate::run);
     wv_run_suite!(tester, tsgate::run);
@@ -69,6 +81,7 @@ pub fn main() -> Result<(), Error> {
     wv_run_suite!(tester, tsrvmsgs::run);
     wv_run_suite!(tester, tsyscalls::run);
     wv_run_suite!(tester, ttreap::run);
+    wv_run_suite!(tester, tvterm::run);
     wv_run_suite!(tester, tactivity::run);
     println!("{}", tester);
     Ok(())
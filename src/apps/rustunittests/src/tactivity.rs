@@ -17,19 +17,27 @@
  */
 
 use m3::cap::Selector;
+use m3::col::Vec;
 use m3::com::chan;
 use m3::com::{recv_msg, RecvGate, SGateArgs, SendGate};
 use m3::env;
 use m3::errors::{Code, Error};
+use m3::rc::Rc;
 use m3::test::{DefaultWvTester, WvTester};
 use m3::tiles::{Activity, ActivityArgs, ChildActivity, OwnActivity, RunningActivity, Tile};
 use m3::time::TimeDuration;
 use m3::util::math;
 
-use m3::{run_with_channels, send_vmsg, wv_assert_eq, wv_assert_ok, wv_run_test};
+use m3::{
+    reply_vmsg, run_with_channels, send_recv, send_vmsg, wv_assert, wv_assert_eq, wv_assert_err,
+    wv_assert_ok, wv_run_test,
+};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, run_stop);
+    wv_run_test!(t, run_suspend_resume);
+    wv_run_test!(t, run_migrate);
+    wv_run_test!(t, run_priorities);
     wv_run_test!(t, run_arguments);
     wv_run_test!(t, run_send_receive);
     wv_run_test!(t, run_send_receive_chan);
@@ -93,6 +101,216 @@ fn run_stop(_t: &mut dyn WvTester) {
     }
 }
 
+fn run_suspend_resume(t: &mut dyn WvTester) {
+    use m3::println;
+
+    // suspend and resume an activity that shares the tile with us
+    match Tile::get("own") {
+        Ok(tile) => _run_suspend_resume(t, tile),
+        Err(_) => println!("Skipping suspend/resume test on shared tile: own tile not shareable"),
+    }
+
+    // and one that is alone on its tile
+    match Tile::get("compat") {
+        Ok(tile) => _run_suspend_resume(t, tile),
+        Err(_) => println!("Skipping suspend/resume test on separate tile: no compatible tile"),
+    }
+}
+
+fn _run_suspend_resume(t: &mut dyn WvTester, tile: Rc<Tile>) {
+    use m3::com::RGateArgs;
+
+    let rg = wv_assert_ok!(RecvGate::new_with(
+        RGateArgs::default().order(6).msg_order(6)
+    ));
+
+    let mut act = wv_assert_ok!(ChildActivity::new_with(tile, ActivityArgs::new("test")));
+
+    let sg = wv_assert_ok!(SendGate::new_with(SGateArgs::new(&rg).credits(1)));
+    wv_assert_ok!(act.delegate_obj(sg.sel()));
+
+    let mut dst = act.data_sink();
+    dst.push(sg.sel());
+
+    let act = wv_assert_ok!(act.run(|| {
+        let sg_sel: Selector = Activity::own().data_source().pop().unwrap();
+        let sg = SendGate::new_bind(sg_sel);
+
+        // send an increasing counter to the parent forever
+        let mut i = 0u64;
+        loop {
+            send_recv!(&sg, RecvGate::def(), i)?;
+            i += 1;
+        }
+    }));
+
+    // only suspended activities can be resumed
+    wv_assert_err!(t, act.resume(), Code::InvState);
+
+    let mut msg = wv_assert_ok!(recv_msg(&rg));
+    let mut last: u64 = wv_assert_ok!(msg.pop());
+    wv_assert_ok!(reply_vmsg!(msg, 0));
+
+    wv_assert_ok!(act.suspend());
+    wv_assert_err!(t, act.suspend(), Code::InvState);
+
+    // the child might have sent another message before it was suspended
+    wv_assert_ok!(OwnActivity::sleep_for(TimeDuration::from_millis(1)));
+    if wv_assert_ok!(rg.has_msgs()) {
+        let mut msg = wv_assert_ok!(recv_msg(&rg));
+        last = wv_assert_ok!(msg.pop());
+        wv_assert_ok!(reply_vmsg!(msg, 0));
+    }
+
+    // but afterwards, it does not run anymore
+    wv_assert_ok!(OwnActivity::sleep_for(TimeDuration::from_millis(1)));
+    wv_assert_eq!(t, rg.has_msgs(), Ok(false));
+
+    wv_assert_ok!(act.resume());
+
+    // the child continues where it left off
+    let mut msg = wv_assert_ok!(recv_msg(&rg));
+    let next: u64 = wv_assert_ok!(msg.pop());
+    wv_assert_eq!(t, next, last + 1);
+    wv_assert_ok!(reply_vmsg!(msg, 0));
+
+    wv_assert_ok!(act.stop());
+}
+
+fn run_migrate(t: &mut dyn WvTester) {
+    use m3::com::RGateArgs;
+    use m3::println;
+
+    // we need two additional tiles with virtual memory that are compatible to ours
+    let (tile1, tile2) = match (Tile::get("compat"), Tile::get("compat")) {
+        (Ok(t1), Ok(t2)) if t1.desc().has_virtmem() => (t1, t2),
+        _ => {
+            println!("Skipping migration test: no two compatible tiles with virtual memory");
+            return;
+        },
+    };
+
+    let rg = wv_assert_ok!(RecvGate::new_with(
+        RGateArgs::default().order(6).msg_order(6)
+    ));
+
+    let mut act = wv_assert_ok!(ChildActivity::new_with(
+        tile1.clone(),
+        ActivityArgs::new("test")
+    ));
+
+    let sg = wv_assert_ok!(SendGate::new_with(SGateArgs::new(&rg).credits(1)));
+    wv_assert_ok!(act.delegate_obj(sg.sel()));
+
+    let mut dst = act.data_sink();
+    dst.push(sg.sel());
+
+    let mut act = wv_assert_ok!(act.run(|| {
+        let sg_sel: Selector = Activity::own().data_source().pop().unwrap();
+        let sg = SendGate::new_bind(sg_sel);
+
+        // send an increasing counter to the parent forever, but leave some time in between
+        let mut i = 0u64;
+        loop {
+            send_recv!(&sg, RecvGate::def(), i)?;
+            OwnActivity::sleep_for(TimeDuration::from_millis(1))?;
+            i += 1;
+        }
+    }));
+
+    // migrating to the current tile is pointless
+    wv_assert_err!(t, act.migrate(tile1), Code::InvArgs);
+
+    let mut msg = wv_assert_ok!(recv_msg(&rg));
+    let mut last: u64 = wv_assert_ok!(msg.pop());
+    wv_assert_ok!(reply_vmsg!(msg, 0));
+
+    // the migration fails as long as the child is in the middle of a message exchange
+    let mut migrated = false;
+    for _ in 0..100 {
+        match act.migrate(tile2.clone()) {
+            Err(e) if e.code() == Code::InvState => {
+                let mut msg = wv_assert_ok!(recv_msg(&rg));
+                last = wv_assert_ok!(msg.pop());
+                wv_assert_ok!(reply_vmsg!(msg, 0));
+            },
+            res => {
+                wv_assert_ok!(res);
+                migrated = true;
+                break;
+            },
+        }
+    }
+    wv_assert!(t, migrated);
+    wv_assert_eq!(t, act.activity().tile().id(), tile2.id());
+
+    // the child continues where it left off
+    let mut msg = wv_assert_ok!(recv_msg(&rg));
+    let next: u64 = wv_assert_ok!(msg.pop());
+    wv_assert_eq!(t, next, last + 1);
+    wv_assert_ok!(reply_vmsg!(msg, 0));
+
+    wv_assert_ok!(act.stop());
+}
+
+fn run_priorities(t: &mut dyn WvTester) {
+    use m3::com::{RGateArgs, Semaphore};
+    use m3::kif::Priority;
+    use m3::time::TimeInstant;
+
+    let rg = wv_assert_ok!(RecvGate::new_with(
+        RGateArgs::default().order(7).msg_order(6)
+    ));
+    let sem = wv_assert_ok!(Semaphore::create(0));
+
+    // both activities share a tile so that the priority decides which one runs first. we cannot
+    // create activities with a higher priority than our own (normal)
+    let tile = wv_assert_ok!(Tile::get("compat|own"));
+    let mut acts = Vec::new();
+    for prio in [Priority::LOW, Priority::NORMAL] {
+        let mut act = wv_assert_ok!(ChildActivity::new_with(
+            tile.clone(),
+            ActivityArgs::new("test").prio(prio)
+        ));
+
+        let sg = wv_assert_ok!(SendGate::new_with(SGateArgs::new(&rg).credits(1)));
+        wv_assert_ok!(act.delegate_obj(sg.sel()));
+        wv_assert_ok!(act.delegate_obj(sem.sel()));
+
+        let mut dst = act.data_sink();
+        dst.push(sg.sel());
+        dst.push(sem.sel());
+        dst.push(prio);
+
+        acts.push(wv_assert_ok!(act.run(|| {
+            let mut src = Activity::own().data_source();
+            let sg = SendGate::new_bind(src.pop().unwrap());
+            let sem = Semaphore::bind(src.pop().unwrap());
+            let prio: Priority = src.pop().unwrap();
+
+            // wait until both activities are ready to compute
+            sem.down()?;
+            let end = TimeInstant::now() + TimeDuration::from_millis(1);
+            while TimeInstant::now() < end {}
+
+            send_vmsg!(&sg, RecvGate::def(), prio)
+        })));
+    }
+
+    // even if the low-priority activity is woken up first, the other one preempts it
+    wv_assert_ok!(sem.up());
+    wv_assert_ok!(sem.up());
+
+    for prio in [Priority::NORMAL, Priority::LOW] {
+        let mut msg = wv_assert_ok!(recv_msg(&rg));
+        wv_assert_eq!(t, msg.pop::<Priority>(), Ok(prio));
+    }
+
+    for act in acts {
+        wv_assert_eq!(t, act.wait(), Ok(Code::Success));
+    }
+}
+
 fn run_arguments(t: &mut dyn WvTester) {
     let tile = wv_assert_ok!(Tile::get("compat|own"));
     let act = wv_assert_ok!(ChildActivity::new_with(tile, ActivityArgs::new("test")));
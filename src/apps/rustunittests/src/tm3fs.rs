@@ -16,18 +16,30 @@
  * General Public License version 2 for more details.
  */
 
-use m3::col::ToString;
+use m3::col::{ToString, Vec};
 use m3::errors::Code;
-use m3::io::Write;
+use m3::io::{Read, Write};
 use m3::test::WvTester;
-use m3::vfs::{FileMode, OpenFlags, VFS};
-use m3::{wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
+use m3::tiles::OwnActivity;
+use m3::time::TimeDuration;
+use m3::vfs::{
+    File, FileEvent, FileInfo, FileMode, FileRef, GenericFile, LockType, OpenFlags, Seek, SeekMode,
+    VFS,
+};
+use m3::{vec, wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, paths);
     wv_run_test!(t, mkdir_rmdir);
     wv_run_test!(t, link_unlink);
     wv_run_test!(t, rename);
+    wv_run_test!(t, symlinks);
+    wv_run_test!(t, permissions);
+    wv_run_test!(t, dir_notify);
+    wv_run_test!(t, fsck);
+    wv_run_test!(t, sparse_files);
+    wv_run_test!(t, locks);
+    wv_run_test!(t, statfs);
 }
 
 fn setup() {
@@ -252,3 +264,436 @@ fn rename(t: &mut dyn WvTester) {
 
     teardown();
 }
+
+fn symlinks(t: &mut dyn WvTester) {
+    setup();
+
+    // relative link within the same directory
+    wv_assert_ok!(VFS::symlink("myfile", "/example/rel"));
+    wv_assert_eq!(t, VFS::readlink("/example/rel"), Ok("myfile".to_string()));
+    let info = wv_assert_ok!(VFS::stat("/example/rel"));
+    wv_assert_eq!(t, info.mode.is_reg(), true);
+    wv_assert_eq!(t, info.size, 5);
+
+    // test errors
+    wv_assert_err!(t, VFS::symlink("foo", "/example/rel"), Code::Exists);
+    wv_assert_err!(t, VFS::symlink("foo", "/example/."), Code::InvArgs);
+    wv_assert_err!(t, VFS::readlink("/example/myfile"), Code::InvArgs);
+    wv_assert_err!(t, VFS::readlink("/example/none"), Code::NoSuchFile);
+    // the target needs to fit into the reply of readlink
+    wv_assert_ok!(VFS::symlink(&"a".repeat(256), "/example/long"));
+    wv_assert_eq!(t, VFS::readlink("/example/long").map(|l| l.len()), Ok(256));
+    wv_assert_err!(
+        t,
+        VFS::symlink(&"a".repeat(257), "/example/long2"),
+        Code::InvArgs
+    );
+
+    // absolute link to a directory
+    wv_assert_ok!(VFS::symlink("/example", "/exdir"));
+    wv_assert_ok!(VFS::open("/exdir/myfile", OpenFlags::R));
+    wv_assert_ok!(VFS::open("/exdir/../exdir/rel", OpenFlags::R));
+
+    // links to links
+    wv_assert_ok!(VFS::symlink("../exdir/rel", "/example/rel2"));
+    let info = wv_assert_ok!(VFS::stat("/example/rel2"));
+    wv_assert_eq!(t, info.size, 5);
+
+    // loops
+    wv_assert_ok!(VFS::symlink("loop2", "/example/loop1"));
+    wv_assert_ok!(VFS::symlink("loop1", "/example/loop2"));
+    wv_assert_err!(t, VFS::stat("/example/loop1"), Code::LinkLoop);
+    wv_assert_ok!(VFS::symlink("/example/aloop", "/example/aloop"));
+    wv_assert_err!(t, VFS::stat("/example/aloop"), Code::LinkLoop);
+
+    // links across mount points
+    wv_assert_ok!(VFS::mount("/fs/", "m3fs", "m3fs-clone"));
+    wv_assert_ok!(VFS::symlink("/example/myfile", "/fs/ext"));
+    let info = wv_assert_ok!(VFS::stat("/fs/ext"));
+    wv_assert_eq!(t, info.size, 5);
+    wv_assert_ok!(VFS::unlink("/fs/ext"));
+    wv_assert_ok!(VFS::unmount("/fs/"));
+
+    // unlink removes the links, not the targets
+    for l in &[
+        "/example/rel",
+        "/example/rel2",
+        "/exdir",
+        "/example/loop1",
+        "/example/loop2",
+        "/example/aloop",
+        "/example/long",
+    ] {
+        wv_assert_ok!(VFS::unlink(l));
+    }
+    wv_assert_ok!(VFS::stat("/example/myfile"));
+
+    teardown();
+}
+
+fn permissions(t: &mut dyn WvTester) {
+    const UID: u32 = 1000;
+    const GID: u32 = 1000;
+    let mode = |bits| FileMode::from_bits(bits).unwrap();
+
+    setup();
+
+    // everything created by root belongs to root
+    let info = wv_assert_ok!(VFS::stat("/example/myfile"));
+    wv_assert_eq!(t, (info.uid, info.gid), (0, 0));
+
+    // this session has uid 1000 and gid 1000
+    wv_assert_ok!(VFS::mount("/user/", "m3fs", "m3fs-user"));
+
+    // the user can read, but not change anything in a directory of root
+    wv_assert_ok!(VFS::open("/user/example/myfile", OpenFlags::R));
+    wv_assert_err!(
+        t,
+        VFS::open("/user/example/myfile", OpenFlags::W),
+        Code::NoPerm
+    );
+    wv_assert_err!(
+        t,
+        VFS::open("/user/example/new", OpenFlags::W | OpenFlags::CREATE),
+        Code::NoPerm
+    );
+    wv_assert_err!(
+        t,
+        VFS::mkdir("/user/example/dir", mode(0o755)),
+        Code::NoPerm
+    );
+    wv_assert_err!(t, VFS::unlink("/user/example/myfile"), Code::NoPerm);
+    wv_assert_err!(
+        t,
+        VFS::rename("/user/example/myfile", "/user/example/other"),
+        Code::NoPerm
+    );
+    wv_assert_err!(
+        t,
+        VFS::chmod("/user/example/myfile", mode(0o666)),
+        Code::NoPerm
+    );
+    wv_assert_err!(
+        t,
+        VFS::chown("/user/example/myfile", UID, GID),
+        Code::NoPerm
+    );
+
+    // give the directory to the user; new files belong to the user
+    wv_assert_ok!(VFS::chown("/example", UID, GID));
+    wv_assert_ok!(VFS::mkdir("/user/example/dir", mode(0o755)));
+    wv_assert_ok!(VFS::open(
+        "/user/example/new",
+        OpenFlags::W | OpenFlags::CREATE
+    ));
+    let info = wv_assert_ok!(VFS::stat("/example/new"));
+    wv_assert_eq!(t, (info.uid, info.gid), (UID, GID));
+    wv_assert_ok!(VFS::rename("/user/example/myfile", "/user/example/moved"));
+    wv_assert_ok!(VFS::rename("/user/example/moved", "/user/example/myfile"));
+
+    // the owner can change the permissions, but cannot give the file away
+    wv_assert_ok!(VFS::chmod("/user/example/new", mode(0o400)));
+    wv_assert_err!(
+        t,
+        VFS::open("/user/example/new", OpenFlags::W),
+        Code::NoPerm
+    );
+    wv_assert_err!(t, VFS::chown("/user/example/new", 0, GID), Code::NoPerm);
+    // root is not restricted by the permission bits
+    wv_assert_ok!(VFS::open("/example/new", OpenFlags::RW));
+
+    // the group permissions apply to members of the group
+    wv_assert_ok!(VFS::chown("/example/new", 0, GID));
+    wv_assert_ok!(VFS::chmod("/example/new", mode(0o640)));
+    wv_assert_ok!(VFS::open("/user/example/new", OpenFlags::R));
+    wv_assert_err!(
+        t,
+        VFS::open("/user/example/new", OpenFlags::W),
+        Code::NoPerm
+    );
+
+    // without search permission, the directory cannot be traversed
+    wv_assert_ok!(VFS::chmod("/user/example", mode(0o600)));
+    wv_assert_err!(t, VFS::stat("/user/example/new"), Code::NoPerm);
+    wv_assert_ok!(VFS::chmod("/user/example", mode(0o755)));
+
+    wv_assert_ok!(VFS::unmount("/user/"));
+
+    // undo changes
+    wv_assert_ok!(VFS::unlink("/example/new"));
+    wv_assert_ok!(VFS::rmdir("/example/dir"));
+    wv_assert_ok!(VFS::chown("/example", 0, 0));
+
+    teardown();
+}
+
+fn wait_for_events(dir: &mut FileRef<GenericFile>, events: FileEvent) -> FileEvent {
+    // events that are held back due to missing credits are sent asynchronously
+    let mut received = FileEvent::empty();
+    for _ in 0..100 {
+        received |= wv_assert_ok!(dir.fetch_events(FileEvent::DIR_CHANGES));
+        if received.contains(events) {
+            break;
+        }
+        wv_assert_ok!(OwnActivity::sleep_for(TimeDuration::from_millis(1)));
+    }
+    received
+}
+
+fn dir_notify(t: &mut dyn WvTester) {
+    setup();
+
+    // only directories can be watched
+    let mut file = wv_assert_ok!(VFS::open(
+        "/example/myfile",
+        OpenFlags::R | OpenFlags::NEW_SESS
+    ));
+    wv_assert_err!(t, file.fetch_events(FileEvent::DIR_CHANGES), Code::NotSup);
+
+    let mut dir = wv_assert_ok!(VFS::open("/example", OpenFlags::R | OpenFlags::NEW_SESS));
+    // start watching
+    wv_assert_eq!(
+        t,
+        dir.fetch_events(FileEvent::DIR_CHANGES),
+        Ok(FileEvent::empty())
+    );
+
+    {
+        let mut f = wv_assert_ok!(VFS::open("/example/new", OpenFlags::W | OpenFlags::CREATE));
+        wv_assert_ok!(write!(f, "foo"));
+    }
+    let events = wait_for_events(&mut dir, FileEvent::CREATE | FileEvent::MODIFY);
+    wv_assert_eq!(t, events, FileEvent::CREATE | FileEvent::MODIFY);
+
+    wv_assert_ok!(VFS::rename("/example/new", "/example/new2"));
+    wv_assert_eq!(
+        t,
+        wait_for_events(&mut dir, FileEvent::RENAME),
+        FileEvent::RENAME
+    );
+
+    // changes in other directories are not reported
+    wv_assert_ok!(VFS::mkdir(
+        "/example/sub",
+        FileMode::from_bits(0o755).unwrap()
+    ));
+    wv_assert_eq!(
+        t,
+        wait_for_events(&mut dir, FileEvent::CREATE),
+        FileEvent::CREATE
+    );
+    wv_assert_ok!(VFS::link("/example/new2", "/example/sub/link"));
+    wv_assert_ok!(VFS::unlink("/example/sub/link"));
+    wv_assert_eq!(
+        t,
+        dir.fetch_events(FileEvent::DIR_CHANGES),
+        Ok(FileEvent::empty())
+    );
+
+    wv_assert_ok!(VFS::rmdir("/example/sub"));
+    wv_assert_ok!(VFS::unlink("/example/new2"));
+    wv_assert_eq!(
+        t,
+        wait_for_events(&mut dir, FileEvent::UNLINK),
+        FileEvent::UNLINK
+    );
+
+    drop(dir);
+    teardown();
+}
+
+fn fsck(t: &mut dyn WvTester) {
+    setup();
+
+    let report = wv_assert_ok!(VFS::fsck("/", false));
+    wv_assert_eq!(t, report.leaked_inodes, 0);
+    wv_assert_eq!(t, report.leaked_blocks, 0);
+    wv_assert_eq!(t, report.errors, 0);
+    wv_assert_eq!(t, report.repaired, false);
+
+    {
+        // an append that is in progress and an unlinked, but open file are no leaks
+        let mut appending = wv_assert_ok!(VFS::open(
+            "/example/appending",
+            OpenFlags::W | OpenFlags::CREATE
+        ));
+        wv_assert_ok!(write!(appending, "foo"));
+        let _unlinked = wv_assert_ok!(VFS::open("/example/myfile", OpenFlags::R));
+        wv_assert_ok!(VFS::unlink("/example/myfile"));
+
+        let report = wv_assert_ok!(VFS::fsck("/", true));
+        wv_assert_eq!(t, report.leaked_inodes, 0);
+        wv_assert_eq!(t, report.leaked_blocks, 0);
+        wv_assert_eq!(t, report.errors, 0);
+        wv_assert_eq!(t, report.repaired, false);
+    }
+
+    // only root can check the file system
+    wv_assert_ok!(VFS::mount("/user/", "m3fs", "m3fs-user"));
+    wv_assert_err!(t, VFS::fsck("/user/", false), Code::NoPerm);
+    wv_assert_ok!(VFS::unmount("/user/"));
+
+    wv_assert_ok!(VFS::unlink("/example/appending"));
+    wv_assert_ok!(VFS::rmdir("/example"));
+}
+
+/// Returns the number of blocks used by the file system, excluding the indirect block of `info`
+fn used_blocks(info: &FileInfo) -> u32 {
+    let report = wv_assert_ok!(VFS::fsck("/", false));
+    // files with more than two extents need an indirect block
+    report.blocks - (info.extents > 2) as u32
+}
+
+fn read_sparse() -> Vec<u8> {
+    // we open the file read-only, because holes are filled on access with write permission
+    let mut file = wv_assert_ok!(VFS::open("/example/sparse", OpenFlags::R));
+    let mut content = Vec::new();
+    wv_assert_ok!(file.read_to_end(&mut content));
+    content
+}
+
+fn sparse_files(t: &mut dyn WvTester) {
+    setup();
+
+    {
+        let mut file = wv_assert_ok!(VFS::open(
+            "/example/sparse",
+            OpenFlags::RW | OpenFlags::CREATE
+        ));
+        let bs = wv_assert_ok!(file.stat()).blocksize as usize;
+
+        let mut expected = vec![0xAAu8; 8 * bs];
+        wv_assert_ok!(file.write_all(&expected));
+        let used = used_blocks(&wv_assert_ok!(file.stat()));
+
+        // the three blocks in the middle are freed, the bytes around them are zeroed
+        wv_assert_ok!(file.punch_hole(2 * bs - 10, 3 * bs + 20));
+        for b in &mut expected[2 * bs - 10..5 * bs + 10] {
+            *b = 0;
+        }
+        let info = wv_assert_ok!(file.stat());
+        wv_assert_eq!(t, info.size, 8 * bs);
+        wv_assert_eq!(t, used_blocks(&info), used - 3);
+        wv_assert_eq!(t, read_sparse(), expected);
+
+        // growing the file appends a hole
+        wv_assert_ok!(file.truncate(10 * bs + 100));
+        expected.resize(10 * bs + 100, 0);
+        let info = wv_assert_ok!(file.stat());
+        wv_assert_eq!(t, info.size, 10 * bs + 100);
+        wv_assert_eq!(t, used_blocks(&info), used - 3);
+        wv_assert_eq!(t, read_sparse(), expected);
+
+        // preallocation extends the file as well, but allocates the blocks of the range
+        wv_assert_ok!(file.allocate(12 * bs, bs));
+        expected.resize(13 * bs, 0);
+        let info = wv_assert_ok!(file.stat());
+        wv_assert_eq!(t, info.size, 13 * bs);
+        wv_assert_eq!(t, used_blocks(&info), used - 2);
+        wv_assert_eq!(t, read_sparse(), expected);
+
+        // writing into a hole allocates blocks again
+        wv_assert_ok!(file.seek(3 * bs, SeekMode::SET));
+        wv_assert_ok!(file.write_all(b"hole"));
+        wv_assert_ok!(file.flush());
+        expected[3 * bs..3 * bs + 4].copy_from_slice(b"hole");
+        wv_assert_eq!(t, read_sparse(), expected);
+
+        // ranges that overflow are rejected
+        wv_assert_err!(t, file.allocate(usize::MAX - 10, 20), Code::InvArgs);
+        wv_assert_err!(t, file.allocate(bs, usize::MAX - 10), Code::InvArgs);
+        wv_assert_err!(t, file.punch_hole(usize::MAX - 10, 20), Code::InvArgs);
+        wv_assert_eq!(t, wv_assert_ok!(file.stat()).size, 13 * bs);
+
+        let report = wv_assert_ok!(VFS::fsck("/", false));
+        wv_assert_eq!(t, report.leaked_blocks, 0);
+        wv_assert_eq!(t, report.errors, 0);
+    }
+
+    // only files opened for writing can be changed
+    let mut file = wv_assert_ok!(VFS::open("/example/sparse", OpenFlags::R));
+    wv_assert_err!(t, file.punch_hole(0, 1), Code::NoPerm);
+    wv_assert_err!(t, file.allocate(0, 1), Code::NoPerm);
+    drop(file);
+
+    wv_assert_ok!(VFS::unlink("/example/sparse"));
+    teardown();
+}
+
+fn locks(t: &mut dyn WvTester) {
+    setup();
+
+    let mut f1 = wv_assert_ok!(VFS::open("/example/myfile", OpenFlags::RW));
+    let mut f2 = wv_assert_ok!(VFS::open("/example/myfile", OpenFlags::R));
+
+    // an exclusive lock on the whole file conflicts with everything
+    wv_assert_ok!(f1.lock(0, 0, LockType::EXCLUSIVE, false));
+    wv_assert_err!(t, f2.lock(0, 1, LockType::SHARED, false), Code::WouldBlock);
+    wv_assert_err!(
+        t,
+        f2.lock(4096, 0, LockType::SHARED, false),
+        Code::WouldBlock
+    );
+
+    // unlocking the middle leaves the locks around it in place
+    wv_assert_ok!(f1.unlock(100, 100));
+    wv_assert_ok!(f2.lock(100, 100, LockType::EXCLUSIVE, false));
+    wv_assert_err!(t, f2.lock(99, 2, LockType::SHARED, false), Code::WouldBlock);
+    wv_assert_err!(
+        t,
+        f2.lock(199, 2, LockType::SHARED, false),
+        Code::WouldBlock
+    );
+    wv_assert_ok!(f1.unlock(0, 0));
+    wv_assert_ok!(f2.unlock(0, 0));
+
+    // shared locks can be held by multiple files, but prevent exclusive locks
+    wv_assert_ok!(f1.lock(0, 10, LockType::SHARED, false));
+    wv_assert_ok!(f2.lock(5, 10, LockType::SHARED, false));
+    wv_assert_err!(
+        t,
+        f1.lock(0, 10, LockType::EXCLUSIVE, false),
+        Code::WouldBlock
+    );
+    wv_assert_ok!(f1.lock(10, 10, LockType::SHARED, false));
+
+    // a file can convert its own lock if nobody else holds one
+    wv_assert_ok!(f2.unlock(0, 0));
+    wv_assert_ok!(f1.lock(0, 20, LockType::EXCLUSIVE, false));
+
+    // closing a file releases its locks
+    drop(f1);
+    wv_assert_ok!(f2.lock(0, 0, LockType::EXCLUSIVE, false));
+    drop(f2);
+
+    teardown();
+}
+
+fn statfs(t: &mut dyn WvTester) {
+    let before = wv_assert_ok!(VFS::statfs("/"));
+    wv_assert!(t, before.free_blocks <= before.total_blocks);
+    wv_assert!(t, before.free_inodes <= before.total_inodes);
+
+    // a new file needs an inode and at least its data blocks
+    let bs = before.block_size as usize;
+    {
+        let mut file = wv_assert_ok!(VFS::open("/statfs", OpenFlags::W | OpenFlags::CREATE));
+        wv_assert_ok!(file.write_all(&vec![0xAAu8; 4 * bs]));
+    }
+    let during = wv_assert_ok!(VFS::statfs("/"));
+    wv_assert_eq!(t, during.free_inodes, before.free_inodes - 1);
+    wv_assert!(t, during.free_blocks <= before.free_blocks - 4);
+
+    // reading it back does not find any corrupt blocks
+    let mut file = wv_assert_ok!(VFS::open("/statfs", OpenFlags::R));
+    let mut buf = vec![0u8; 4 * bs];
+    wv_assert_ok!(file.read_exact(&mut buf));
+    wv_assert!(t, buf.iter().all(|b| *b == 0xAA));
+    drop(file);
+
+    wv_assert_ok!(VFS::unlink("/statfs"));
+    let after = wv_assert_ok!(VFS::statfs("/"));
+    wv_assert_eq!(t, after.free_inodes, before.free_inodes);
+    wv_assert_eq!(t, after.free_blocks, before.free_blocks);
+    wv_assert_eq!(t, after.checksum_errors, before.checksum_errors);
+}
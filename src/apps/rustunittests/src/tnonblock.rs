@@ -13,14 +13,16 @@
  * General Public License version 2 for more details.
  */
 
-use m3::com::MemGate;
+use m3::com::{MemGate, RGateArgs, RecvGate, SGateArgs, SendGate};
 use m3::errors::Code;
 use m3::io::{Read, Write};
 use m3::kif;
+use m3::send_vmsg;
 use m3::session::Pipes;
 use m3::test::WvTester;
 use m3::tiles::OwnActivity;
-use m3::vfs::{File, IndirectPipe, OpenFlags, VFS};
+use m3::time::TimeDuration;
+use m3::vfs::{self, File, FileEvent, IndirectPipe, OpenFlags, PollFd, VFS};
 use m3::{wv_assert_eq, wv_assert_err, wv_assert_ok, wv_assert_some, wv_run_test};
 
 const PIPE_SIZE: usize = 16;
@@ -29,6 +31,7 @@ const DATA_SIZE: usize = PIPE_SIZE / 4;
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, files);
     wv_run_test!(t, pipes);
+    wv_run_test!(t, poll);
 }
 
 fn files(t: &mut dyn WvTester) {
@@ -102,3 +105,59 @@ fn pipes(t: &mut dyn WvTester) {
         }
     }
 }
+
+fn poll(t: &mut dyn WvTester) {
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let pipe_mem = wv_assert_ok!(MemGate::new(PIPE_SIZE, kif::Perm::RW));
+    let pipe = wv_assert_ok!(IndirectPipe::new(&pipeserv, &pipe_mem, PIPE_SIZE));
+
+    let mut fin = wv_assert_some!(pipe.reader());
+    let mut fout = wv_assert_some!(pipe.writer());
+    wv_assert_ok!(fin.set_blocking(false));
+
+    let rgate = wv_assert_ok!(RecvGate::new_with(
+        RGateArgs::default().order(6).msg_order(6)
+    ));
+    wv_assert_ok!(rgate.activate());
+    let sgate = wv_assert_ok!(SendGate::new_with(SGateArgs::new(&rgate).credits(1)));
+
+    // nothing has arrived yet
+    let mut fds = [
+        PollFd::new(fin.fd(), FileEvent::INPUT),
+        PollFd::new_gate(&rgate),
+    ];
+    wv_assert_eq!(t, vfs::poll(&mut fds, Some(TimeDuration::ZERO)), Ok(0));
+    wv_assert_eq!(
+        t,
+        vfs::poll(&mut fds, Some(TimeDuration::from_millis(1))),
+        Ok(0)
+    );
+
+    // files in blocking mode are always ready
+    let mut out_fds = [PollFd::new(fout.fd(), FileEvent::OUTPUT)];
+    wv_assert_eq!(t, vfs::poll(&mut out_fds, None), Ok(1));
+    wv_assert_eq!(t, out_fds[0].revents(), FileEvent::OUTPUT);
+
+    // a message makes the receive gate ready
+    wv_assert_ok!(send_vmsg!(&sgate, RecvGate::def(), 1));
+    wv_assert_eq!(t, vfs::poll(&mut fds, None), Ok(1));
+    wv_assert_eq!(t, fds[0].revents(), FileEvent::empty());
+    wv_assert_eq!(t, fds[1].revents(), FileEvent::INPUT);
+    let msg = wv_assert_ok!(rgate.fetch());
+    wv_assert_ok!(rgate.ack_msg(msg));
+
+    // data makes the pipe ready
+    wv_assert_eq!(t, fout.write(b"test"), Ok(4));
+    wv_assert_ok!(fout.flush());
+    wv_assert_eq!(t, vfs::poll(&mut fds, None), Ok(1));
+    wv_assert_eq!(t, fds[0].revents(), FileEvent::INPUT);
+    wv_assert_eq!(t, fds[1].revents(), FileEvent::empty());
+
+    let mut buf = [0u8; DATA_SIZE];
+    wv_assert_eq!(t, fin.read(&mut buf), Ok(DATA_SIZE));
+    wv_assert_eq!(t, &buf, b"test");
+
+    // invalid file descriptors are reported
+    let mut inv_fds = [PollFd::new(1000, FileEvent::INPUT)];
+    wv_assert_err!(t, vfs::poll(&mut inv_fds, None), Code::BadFd);
+}
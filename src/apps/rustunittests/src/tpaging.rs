@@ -13,14 +13,26 @@
  * General Public License version 2 for more details.
  */
 
+use m3::cfg;
 use m3::com::MemGate;
+use m3::env;
+use m3::errors::Code;
+use m3::io::{Read, Write};
 use m3::kif::Perm;
+use m3::session::{Advice, MapFlags};
 use m3::test::WvTester;
 use m3::tiles::Activity;
-use m3::{wv_assert_ok, wv_run_test};
+use m3::vec;
+use m3::vfs::{Map, OpenFlags, VFS};
+use m3::{wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, large_pages);
+    wv_run_test!(t, anon_pages);
+    wv_run_test!(t, shared_file);
+    wv_run_test!(t, private_file);
+    wv_run_test!(t, protect_advise);
+    wv_run_test!(t, stats);
 }
 
 fn large_pages(_t: &mut dyn WvTester) {
@@ -41,3 +53,257 @@ fn large_pages(_t: &mut dyn WvTester) {
         m3::println!("Skipping paging test without pager");
     }
 }
+
+fn anon_pages(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        // if the pager has a swap space and our quota is small, this needs to swap pages out and in
+        const VIRT: u64 = 0x3100_0000;
+        const MEM_SIZE: usize = 16 * 1024 * 1024;
+        const PAGES: usize = MEM_SIZE / cfg::PAGE_SIZE;
+        wv_assert_ok!(pager.map_anon(VIRT, MEM_SIZE, Perm::RW, MapFlags::NOLPAGE));
+
+        let words = cfg::PAGE_SIZE / 8;
+        let ptr = VIRT as *mut u64;
+        for i in 0..PAGES {
+            unsafe {
+                ptr.add(i * words).write(i as u64);
+                ptr.add(i * words + words - 1).write(!(i as u64));
+            }
+        }
+
+        let mut errors = 0;
+        for i in 0..PAGES {
+            let (first, last) = unsafe {
+                (
+                    ptr.add(i * words).read(),
+                    ptr.add(i * words + words - 1).read(),
+                )
+            };
+            if first != i as u64 || last != !(i as u64) {
+                errors += 1;
+            }
+        }
+        wv_assert_eq!(t, errors, 0);
+
+        // with a swap space, the quota is too small to keep all pages resident
+        if env::args().nth(1) == Some("swap") {
+            let stats = wv_assert_ok!(pager.stats());
+            wv_assert!(t, stats.faults.swap > 0);
+        }
+
+        wv_assert_ok!(pager.unmap(VIRT));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn shared_file(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT1: u64 = 0x3200_0000;
+        const VIRT2: u64 = 0x3300_0000;
+        const SIZE: usize = 2 * cfg::PAGE_SIZE;
+
+        {
+            let mut file = wv_assert_ok!(VFS::open("/shared", OpenFlags::W | OpenFlags::CREATE));
+            wv_assert_ok!(file.write_all(&vec![0u8; SIZE]));
+        }
+
+        // map the file twice: once writable and once read-only
+        let wfile = wv_assert_ok!(VFS::open("/shared", OpenFlags::RW));
+        let rfile = wv_assert_ok!(VFS::open("/shared", OpenFlags::R));
+        wv_assert_ok!(wfile.map(pager, VIRT1, 0, SIZE, Perm::RW, MapFlags::SHARED));
+        wv_assert_ok!(rfile.map(pager, VIRT2, 0, SIZE, Perm::R, MapFlags::SHARED));
+
+        // read first so that the write faults on a present page
+        let wptr = VIRT1 as *mut u8;
+        let rptr = VIRT2 as *const u8;
+        wv_assert_eq!(t, unsafe { wptr.read_volatile() }, 0);
+
+        // both mappings see the same contents
+        unsafe {
+            wptr.write_volatile(0xAB);
+            wptr.add(SIZE - 1).write_volatile(0xCD);
+        }
+        wv_assert_eq!(t, unsafe { rptr.read_volatile() }, 0xAB);
+        wv_assert_eq!(t, unsafe { rptr.add(SIZE - 1).read_volatile() }, 0xCD);
+
+        // write back the first page explicitly and the second one by unmapping it
+        // the read-only mapping cannot be made writable
+        wv_assert_err!(t, pager.protect(VIRT2, SIZE, Perm::RW), Code::NoPerm);
+
+        wv_assert_ok!(pager.msync(VIRT1, cfg::PAGE_SIZE));
+        unsafe { wptr.write_volatile(0xEF) };
+        wv_assert_ok!(pager.unmap(VIRT1));
+        wv_assert_ok!(pager.unmap(VIRT2));
+        drop(wfile);
+        drop(rfile);
+
+        let mut file = wv_assert_ok!(VFS::open("/shared", OpenFlags::R));
+        let mut buf = vec![0u8; SIZE];
+        wv_assert_ok!(file.read_exact(&mut buf));
+        wv_assert_eq!(t, buf[0], 0xEF);
+        wv_assert_eq!(t, buf[SIZE - 1], 0xCD);
+        drop(file);
+
+        wv_assert_ok!(VFS::unlink("/shared"));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn private_file(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT: u64 = 0x3280_0000;
+        const SIZE: usize = cfg::PAGE_SIZE;
+
+        {
+            let mut file = wv_assert_ok!(VFS::open("/private", OpenFlags::W | OpenFlags::CREATE));
+            wv_assert_ok!(file.write_all(&vec![0u8; SIZE]));
+        }
+
+        // fault the page in while the mapping is read-only and make it writable afterwards
+        let file = wv_assert_ok!(VFS::open("/private", OpenFlags::RW));
+        wv_assert_ok!(file.map(pager, VIRT, 0, SIZE, Perm::RW, MapFlags::PRIVATE));
+        wv_assert_ok!(pager.protect(VIRT, SIZE, Perm::R));
+        let ptr = VIRT as *mut u8;
+        wv_assert_eq!(t, unsafe { ptr.read_volatile() }, 0);
+        wv_assert_ok!(pager.protect(VIRT, SIZE, Perm::RW));
+        unsafe { ptr.write_volatile(0xAB) };
+        wv_assert_eq!(t, unsafe { ptr.read_volatile() }, 0xAB);
+        wv_assert_ok!(pager.unmap(VIRT));
+        drop(file);
+
+        // the write went to a private copy, not to the file
+        let mut file = wv_assert_ok!(VFS::open("/private", OpenFlags::R));
+        let mut buf = vec![0u8; SIZE];
+        wv_assert_ok!(file.read_exact(&mut buf));
+        wv_assert_eq!(t, buf[0], 0);
+        drop(file);
+
+        wv_assert_ok!(VFS::unlink("/private"));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn protect_advise(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT: u64 = 0x3400_0000;
+        const PAGES: usize = 4;
+        const SIZE: usize = PAGES * cfg::PAGE_SIZE;
+        const PAGE: u64 = cfg::PAGE_SIZE as u64;
+
+        wv_assert_ok!(pager.map_anon(VIRT, SIZE, Perm::RW, MapFlags::NOLPAGE));
+
+        let ptr = VIRT as *mut u8;
+        for i in 0..PAGES {
+            unsafe { ptr.add(i * cfg::PAGE_SIZE).write_volatile(i as u8 + 1) };
+        }
+
+        // make the middle pages read-only; the contents stay the same
+        wv_assert_ok!(pager.protect(VIRT + PAGE, 2 * PAGE as usize, Perm::R));
+        for i in 0..PAGES {
+            wv_assert_eq!(
+                t,
+                unsafe { ptr.add(i * cfg::PAGE_SIZE).read_volatile() },
+                i as u8 + 1
+            );
+        }
+
+        // and writable again
+        wv_assert_ok!(pager.protect(VIRT + PAGE, 2 * PAGE as usize, Perm::RW));
+        unsafe { ptr.add(cfg::PAGE_SIZE).write_volatile(0xAA) };
+        wv_assert_eq!(t, unsafe { ptr.add(cfg::PAGE_SIZE).read_volatile() }, 0xAA);
+
+        // hints that only change the fault handling must not change the contents
+        wv_assert_ok!(pager.advise(VIRT, SIZE, Advice::SEQUENTIAL));
+        wv_assert_ok!(pager.advise(VIRT, SIZE, Advice::WILLNEED));
+        wv_assert_ok!(pager.advise(VIRT, SIZE, Advice::NORMAL));
+        wv_assert_eq!(t, unsafe { ptr.read_volatile() }, 1);
+        wv_assert_eq!(t, unsafe { ptr.add(3 * cfg::PAGE_SIZE).read_volatile() }, 4);
+
+        // discarded anonymous memory reads as zero afterwards
+        wv_assert_ok!(pager.advise(VIRT + 2 * PAGE, cfg::PAGE_SIZE, Advice::DONTNEED));
+        wv_assert_eq!(t, unsafe { ptr.add(2 * cfg::PAGE_SIZE).read_volatile() }, 0);
+        wv_assert_eq!(t, unsafe { ptr.add(3 * cfg::PAGE_SIZE).read_volatile() }, 4);
+
+        // invalid ranges
+        wv_assert_err!(
+            t,
+            pager.protect(VIRT + 1, cfg::PAGE_SIZE, Perm::R),
+            Code::InvArgs
+        );
+        wv_assert_err!(
+            t,
+            pager.protect(VIRT + SIZE as u64, cfg::PAGE_SIZE, Perm::R),
+            Code::NotFound
+        );
+        wv_assert_err!(
+            t,
+            pager.advise(VIRT, SIZE + cfg::PAGE_SIZE, Advice::DONTNEED),
+            Code::NotFound
+        );
+
+        // unmapping removes all pieces of the mapping
+        wv_assert_ok!(pager.unmap(VIRT));
+        wv_assert_err!(
+            t,
+            pager.protect(VIRT, cfg::PAGE_SIZE, Perm::R),
+            Code::NotFound
+        );
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn stats(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT: u64 = 0x3500_0000;
+        const PAGES: usize = 4;
+        const SIZE: usize = PAGES * cfg::PAGE_SIZE;
+
+        let before = wv_assert_ok!(pager.stats());
+
+        wv_assert_ok!(pager.map_anon(VIRT, SIZE, Perm::RW, MapFlags::NOLPAGE));
+        let mapped = wv_assert_ok!(pager.stats());
+        wv_assert_eq!(t, mapped.dataspaces, before.dataspaces + 1);
+        wv_assert_eq!(t, mapped.virt_size, before.virt_size + SIZE as u64);
+
+        // touch every page
+        let ptr = VIRT as *mut u8;
+        for i in 0..PAGES {
+            unsafe { ptr.add(i * cfg::PAGE_SIZE).write_volatile(i as u8) };
+        }
+
+        let after = wv_assert_ok!(pager.stats());
+        // other parts of our address space might have grown in the meantime
+        wv_assert!(t, after.resident >= mapped.resident + PAGES);
+        wv_assert!(t, after.anon >= mapped.anon + PAGES);
+        wv_assert!(t, after.faults.anon > mapped.faults.anon);
+
+        // find our dataspace
+        let info = (0..after.dataspaces)
+            .map(|i| wv_assert_ok!(pager.dataspace_info(i)))
+            .find(|ds| ds.virt == VIRT);
+        wv_assert!(t, info.is_some());
+        let info = info.unwrap();
+        wv_assert_eq!(t, info.size, SIZE as u64);
+        wv_assert_eq!(t, info.perm, Perm::RW);
+        wv_assert_eq!(t, info.file, false);
+        wv_assert_eq!(t, info.resident, PAGES);
+        wv_assert_eq!(t, info.cow, 0);
+
+        wv_assert_err!(t, pager.dataspace_info(after.dataspaces), Code::NotFound);
+
+        wv_assert_ok!(pager.unmap(VIRT));
+        let unmapped = wv_assert_ok!(pager.stats());
+        wv_assert_eq!(t, unmapped.dataspaces, before.dataspaces);
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
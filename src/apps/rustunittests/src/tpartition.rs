@@ -0,0 +1,211 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::Vec;
+use m3::errors::{Code, Error};
+use m3::test::WvTester;
+use m3::{vec, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
+
+use partition::{crc32, parse_partitions, Partition, PartitionKind, PartitionSpec, SECTOR_SIZE};
+
+const IMG_SECTORS: usize = 128;
+
+const GPT_ENTRIES_LBA: usize = 2;
+const GPT_ENTRY_COUNT: usize = 128;
+const GPT_ENTRY_SIZE: usize = 128;
+
+pub fn run(t: &mut dyn WvTester) {
+    wv_run_test!(t, no_table);
+    wv_run_test!(t, mbr_primary);
+    wv_run_test!(t, mbr_logical);
+    wv_run_test!(t, mbr_loop);
+    wv_run_test!(t, gpt);
+    wv_run_test!(t, gpt_corrupt);
+    wv_run_test!(t, select);
+}
+
+fn parse(img: &[u8]) -> Result<Vec<Partition>, Error> {
+    parse_partitions(|lba, buf| {
+        let off = lba as usize * SECTOR_SIZE;
+        if off + SECTOR_SIZE > img.len() {
+            return Err(Error::new(Code::EndOfFile));
+        }
+        buf.copy_from_slice(&img[off..off + SECTOR_SIZE]);
+        Ok(())
+    })
+}
+
+fn put(img: &mut [u8], off: usize, bytes: &[u8]) {
+    img[off..off + bytes.len()].copy_from_slice(bytes);
+}
+
+fn put_mbr_entry(img: &mut [u8], sector: usize, slot: usize, sys_id: u8, start: u32, size: u32) {
+    let off = sector * SECTOR_SIZE + 0x1BE + slot * 16;
+    img[off + 4] = sys_id;
+    put(img, off + 8, &start.to_le_bytes());
+    put(img, off + 12, &size.to_le_bytes());
+    put(img, sector * SECTOR_SIZE + 0x1FE, &[0x55, 0xAA]);
+}
+
+fn check_part(
+    t: &mut dyn WvTester,
+    part: &Partition,
+    id: usize,
+    kind: PartitionKind,
+    start: u64,
+    size: u64,
+    name: &str,
+) {
+    wv_assert_eq!(t, part.id(), id);
+    wv_assert_eq!(t, part.kind(), kind);
+    wv_assert_eq!(t, part.start_sector(), start);
+    wv_assert_eq!(t, part.sector_count(), size);
+    wv_assert_eq!(t, part.name(), name);
+}
+
+fn gpt_image(parts: &[(u64, u64, &str)]) -> Vec<u8> {
+    let mut img = vec![0u8; IMG_SECTORS * SECTOR_SIZE];
+
+    // protective MBR
+    put_mbr_entry(&mut img, 0, 0, 0xEE, 1, IMG_SECTORS as u32 - 1);
+
+    // partition entries
+    let ents = GPT_ENTRIES_LBA * SECTOR_SIZE;
+    for (i, (first, last, name)) in parts.iter().enumerate() {
+        let off = ents + i * GPT_ENTRY_SIZE;
+        // use a simple non-zero type GUID
+        img[off] = 0x83;
+        put(&mut img, off + 32, &first.to_le_bytes());
+        put(&mut img, off + 40, &last.to_le_bytes());
+        for (j, unit) in name.encode_utf16().enumerate() {
+            put(&mut img, off + 56 + j * 2, &unit.to_le_bytes());
+        }
+    }
+    let ents_crc = crc32(&img[ents..ents + GPT_ENTRY_COUNT * GPT_ENTRY_SIZE]);
+
+    // header
+    let hdr = SECTOR_SIZE;
+    put(&mut img, hdr, b"EFI PART");
+    put(&mut img, hdr + 8, &0x0001_0000u32.to_le_bytes());
+    put(&mut img, hdr + 12, &92u32.to_le_bytes());
+    put(&mut img, hdr + 24, &1u64.to_le_bytes());
+    put(&mut img, hdr + 32, &(IMG_SECTORS as u64 - 1).to_le_bytes());
+    put(&mut img, hdr + 40, &34u64.to_le_bytes());
+    put(&mut img, hdr + 48, &(IMG_SECTORS as u64 - 34).to_le_bytes());
+    put(&mut img, hdr + 72, &(GPT_ENTRIES_LBA as u64).to_le_bytes());
+    put(&mut img, hdr + 80, &(GPT_ENTRY_COUNT as u32).to_le_bytes());
+    put(&mut img, hdr + 84, &(GPT_ENTRY_SIZE as u32).to_le_bytes());
+    put(&mut img, hdr + 88, &ents_crc.to_le_bytes());
+    let hdr_crc = crc32(&img[hdr..hdr + 92]);
+    put(&mut img, hdr + 16, &hdr_crc.to_le_bytes());
+
+    img
+}
+
+fn no_table(t: &mut dyn WvTester) {
+    let img = vec![0u8; IMG_SECTORS * SECTOR_SIZE];
+    let parts = wv_assert_ok!(parse(&img));
+    wv_assert_eq!(t, parts.len(), 0);
+}
+
+fn mbr_primary(t: &mut dyn WvTester) {
+    let mut img = vec![0u8; IMG_SECTORS * SECTOR_SIZE];
+    put_mbr_entry(&mut img, 0, 0, 0x83, 1, 10);
+    put_mbr_entry(&mut img, 0, 2, 0x0C, 11, 20);
+
+    let parts = wv_assert_ok!(parse(&img));
+    wv_assert_eq!(t, parts.len(), 2);
+    check_part(t, &parts[0], 0, PartitionKind::Primary, 1, 10, "");
+    check_part(t, &parts[1], 2, PartitionKind::Primary, 11, 20, "");
+}
+
+fn mbr_logical(t: &mut dyn WvTester) {
+    let mut img = vec![0u8; IMG_SECTORS * SECTOR_SIZE];
+    put_mbr_entry(&mut img, 0, 0, 0x83, 1, 9);
+    put_mbr_entry(&mut img, 0, 1, 0x05, 10, 40);
+    // first EBR: logical partition relative to the EBR, next EBR relative to extended partition
+    put_mbr_entry(&mut img, 10, 0, 0x83, 1, 9);
+    put_mbr_entry(&mut img, 10, 1, 0x05, 10, 20);
+    // second EBR: last logical partition
+    put_mbr_entry(&mut img, 20, 0, 0x83, 2, 8);
+
+    let parts = wv_assert_ok!(parse(&img));
+    wv_assert_eq!(t, parts.len(), 3);
+    check_part(t, &parts[0], 0, PartitionKind::Primary, 1, 9, "");
+    check_part(t, &parts[1], 4, PartitionKind::Logical, 11, 9, "");
+    check_part(t, &parts[2], 5, PartitionKind::Logical, 22, 8, "");
+}
+
+fn mbr_loop(t: &mut dyn WvTester) {
+    let mut img = vec![0u8; IMG_SECTORS * SECTOR_SIZE];
+    put_mbr_entry(&mut img, 0, 0, 0x05, 10, 40);
+    // the EBR refers to itself as the next EBR
+    put_mbr_entry(&mut img, 10, 0, 0x83, 1, 9);
+    put_mbr_entry(&mut img, 10, 1, 0x05, 0, 40);
+
+    wv_assert_err!(t, parse(&img), Code::InvArgs);
+}
+
+fn gpt(t: &mut dyn WvTester) {
+    let img = gpt_image(&[(34, 49, "boot"), (50, 89, "root")]);
+
+    let parts = wv_assert_ok!(parse(&img));
+    wv_assert_eq!(t, parts.len(), 2);
+    check_part(t, &parts[0], 0, PartitionKind::Gpt, 34, 16, "boot");
+    check_part(t, &parts[1], 1, PartitionKind::Gpt, 50, 40, "root");
+}
+
+fn gpt_corrupt(t: &mut dyn WvTester) {
+    let good = gpt_image(&[(34, 49, "boot"), (50, 89, "root")]);
+
+    // change the first usable LBA in the header
+    let mut img = good.clone();
+    img[SECTOR_SIZE + 40] = 35;
+    wv_assert_err!(t, parse(&img), Code::InvChecksum);
+
+    // change the name of a partition
+    let mut img = good.clone();
+    img[GPT_ENTRIES_LBA * SECTOR_SIZE + 56] = b'B';
+    wv_assert_err!(t, parse(&img), Code::InvChecksum);
+
+    // destroy the signature
+    let mut img = good;
+    img[SECTOR_SIZE] = b'X';
+    wv_assert_err!(t, parse(&img), Code::InvArgs);
+
+    // partition outside of the usable area
+    let img = gpt_image(&[(34, IMG_SECTORS as u64 - 1, "all")]);
+    wv_assert_err!(t, parse(&img), Code::InvArgs);
+}
+
+fn select(t: &mut dyn WvTester) {
+    let img = gpt_image(&[(34, 49, "boot"), (50, 89, "root")]);
+    let parts = wv_assert_ok!(parse(&img));
+
+    wv_assert_eq!(t, PartitionSpec::parse("1"), PartitionSpec::Index(1));
+    wv_assert_eq!(t, PartitionSpec::parse("root"), PartitionSpec::Name("root"));
+
+    wv_assert_eq!(t, PartitionSpec::Index(1).find(parts.iter()), Some(1));
+    wv_assert_eq!(t, PartitionSpec::Index(2).find(parts.iter()), None);
+    wv_assert_eq!(t, PartitionSpec::Name("root").find(parts.iter()), Some(1));
+    wv_assert_eq!(t, PartitionSpec::Name("swap").find(parts.iter()), None);
+
+    // names are only supported for GPT partitions
+    let mut img = vec![0u8; IMG_SECTORS * SECTOR_SIZE];
+    put_mbr_entry(&mut img, 0, 0, 0x83, 1, 10);
+    let parts = wv_assert_ok!(parse(&img));
+    wv_assert_eq!(t, PartitionSpec::Index(0).find(parts.iter()), Some(0));
+    wv_assert_eq!(t, PartitionSpec::Name("").find(parts.iter()), None);
+}
@@ -17,15 +17,16 @@
  */
 
 use m3::col::String;
+use m3::col::Vec;
 use m3::com::MemGate;
-use m3::errors::Code;
+use m3::errors::{Code, Error};
 use m3::io::{self, Read, Write};
 use m3::kif;
 use m3::session::Pipes;
 use m3::test::{DefaultWvTester, WvTester};
-use m3::tiles::{ActivityArgs, ChildActivity, RunningActivity, Tile};
-use m3::vfs::{BufReader, IndirectPipe};
-use m3::{println, wv_assert_eq, wv_assert_ok, wv_run_test};
+use m3::tiles::{Activity, ActivityArgs, ChildActivity, RunningActivity, Tile};
+use m3::vfs::{BufReader, FileMode, IndirectPipe, OpenFlags, VFS};
+use m3::{println, wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, child_to_parent);
@@ -34,6 +35,9 @@ pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, exec_child_to_child);
     wv_run_test!(t, writer_quit);
     wv_run_test!(t, reader_quit);
+    wv_run_test!(t, packets);
+    wv_run_test!(t, packet_readers);
+    wv_run_test!(t, named_pipes);
 }
 
 fn child_to_parent(t: &mut dyn WvTester) {
@@ -215,3 +219,147 @@ fn reader_quit(t: &mut dyn WvTester) {
 
     wv_assert_eq!(t, act.wait(), Ok(Code::Success));
 }
+
+fn packets(t: &mut dyn WvTester) {
+    const SIZE: usize = 0x1000;
+
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let pipe_mem = wv_assert_ok!(MemGate::new(SIZE, kif::Perm::RW));
+    let pipe = wv_assert_ok!(IndirectPipe::new_packet(&pipeserv, &pipe_mem, SIZE));
+
+    let mut output = pipe.writer().unwrap();
+    let mut input = pipe.reader().unwrap();
+
+    // writes are not merged and the rest of a packet is discarded if the buffer is too small
+    wv_assert_eq!(t, output.write(b"abc"), Ok(3));
+    wv_assert_eq!(t, output.write(b"defgh"), Ok(5));
+    let mut buf = [0u8; SIZE];
+    wv_assert_eq!(t, input.read(&mut buf[0..2]), Ok(2));
+    wv_assert_eq!(t, &buf[0..2], b"ab");
+    wv_assert_eq!(t, input.read(&mut buf), Ok(5));
+    wv_assert_eq!(t, &buf[0..5], b"defgh");
+
+    // packets can be as large as the pipe's buffer
+    wv_assert_eq!(t, output.write(&[0xAB; SIZE]), Ok(SIZE));
+    wv_assert_eq!(t, input.read(&mut buf), Ok(SIZE));
+    wv_assert!(t, buf.iter().all(|b| *b == 0xAB));
+
+    pipe.close_writer();
+    wv_assert_eq!(t, input.read(&mut buf), Ok(0));
+    pipe.close_reader();
+}
+
+const PACKET_SIZE: usize = 100;
+const PACKETS: usize = 50;
+
+fn packet_reader() -> Result<(), Error> {
+    let mut t = DefaultWvTester::default();
+    let mut input = Activity::own().files().get(io::STDIN_FILENO).unwrap();
+    let mut output = Activity::own().files().get(io::STDOUT_FILENO).unwrap();
+
+    // receive packets until EOF and check that we always got complete ones
+    let mut count = 0u32;
+    let mut buf = [0u8; PACKET_SIZE * 2];
+    loop {
+        let amount = wv_assert_ok!(input.read(&mut buf));
+        if amount == 0 {
+            break;
+        }
+        wv_assert_eq!(t, amount, PACKET_SIZE);
+        wv_assert!(t, buf[0..amount].iter().all(|b| *b == buf[0]));
+        count += 1;
+    }
+
+    wv_assert_eq!(t, output.write(&count.to_le_bytes()), Ok(4));
+    Ok(())
+}
+
+fn packet_readers(t: &mut dyn WvTester) {
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let work_mem = wv_assert_ok!(MemGate::new(0x1000, kif::Perm::RW));
+    let work = wv_assert_ok!(IndirectPipe::new_packet(&pipeserv, &work_mem, 0x1000));
+    let res_mem = wv_assert_ok!(MemGate::new(0x1000, kif::Perm::RW));
+    let res = wv_assert_ok!(IndirectPipe::new_packet(&pipeserv, &res_mem, 0x1000));
+
+    // two readers share the read end of the work pipe
+    let mut acts = Vec::new();
+    for _ in 0..2 {
+        let tile = wv_assert_ok!(Tile::get("compat|own"));
+        let mut act = wv_assert_ok!(ChildActivity::new_with(tile, ActivityArgs::new("reader")));
+        act.add_file(io::STDIN_FILENO, work.reader().unwrap().fd());
+        act.add_file(io::STDOUT_FILENO, res.writer().unwrap().fd());
+        acts.push(wv_assert_ok!(act.run(packet_reader)));
+    }
+
+    work.close_reader();
+    res.close_writer();
+
+    let mut output = work.writer().unwrap();
+    for i in 0..PACKETS {
+        wv_assert_eq!(t, output.write(&[i as u8; PACKET_SIZE]), Ok(PACKET_SIZE));
+    }
+    work.close_writer();
+
+    // every packet has been received by exactly one reader
+    let mut input = res.reader().unwrap();
+    let mut total = 0;
+    for _ in 0..2 {
+        let mut buf = [0u8; 4];
+        wv_assert_eq!(t, input.read(&mut buf), Ok(4));
+        total += u32::from_le_bytes(buf);
+    }
+    wv_assert_eq!(t, total, PACKETS as u32);
+
+    for act in acts {
+        wv_assert_eq!(t, act.wait(), Ok(Code::Success));
+    }
+}
+
+fn named_pipes(t: &mut dyn WvTester) {
+    wv_assert_ok!(VFS::mount("/pipes/", "pipes", "pipes"));
+
+    wv_assert_ok!(VFS::mkfifo("/pipes/test"));
+    wv_assert_err!(t, VFS::mkfifo("/pipes/test"), Code::Exists);
+    wv_assert_err!(t, VFS::mkdir("/pipes/dir", FileMode::DIR_DEF), Code::NotSup);
+    wv_assert_err!(t, VFS::open("/pipes/foo", OpenFlags::R), Code::NoSuchFile);
+    wv_assert_err!(t, VFS::open("/pipes/test", OpenFlags::RW), Code::InvArgs);
+
+    let info = wv_assert_ok!(VFS::stat("/pipes/test"));
+    wv_assert!(t, info.mode.is_pip());
+
+    // an unrelated activity can find the pipe by its path
+    let tile = wv_assert_ok!(Tile::get("compat|own"));
+    let mut act = wv_assert_ok!(ChildActivity::new_with(tile, ActivityArgs::new("writer")));
+    act.add_mount("/pipes/", "/pipes/");
+
+    let act = wv_assert_ok!(act.run(|| {
+        let mut file = VFS::open("/pipes/test", OpenFlags::W)?;
+        file.write_all(b"Hello via FIFO!")?;
+        Ok(())
+    }));
+
+    let mut input = wv_assert_ok!(VFS::open("/pipes/test", OpenFlags::R));
+    let s = wv_assert_ok!(input.read_to_string());
+    wv_assert_eq!(t, s, "Hello via FIFO!");
+    wv_assert_eq!(t, act.wait(), Ok(Code::Success));
+    drop(input);
+
+    // the pipe can be used again after all readers and writers have left
+    {
+        let mut output = wv_assert_ok!(VFS::open("/pipes/test", OpenFlags::W));
+        wv_assert_eq!(t, output.write(b"again"), Ok(5));
+    }
+    let mut input = wv_assert_ok!(VFS::open("/pipes/test", OpenFlags::R));
+    let s = wv_assert_ok!(input.read_to_string());
+    wv_assert_eq!(t, s, "again");
+    drop(input);
+
+    // pipes can also be created on open
+    wv_assert_ok!(VFS::open("/pipes/new", OpenFlags::W | OpenFlags::CREATE));
+    wv_assert_ok!(VFS::unlink("/pipes/new"));
+
+    wv_assert_ok!(VFS::unlink("/pipes/test"));
+    wv_assert_err!(t, VFS::stat("/pipes/test"), Code::NoSuchFile);
+
+    wv_assert_ok!(VFS::unmount("/pipes/"));
+}
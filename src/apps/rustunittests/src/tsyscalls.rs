@@ -18,18 +18,20 @@
 
 use m3::cap::Selector;
 use m3::cfg::PAGE_SIZE;
-use m3::com::{MemGate, RecvGate, SendGate};
+use m3::com::{MemGate, RecvGate, Semaphore, SendGate};
 use m3::cpu::{CPUOps, CPU};
 use m3::errors::{Code, Error};
 use m3::goff;
-use m3::kif::syscalls::{ActivityOp, SemOp};
-use m3::kif::{CapRngDesc, CapType, Perm, INVALID_SEL, SEL_ACT, SEL_KMEM, SEL_TILE};
+use m3::kif::syscalls::{ActivityOp, KObjType, SemOp};
+use m3::kif::{
+    CapRngDesc, CapType, Perm, Priority, INVALID_SEL, PRIO_COUNT, SEL_ACT, SEL_KMEM, SEL_TILE,
+};
 use m3::server::{Handler, Server, SessId, SessionContainer};
 use m3::session::{ServerSession, M3FS};
 use m3::syscalls;
 use m3::tcu::{AVAIL_EPS, FIRST_USER_EP, TOTAL_EPS};
 use m3::test::WvTester;
-use m3::tiles::{Activity, ActivityArgs, ChildActivity, Tile};
+use m3::tiles::{Activity, ActivityArgs, ChildActivity, RunningActivity, Tile};
 use m3::util::math;
 use m3::{wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
@@ -56,6 +58,8 @@ pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, tile_quota);
     wv_run_test!(t, tile_set_quota);
     wv_run_test!(t, sem_ctrl);
+    wv_run_test!(t, list_caps);
+    wv_run_test!(t, activity_stats);
 
     wv_run_test!(t, delegate);
     wv_run_test!(t, obtain);
@@ -351,35 +355,61 @@ fn create_activity(t: &mut dyn WvTester) {
     // invalid dest selector
     wv_assert_err!(
         t,
-        syscalls::create_activity(SEL_KMEM, "test", tile.sel(), kmem),
+        syscalls::create_activity(SEL_KMEM, "test", tile.sel(), kmem, Priority::NORMAL),
         Code::InvArgs
     );
 
     // invalid name
     wv_assert_err!(
         t,
-        syscalls::create_activity(sels, "", tile.sel(), kmem),
+        syscalls::create_activity(sels, "", tile.sel(), kmem, Priority::NORMAL),
         Code::InvArgs
     );
 
     // invalid kmem
     wv_assert_err!(
         t,
-        syscalls::create_activity(sels, "test", tile.sel(), INVALID_SEL),
+        syscalls::create_activity(sels, "test", tile.sel(), INVALID_SEL, Priority::NORMAL),
         Code::InvArgs
     );
     wv_assert_err!(
         t,
-        syscalls::create_activity(sels, "test", tile.sel(), SEL_ACT),
+        syscalls::create_activity(sels, "test", tile.sel(), SEL_ACT, Priority::NORMAL),
         Code::InvArgs
     );
 
-    wv_assert_ok!(syscalls::create_activity(sels, "test", tile.sel(), kmem));
+    // invalid priority
+    wv_assert_err!(
+        t,
+        syscalls::create_activity(
+            sels,
+            "test",
+            tile.sel(),
+            kmem,
+            Priority::from(PRIO_COUNT as u64)
+        ),
+        Code::InvArgs
+    );
+
+    // priority above our own
+    wv_assert_err!(
+        t,
+        syscalls::create_activity(sels, "test", tile.sel(), kmem, Priority::HIGH),
+        Code::NoPerm
+    );
+
+    wv_assert_ok!(syscalls::create_activity(
+        sels,
+        "test",
+        tile.sel(),
+        kmem,
+        Priority::NORMAL
+    ));
     if !tile.desc().has_virtmem() {
         let new_sels = Activity::own().alloc_sels(3);
         wv_assert_err!(
             t,
-            syscalls::create_activity(new_sels, "test", tile.sel(), kmem),
+            syscalls::create_activity(new_sels, "test", tile.sel(), kmem, Priority::NORMAL),
             Code::NotSup
         );
     }
@@ -888,6 +918,70 @@ fn kmem_quota(t: &mut dyn WvTester) {
     );
 }
 
+fn list_caps(t: &mut dyn WvTester) {
+    // invalid selector
+    wv_assert_err!(
+        t,
+        syscalls::list_caps(SEL_KMEM, CapType::OBJECT, 0),
+        Code::InvArgs
+    );
+
+    // the first page contains the own activity, tile and kmem caps
+    let (caps, _) = wv_assert_ok!(syscalls::list_caps(SEL_ACT, CapType::OBJECT, 0));
+    wv_assert!(t, caps.len() >= 3);
+    wv_assert_eq!(t, caps[0].sel, SEL_TILE);
+    wv_assert_eq!(t, caps[0].obj, KObjType::TILE);
+    wv_assert_eq!(t, caps[1].sel, SEL_KMEM);
+    wv_assert_eq!(t, caps[1].obj, KObjType::KMEM);
+    wv_assert_eq!(t, caps[2].sel, SEL_ACT);
+    wv_assert_eq!(t, caps[2].obj, KObjType::ACT);
+
+    // new caps show up with the right type and derived caps know their parent
+    let sem = wv_assert_ok!(Semaphore::create(0));
+    let caps = wv_assert_ok!(Activity::own().caps(CapType::OBJECT));
+    let info = caps.iter().find(|c| c.sel == sem.sel());
+    wv_assert_eq!(t, info.map(|c| c.obj), Some(KObjType::SEM));
+    wv_assert_eq!(t, info.map(|c| c.children), Some(0));
+
+    let child = wv_assert_ok!(ChildActivity::new(Activity::own().tile().clone(), "test"));
+    wv_assert_ok!(child.delegate_obj(sem.sel()));
+
+    let caps = wv_assert_ok!(Activity::own().caps(CapType::OBJECT));
+    let info = caps.iter().find(|c| c.sel == sem.sel());
+    wv_assert_eq!(t, info.map(|c| c.children), Some(1));
+
+    let caps = wv_assert_ok!(child.caps(CapType::OBJECT));
+    let info = caps.iter().find(|c| c.sel == sem.sel());
+    wv_assert_eq!(t, info.map(|c| c.derived), Some(true));
+    wv_assert_eq!(t, info.map(|c| c.parent_sel), Some(sem.sel()));
+    wv_assert_eq!(t, info.map(|c| c.parent_act), Some(Activity::own().id()));
+}
+
+fn activity_stats(t: &mut dyn WvTester) {
+    // invalid selector
+    wv_assert_err!(t, syscalls::activity_stats(SEL_KMEM), Code::InvArgs);
+    wv_assert_err!(
+        t,
+        syscalls::activity_stats(Activity::own().alloc_sel()),
+        Code::InvArgs
+    );
+
+    // we are running, so we have consumed some time and have been scheduled at least once
+    let stats = wv_assert_ok!(Activity::own().stats());
+    wv_assert!(t, stats.cpu_time > 0);
+    wv_assert!(t, stats.ctxsws > 0);
+
+    // the stats of a child are still available after it exited
+    let tile = wv_assert_ok!(Tile::get("compat|own"));
+    let child = wv_assert_ok!(ChildActivity::new(tile, "test"));
+    let act = wv_assert_ok!(child.run(|| Ok(())));
+    wv_assert_eq!(t, act.wait(), Ok(Code::Success));
+
+    let stats = wv_assert_ok!(act.activity().stats());
+    wv_assert!(t, stats.cpu_time > 0);
+    wv_assert!(t, stats.ctxsws > 0);
+}
+
 fn tile_quota(t: &mut dyn WvTester) {
     // invalid selector
     wv_assert_err!(t, syscalls::tile_quota(SEL_ACT), Code::InvArgs);
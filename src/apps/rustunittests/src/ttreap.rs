@@ -24,6 +24,7 @@ pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, test_in_order);
     wv_run_test!(t, test_rev_order);
     wv_run_test!(t, test_rand_order);
+    wv_run_test!(t, test_lower_bound);
 }
 
 const TEST_NODE_COUNT: u32 = 10;
@@ -74,3 +75,17 @@ fn test_add_modify_and_rem(t: &mut dyn WvTester, vals: &[u32]) {
         wv_assert_eq!(t, treap.get(v), None);
     }
 }
+
+fn test_lower_bound(t: &mut dyn WvTester) {
+    let mut treap = Treap::new();
+    for v in [4, 8, 2, 10, 6] {
+        treap.insert(v, v);
+    }
+
+    wv_assert_eq!(t, treap.lower_bound(&0), Some(&2));
+    wv_assert_eq!(t, treap.lower_bound(&2), Some(&2));
+    wv_assert_eq!(t, treap.lower_bound(&3), Some(&4));
+    wv_assert_eq!(t, treap.lower_bound(&7), Some(&8));
+    wv_assert_eq!(t, treap.lower_bound(&10), Some(&10));
+    wv_assert_eq!(t, treap.lower_bound(&11), None);
+}
@@ -0,0 +1,327 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::Vec;
+use m3::errors::Code;
+use m3::test::WvTester;
+use m3::{format, vec, wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
+
+use vterm_util::args::{parse_args, parse_session_arg, MAX_CONSOLES};
+use vterm_util::line::{Event, History, LineEditor, SpecialChars};
+
+pub fn run(t: &mut dyn WvTester) {
+    wv_run_test!(t, special_chars);
+    wv_run_test!(t, insert_and_move);
+    wv_run_test!(t, erase);
+    wv_run_test!(t, kill_and_werase);
+    wv_run_test!(t, eof_and_intr);
+    wv_run_test!(t, custom_chars);
+    wv_run_test!(t, history);
+    wv_run_test!(t, redraw);
+    wv_run_test!(t, args);
+    wv_run_test!(t, session_args);
+}
+
+/// Feeds all bytes into the editor and returns the event of the last byte
+fn feed(ed: &mut LineEditor, mut hist: Option<&mut History>, bytes: &[u8]) -> Option<Event> {
+    let mut out = Vec::new();
+    let mut ev = None;
+    for b in bytes {
+        ev = ed.handle(*b, hist.as_deref_mut(), &mut out);
+    }
+    ev
+}
+
+/// Feeds all bytes into the editor and returns the line, if the bytes completed it
+fn enter(ed: &mut LineEditor, hist: Option<&mut History>, bytes: &[u8]) -> Option<Vec<u8>> {
+    match feed(ed, hist, bytes) {
+        Some(Event::Line) => Some(ed.take_line()),
+        _ => None,
+    }
+}
+
+fn special_chars(t: &mut dyn WvTester) {
+    let mut chars = SpecialChars::default();
+    wv_assert_eq!(t, chars.eof, 0x04);
+    wv_assert_eq!(t, chars.erase, 0x7f);
+
+    wv_assert_ok!(chars.set("kill=^K"));
+    wv_assert_eq!(t, chars.kill, 0x0b);
+    wv_assert_ok!(chars.set("werase=^w"));
+    wv_assert_eq!(t, chars.werase, 0x17);
+    wv_assert_ok!(chars.set("erase=^?"));
+    wv_assert_eq!(t, chars.erase, 0x7f);
+    wv_assert_ok!(chars.set("intr=7"));
+    wv_assert_eq!(t, chars.intr, 7);
+
+    wv_assert!(t, chars.set("eof").is_err());
+    wv_assert!(t, chars.set("eof=^1").is_err());
+    wv_assert!(t, chars.set("eof=256").is_err());
+    wv_assert!(t, chars.set("lnext=^V").is_err());
+}
+
+fn insert_and_move(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new(SpecialChars::default());
+
+    let mut out = Vec::new();
+    wv_assert_eq!(t, ed.handle(b'a', None, &mut out), None);
+    wv_assert_eq!(t, out, b"a\x1b[K".to_vec());
+
+    // insert in front of the 'c' via cursor left (ESC [ D)
+    wv_assert_eq!(t, feed(&mut ed, None, b"c\x1b[Db"), None);
+    // jump to the beginning (ESC [ H) and the end (ESC [ F)
+    wv_assert_eq!(t, feed(&mut ed, None, b"\x1b[H>\x1b[F<"), None);
+    wv_assert_eq!(t, enter(&mut ed, None, b"\n"), Some(b">abc<\n".to_vec()));
+
+    // multi-byte characters are inserted and moved over as a whole
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, "ä\x1b[Dx\n".as_bytes()),
+        Some("xä\n".as_bytes().to_vec())
+    );
+}
+
+fn erase(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new(SpecialChars::default());
+
+    let mut out = Vec::new();
+    wv_assert_eq!(t, feed(&mut ed, None, b"ab"), None);
+    wv_assert_eq!(t, ed.handle(0x7f, None, &mut out), None);
+    // move left, erase the rest of the line and move back
+    wv_assert_eq!(t, out, b"\x1b[1D\x1b[K".to_vec());
+
+    // erase at the beginning of the line has no effect
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"\x7f\x7f\x7fc\n"),
+        Some(b"c\n".to_vec())
+    );
+
+    // delete the character under the cursor (ESC [ 3 ~)
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"xyz\x1b[H\x1b[3~\n"),
+        Some(b"yz\n".to_vec())
+    );
+
+    // multi-byte characters are erased as a whole
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, "aü\x7f\n".as_bytes()),
+        Some(b"a\n".to_vec())
+    );
+}
+
+fn kill_and_werase(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new(SpecialChars::default());
+
+    // kill erases everything before the cursor
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"abc\x15d\n"),
+        Some(b"d\n".to_vec())
+    );
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"abc\x1b[D\x15\n"),
+        Some(b"c\n".to_vec())
+    );
+
+    // werase erases the word before the cursor including trailing spaces
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"foo bar  \x17\n"),
+        Some(b"foo \n".to_vec())
+    );
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"foo\x17\x17\n"),
+        Some(b"\n".to_vec())
+    );
+}
+
+fn eof_and_intr(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new(SpecialChars::default());
+
+    // eof ends the input, but keeps what has been entered so far
+    wv_assert_eq!(t, feed(&mut ed, None, b"ab\x04"), Some(Event::Eof));
+    wv_assert_eq!(t, ed.take_line(), b"ab".to_vec());
+    wv_assert_eq!(t, feed(&mut ed, None, b"\x04"), Some(Event::Eof));
+    wv_assert_eq!(t, ed.take_line(), b"".to_vec());
+
+    // intr leaves the line untouched
+    wv_assert_eq!(t, feed(&mut ed, None, b"ab\x03"), Some(Event::Signal));
+    wv_assert_eq!(t, enter(&mut ed, None, b"\n"), Some(b"ab\n".to_vec()));
+
+    // other control characters are ignored
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"a\x01\x07b\n"),
+        Some(b"ab\n".to_vec())
+    );
+}
+
+fn custom_chars(t: &mut dyn WvTester) {
+    let mut chars = SpecialChars::default();
+    wv_assert_ok!(chars.set("erase=^H"));
+    wv_assert_ok!(chars.set("kill=^K"));
+    wv_assert_ok!(chars.set("eof=^Z"));
+    wv_assert_ok!(chars.set("intr=^X"));
+    wv_assert_ok!(chars.set("werase=^B"));
+    let mut ed = LineEditor::new(chars);
+
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"abc\x08\n"),
+        Some(b"ab\n".to_vec())
+    );
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"abc\x0bd\n"),
+        Some(b"d\n".to_vec())
+    );
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"ab cd\x02\n"),
+        Some(b"ab \n".to_vec())
+    );
+    wv_assert_eq!(t, feed(&mut ed, None, b"ab\x18"), Some(Event::Signal));
+    wv_assert_eq!(t, feed(&mut ed, None, b"\x1a"), Some(Event::Eof));
+    wv_assert_eq!(t, ed.take_line(), b"ab".to_vec());
+
+    // the default characters have no special meaning anymore
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"a\x7f\x15\x17\x04\x03\n"),
+        Some(b"a\n".to_vec())
+    );
+}
+
+fn history(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new(SpecialChars::default());
+    let mut hist = History::default();
+
+    for l in [&b"one\n"[..], b"two\n", b"two\n", b"\n"] {
+        wv_assert_eq!(t, feed(&mut ed, Some(&mut hist), l), Some(Event::Line));
+        wv_assert_eq!(t, ed.take_line(), l.to_vec());
+    }
+
+    // without history, the arrow keys do nothing
+    wv_assert_eq!(t, enter(&mut ed, None, b"\x1b[A\n"), Some(b"\n".to_vec()));
+
+    // duplicates and empty lines are not recorded
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, Some(&mut hist), b"\x1b[A\x1b[A\n"),
+        Some(b"one\n".to_vec())
+    );
+    // going beyond the oldest line keeps it
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, Some(&mut hist), b"\x1b[A\x1b[A\x1b[A\n"),
+        Some(b"one\n".to_vec())
+    );
+
+    // browsing back to the newest position restores the line that was being edited
+    wv_assert_eq!(t, feed(&mut ed, Some(&mut hist), b"x\x1b[A\x1b[B"), None);
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, Some(&mut hist), b"y\n"),
+        Some(b"xy\n".to_vec())
+    );
+
+    // recalled lines can be edited and the SS3 sequences of the application cursor mode work
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, Some(&mut hist), b"\x1bOA\x7f!\n"),
+        Some(b"x!\n".to_vec())
+    );
+
+    // the history is bounded
+    for i in 0..vterm_util::line::HISTORY_SIZE + 1 {
+        let line = vec![b'a' + (i % 26) as u8, b'0' + (i / 26) as u8, b'\n'];
+        wv_assert_eq!(t, feed(&mut ed, Some(&mut hist), &line), Some(Event::Line));
+        ed.take_line();
+    }
+    let mut up = Vec::new();
+    for _ in 0..vterm_util::line::HISTORY_SIZE + 1 {
+        up.extend_from_slice(b"\x1b[A");
+    }
+    up.push(b'\n');
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, Some(&mut hist), &up),
+        Some(b"b0\n".to_vec())
+    );
+}
+
+fn redraw(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new(SpecialChars::default());
+
+    let mut out = Vec::new();
+    ed.redraw(&mut out);
+    wv_assert_eq!(t, out, b"\x1b[K".to_vec());
+
+    // the terminal cursor is placed at the cursor position within the line
+    wv_assert_eq!(t, feed(&mut ed, None, "aäc\x1b[D\x1b[D".as_bytes()), None);
+    out.clear();
+    ed.redraw(&mut out);
+    wv_assert_eq!(t, out, "aäc\x1b[K\x1b[2D".as_bytes().to_vec());
+
+    // redrawing does not change the line
+    wv_assert_eq!(
+        t,
+        enter(&mut ed, None, b"b\n"),
+        Some("abäc\n".as_bytes().to_vec())
+    );
+}
+
+fn args(t: &mut dyn WvTester) {
+    let settings = wv_assert_ok!(parse_args(&["vterm"]));
+    wv_assert_eq!(t, settings.consoles, 1);
+    wv_assert_eq!(t, settings.chars.kill, SpecialChars::default().kill);
+
+    let settings = wv_assert_ok!(parse_args(&["vterm", "-n", "3", "-c", "kill=^K"]));
+    wv_assert_eq!(t, settings.consoles, 3);
+    wv_assert_eq!(t, settings.chars.kill, 0x0b);
+
+    let max = format!("{}", MAX_CONSOLES);
+    let settings = wv_assert_ok!(parse_args(&["vterm", "-n", &max]));
+    wv_assert_eq!(t, settings.consoles, MAX_CONSOLES);
+
+    let too_many = format!("{}", MAX_CONSOLES + 1);
+    wv_assert!(t, parse_args(&["vterm", "-n", &too_many]).is_err());
+    wv_assert!(t, parse_args(&["vterm", "-n", "0"]).is_err());
+    wv_assert!(t, parse_args(&["vterm", "-n", "foo"]).is_err());
+    wv_assert!(t, parse_args(&["vterm", "-n"]).is_err());
+    wv_assert!(t, parse_args(&["vterm", "-c"]).is_err());
+    wv_assert!(t, parse_args(&["vterm", "-c", "foo=^K"]).is_err());
+}
+
+fn session_args(t: &mut dyn WvTester) {
+    // without argument, the first console is used
+    wv_assert_eq!(t, parse_session_arg("", 3), Ok(0));
+    wv_assert_eq!(t, parse_session_arg("foo bar", 3), Ok(0));
+
+    // the argument starts counting at 1
+    wv_assert_eq!(t, parse_session_arg("console=1", 3), Ok(0));
+    wv_assert_eq!(t, parse_session_arg("console=3", 3), Ok(2));
+    wv_assert_eq!(t, parse_session_arg("foo console=2", 3), Ok(1));
+
+    wv_assert_err!(t, parse_session_arg("console=0", 3), Code::InvArgs);
+    wv_assert_err!(t, parse_session_arg("console=4", 3), Code::InvArgs);
+    wv_assert_err!(t, parse_session_arg("console=", 3), Code::InvArgs);
+    wv_assert_err!(t, parse_session_arg("console=x", 3), Code::InvArgs);
+}
@@ -18,8 +18,10 @@
 
 #include <m3/Test.h>
 #include <m3/pipe/DirectPipe.h>
+#include <m3/pipe/IndirectPipe.h>
 #include <m3/tiles/ChildActivity.h>
 #include <m3/vfs/FileRef.h>
+#include <m3/vfs/VFS.h>
 
 #include "../unittests.h"
 
@@ -141,8 +143,103 @@ static void child_to_child() {
     WVASSERTEQ(writer.wait(), 0);
 }
 
+static void packets() {
+    const size_t SIZE = 0x1000;
+
+    Pipes pipesrv("pipes");
+    MemGate mem = MemGate::create_global(SIZE, MemGate::RW);
+    IndirectPipe pipe(pipesrv, mem, SIZE, FILE_PACKET);
+
+    // writes are not merged and the rest of a packet is discarded if the buffer is too small
+    WVASSERTEQ(pipe.writer().write("abc", 3).unwrap(), 3u);
+    WVASSERTEQ(pipe.writer().write("defgh", 5).unwrap(), 5u);
+    static char buf[SIZE];
+    WVASSERTEQ(pipe.reader().read(buf, 2).unwrap(), 2u);
+    WVASSERT(strncmp(buf, "ab", 2) == 0);
+    WVASSERTEQ(pipe.reader().read(buf, sizeof(buf)).unwrap(), 5u);
+    WVASSERT(strncmp(buf, "defgh", 5) == 0);
+
+    // packets can be as large as the pipe's buffer
+    memset(buf, 0xAB, sizeof(buf));
+    WVASSERTEQ(pipe.writer().write(buf, sizeof(buf)).unwrap(), SIZE);
+    memset(buf, 0, sizeof(buf));
+    WVASSERTEQ(pipe.reader().read(buf, sizeof(buf)).unwrap(), SIZE);
+    for(size_t i = 0; i < SIZE; ++i)
+        WVASSERTEQ(static_cast<uint8_t>(buf[i]), 0xABu);
+
+    pipe.close_writer();
+    WVASSERTEQ(pipe.reader().read(buf, sizeof(buf)).unwrap(), 0u);
+    pipe.close_reader();
+}
+
+static size_t read_all(GenericFile &file, char *buf, size_t size) {
+    size_t total = 0;
+    while(total < size) {
+        size_t amount = file.read(buf + total, size - total).unwrap();
+        if(amount == 0)
+            break;
+        total += amount;
+    }
+    return total;
+}
+
+static void named_pipes() {
+    VFS::mount("/pipes/", "pipes", "pipes");
+
+    VFS::mkfifo("/pipes/test");
+    WVASSERTERR(Errors::EXISTS, [] {
+        VFS::mkfifo("/pipes/test");
+    });
+    WVASSERTERR(Errors::NOT_SUP, [] {
+        VFS::mkdir("/pipes/dir", 0755);
+    });
+    WVASSERTERR(Errors::NO_SUCH_FILE, [] {
+        VFS::open("/pipes/foo", FILE_R);
+    });
+    WVASSERTERR(Errors::INV_ARGS, [] {
+        VFS::open("/pipes/test", FILE_RW);
+    });
+
+    FileInfo info;
+    VFS::stat("/pipes/test", info);
+    WVASSERT(M3FS_ISPIP(info.mode));
+
+    // an unrelated activity can find the pipe by its path
+    auto tile = Tile::get("compat|own");
+    ChildActivity writer(tile, "writer");
+    writer.add_mount("/pipes/", "/pipes/");
+
+    writer.run([] {
+        auto file = VFS::open("/pipes/test", FILE_W);
+        file->write_all("Hello via FIFO!", 15);
+        return 0;
+    });
+
+    {
+        auto file = VFS::open("/pipes/test", FILE_R);
+        size_t count = read_all(*file, buffer, sizeof(buffer) - 1);
+        buffer[count] = '\0';
+        WVASSERTSTREQ(buffer, "Hello via FIFO!");
+    }
+    WVASSERTEQ(writer.wait(), 0);
+
+    // pipes can also be created on open
+    VFS::open("/pipes/new", FILE_W | FILE_CREATE);
+    VFS::unlink("/pipes/new");
+
+    VFS::unlink("/pipes/test");
+    WVASSERTERR(Errors::NO_SUCH_FILE, [] {
+        FileInfo info;
+        VFS::stat("/pipes/test", info);
+    });
+
+    VFS::unmount("/pipes/");
+}
+
 void tpipe() {
     RUN_TEST(reader_quit);
     RUN_TEST(writer_quit);
     RUN_TEST(child_to_child);
+    RUN_TEST(packets);
+    RUN_TEST(named_pipes);
 }
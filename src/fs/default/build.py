@@ -3,4 +3,5 @@ def build(gen, env):
         blocks = 160 * 1024
     else:
         blocks = 32 * 1024
-    env.build_fs(gen, out='default.img', dir='.', blocks=blocks, inodes=512)
+    env.build_fs(gen, out='default.img', dir='.', blocks=blocks, inodes=512,
+                 csum_out='default-csum.img', journal_out='default-journal.img')
@@ -95,6 +95,11 @@ struct Errors {
         SOCKET_CLOSED,
         CONNECTION_FAILED,
         CONN_CLOSED,
+        // symbolic links
+        LINK_LOOP,
+        FOREIGN_LINK,
+        // file system integrity
+        DATA_CORRUPTED,
     };
 
     /**
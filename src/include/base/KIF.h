@@ -62,6 +62,16 @@ struct KIF {
      */
     static const uint FIRST_FREE_SEL = SEL_ACT + 1;
 
+    /**
+     * The priority classes of activities
+     */
+    enum Priority {
+        PRIO_LOW,
+        PRIO_NORMAL,
+        PRIO_HIGH,
+        PRIO_COUNT,
+    };
+
     /**
      * The activity id of TileMux
      */
@@ -152,6 +162,23 @@ struct KIF {
         unsigned char data[64];
     } PACKED;
 
+    /**
+     * The resource usage of an activity as tracked by TileMux
+     */
+    struct ActivityStats {
+        // the time the activity was running (in nanoseconds)
+        xfer_t cpu_time;
+        // the time the activity was blocked (in nanoseconds)
+        xfer_t blocked_time;
+        // the number of context switches to the activity
+        xfer_t ctxsws;
+        // the number of page faults
+        xfer_t page_faults;
+        // the number of messages that arrived while the activity was not running (messages that
+        // arrive while it is running do not involve TileMux and are therefore not counted)
+        xfer_t desched_msgs;
+    } PACKED;
+
     /**
      * System calls
      */
@@ -193,6 +220,8 @@ struct KIF {
             // misc
             RESET_STATS,
             NOOP,
+            LIST_CAPS,
+            ACT_STATS,
 
             COUNT
         };
@@ -201,6 +230,9 @@ struct KIF {
             VCTRL_INIT,
             VCTRL_START,
             VCTRL_STOP,
+            VCTRL_SUSPEND,
+            VCTRL_RESUME,
+            VCTRL_MIGRATE,
         };
 
         enum SemOp {
@@ -258,6 +290,7 @@ struct KIF {
             xfer_t dst_sel;
             xfer_t tile_sel;
             xfer_t kmem_sel;
+            xfer_t prio;
             xfer_t namelen;
             char name[MAX_STR_SIZE];
         } PACKED;
@@ -431,6 +464,14 @@ struct KIF {
             xfer_t own;
         } PACKED;
 
+        struct ActStats : public DefaultRequest {
+            xfer_t act_sel;
+        } PACKED;
+
+        struct ActStatsReply : public DefaultReply {
+            ActivityStats stats;
+        } PACKED;
+
         struct ResetStats : public DefaultRequest {
         } PACKED;
 
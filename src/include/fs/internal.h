@@ -21,9 +21,7 @@
 #include <stddef.h>
 #include <stdint.h>
 
-#if !defined(__tools__)
-#    include <m3/com/MemGate.h>
-#endif
+#include <m3/com/MemGate.h>
 
 namespace m3 {
 
@@ -67,13 +65,6 @@ using inodeno_t = uint32_t;
 using blockno_t = uint32_t;
 using time_t = uint32_t;
 
-enum {
-    INODE_DIR_COUNT = 3,
-    MAX_BLOCK_SIZE = 4096,
-};
-
-constexpr inodeno_t INVALID_INO = static_cast<inodeno_t>(-1);
-
 #define M3FS_SEEK_SET 0
 #define M3FS_SEEK_CUR 1
 #define M3FS_SEEK_END 2
@@ -89,18 +80,12 @@ enum {
     FILE_CREATE = 32,
     FILE_NODATA = 64,
     FILE_NEWSESS = 128,
+    FILE_PACKET = 256,
 };
 
-#if !defined(__tools__)
 static_assert(FILE_R == MemGate::R, "FILE_R is out of sync");
 static_assert(FILE_W == MemGate::W, "FILE_W is out of sync");
 static_assert(FILE_X == MemGate::X, "FILE_X is out of sync");
-#endif
-
-struct Extent {
-    uint32_t start;
-    uint32_t length;
-};
 
 struct FileInfo {
     dev_t devno;
@@ -114,24 +99,12 @@ struct FileInfo {
     // for debugging
     uint32_t extents;
     blockno_t firstblock;
+    uint32_t uid;
+    uint32_t gid;
 };
 
-// should be 64 bytes large
-struct alignas(8) INode {
-    dev_t devno;
-    uint8_t : 8;
-    uint16_t links;
-    time_t lastaccess;
-    time_t lastmod;
-    uint32_t extents;
-    inodeno_t inode;
-    mode_t mode;
-    uint64_t size;
-    Extent direct[INODE_DIR_COUNT];
-    blockno_t indirect;
-    blockno_t dindirect;
-} __attribute__((packed));
-
+// the header of the entries that are read from directories; the on-disk layout of m3fs is defined
+// in the m3fs_layout crate
 struct DirEntry {
     inodeno_t nodeno;
     uint32_t namelen;
@@ -139,186 +112,4 @@ struct DirEntry {
     char name[];
 } __attribute__((packed));
 
-struct SuperBlock {
-    blockno_t first_inodebm_block() const {
-        static_assert(sizeof(INode) == 64, "INode not 64-byte large");
-        return 1;
-    }
-    blockno_t inodebm_blocks() const {
-        return (((total_inodes + 7) / 8) + blocksize - 1) / blocksize;
-    }
-    blockno_t first_blockbm_block() const {
-        return first_inodebm_block() + inodebm_blocks();
-    }
-    blockno_t blockbm_blocks() const {
-        return (((total_blocks + 7) / 8) + blocksize - 1) / blocksize;
-    }
-    blockno_t first_inode_block() const {
-        return first_blockbm_block() + blockbm_blocks();
-    }
-    blockno_t inode_blocks() const {
-        return (total_inodes * sizeof(INode) + blocksize - 1) / blocksize;
-    }
-    blockno_t first_data_block() const {
-        return first_inode_block() + inode_blocks();
-    }
-    unsigned extents_per_block() const {
-        return blocksize / sizeof(Extent);
-    }
-    unsigned inodes_per_block() const {
-        return blocksize / sizeof(INode);
-    }
-    uint32_t get_checksum() const {
-        return 1 + blocksize * 2 + total_inodes * 3 + total_blocks * 5 + free_inodes * 7 +
-               free_blocks * 11 + first_free_inode * 13 + first_free_block * 17;
-    }
-
-    uint32_t blocksize;
-    uint32_t total_inodes;
-    uint32_t total_blocks;
-    uint32_t free_inodes;
-    uint32_t free_blocks;
-    uint32_t first_free_inode;
-    uint32_t first_free_block;
-    uint32_t checksum;
-} __attribute__((packed));
-
-class Bitmap {
-public:
-    typedef uint32_t word_t;
-
-    static const int WORD_BITS = sizeof(word_t) * 8;
-
-    explicit Bitmap(size_t bits)
-        : _allocated(true),
-          _words(new word_t[(bits + WORD_BITS - 1) / WORD_BITS]()) {
-    }
-    explicit Bitmap(word_t *bytes) : _allocated(false), _words(bytes) {
-    }
-    ~Bitmap() {
-        if(_allocated)
-            delete[] _words;
-    }
-
-    word_t *bytes() {
-        return _words;
-    }
-
-    bool is_word_set(unsigned bit) const {
-        return _words[idx(bit)] == static_cast<word_t>(-1);
-    }
-    bool is_word_free(unsigned bit) const {
-        return _words[idx(bit)] == 0;
-    }
-    void set_word(unsigned bit) {
-        _words[idx(bit)] = static_cast<word_t>(-1);
-    }
-    void clear_word(unsigned bit) {
-        _words[idx(bit)] = 0;
-    }
-
-    bool is_set(unsigned bit) const {
-        return (_words[idx(bit)] & bitpos(bit)) != 0;
-    }
-    void set(unsigned bit) {
-        _words[idx(bit)] |= bitpos(bit);
-    }
-    void unset(unsigned bit) {
-        _words[idx(bit)] &= ~bitpos(bit);
-    }
-
-private:
-    static size_t idx(unsigned bit) {
-        return bit / WORD_BITS;
-    }
-    static word_t bitpos(unsigned bit) {
-        return 1UL << (bit % WORD_BITS);
-    }
-
-    bool _allocated;
-    word_t *_words;
-};
-
-}
-
-#if defined(__tools__)
-
-#    include <err.h>
-#    include <errno.h>
-#    include <memory>
-#    include <stdarg.h>
-#    include <stdio.h>
-#    include <stdlib.h>
-#    include <string.h>
-
-extern m3::SuperBlock sb;
-extern FILE *file;
-
-static __attribute__((unused)) inline void read_from_block(void *buffer, size_t len,
-                                                           m3::blockno_t bno, size_t off = 0) {
-    off_t offset = static_cast<off_t>(static_cast<size_t>(bno * sb.blocksize) + off);
-    if(fseek(file, offset, SEEK_SET) != 0)
-        err(1, "Unable to seek to block %u+%zu: %s\n", bno, off, strerror(errno));
-    if(fread(buffer, 1, len, file) != len)
-        err(1, "Unable to read from block %u: %s\n", bno, strerror(errno));
-}
-
-static __attribute__((unused)) inline void write_to_block(const void *buffer, size_t len,
-                                                          m3::blockno_t bno, size_t off = 0) {
-    off_t offset = static_cast<off_t>(static_cast<size_t>(bno * sb.blocksize) + off);
-    if(fseek(file, offset, SEEK_SET) != 0)
-        err(1, "Unable to seek to block %u+%zu: %s\n", bno, off, strerror(errno));
-    if(fwrite(buffer, 1, len, file) != len)
-        err(1, "Unable to write to block %u: %s\n", bno, strerror(errno));
-}
-
-static __attribute__((unused)) m3::INode read_inode(m3::inodeno_t ino) {
-    m3::INode inode;
-    read_from_block(&inode, sizeof(inode), sb.first_inode_block(), ino * sizeof(m3::INode));
-    return inode;
 }
-
-static __attribute__((unused)) m3::blockno_t get_block_no_rec(const m3::INode &ino,
-                                                              m3::blockno_t indirect, size_t &no,
-                                                              int layer) {
-    m3::blockno_t res = 0;
-    std::unique_ptr<m3::Extent[]> extents(new m3::Extent[sb.extents_per_block()]);
-    read_from_block(extents.get(), sb.blocksize, indirect);
-    for(size_t i = 0; i < sb.extents_per_block(); ++i) {
-        if(layer > 0) {
-            res = get_block_no_rec(ino, extents[i].start, no, layer - 1);
-            if(res)
-                break;
-        }
-        else {
-            if(extents[i].length > no) {
-                res = extents[i].start + no;
-                break;
-            }
-            no -= extents[i].length;
-        }
-    }
-    return res;
-}
-
-static __attribute__((unused)) m3::blockno_t get_block_no(const m3::INode &ino, size_t no) {
-    for(size_t i = 0; i < m3::INODE_DIR_COUNT; ++i) {
-        if(ino.direct[i].length > no)
-            return ino.direct[i].start + no;
-        no -= ino.direct[i].length;
-    }
-
-    m3::blockno_t res = get_block_no_rec(ino, ino.indirect, no, 0);
-    if(res)
-        return res;
-    return get_block_no_rec(ino, ino.dindirect, no, 1);
-}
-
-static __attribute__((unused)) unsigned first_free(m3::Bitmap &bm, unsigned total) {
-    unsigned i;
-    for(i = 0; bm.is_set(i) && i < total; ++i)
-        ;
-    return i;
-}
-
-#endif
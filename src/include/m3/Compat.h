@@ -24,6 +24,7 @@
 
 #include <base/Common.h>
 
+#include <m3/session/Pager.h>
 #include <m3/vfs/Dir.h>
 #include <m3/vfs/File.h>
 
@@ -55,10 +56,16 @@ EXTERN_C m3::Errors::Code __m3c_fflush(int fd);
 EXTERN_C m3::Errors::Code __m3c_lseek(int fd, size_t *offset, int whence);
 EXTERN_C m3::Errors::Code __m3c_ftruncate(int fd, size_t length);
 EXTERN_C m3::Errors::Code __m3c_truncate(const char *pathname, size_t length);
+EXTERN_C m3::Errors::Code __m3c_lock(int fd, size_t off, size_t len, m3::File::LockType type,
+                                     bool wait);
+EXTERN_C m3::Errors::Code __m3c_unlock(int fd, size_t off, size_t len);
 EXTERN_C m3::Errors::Code __m3c_sync(int fd);
 EXTERN_C bool __m3c_isatty(int fd);
 EXTERN_C void __m3c_close(int fd);
 
+EXTERN_C m3::Errors::Code __m3c_mprotect(uintptr_t addr, size_t len, int prot);
+EXTERN_C m3::Errors::Code __m3c_madvise(uintptr_t addr, size_t len, m3::Pager::Advice advice);
+
 typedef void (*waiter_fetch_cb)(void *p, int fd, uint fdevs);
 
 EXTERN_C m3::Errors::Code __m3c_waiter_create(void **waiter);
@@ -70,6 +77,17 @@ EXTERN_C void __m3c_waiter_waitfor(void *waiter, uint64_t timeout);
 EXTERN_C void __m3c_waiter_fetch(void *waiter, void *arg, waiter_fetch_cb cb);
 EXTERN_C void __m3c_waiter_destroy(void *waiter);
 
+struct CompatPollFd {
+    int fd;
+    uint events;
+    uint revents;
+};
+
+EXTERN_C m3::Errors::Code __m3c_poll(CompatPollFd *fds, size_t nfds, int64_t timeout,
+                                     size_t *ready);
+EXTERN_C m3::Errors::Code __m3c_select(int nfds, uint64_t *readfds, uint64_t *writefds,
+                                       int64_t timeout, int *ready);
+
 enum CompatSock {
     INVALID,
     DGRAM,
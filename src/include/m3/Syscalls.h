@@ -67,7 +67,8 @@ public:
     static void create_rgate(capsel_t dst, uint order, uint msgorder);
     static void create_sgate(capsel_t dst, capsel_t rgate, label_t label, uint credits);
     static std::pair<epid_t, actid_t> create_activity(capsel_t dst, const std::string_view &name,
-                                                      capsel_t tile, capsel_t kmem);
+                                                      capsel_t tile, capsel_t kmem,
+                                                      KIF::Priority prio);
     static void create_map(capsel_t dst, capsel_t act, capsel_t mgate, capsel_t first,
                            capsel_t pages, int perms);
     static void create_sem(capsel_t dst, uint value);
@@ -100,6 +101,7 @@ public:
     static void exchange(capsel_t act, const KIF::CapRngDesc &own, capsel_t other, bool obtain);
     static void revoke(capsel_t act, const KIF::CapRngDesc &crd, bool own = true);
 
+    static KIF::ActivityStats activity_stats(capsel_t act);
     static void reset_stats();
     static void noop();
 
@@ -26,6 +26,10 @@ namespace m3 {
 
 class IndirectPipe {
 public:
+    /**
+     * Creates a new pipe at <pipes> using <mem> of <memsize> bytes as shared memory. The <flags>
+     * are used for both channels; FILE_PACKET creates a packet pipe (see Pipes::create_pipe).
+     */
     explicit IndirectPipe(Pipes &pipes, MemGate &mem, size_t memsize, int flags = 0);
     ~IndirectPipe();
 
@@ -76,9 +76,17 @@ public:
     virtual Errors::Code try_stat(const char *path, FileInfo &info) noexcept override;
     virtual Errors::Code try_mkdir(const char *path, mode_t mode) override;
     virtual Errors::Code try_rmdir(const char *path) override;
+    virtual Errors::Code try_mkfifo(const char *) override {
+        // named pipes are provided by the pipes server
+        return Errors::NOT_SUP;
+    }
     virtual Errors::Code try_link(const char *oldpath, const char *newpath) override;
     virtual Errors::Code try_unlink(const char *path) override;
     virtual Errors::Code try_rename(const char *oldpath, const char *newpath) override;
+    virtual Errors::Code try_symlink(const char *target, const char *path) override;
+    virtual Errors::Code try_readlink(const char *path, std::string &target) override;
+    virtual Errors::Code try_chmod(const char *path, mode_t mode) override;
+    virtual Errors::Code try_chown(const char *path, uint32_t uid, uint32_t gid) override;
 
     virtual void delegate(ChildActivity &act) override;
     virtual void serialize(Marshaller &m) override;
@@ -102,24 +110,27 @@ private:
 
 template<>
 struct OStreamSize<FileInfo> {
-    static const size_t value = 10 * sizeof(xfer_t);
+    static const size_t value = 12 * sizeof(xfer_t);
 };
 
 static inline Unmarshaller &operator>>(Unmarshaller &u, FileInfo &info) noexcept {
     u >> info.devno >> info.inode >> info.mode >> info.links >> info.size >> info.lastaccess >>
-        info.lastmod >> info.blocksize >> info.extents >> info.firstblock;
+        info.lastmod >> info.blocksize >> info.extents >> info.firstblock >> info.uid >>
+        info.gid;
     return u;
 }
 
 static inline GateIStream &operator>>(GateIStream &is, FileInfo &info) noexcept {
     is >> info.devno >> info.inode >> info.mode >> info.links >> info.size >> info.lastaccess >>
-        info.lastmod >> info.blocksize >> info.extents >> info.firstblock;
+        info.lastmod >> info.blocksize >> info.extents >> info.firstblock >> info.uid >>
+        info.gid;
     return is;
 }
 
 static inline Marshaller &operator<<(Marshaller &m, const FileInfo &info) noexcept {
     m << info.devno << info.inode << info.mode << info.links << info.size << info.lastaccess
-      << info.lastmod << info.blocksize << info.extents << info.firstblock;
+      << info.lastmod << info.blocksize << info.extents << info.firstblock << info.uid
+      << info.gid;
     return m;
 }
 
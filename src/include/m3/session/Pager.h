@@ -46,9 +46,20 @@ public:
         MAP_MEM,
         UNMAP,
         CLOSE,
+        MSYNC,
+        PROTECT,
+        ADVISE,
+        STATS,
         COUNT,
     };
 
+    enum Advice {
+        NORMAL,
+        SEQUENTIAL,
+        WILLNEED,
+        DONTNEED,
+    };
+
     enum Flags {
         MAP_PRIVATE = 0,
         MAP_SHARED = 0x2000,
@@ -81,6 +92,9 @@ public:
                 size_t offset);
     void map_mem(goff_t *virt, MemGate &mem, size_t len, int prot);
     void unmap(goff_t virt);
+    void msync(goff_t virt, size_t len);
+    void protect(goff_t virt, size_t len, int prot);
+    void advise(goff_t virt, size_t len, Advice advice);
 
 private:
     capsel_t get_sgate();
@@ -0,0 +1,85 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+#pragma once
+
+#include <m3/session/ClientSession.h>
+#include <m3/session/Pipes.h>
+#include <m3/vfs/FileSystem.h>
+
+namespace m3 {
+
+/**
+ * Represents a session at the pipes server that provides access to named pipes.
+ *
+ * The file system consists of a single directory that contains named pipes only. Named pipes are
+ * created via VFS::mkfifo or by opening them with FILE_CREATE and are opened either for reading or
+ * for writing. Opening a named pipe yields a channel of the pipe, just like
+ * Pipes::Pipe::create_channel.
+ */
+class PipeFS : public ClientSession, public FileSystem {
+public:
+    explicit PipeFS(size_t id, const std::string_view &service)
+        : ClientSession(service),
+          FileSystem(id) {
+    }
+    explicit PipeFS(size_t id, capsel_t sel) noexcept : ClientSession(sel), FileSystem(id) {
+    }
+
+    virtual char type() const noexcept override {
+        return 'P';
+    }
+
+    virtual std::unique_ptr<GenericFile> open(const char *path, int perms) override;
+    virtual void close(size_t) override {
+    }
+    virtual Errors::Code try_stat(const char *path, FileInfo &info) noexcept override;
+    virtual Errors::Code try_mkdir(const char *, mode_t) override {
+        return Errors::NOT_SUP;
+    }
+    virtual Errors::Code try_rmdir(const char *) override {
+        return Errors::NOT_SUP;
+    }
+    virtual Errors::Code try_mkfifo(const char *path) override;
+    virtual Errors::Code try_link(const char *, const char *) override {
+        return Errors::NOT_SUP;
+    }
+    virtual Errors::Code try_unlink(const char *path) override;
+    virtual Errors::Code try_rename(const char *, const char *) override {
+        return Errors::NOT_SUP;
+    }
+    virtual Errors::Code try_symlink(const char *, const char *) override {
+        return Errors::NOT_SUP;
+    }
+    virtual Errors::Code try_readlink(const char *, std::string &) override {
+        // there are no symbolic links
+        return Errors::INV_ARGS;
+    }
+    virtual Errors::Code try_chmod(const char *, mode_t) override {
+        return Errors::NOT_SUP;
+    }
+    virtual Errors::Code try_chown(const char *, uint32_t, uint32_t) override {
+        return Errors::NOT_SUP;
+    }
+
+    virtual void delegate(ChildActivity &act) override;
+    virtual void serialize(Marshaller &m) override;
+    static FileSystem *unserialize(Unmarshaller &um);
+
+private:
+    Errors::Code fifo_op(int op, const char *path, size_t *ino = nullptr) noexcept;
+};
+
+}
@@ -27,26 +27,36 @@
 namespace m3 {
 
 class Pipes : public ClientSession {
+    friend class PipeFS;
+
     enum {
         OPEN_PIPE = GenericFile::REQ_NOTIFY + 1,
         OPEN_CHAN,
         SET_MEM,
         CLOSE_PIPE,
+        OPEN_FIFO,
+        MKFIFO,
+        UNLINK_FIFO,
+        STAT_FIFO,
     };
 
 public:
     class Pipe : public ClientSession {
     public:
-        explicit Pipe(capsel_t sel, MemGate &memory)
+        explicit Pipe(capsel_t sel, MemGate &memory, bool packet)
             : ClientSession(sel),
-              _sgate(SendGate::bind(sel + 1)) {
+              _sgate(SendGate::bind(sel + 1)),
+              _packet(packet) {
             KIF::ExchangeArgs args;
             ExchangeOStream os(args);
             os << SET_MEM;
             args.bytes = os.total();
             delegate(KIF::CapRngDesc(KIF::CapRngDesc::OBJ, memory.sel(), 1), &args);
         }
-        Pipe(Pipe &&p) noexcept : ClientSession(std::move(p)), _sgate(std::move(p._sgate)) {
+        Pipe(Pipe &&p) noexcept
+            : ClientSession(std::move(p)),
+              _sgate(std::move(p._sgate)),
+              _packet(p._packet) {
         }
         virtual ~Pipe() {
             send_receive_vmsg(_sgate, CLOSE_PIPE);
@@ -59,6 +69,8 @@ public:
             args.bytes = os.total();
             KIF::CapRngDesc desc = obtain(2, &args);
             flags |= FILE_NEWSESS | (read ? FILE_R : FILE_W);
+            if(_packet)
+                flags |= FILE_PACKET;
             auto file = std::unique_ptr<GenericFile>(
                 new GenericFile(flags, desc.start(), static_cast<size_t>(-1)));
             return Activity::own().files()->alloc(std::move(file));
@@ -66,18 +78,26 @@ public:
 
     private:
         SendGate _sgate;
+        bool _packet;
     };
 
     explicit Pipes(const std::string_view &service) : ClientSession(service) {
     }
 
-    Pipe create_pipe(MemGate &memory, size_t memsize) {
+    /**
+     * Creates a new pipe using <memory> of <memsize> bytes as shared memory for the data exchange.
+     *
+     * In packet pipes, every write of up to <memsize> bytes is received as a unit by exactly one
+     * read of one of the readers. If the read buffer is smaller than the packet, the rest of the
+     * packet is discarded.
+     */
+    Pipe create_pipe(MemGate &memory, size_t memsize, bool packet = false) {
         KIF::ExchangeArgs args;
         ExchangeOStream os(args);
-        os << OPEN_PIPE << memsize;
+        os << OPEN_PIPE << memsize << packet;
         args.bytes = os.total();
         KIF::CapRngDesc desc = obtain(2, &args);
-        return Pipe(desc.start(), memory);
+        return Pipe(desc.start(), memory, packet);
     }
 };
 
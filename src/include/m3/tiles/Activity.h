@@ -124,6 +124,14 @@ public:
      */
     MemGate get_mem(goff_t addr, size_t size, int perms);
 
+    /**
+     * Retrieves the resource usage of this activity as tracked by TileMux. After the activity has
+     * exited, the values at the time of its exit are returned.
+     *
+     * @return the statistics
+     */
+    KIF::ActivityStats stats() const;
+
     /**
      * Allocates capability selectors.
      *
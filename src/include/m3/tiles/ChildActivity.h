@@ -46,11 +46,16 @@ public:
         _kmem = kmem;
         return *this;
     }
+    ActivityArgs &prio(KIF::Priority prio) noexcept {
+        _prio = prio;
+        return *this;
+    }
 
 private:
     ResMng *_rmng;
     Reference<Pager> _pager;
     Reference<KMem> _kmem;
+    KIF::Priority _prio;
 };
 
 /**
@@ -190,6 +195,26 @@ public:
      */
     void stop();
 
+    /**
+     * Suspends the activity, i.e., it is no longer scheduled until it is resumed. The activity
+     * keeps its capabilities and memory.
+     */
+    void suspend();
+
+    /**
+     * Resumes the previously suspended activity.
+     */
+    void resume();
+
+    /**
+     * Migrates the activity to the given tile, which needs to be of the same type as the current
+     * one. The activity continues where it left off, but is suspended before and resumed after the
+     * migration, if it was running.
+     *
+     * @param tile the destination tile
+     */
+    void migrate(const Reference<class Tile> &tile);
+
     /**
      * Waits until the currently executing program on this activity is finished
      *
@@ -55,10 +55,20 @@ public:
         COOKED = 1,
     };
 
+    enum class LockType {
+        SHARED = 0,
+        EXCLUSIVE = 1,
+    };
+
     enum Event {
         INPUT = 1,
         OUTPUT = 2,
         SIGNAL = 4,
+        // changes of directories
+        CREATE = 8,
+        UNLINK = 16,
+        RENAME = 32,
+        MODIFY = 64,
     };
 
     static constexpr size_t NOTIFY_MSG_SIZE = 64;
@@ -223,6 +233,31 @@ public:
         throw Exception(Errors::NOT_SUP);
     }
 
+    /**
+     * Acquires an advisory lock of given type for the <len> bytes at offset <off>. A length of 0
+     * locks everything from <off> onwards. If another file holds a conflicting lock, the call waits
+     * until it is released if <wait> is true and throws a WOULD_BLOCK exception otherwise.
+     *
+     * @param off the start of the range
+     * @param len the length of the range (0 = until infinity)
+     * @param type the lock type
+     * @param wait whether to wait for conflicting locks
+     */
+    virtual void lock(UNUSED size_t off, UNUSED size_t len, UNUSED LockType type,
+                      UNUSED bool wait) {
+        throw Exception(Errors::NOT_SUP);
+    }
+
+    /**
+     * Releases the locks of this file for the <len> bytes at offset <off>.
+     *
+     * @param off the start of the range
+     * @param len the length of the range (0 = until infinity)
+     */
+    virtual void unlock(UNUSED size_t off, UNUSED size_t len) {
+        throw Exception(Errors::NOT_SUP);
+    }
+
     /**
      * @return the absolute path for this file, including its mount point
      */
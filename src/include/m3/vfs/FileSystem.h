@@ -62,6 +62,17 @@ public:
         GET_MEM,
         DEL_EP,
         OPEN_PRIV,
+        SYMLINK,
+        READLINK,
+        CHMOD,
+        CHOWN,
+        FSCK,
+        ALLOCATE,
+        PUNCH_HOLE,
+        LOCK,
+        UNLOCK,
+        STATFS,
+        SYNC_MEM,
     };
 
     explicit FileSystem(size_t id) noexcept : RefCounted(), _id(id) {
@@ -161,6 +172,26 @@ public:
      */
     virtual Errors::Code try_rmdir(const char *path) = 0;
 
+    /**
+     * Creates a named pipe at <path>.
+     *
+     * @param path the path of the pipe
+     */
+    void mkfifo(const char *path) {
+        Errors::Code res = try_mkfifo(path);
+        if(res != Errors::SUCCESS)
+            throw Exception(res);
+    }
+
+    /**
+     * Tries to create a named pipe at <path>. That is, on error it does not throw an exception, but
+     * the error code is returned.
+     *
+     * @param path the path of the pipe
+     * @return the error code on failure
+     */
+    virtual Errors::Code try_mkfifo(const char *path) = 0;
+
     /**
      * Creates a link at <newpath> to <oldpath>.
      *
@@ -225,6 +256,98 @@ public:
      */
     virtual Errors::Code try_rename(const char *oldpath, const char *newpath) = 0;
 
+    /**
+     * Creates a symbolic link at <path> that points to <target>.
+     *
+     * @param target the target of the link
+     * @param path the link to create
+     */
+    void symlink(const char *target, const char *path) {
+        Errors::Code res = try_symlink(target, path);
+        if(res != Errors::SUCCESS)
+            throw Exception(res);
+    }
+
+    /**
+     * Tries to create a symbolic link at <path> that points to <target>. That is, on error it does
+     * not throw an exception, but the error code is returned.
+     *
+     * @param target the target of the link
+     * @param path the link to create
+     * @return the error code on failure
+     */
+    virtual Errors::Code try_symlink(const char *target, const char *path) = 0;
+
+    /**
+     * Retrieves the target of the symbolic link at <path>.
+     *
+     * @param path the path of the link
+     * @return the target
+     */
+    std::string readlink(const char *path) {
+        std::string target;
+        Errors::Code res = try_readlink(path, target);
+        if(res != Errors::SUCCESS)
+            throw Exception(res);
+        return target;
+    }
+
+    /**
+     * Tries to retrieve the target of the symbolic link at <path>. That is, on error it does not
+     * throw an exception, but the error code is returned.
+     *
+     * @param path the path of the link
+     * @param target where to write the target to
+     * @return the error code on failure
+     */
+    virtual Errors::Code try_readlink(const char *path, std::string &target) = 0;
+
+    /**
+     * Changes the permissions of <path> to <mode>.
+     *
+     * @param path the path of the file
+     * @param mode the new permissions
+     */
+    void chmod(const char *path, mode_t mode) {
+        Errors::Code res = try_chmod(path, mode);
+        if(res != Errors::SUCCESS)
+            throw Exception(res);
+    }
+
+    /**
+     * Tries to change the permissions of <path> to <mode>. That is, on error it does not throw an
+     * exception, but the error code is returned.
+     *
+     * @param path the path of the file
+     * @param mode the new permissions
+     * @return the error code on failure
+     */
+    virtual Errors::Code try_chmod(const char *path, mode_t mode) = 0;
+
+    /**
+     * Changes the owner of <path> to <uid> and <gid>.
+     *
+     * @param path the path of the file
+     * @param uid the new user id
+     * @param gid the new group id
+     */
+    void chown(const char *path, uint32_t uid, uint32_t gid) {
+        Errors::Code res = try_chown(path, uid, gid);
+        if(res != Errors::SUCCESS)
+            throw Exception(res);
+    }
+
+    /**
+     * Tries to change the owner of <path> to <uid> and <gid>. That is, on error it does not throw
+     * an exception, but the error code is returned.
+     *
+     * @param path the path of the file
+     * @param uid the new user id
+     * @param gid the new group id
+     * @return the error code on failure
+     */
+    virtual Errors::Code try_chown(const char *path, uint32_t uid, uint32_t gid) = 0;
+
     /**
      * Delegates all this filesystem to the given activity.
      *
@@ -74,6 +74,9 @@ public:
 
     virtual void truncate(size_t length) override;
 
+    virtual void lock(size_t off, size_t len, LockType type, bool wait) override;
+    virtual void unlock(size_t off, size_t len) override;
+
     virtual std::string path() override;
 
     virtual void flush() override {
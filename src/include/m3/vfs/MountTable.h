@@ -120,6 +120,20 @@ public:
      */
     Reference<FileSystem> try_resolve(const char **path, char *buffer, size_t bufsize) noexcept;
 
+    /**
+     * Replaces the first symbolic link within the given path by its target.
+     *
+     * File systems cannot resolve symbolic links with absolute targets themselves, because the
+     * target might belong to a different mount point. Instead, they fail with
+     * Errors::FOREIGN_LINK, upon which the client resolves the link via this function and retries
+     * the operation with the resulting path.
+     *
+     * @param path the path containing the link
+     * @param result the path with the link replaced by its target
+     * @return the error code on failure
+     */
+    Errors::Code try_resolve_link(const char *path, std::string &result);
+
     /**
      * @param id the id of the filesystem
      * @return the filesystem with given id
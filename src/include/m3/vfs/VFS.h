@@ -160,6 +160,22 @@ public:
      */
     static Errors::Code try_rmdir(const char *path);
 
+    /**
+     * Creates a named pipe at <path>. This is only supported by file systems of type "pipes".
+     *
+     * @param path the path
+     */
+    static void mkfifo(const char *path);
+
+    /**
+     * Tries to create a named pipe at <path>. That is, on error it does not throw an exception, but
+     * returns the error code.
+     *
+     * @param path the path
+     * @return the error code on failure
+     */
+    static Errors::Code try_mkfifo(const char *path);
+
     /**
      * Creates a link at <newpath> to <oldpath>.
      *
@@ -212,6 +228,80 @@ public:
      */
     static Errors::Code try_rename(const char *oldpath, const char *newpath);
 
+    /**
+     * Creates a symbolic link at <path> that points to <target>.
+     *
+     * @param target the target of the link
+     * @param path the link to create
+     */
+    static void symlink(const char *target, const char *path);
+
+    /**
+     * Tries to create a symbolic link at <path> that points to <target>. That is, on error it does
+     * not throw an exception, but returns the error code.
+     *
+     * @param target the target of the link
+     * @param path the link to create
+     * @return the error code on failure
+     */
+    static Errors::Code try_symlink(const char *target, const char *path);
+
+    /**
+     * Retrieves the target of the symbolic link at <path>.
+     *
+     * @param path the path of the link
+     * @return the target
+     */
+    static std::string readlink(const char *path);
+
+    /**
+     * Tries to retrieve the target of the symbolic link at <path>. That is, on error it does not
+     * throw an exception, but returns the error code.
+     *
+     * @param path the path of the link
+     * @param target where to write the target to
+     * @return the error code on failure
+     */
+    static Errors::Code try_readlink(const char *path, std::string &target);
+
+    /**
+     * Changes the permissions of <path> to <mode>.
+     *
+     * @param path the path of the file
+     * @param mode the new permissions
+     */
+    static void chmod(const char *path, mode_t mode);
+
+    /**
+     * Tries to change the permissions of <path> to <mode>. That is, on error it does not throw an
+     * exception, but returns the error code.
+     *
+     * @param path the path of the file
+     * @param mode the new permissions
+     * @return the error code on failure
+     */
+    static Errors::Code try_chmod(const char *path, mode_t mode);
+
+    /**
+     * Changes the owner of <path> to <uid> and <gid>.
+     *
+     * @param path the path of the file
+     * @param uid the new user id
+     * @param gid the new group id
+     */
+    static void chown(const char *path, uint32_t uid, uint32_t gid);
+
+    /**
+     * Tries to change the owner of <path> to <uid> and <gid>. That is, on error it does not throw
+     * an exception, but returns the error code.
+     *
+     * @param path the path of the file
+     * @param uid the new user id
+     * @param gid the new group id
+     * @return the error code on failure
+     */
+    static Errors::Code try_chown(const char *path, uint32_t uid, uint32_t gid);
+
     /**
      * Prints the current mounts to <os>.
      *
@@ -18,7 +18,7 @@ use base::cfg;
 use base::col::Treap;
 use base::errors::{Code, Error};
 use base::goff;
-use base::kif::{CapRngDesc, CapSel, SEL_ACT, SEL_KMEM, SEL_TILE};
+use base::kif::{syscalls::CapInfo, CapRngDesc, CapSel, INVALID_SEL, SEL_ACT, SEL_KMEM, SEL_TILE};
 use base::mem::size_of;
 use base::rc::Rc;
 use core::cmp;
@@ -124,6 +124,27 @@ impl CapTable {
         self.caps.get_mut(&SelRange::new(sel))
     }
 
+    /// Fills `infos` with the capabilities at or behind `start` and returns the number of entries
+    /// and the selector to continue with (`INVALID_SEL` if there are no more capabilities).
+    pub fn list(&self, start: CapSel, infos: &mut [CapInfo]) -> (usize, CapSel) {
+        let mut sel = start;
+        for (i, info) in infos.iter_mut().enumerate() {
+            match self.caps.lower_bound(&SelRange::new(sel)) {
+                Some(cap) => {
+                    *info = cap.info();
+                    sel = cap.sel() + cap.len();
+                },
+                None => return (i, INVALID_SEL),
+            }
+        }
+
+        // check whether there is more to come to save the caller an additional call
+        match self.caps.lower_bound(&SelRange::new(sel)) {
+            Some(_) => (infos.len(), sel),
+            None => (infos.len(), INVALID_SEL),
+        }
+    }
+
     #[inline(always)]
     pub fn insert(&mut self, cap: Capability) -> Result<(), Error> {
         self.insert_new(cap, None)
@@ -300,10 +321,41 @@ impl Capability {
         &self.obj
     }
 
+    pub fn replace(&mut self, obj: KObject) -> KObject {
+        core::mem::replace(&mut self.obj, obj)
+    }
+
     pub fn has_parent(&self) -> bool {
         self.parent.is_some()
     }
 
+    pub fn info(&self) -> CapInfo {
+        let (parent_act, parent_sel) = match self.parent {
+            Some(p) => unsafe {
+                let parent = &*p.as_ptr();
+                (parent.activity().id(), parent.sel())
+            },
+            None => (0, INVALID_SEL),
+        };
+
+        let mut children = 0;
+        let mut next = self.child;
+        while let Some(n) = next {
+            children += 1;
+            next = unsafe { (*n.as_ptr()).next };
+        }
+
+        CapInfo {
+            sel: self.sel(),
+            count: self.len(),
+            obj: self.obj.obj_type(),
+            derived: self.derived,
+            parent_sel,
+            parent_act,
+            children,
+        }
+    }
+
     pub fn get_root(&mut self) -> &mut Capability {
         if let Some(mut cap) = self.parent {
             unsafe {
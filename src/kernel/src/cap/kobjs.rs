@@ -16,7 +16,7 @@
 use base::cell::{Cell, Ref, RefCell, RefMut, StaticCell};
 use base::errors::{Code, Error};
 use base::goff;
-use base::kif::{self, tilemux::QuotaId};
+use base::kif::{self, syscalls::KObjType, tilemux::QuotaId};
 use base::mem::{size_of, GlobAddr};
 use base::rc::{Rc, SRc, Weak};
 use base::tcu::{EpId, Label, TileId};
@@ -81,6 +81,22 @@ impl KObject {
         let idx: usize = unsafe { *(self as *const _ as *const usize) };
         KOBJ_SIZES[idx]
     }
+
+    pub fn obj_type(&self) -> KObjType {
+        match self {
+            KObject::RGate(_) => KObjType::RGATE,
+            KObject::SGate(_) => KObjType::SGATE,
+            KObject::MGate(_) => KObjType::MGATE,
+            KObject::Map(_) => KObjType::MAP,
+            KObject::Serv(_) => KObjType::SERV,
+            KObject::Sess(_) => KObjType::SESS,
+            KObject::Sem(_) => KObjType::SEM,
+            KObject::Activity(_) => KObjType::ACT,
+            KObject::KMem(_) => KObjType::KMEM,
+            KObject::Tile(_) => KObjType::TILE,
+            KObject::EP(_) => KObjType::EP,
+        }
+    }
 }
 
 impl fmt::Debug for KObject {
@@ -123,6 +139,7 @@ impl GateEP {
     }
 }
 
+#[derive(Clone)]
 pub enum GateObject {
     Recv(SRc<RGateObject>),
     Send(SRc<SGateObject>),
@@ -680,7 +697,7 @@ pub struct EPObject {
     act: Weak<Activity>,
     ep: EpId,
     replies: u32,
-    tile: SRc<TileObject>,
+    tile: RefCell<SRc<TileObject>>,
 }
 
 impl EPObject {
@@ -698,7 +715,7 @@ impl EPObject {
             act,
             ep,
             replies,
-            tile: tile.clone(),
+            tile: RefCell::from(tile.clone()),
         });
         if let Some(v) = maybe_act {
             v.add_ep(ep.clone());
@@ -707,13 +724,21 @@ impl EPObject {
     }
 
     pub fn tile_id(&self) -> TileId {
-        self.tile.tile()
+        self.tile.borrow().tile()
+    }
+
+    pub fn set_tile(&self, tile: &SRc<TileObject>) {
+        self.tile.replace(tile.clone());
     }
 
     pub fn activity(&self) -> Option<Rc<Activity>> {
         self.act.upgrade()
     }
 
+    pub fn is_std(&self) -> bool {
+        self.is_std
+    }
+
     pub fn ep(&self) -> EpId {
         self.ep
     }
@@ -726,6 +751,10 @@ impl EPObject {
         matches!(self.gate.borrow().as_ref(), Some(GateObject::Recv(_)))
     }
 
+    pub fn gate(&self) -> Option<GateObject> {
+        self.gate.borrow().clone()
+    }
+
     pub fn set_gate(&self, g: GateObject) {
         self.gate.replace(Some(g));
     }
@@ -791,9 +820,10 @@ impl EPObject {
 impl Drop for EPObject {
     fn drop(&mut self) {
         if !self.is_std {
-            tilemng::tilemux(self.tile.tile).free_eps(self.ep, 1 + self.replies);
+            let tile = self.tile.borrow();
+            tilemng::tilemux(tile.tile).free_eps(self.ep, 1 + self.replies);
 
-            self.tile.free(1 + self.replies);
+            tile.free(1 + self.replies);
         }
     }
 }
@@ -806,7 +836,7 @@ impl fmt::Debug for EPObject {
             self.activity().unwrap().id(),
             self.ep,
             self.replies,
-            self.tile
+            self.tile.borrow()
         )
     }
 }
@@ -936,6 +966,10 @@ impl MapObject {
         self.flags.get()
     }
 
+    pub fn set_global(&self, glob: GlobAddr) {
+        self.glob.set(glob);
+    }
+
     pub fn map_async(
         &self,
         act: &Activity,
@@ -144,6 +144,10 @@ impl SendQueue {
         self.queue.sender().id
     }
 
+    pub fn set_tile(&mut self, tile: tcu::TileId) {
+        self.queue.sender_mut().tile = tile;
+    }
+
     pub fn send(
         &mut self,
         rep: tcu::EpId,
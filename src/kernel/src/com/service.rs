@@ -66,6 +66,10 @@ impl Service {
         SendQueue::receive_async(event)
     }
 
+    pub fn set_tile(&self, tile: tcu::TileId) {
+        self.queue.borrow_mut().set_tile(tile);
+    }
+
     pub fn abort(&self) {
         self.queue.borrow_mut().abort();
     }
@@ -20,8 +20,8 @@ use base::goff;
 use base::kif;
 use base::mem;
 use base::tcu::{
-    ActId, EpId, ExtCmdOpCode, ExtReg, Header, Label, Message, Reg, TileId, AVAIL_EPS, EP_REGS,
-    PMEM_PROT_EPS, TCU, UNLIM_CREDITS,
+    ActId, EpId, EpType, ExtCmdOpCode, ExtReg, Header, Label, Message, Reg, TileId, AVAIL_EPS,
+    EP_REGS, PMEM_PROT_EPS, TCU, UNLIM_CREDITS,
 };
 
 use crate::platform;
@@ -282,6 +282,37 @@ pub fn glob_to_phys_remote(
     })
 }
 
+pub fn phys_to_glob_remote(tile: TileId, phys: goff) -> Result<mem::GlobAddr, Error> {
+    let phys = phys - cfg::MEM_OFFSET as goff;
+    let ep = (phys >> 30) as EpId;
+    let off = phys & 0x3FFF_FFFF;
+    if ep >= PMEM_PROT_EPS as EpId {
+        return Err(Error::new(Code::InvArgs));
+    }
+
+    let mut regs = [0; 3];
+    read_ep_remote(tile, ep, &mut regs)?;
+    match TCU::unpack_mem_regs(&regs) {
+        Some((mem_tile, addr, size, _)) if off < size => {
+            Ok(mem::GlobAddr::new_with(mem_tile, addr + off))
+        },
+        _ => Err(Error::new(Code::InvArgs)),
+    }
+}
+
+pub fn ep_is_idle_remote(tile: TileId, ep: EpId) -> Result<bool, Error> {
+    let mut regs = [0; EP_REGS];
+    read_ep_remote(tile, ep, &mut regs)?;
+
+    match regs[0] & 0x7 {
+        // no outstanding replies
+        t if t == EpType::SEND.val => Ok(((regs[0] >> 19) & 0x3F) == ((regs[0] >> 25) & 0x3F)),
+        // no occupied or unread slots
+        t if t == EpType::RECEIVE.val => Ok(regs[2] == 0),
+        _ => Ok(true),
+    }
+}
+
 pub fn read_ep_remote(tile: TileId, ep: EpId, regs: &mut [Reg]) -> Result<(), Error> {
     for i in 0..regs.len() {
         try_read_slice(
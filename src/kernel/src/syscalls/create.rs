@@ -18,7 +18,7 @@ use base::cfg;
 use base::col::ToString;
 use base::errors::{Code, VerboseError};
 use base::goff;
-use base::kif::{syscalls, CapRngDesc, CapSel, CapType, PageFlags, Perm};
+use base::kif::{self, syscalls, CapRngDesc, CapSel, CapType, PageFlags, Perm};
 use base::mem::{GlobAddr, MsgBuf};
 use base::rc::Rc;
 use base::tcu;
@@ -254,11 +254,12 @@ pub fn create_activity_async(
     let r: syscalls::CreateActivity<'_> = get_request(msg)?;
     sysc_log!(
         act,
-        "create_activity(dst={}, name={}, tile={}, kmem={})",
+        "create_activity(dst={}, name={}, tile={}, kmem={}, prio={})",
         r.dst,
         r.name,
         r.tile,
-        r.kmem
+        r.kmem,
+        r.prio
     );
 
     if !act
@@ -276,6 +277,18 @@ pub fn create_activity_async(
     if r.name.is_empty() {
         sysc_err!(Code::InvArgs, "Invalid name");
     }
+    if r.prio.val as usize >= kif::PRIO_COUNT {
+        sysc_err!(Code::InvArgs, "Invalid priority {}", r.prio);
+    }
+    // activities must not create activities of a higher class than their own
+    if r.prio > act.prio() {
+        sysc_err!(
+            Code::NoPerm,
+            "Priority {} exceeds own priority {}",
+            r.prio,
+            act.prio()
+        );
+    }
 
     let tile = get_kobj!(act, r.tile, Tile);
     if !tile.has_quota(tcu::STD_EPS_COUNT as u32) {
@@ -303,11 +316,17 @@ pub fn create_activity_async(
     drop(tilemux);
 
     // create activity
-    let nact =
-        match ActivityMng::create_activity_async(r.name, tile, eps, kmem, ActivityFlags::empty()) {
-            Ok(nact) => nact,
-            Err(e) => sysc_err!(e.code(), "Unable to create Activity"),
-        };
+    let nact = match ActivityMng::create_activity_async(
+        r.name,
+        tile,
+        eps,
+        r.prio,
+        kmem,
+        ActivityFlags::empty(),
+    ) {
+        Ok(nact) => nact,
+        Err(e) => sysc_err!(e.code(), "Unable to create Activity"),
+    };
 
     // give activity cap to the parent
     let cap = Capability::new(r.dst, KObject::Activity(Rc::downgrade(&nact)));
@@ -322,7 +341,7 @@ pub fn create_activity_async(
         {
             let scap = Capability::new(
                 r.dst + 1 + i as CapSel,
-                KObject::EP(EPObject::new(true, nact_rc.clone(), *ep, 0, nact.tile())),
+                KObject::EP(EPObject::new(true, nact_rc.clone(), *ep, 0, &nact.tile())),
             );
             try_kmem_quota!(act.obj_caps().borrow_mut().insert_as_child(scap, r.dst));
         }
@@ -372,6 +391,8 @@ pub fn create_map_async(
     );
 
     let dst_act = get_kobj!(act, r.act, Activity).upgrade().unwrap();
+    // the mapping has to be established on the tile the activity ends up on
+    dst_act.wait_for_migration_async();
     if !platform::tile_desc(dst_act.tile_id()).has_virtmem() {
         sysc_err!(Code::InvArgs, "Tile has no virtual-memory support");
     }
@@ -95,7 +95,7 @@ pub fn alloc_ep(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), Ve
             Rc::downgrade(&dst_act),
             epid,
             r.replies,
-            dst_act.tile(),
+            &dst_act.tile(),
         )),
     );
     try_kmem_quota!(act.obj_caps().borrow_mut().insert_as_child(cap, r.act));
@@ -610,6 +610,29 @@ pub fn activity_ctrl_async(
             }
         },
 
+        kif::syscalls::ActivityOp::SUSPEND => {
+            if let Err(e) = actcap.suspend_app_async() {
+                sysc_err!(e.code(), "Unable to suspend Activity");
+            }
+        },
+
+        kif::syscalls::ActivityOp::RESUME => {
+            if let Err(e) = actcap.resume_app_async() {
+                sysc_err!(e.code(), "Unable to resume Activity");
+            }
+        },
+
+        kif::syscalls::ActivityOp::MIGRATE => {
+            if Rc::ptr_eq(act, &actcap) {
+                sysc_err!(Code::InvArgs, "Activity can't migrate itself");
+            }
+
+            let tile = get_kobj!(act, r.arg as kif::CapSel, Tile);
+            if let Err(e) = actcap.migrate_app_async(tile) {
+                sysc_err!(e.code(), "Unable to migrate Activity");
+            }
+        },
+
         _ => sysc_err!(Code::InvArgs, "ActivityOp unsupported: {:?}", r.op),
     };
 
@@ -662,6 +685,63 @@ pub fn reset_stats(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(),
     Ok(())
 }
 
+#[inline(never)]
+pub fn list_caps(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), VerboseError> {
+    let r: syscalls::ListCaps = get_request(msg)?;
+    sysc_log!(
+        act,
+        "list_caps(act={}, type={}, start={})",
+        r.act,
+        r.ty,
+        r.start
+    );
+
+    // the activity capability is the permission to inspect the capabilities of that activity
+    let actcap = get_kobj!(act, r.act, Activity).upgrade().unwrap();
+
+    let mut reply_msg = syscalls::ListCapsReply {
+        count: 0,
+        next: kif::INVALID_SEL,
+        caps: [syscalls::CapInfo::default(); syscalls::MAX_CAP_INFOS],
+    };
+
+    let table = match r.ty {
+        kif::CapType::OBJECT => actcap.obj_caps().borrow(),
+        _ => actcap.map_caps().borrow(),
+    };
+    let (count, next) = table.list(r.start, &mut reply_msg.caps);
+    reply_msg.count = count;
+    reply_msg.next = next;
+
+    let mut kreply = MsgBuf::borrow_def();
+    build_vmsg!(kreply, Code::Success, reply_msg);
+    send_reply(msg, &kreply);
+
+    Ok(())
+}
+
+#[inline(never)]
+pub fn activity_stats_async(
+    act: &Rc<Activity>,
+    msg: &'static tcu::Message,
+) -> Result<(), VerboseError> {
+    let r: syscalls::ActStats = get_request(msg)?;
+    sysc_log!(act, "activity_stats(act={})", r.act);
+
+    let actcap = get_kobj!(act, r.act, Activity).upgrade().unwrap();
+
+    let stats = match actcap.stats_async() {
+        Ok(stats) => stats,
+        Err(e) => sysc_err!(e.code(), "Unable to get stats of Activity {}", actcap.id()),
+    };
+
+    let mut kreply = MsgBuf::borrow_def();
+    build_vmsg!(kreply, Code::Success, stats);
+    send_reply(msg, &kreply);
+
+    Ok(())
+}
+
 pub fn noop(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), VerboseError> {
     sysc_log!(act, "noop()",);
 
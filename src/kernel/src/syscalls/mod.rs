@@ -148,6 +148,8 @@ pub fn handle_async(msg: &'static tcu::Message) {
 
         kif::syscalls::Operation::RESET_STATS => misc::reset_stats(&act, msg),
         kif::syscalls::Operation::NOOP => misc::noop(&act, msg),
+        kif::syscalls::Operation::LIST_CAPS => misc::list_caps(&act, msg),
+        kif::syscalls::Operation::ACT_STATS => misc::activity_stats_async(&act, msg),
 
         _ => panic!("Unexpected operation: {}", opcode),
     };
@@ -19,7 +19,7 @@ use base::cell::{Cell, RefCell, StaticRefCell};
 use base::col::{String, ToString, Vec};
 use base::errors::{Code, Error};
 use base::goff;
-use base::kif::{self, CapRngDesc, CapSel, CapType, TileDesc};
+use base::kif::{self, CapRngDesc, CapSel, CapType, Priority, TileDesc};
 use base::mem::MsgBuf;
 use base::rc::{Rc, SRc};
 use base::tcu::Label;
@@ -32,7 +32,7 @@ use crate::com::{QueueId, SendQueue};
 use crate::ktcu;
 use crate::platform;
 use crate::thread_startup;
-use crate::tiles::{loader, tilemng, ActivityMng};
+use crate::tiles::{loader, migration, tilemng, ActivityMng, TileMux};
 
 bitflags! {
     pub struct ActivityFlags : u32 {
@@ -44,6 +44,7 @@ bitflags! {
 pub enum State {
     INIT,
     RUNNING,
+    SUSPENDED,
     DEAD,
 }
 
@@ -64,12 +65,15 @@ pub struct Activity {
     name: String,
     flags: ActivityFlags,
     eps_start: EpId,
+    prio: Priority,
 
-    tile: SRc<TileObject>,
+    tile: RefCell<SRc<TileObject>>,
     kmem: SRc<KMemObject>,
 
     state: Cell<State>,
+    migrating: Cell<bool>,
     exit_code: Cell<Option<Code>>,
+    final_stats: Cell<Option<kif::ActivityStats>>,
     first_sel: Cell<CapSel>,
 
     obj_caps: RefCell<CapTable>,
@@ -86,6 +90,7 @@ impl Activity {
         id: ActId,
         tile: SRc<TileObject>,
         eps_start: EpId,
+        prio: Priority,
         kmem: SRc<KMemObject>,
         flags: ActivityFlags,
     ) -> Result<Rc<Self>, Error> {
@@ -94,16 +99,19 @@ impl Activity {
             name: name.to_string(),
             flags,
             eps_start,
+            prio,
             kmem,
             state: Cell::from(State::INIT),
+            migrating: Cell::from(false),
             exit_code: Cell::from(None),
+            final_stats: Cell::from(None),
             first_sel: Cell::from(kif::FIRST_FREE_SEL),
             obj_caps: RefCell::from(CapTable::default()),
             map_caps: RefCell::from(CapTable::default()),
             eps: RefCell::from(Vec::new()),
             rbuf_phys: Cell::from(0),
             upcalls: RefCell::from(SendQueue::new(QueueId::Activity(id), tile.tile())),
-            tile,
+            tile: RefCell::from(tile),
         });
 
         {
@@ -116,10 +124,9 @@ impl Activity {
                 KObject::KMem(act.kmem.clone()),
             ))?;
             // tile cap
-            act.obj_caps().borrow_mut().insert(Capability::new(
-                kif::SEL_TILE,
-                KObject::Tile(act.tile.clone()),
-            ))?;
+            act.obj_caps()
+                .borrow_mut()
+                .insert(Capability::new(kif::SEL_TILE, KObject::Tile(act.tile())))?;
             // cap for own activity
             act.obj_caps().borrow_mut().insert(Capability::new(
                 kif::SEL_ACT,
@@ -128,10 +135,10 @@ impl Activity {
 
             // alloc standard EPs
             tilemng::tilemux(act.tile_id()).alloc_eps(eps_start, STD_EPS_COUNT as u32);
-            act.tile.alloc(STD_EPS_COUNT as u32);
+            act.tile().alloc(STD_EPS_COUNT as u32);
 
             // add us to tile
-            act.tile.add_activity();
+            act.tile().add_activity();
         }
 
         // some system calls are blocking, leading to a thread switch in the kernel. there is just
@@ -151,7 +158,7 @@ impl Activity {
         }
     }
 
-    fn init_eps_async(&self) -> Result<(), Error> {
+    pub fn init_eps_async(&self) -> Result<(), Error> {
         use crate::cap::{RGateObject, SGateObject};
         use base::cfg;
         use base::kif::PageFlags;
@@ -234,12 +241,17 @@ impl Activity {
         self.id
     }
 
-    pub fn tile(&self) -> &SRc<TileObject> {
-        &self.tile
+    pub fn tile(&self) -> SRc<TileObject> {
+        self.tile.borrow().clone()
     }
 
     pub fn tile_id(&self) -> TileId {
-        self.tile.tile()
+        self.tile.borrow().tile()
+    }
+
+    pub fn set_tile(&self, tile: SRc<TileObject>) {
+        self.upcalls.borrow_mut().set_tile(tile.tile());
+        self.tile.replace(tile);
     }
 
     pub fn tile_desc(&self) -> TileDesc {
@@ -258,6 +270,10 @@ impl Activity {
         self.eps_start
     }
 
+    pub fn prio(&self) -> Priority {
+        self.prio
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -290,6 +306,10 @@ impl Activity {
         self.exit_code.replace(None)
     }
 
+    pub fn eps(&self) -> &RefCell<Vec<Rc<EPObject>>> {
+        &self.eps
+    }
+
     pub fn add_ep(&self, ep: Rc<EPObject>) {
         self.eps.borrow_mut().push(ep);
     }
@@ -340,7 +360,7 @@ impl Activity {
             }
 
             // if we want to be notified by upcall, don't wait, just stop here
-            if event != 0 || self.state() != State::RUNNING {
+            if event != 0 || !matches!(self.state(), State::RUNNING | State::SUSPENDED) {
                 break None;
             }
 
@@ -443,6 +463,88 @@ impl Activity {
         ActivityMng::start_activity_async(self)
     }
 
+    pub fn suspend_app_async(&self) -> Result<(), Error> {
+        if self.state.get() != State::RUNNING || self.migrating.get() {
+            return Err(Error::new(Code::InvState));
+        }
+
+        klog!(
+            ACTIVITIES,
+            "Suspending Activity {} [id={}]",
+            self.name(),
+            self.id()
+        );
+
+        ActivityMng::suspend_activity_async(self)?;
+
+        // the activity might have been stopped or changed otherwise in the meantime
+        if self.state.get() != State::RUNNING {
+            return Err(Error::new(Code::InvState));
+        }
+        self.state.set(State::SUSPENDED);
+        Ok(())
+    }
+
+    pub fn resume_app_async(&self) -> Result<(), Error> {
+        if self.state.get() != State::SUSPENDED || self.migrating.get() {
+            return Err(Error::new(Code::InvState));
+        }
+
+        klog!(
+            ACTIVITIES,
+            "Resuming Activity {} [id={}]",
+            self.name(),
+            self.id()
+        );
+
+        ActivityMng::resume_activity_async(self)?;
+
+        // the activity might have been stopped or changed otherwise in the meantime
+        if self.state.get() != State::SUSPENDED {
+            return Err(Error::new(Code::InvState));
+        }
+        self.state.set(State::RUNNING);
+        Ok(())
+    }
+
+    pub fn migrate_app_async(&self, tile: SRc<TileObject>) -> Result<(), Error> {
+        if !matches!(self.state.get(), State::RUNNING | State::SUSPENDED) || self.migrating.get() {
+            return Err(Error::new(Code::InvState));
+        }
+        if self.is_root() {
+            return Err(Error::new(Code::NotSup));
+        }
+
+        klog!(
+            ACTIVITIES,
+            "Migrating Activity {} [id={}] from tile {} to tile {}",
+            self.name(),
+            self.id(),
+            self.tile_id(),
+            tile.tile()
+        );
+
+        self.migrating.set(true);
+        let res = migration::migrate_async(self, tile);
+        self.migrating.set(false);
+        thread::notify(self.migration_event(), None);
+        res
+    }
+
+    /// Waits until the currently running migration of this activity, if any, is finished.
+    ///
+    /// The migration copies the address space, which is built by the pager via map capabilities,
+    /// to the destination tile. Thus, new mappings need to wait until the migration is done.
+    pub fn wait_for_migration_async(&self) {
+        while self.migrating.get() {
+            thread::wait_for(self.migration_event());
+        }
+    }
+
+    fn migration_event(&self) -> thread::Event {
+        &self.migrating as *const _ as thread::Event
+    }
+
     pub fn stop_app_async(&self, exit_code: Code, is_self: bool) {
         if self.state.get() == State::DEAD {
             return;
@@ -458,7 +560,7 @@ impl Activity {
         if is_self {
             self.exit_app_async(exit_code, false);
         }
-        else if self.state.get() == State::RUNNING {
+        else if matches!(self.state.get(), State::RUNNING | State::SUSPENDED) {
             // devices always exit successfully
             let exit_code = if self.tile_desc().is_device() {
                 Code::Success
@@ -475,7 +577,31 @@ impl Activity {
         }
     }
 
+    pub fn stats_async(&self) -> Result<kif::ActivityStats, Error> {
+        // once the activity is gone in TileMux, report the stats it had at that point
+        if let Some(stats) = self.final_stats.get() {
+            return Ok(stats);
+        }
+        if self.state.get() == State::DEAD {
+            return Err(Error::new(Code::NotFound));
+        }
+        if !self.tile_desc().supports_tilemux() {
+            return Err(Error::new(Code::NotSup));
+        }
+
+        TileMux::activity_stats_async(tilemng::tilemux(self.tile_id()), self.id())
+    }
+
     fn exit_app_async(&self, exit_code: Code, stop: bool) {
+        // remember the stats before TileMux forgets about the activity
+        if self.final_stats.get().is_none() && self.tile_desc().supports_tilemux() {
+            if let Ok(stats) =
+                TileMux::activity_stats_async(tilemng::tilemux(self.tile_id()), self.id())
+            {
+                self.final_stats.set(Some(stats));
+            }
+        }
+
         let mut tilemux = tilemng::tilemux(self.tile_id());
         // force-invalidate standard EPs
         for ep in self.eps_start..self.eps_start + STD_EPS_COUNT as EpId {
@@ -536,10 +662,10 @@ impl Drop for Activity {
 
         // free standard EPs
         tilemng::tilemux(self.tile_id()).free_eps(self.eps_start, STD_EPS_COUNT as u32);
-        self.tile.free(STD_EPS_COUNT as u32);
+        self.tile().free(STD_EPS_COUNT as u32);
 
         // remove us from tile
-        self.tile.rem_activity();
+        self.tile().rem_activity();
 
         assert!(self.obj_caps.borrow().is_empty());
         assert!(self.map_caps.borrow().is_empty());
@@ -57,6 +57,10 @@ impl ActivityMng {
         INST.borrow().count
     }
 
+    pub fn activities() -> Vec<Rc<Activity>> {
+        INST.borrow().acts.iter().flatten().cloned().collect()
+    }
+
     #[inline(always)]
     pub fn activity(id: tcu::ActId) -> Option<Rc<Activity>> {
         INST.borrow().acts[id as usize].as_ref().cloned()
@@ -85,20 +89,22 @@ impl ActivityMng {
         name: &str,
         tile: SRc<TileObject>,
         eps_start: tcu::EpId,
+        prio: kif::Priority,
         kmem: SRc<KMemObject>,
         flags: ActivityFlags,
     ) -> Result<Rc<Activity>, Error> {
         let id: tcu::ActId = Self::get_id()?;
         let tile_id = tile.tile();
 
-        let act = Activity::new(name, id, tile, eps_start, kmem, flags)?;
+        let act = Activity::new(name, id, tile, eps_start, prio, kmem, flags)?;
 
         klog!(
             ACTIVITIES,
-            "Created Activity {} [id={}, tile={}]",
+            "Created Activity {} [id={}, tile={}, prio={}]",
             name,
             id,
-            tile_id
+            tile_id,
+            prio
         );
 
         let clone = act.clone();
@@ -123,6 +129,7 @@ impl ActivityMng {
                 act.id(),
                 act.tile().time_quota_id(),
                 act.tile().pt_quota_id(),
+                act.prio(),
                 act.eps_start(),
             )?;
         }
@@ -143,6 +150,31 @@ impl ActivityMng {
         }
     }
 
+    pub fn suspend_activity_async(act: &Activity) -> Result<(), Error> {
+        // without TileMux, there is nobody that could stop scheduling the activity
+        if !platform::tile_desc(act.tile_id()).supports_tilemux() {
+            return Err(Error::new(Code::NotSup));
+        }
+
+        TileMux::activity_ctrl_async(
+            tilemng::tilemux(act.tile_id()),
+            act.id(),
+            kif::tilemux::ActivityOp::SUSPEND,
+        )
+    }
+
+    pub fn resume_activity_async(act: &Activity) -> Result<(), Error> {
+        if !platform::tile_desc(act.tile_id()).supports_tilemux() {
+            return Err(Error::new(Code::NotSup));
+        }
+
+        TileMux::activity_ctrl_async(
+            tilemng::tilemux(act.tile_id()),
+            act.id(),
+            kif::tilemux::ActivityOp::RESUME,
+        )
+    }
+
     pub fn stop_activity_async(act: &Activity, stop: bool, reset: bool) -> Result<(), Error> {
         if stop && platform::tile_desc(act.tile_id()).supports_tilemux() {
             TileMux::activity_ctrl_async(
@@ -176,6 +208,8 @@ impl ActivityMng {
             "root",
             tile,
             tcu::FIRST_USER_EP,
+            // root can hand out all priority classes to its children
+            kif::Priority::HIGH,
             kmem,
             ActivityFlags::IS_ROOT,
         )
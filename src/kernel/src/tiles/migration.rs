@@ -0,0 +1,451 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Moves an activity between two tiles of the same type.
+//!
+//! The migration happens in two phases. In the first phase, the activity is suspended on the source
+//! tile and a copy of it is created on the destination tile, including the address space, the
+//! memory owned by TileMux (environment), and the execution state. The address space is built by
+//! the activity's pager via map capabilities, from which it is copied. The pager's requests to
+//! change mappings wait until the migration is finished. Since messages cannot be moved,
+//! the activity's endpoints on the source tile are invalidated in this phase as well, so that
+//! senders cannot deliver messages anymore. Until then, everything can be undone by removing the
+//! copy on the destination tile and configuring the endpoints on the source tile again. In the
+//! second phase, the activity's endpoints are moved over to the destination tile and the activity
+//! is removed from the source tile.
+
+use base::cfg::{ENV_SIZE, ENV_START, PAGE_BITS, PAGE_MASK, PAGE_SIZE, RBUF_STD_SIZE};
+use base::col::Vec;
+use base::env;
+use base::errors::{Code, Error};
+use base::goff;
+use base::kif::{self, CapSel, PageFlags};
+use base::mem::GlobAddr;
+use base::rc::SRc;
+use base::tcu::{ActId, EpId, TileId, STD_EPS_COUNT};
+
+use crate::cap::{CapTable, Capability, GateObject, KObject, RGateObject, TileObject};
+use crate::ktcu;
+use crate::platform;
+use crate::tiles::{tilemng, Activity, ActivityMng, State, TileMux};
+
+pub fn migrate_async(act: &Activity, tile: SRc<TileObject>) -> Result<(), Error> {
+    let src = act.tile_id();
+    let dst = tile.tile();
+    let eps = check_migration(act, &tile)?;
+
+    let running = act.state() == State::RUNNING;
+    if running {
+        ActivityMng::suspend_activity_async(act)?;
+    }
+
+    let state = match save_async(act, src, &eps) {
+        Ok(state) => state,
+        Err(e) => {
+            if running {
+                ActivityMng::resume_activity_async(act).ok();
+            }
+            return Err(e);
+        },
+    };
+
+    if let Err(e) = prepare_async(act, &tile, state) {
+        klog!(
+            ACTIVITIES,
+            "Migration of Activity {} to tile {} failed: {:?}",
+            act.id(),
+            dst,
+            e.code()
+        );
+        undo_async(act, dst, running);
+        return Err(e);
+    }
+
+    // the activity might have been killed in the meantime
+    if act.state() == State::DEAD {
+        undo_async(act, dst, false);
+        return Err(Error::new(Code::ActivityGone));
+    }
+
+    commit_async(act, tile, &eps, running)
+}
+
+fn check_migration(act: &Activity, tile: &SRc<TileObject>) -> Result<Vec<(EpId, u32)>, Error> {
+    let src_desc = platform::tile_desc(act.tile_id());
+    let dst_desc = platform::tile_desc(tile.tile());
+
+    if act.tile_id() == tile.tile() {
+        return Err(Error::new(Code::InvArgs));
+    }
+    if !src_desc.has_virtmem() || !dst_desc.has_virtmem() {
+        return Err(Error::new(Code::NotSup));
+    }
+    if src_desc.isa() != dst_desc.isa() || src_desc.tile_type() != dst_desc.tile_type() {
+        return Err(Error::new(Code::InvArgs));
+    }
+
+    // the activity keeps its EP ids, so that all of them need to be available on the destination
+    let mut eps = Vec::new();
+    eps.push((act.eps_start(), STD_EPS_COUNT as u32));
+    for ep in act.eps().borrow().iter().filter(|ep| !ep.is_std()) {
+        eps.push((ep.ep(), 1 + ep.replies()));
+    }
+
+    let total = eps.iter().map(|(_, count)| count).sum();
+    if !tile.has_quota(total) {
+        return Err(Error::new(Code::NoSpace));
+    }
+    let tilemux = tilemng::tilemux(tile.tile());
+    if !eps.iter().all(|(ep, count)| tilemux.eps_free(*ep, *count)) {
+        return Err(Error::new(Code::NoSpace));
+    }
+
+    // memory gates to the address space are based on tile-local physical addresses
+    let mut deps = false;
+    for_each_cap(&act.map_caps().borrow(), |cap| {
+        deps |= cap.info().children > 0;
+    });
+    if deps {
+        return Err(Error::new(Code::InvState));
+    }
+
+    Ok(eps)
+}
+
+fn save_async(
+    act: &Activity,
+    src: TileId,
+    eps: &[(EpId, u32)],
+) -> Result<(GlobAddr, usize), Error> {
+    // outstanding messages and replies cannot be moved to the destination tile
+    for (start, count) in eps {
+        for ep in *start..*start + *count as EpId {
+            if !ktcu::ep_is_idle_remote(src, ep)? {
+                return Err(Error::new(Code::InvState));
+            }
+        }
+    }
+
+    // messages might arrive until the EPs are invalid. Therefore, invalidate them without force,
+    // which atomically reports messages that arrived since the check above. From now on, senders
+    // get an error instead of placing messages into the source tile.
+    let res = invalidate_eps(act, src, eps)
+        .and_then(|_| TileMux::activity_save_async(tilemng::tilemux(src), act.id()));
+    if res.is_err() {
+        restore_eps_async(act).ok();
+    }
+    res
+}
+
+fn invalidate_eps(act: &Activity, src: TileId, eps: &[(EpId, u32)]) -> Result<(), Error> {
+    for (start, count) in eps {
+        for ep in *start..*start + *count as EpId {
+            klog!(EPS, "{}:EP{} = invalid", src, ep);
+            let unread = ktcu::invalidate_ep_remote(src, ep, false)?;
+            if unread != 0 {
+                klog!(
+                    ACTIVITIES,
+                    "Migration of Activity {} failed: EP{} received messages",
+                    act.id(),
+                    ep
+                );
+                return Err(Error::new(Code::InvState));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Configures the EPs of `act` on its current tile again after they have been invalidated
+fn restore_eps_async(act: &Activity) -> Result<(), Error> {
+    act.init_eps_async()?;
+
+    let tile = act.tile_id();
+    let gated: Vec<_> = act
+        .eps()
+        .borrow()
+        .iter()
+        .filter_map(|ep| ep.gate().map(|g| (ep.ep(), ep.replies(), g)))
+        .collect();
+    for (ep, replies, gate) in gated {
+        let mut mux = tilemng::tilemux(tile);
+        match gate {
+            GateObject::Send(sg) => mux.config_snd_ep(ep, act.id(), &sg)?,
+            GateObject::Mem(mg) => mux.config_mem_ep(ep, act.id(), &mg, mg.tile_id())?,
+            GateObject::Recv(rg) => {
+                let reply_eps = if replies > 0 { Some(ep + 1) } else { None };
+                mux.config_rcv_ep(ep, act.id(), reply_eps, &rg)?;
+            },
+        }
+    }
+    Ok(())
+}
+
+fn prepare_async(
+    act: &Activity,
+    tile: &SRc<TileObject>,
+    (src_state, src_size): (GlobAddr, usize),
+) -> Result<(), Error> {
+    let src = act.tile_id();
+    let dst = tile.tile();
+
+    tilemng::tilemux(dst).add_activity(act.id());
+    TileMux::activity_init_async(
+        tilemng::tilemux(dst),
+        act.id(),
+        tile.time_quota_id(),
+        tile.pt_quota_id(),
+        act.prio(),
+        act.eps_start(),
+    )?;
+
+    // rebuild the address space on the destination tile
+    let mut maps = Vec::new();
+    for_each_cap(&act.map_caps().borrow(), |cap| {
+        if let KObject::Map(m) = cap.get() {
+            if m.mapped() {
+                maps.push((cap.sel(), cap.len(), m.global(), m.flags()));
+            }
+        }
+    });
+    for (sel, pages, global, flags) in maps {
+        TileMux::map_async(
+            tilemng::tilemux(dst),
+            act.id(),
+            (sel as goff) << PAGE_BITS,
+            global,
+            pages as usize,
+            flags,
+        )?;
+    }
+
+    // copy the environment, which is owned by TileMux
+    let env_page = (ENV_START & !PAGE_MASK) as goff;
+    for off in (0..ENV_SIZE as goff).step_by(PAGE_SIZE) {
+        let src_page = translate_async(src, act.id(), env_page + off)?;
+        let dst_page = translate_async(dst, act.id(), env_page + off)?;
+        ktcu::copy(
+            dst_page.tile(),
+            dst_page.offset(),
+            src_page.tile(),
+            src_page.offset(),
+            PAGE_SIZE,
+        )?;
+    }
+
+    // tell the activity where it lives now
+    let env_addr =
+        translate_async(dst, act.id(), ENV_START as goff)? + (ENV_START & PAGE_MASK) as goff;
+    let mut senv: env::BaseEnv = ktcu::try_read_obj(env_addr.tile(), env_addr.offset())?;
+    senv.boot.tile_id = dst.raw() as u64;
+    senv.boot.tile_desc = platform::tile_desc(dst).value();
+    ktcu::try_write_slice(env_addr.tile(), env_addr.offset(), &[senv])?;
+
+    // finally, transfer the execution state
+    let (dst_state, dst_size) = TileMux::activity_restore_async(tilemng::tilemux(dst), act.id())?;
+    if src_size != dst_size {
+        return Err(Error::new(Code::InvState));
+    }
+    ktcu::copy(
+        dst_state.tile(),
+        dst_state.offset(),
+        src_state.tile(),
+        src_state.offset(),
+        src_size,
+    )
+}
+
+fn undo_async(act: &Activity, dst: TileId, resume: bool) {
+    TileMux::activity_ctrl_async(
+        tilemng::tilemux(dst),
+        act.id(),
+        kif::tilemux::ActivityOp::STOP,
+    )
+    .ok();
+    tilemng::tilemux(dst).rem_activity(act.id());
+
+    // the activity stays on the source tile
+    if act.state() != State::DEAD {
+        restore_eps_async(act).ok();
+    }
+    if resume {
+        ActivityMng::resume_activity_async(act).ok();
+    }
+}
+
+fn commit_async(
+    act: &Activity,
+    tile: SRc<TileObject>,
+    eps: &[(EpId, u32)],
+    resume: bool,
+) -> Result<(), Error> {
+    let src = act.tile_id();
+    let dst = tile.tile();
+    let old_tile = act.tile();
+    let old_rbuf = act.rbuf_addr();
+
+    // the EPs on the source tile have been invalidated while saving the state
+    {
+        let mut src_mux = tilemng::tilemux(src);
+        for (start, count) in eps {
+            src_mux.free_eps(*start, *count);
+        }
+        src_mux.rem_activity(act.id());
+    }
+    {
+        let mut dst_mux = tilemng::tilemux(dst);
+        for (start, count) in eps {
+            dst_mux.alloc_eps(*start, *count);
+        }
+    }
+
+    let total = eps.iter().map(|(_, count)| count).sum();
+    old_tile.free(total);
+    old_tile.rem_activity();
+    tile.alloc(total);
+    tile.add_activity();
+
+    act.set_tile(tile.clone());
+    for ep in act.eps().borrow().iter() {
+        ep.set_tile(&tile);
+    }
+
+    // let the activity's own tile capability refer to the new tile, unless it has been used to
+    // derive other tile capabilities
+    if let Some(cap) = act.obj_caps().borrow_mut().get_mut(kif::SEL_TILE) {
+        if cap.info().children == 0 && matches!(cap.get(), KObject::Tile(_)) {
+            cap.replace(KObject::Tile(tile));
+        }
+    }
+
+    // retarget the services of the activity
+    for_each_cap(&act.obj_caps().borrow(), |cap| {
+        if let KObject::Serv(s) = cap.get() {
+            if s.service().activity().id() == act.id() {
+                s.service().set_tile(dst);
+            }
+        }
+    });
+
+    // the state on the source tile is not needed anymore
+    TileMux::activity_ctrl_async(
+        tilemng::tilemux(src),
+        act.id(),
+        kif::tilemux::ActivityOp::STOP,
+    )
+    .ok();
+
+    act.init_eps_async()?;
+
+    // reconfigure all EPs that have a gate attached
+    let gated: Vec<_> = act
+        .eps()
+        .borrow()
+        .iter()
+        .filter_map(|ep| ep.gate().map(|g| (ep.ep(), ep.replies(), g)))
+        .collect();
+    for (ep, replies, gate) in gated {
+        let mut dst_mux = tilemng::tilemux(dst);
+        match gate {
+            GateObject::Send(sg) => dst_mux.config_snd_ep(ep, act.id(), &sg)?,
+            GateObject::Mem(mg) => dst_mux.config_mem_ep(ep, act.id(), &mg, mg.tile_id())?,
+            GateObject::Recv(rg) => {
+                let addr = if rg.addr() >= old_rbuf && rg.addr() < old_rbuf + RBUF_STD_SIZE as goff
+                {
+                    act.rbuf_addr() + (rg.addr() - old_rbuf)
+                }
+                else {
+                    let glob = ktcu::phys_to_glob_remote(src, rg.addr())?;
+                    ktcu::glob_to_phys_remote(dst, glob, PageFlags::RW)?
+                };
+                let reply_eps = if replies > 0 { Some(ep + 1) } else { None };
+
+                rg.activate(dst, ep, addr);
+                dst_mux.config_rcv_ep(ep, act.id(), reply_eps, &rg)?;
+                drop(dst_mux);
+
+                retarget_senders(&rg);
+            },
+        }
+    }
+
+    // the environment has moved as well
+    let mut sels = Vec::new();
+    for_each_cap(&act.map_caps().borrow(), |cap| {
+        if let KObject::Map(m) = cap.get() {
+            if !m.mapped() {
+                sels.push(cap.sel());
+            }
+        }
+    });
+    for sel in sels {
+        let glob = translate_async(dst, act.id(), (sel as goff) << PAGE_BITS)?;
+        if let Some(KObject::Map(m)) = act.map_caps().borrow().get(sel).map(|c| c.get()) {
+            m.set_global(glob);
+        }
+    }
+
+    klog!(
+        ACTIVITIES,
+        "Migrated Activity {} [id={}] from tile {} to tile {}",
+        act.name(),
+        act.id(),
+        src,
+        dst
+    );
+
+    if resume {
+        ActivityMng::resume_activity_async(act)?;
+    }
+    Ok(())
+}
+
+fn retarget_senders(rgate: &SRc<RGateObject>) {
+    for owner in ActivityMng::activities() {
+        for ep in owner.eps().borrow().iter() {
+            if let Some(GateObject::Send(sg)) = ep.gate() {
+                if core::ptr::eq(&**sg.rgate(), &**rgate) {
+                    tilemng::tilemux(ep.tile_id())
+                        .config_snd_ep(ep.ep(), owner.id(), &sg)
+                        .ok();
+                }
+            }
+        }
+    }
+}
+
+fn translate_async(tile: TileId, act: ActId, virt: goff) -> Result<GlobAddr, Error> {
+    TileMux::translate_async(tilemng::tilemux(tile), act, virt, PageFlags::R)
+}
+
+fn for_each_cap<F>(table: &CapTable, mut func: F)
+where
+    F: FnMut(&Capability),
+{
+    let mut infos = [kif::syscalls::CapInfo::default(); 8];
+    let mut sel: CapSel = 0;
+    loop {
+        let (count, next) = table.list(sel, &mut infos);
+        for info in &infos[0..count] {
+            if let Some(cap) = table.get(info.sel) {
+                func(cap);
+            }
+        }
+        if next == kif::INVALID_SEL {
+            break;
+        }
+        sel = next;
+    }
+}
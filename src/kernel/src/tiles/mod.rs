@@ -19,6 +19,7 @@
 mod activities;
 mod actmng;
 pub mod loader;
+mod migration;
 pub mod tilemng;
 mod tilemux;
 
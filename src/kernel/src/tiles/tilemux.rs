@@ -23,6 +23,7 @@ use base::mem::GlobAddr;
 use base::mem::MsgBuf;
 use base::quota;
 use base::rc::{Rc, SRc, Weak};
+use base::serialize::Deserialize;
 use base::tcu::{self, ActId, EpId, TileId};
 use core::cmp;
 
@@ -396,6 +397,7 @@ impl TileMux {
         act: ActId,
         time_quota: quota::Id,
         pt_quota: quota::Id,
+        prio: kif::Priority,
         eps_start: EpId,
     ) -> Result<(), Error> {
         let mut buf = MsgBuf::borrow_def();
@@ -403,6 +405,7 @@ impl TileMux {
             act_id: act as u64,
             time_quota,
             pt_quota,
+            prio,
             eps_start,
         };
         build_vmsg!(buf, kif::tilemux::Sidecalls::ACT_INIT, &msg);
@@ -427,6 +430,43 @@ impl TileMux {
             .map(|_| ())
     }
 
+    pub fn activity_stats_async(
+        tilemux: RefMut<'_, Self>,
+        act: ActId,
+    ) -> Result<kif::ActivityStats, Error> {
+        let mut buf = MsgBuf::borrow_def();
+        let msg = kif::tilemux::ActStats { act_id: act as u64 };
+        build_vmsg!(buf, kif::tilemux::Sidecalls::ACT_STATS, &msg);
+
+        Self::send_receive_sidecall_with_async::<kif::tilemux::ActStats, kif::ActivityStats>(
+            tilemux, None, buf, &msg,
+        )
+    }
+
+    pub fn activity_save_async(
+        tilemux: RefMut<'_, Self>,
+        act: ActId,
+    ) -> Result<(GlobAddr, usize), Error> {
+        let mut buf = MsgBuf::borrow_def();
+        let msg = kif::tilemux::ActSave { act_id: act as u64 };
+        build_vmsg!(buf, kif::tilemux::Sidecalls::ACT_SAVE, &msg);
+
+        Self::send_receive_sidecall_async::<kif::tilemux::ActSave>(tilemux, None, buf, &msg)
+            .map(|r| (GlobAddr::new(r.val1), r.val2 as usize))
+    }
+
+    pub fn activity_restore_async(
+        tilemux: RefMut<'_, Self>,
+        act: ActId,
+    ) -> Result<(GlobAddr, usize), Error> {
+        let mut buf = MsgBuf::borrow_def();
+        let msg = kif::tilemux::ActRestore { act_id: act as u64 };
+        build_vmsg!(buf, kif::tilemux::Sidecalls::ACT_RESTORE, &msg);
+
+        Self::send_receive_sidecall_async::<kif::tilemux::ActRestore>(tilemux, None, buf, &msg)
+            .map(|r| (GlobAddr::new(r.val1), r.val2 as usize))
+    }
+
     pub fn derive_quota_async(
         tilemux: RefMut<'_, Self>,
         parent_time: quota::Id,
@@ -592,11 +632,20 @@ impl TileMux {
     }
 
     fn send_receive_sidecall_async<R: core::fmt::Debug>(
-        mut tilemux: RefMut<'_, Self>,
+        tilemux: RefMut<'_, Self>,
         act: Option<ActId>,
         req: base::mem::MsgBufRef<'_>,
         msg: &R,
     ) -> Result<kif::tilemux::Response, Error> {
+        Self::send_receive_sidecall_with_async::<R, kif::tilemux::Response>(tilemux, act, req, msg)
+    }
+
+    fn send_receive_sidecall_with_async<R: core::fmt::Debug, T: Deserialize<'static>>(
+        mut tilemux: RefMut<'_, Self>,
+        act: Option<ActId>,
+        req: base::mem::MsgBufRef<'_>,
+        msg: &R,
+    ) -> Result<T, Error> {
         use crate::com::SendQueue;
 
         let event = tilemux.send_sidecall::<R>(act, &req, msg)?;
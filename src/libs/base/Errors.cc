@@ -94,6 +94,13 @@ static const char *errmsgs[] = {
     "Socket is closed",
     "Connection failed",
     "Connection closed gracefully",
+
+    /* Symbolic links */
+    "Too many levels of symbolic links",
+    "Symbolic link leaves the filesystem",
+
+    /* File system integrity */
+    "Data is corrupted",
 };
 
 const char *Errors::to_string(Code code) {
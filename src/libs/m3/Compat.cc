@@ -185,6 +185,27 @@ EXTERN_C m3::Errors::Code __m3c_truncate(const char *pathname, size_t length) {
         return e.code();
     }
 }
+EXTERN_C m3::Errors::Code __m3c_lock(int fd, size_t off, size_t len, m3::File::LockType type,
+                                     bool wait) {
+    try {
+        auto file = m3::Activity::own().files()->get(fd);
+        file->lock(off, len, type, wait);
+        return m3::Errors::SUCCESS;
+    }
+    catch(const m3::Exception &e) {
+        return e.code();
+    }
+}
+EXTERN_C m3::Errors::Code __m3c_unlock(int fd, size_t off, size_t len) {
+    try {
+        auto file = m3::Activity::own().files()->get(fd);
+        file->unlock(off, len);
+        return m3::Errors::SUCCESS;
+    }
+    catch(const m3::Exception &e) {
+        return e.code();
+    }
+}
 EXTERN_C m3::Errors::Code __m3c_sync(int fd) {
     try {
         auto file = m3::Activity::own().files()->get(fd);
@@ -207,6 +228,31 @@ EXTERN_C void __m3c_close(int fd) {
     m3::Activity::own().files()->remove(fd);
 }
 
+EXTERN_C m3::Errors::Code __m3c_mprotect(uintptr_t addr, size_t len, int prot) {
+    auto &pager = m3::Activity::own().pager();
+    if(!pager)
+        return m3::Errors::NOT_SUP;
+    try {
+        pager->protect(addr, len, prot);
+        return m3::Errors::SUCCESS;
+    }
+    catch(const m3::Exception &e) {
+        return e.code();
+    }
+}
+EXTERN_C m3::Errors::Code __m3c_madvise(uintptr_t addr, size_t len, m3::Pager::Advice advice) {
+    auto &pager = m3::Activity::own().pager();
+    if(!pager)
+        return m3::Errors::NOT_SUP;
+    try {
+        pager->advise(addr, len, advice);
+        return m3::Errors::SUCCESS;
+    }
+    catch(const m3::Exception &e) {
+        return e.code();
+    }
+}
+
 EXTERN_C m3::Errors::Code __m3c_waiter_create(void **waiter) {
     *waiter = new m3::FileWaiter();
     return m3::Errors::SUCCESS;
@@ -235,6 +281,97 @@ EXTERN_C void __m3c_waiter_destroy(void *waiter) {
     delete static_cast<m3::FileWaiter *>(waiter);
 }
 
+static size_t poll_files(CompatPollFd *fds, size_t nfds) {
+    size_t ready = 0;
+    for(size_t i = 0; i < nfds; ++i) {
+        fds[i].revents = 0;
+        // entries with negative file descriptors are ignored
+        if(fds[i].fd < 0)
+            continue;
+
+        auto file = m3::Activity::own().files()->get(fds[i].fd);
+        // check the events individually, because files might only report whether all given
+        // events have arrived
+        for(uint ev : {m3::File::INPUT, m3::File::OUTPUT, m3::File::SIGNAL}) {
+            if((fds[i].events & ev) && file->check_events(ev))
+                fds[i].revents |= ev;
+        }
+        if(fds[i].revents)
+            ready++;
+    }
+    return ready;
+}
+
+EXTERN_C m3::Errors::Code __m3c_poll(CompatPollFd *fds, size_t nfds, int64_t timeout,
+                                     size_t *ready) {
+    try {
+        // negative timeouts wait forever
+        auto end = m3::TimeInstant::now() +
+                   m3::TimeDuration::from_nanos(timeout < 0 ? 0 : static_cast<uint64_t>(timeout));
+        while(true) {
+            *ready = poll_files(fds, nfds);
+            if(*ready > 0)
+                return m3::Errors::SUCCESS;
+
+            if(timeout < 0)
+                m3::OwnActivity::sleep();
+            else {
+                auto now = m3::TimeInstant::now();
+                if(!(now < end))
+                    return m3::Errors::SUCCESS;
+                m3::OwnActivity::sleep_for(end.duration_since(now));
+            }
+        }
+    }
+    catch(const m3::Exception &e) {
+        return e.code();
+    }
+}
+
+static bool fdset_test(uint64_t *set, int fd) {
+    return set && (set[fd / 64] & (static_cast<uint64_t>(1) << (fd % 64)));
+}
+
+static void fdset_set(uint64_t *set, int fd, bool val) {
+    if(set) {
+        if(val)
+            set[fd / 64] |= static_cast<uint64_t>(1) << (fd % 64);
+        else
+            set[fd / 64] &= ~(static_cast<uint64_t>(1) << (fd % 64));
+    }
+}
+
+EXTERN_C m3::Errors::Code __m3c_select(int nfds, uint64_t *readfds, uint64_t *writefds,
+                                       int64_t timeout, int *ready) {
+    CompatPollFd fds[m3::FileTable::MAX_FDS];
+    size_t count = 0;
+    for(int fd = 0; fd < nfds && fd < static_cast<int>(m3::FileTable::MAX_FDS); ++fd) {
+        uint events = 0;
+        if(fdset_test(readfds, fd))
+            events |= m3::File::INPUT;
+        if(fdset_test(writefds, fd))
+            events |= m3::File::OUTPUT;
+        if(events)
+            fds[count++] = CompatPollFd{fd, events, 0};
+    }
+
+    size_t ready_files;
+    auto res = __m3c_poll(fds, count, timeout, &ready_files);
+    if(res != m3::Errors::SUCCESS)
+        return res;
+
+    // in contrast to poll, select counts the ready events instead of the ready files
+    *ready = 0;
+    for(size_t i = 0; i < count; ++i) {
+        bool in = fds[i].revents & m3::File::INPUT;
+        bool out = fds[i].revents & m3::File::OUTPUT;
+        fdset_set(readfds, fds[i].fd, in);
+        fdset_set(writefds, fds[i].fd, out);
+        *ready += in + out;
+    }
+    return m3::Errors::SUCCESS;
+}
+
 static m3::NetworkManager *netmng = nullptr;
 
 EXTERN_C m3::Errors::Code __m3c_init_netmng(const char *name) {
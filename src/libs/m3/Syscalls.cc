@@ -128,13 +128,15 @@ void Syscalls::create_map(capsel_t dst, capsel_t act, capsel_t mgate, capsel_t f
 }
 
 std::pair<epid_t, actid_t> Syscalls::create_activity(capsel_t dst, const std::string_view &name,
-                                                     capsel_t tile, capsel_t kmem) {
+                                                     capsel_t tile, capsel_t kmem,
+                                                     KIF::Priority prio) {
     MsgBuf req_buf;
     auto &req = req_buf.cast<KIF::Syscall::CreateActivity>();
     req.opcode = KIF::Syscall::CREATE_ACT;
     req.dst_sel = dst;
     req.tile_sel = tile;
     req.kmem_sel = kmem;
+    req.prio = prio;
     req.namelen = Math::min(name.length(), sizeof(req.name));
     memcpy(req.name, name.data(), req.namelen);
 
@@ -422,6 +424,20 @@ void Syscalls::revoke(capsel_t act, const KIF::CapRngDesc &crd, bool own) {
     send_receive_throw(req_buf);
 }
 
+KIF::ActivityStats Syscalls::activity_stats(capsel_t act) {
+    MsgBuf req_buf;
+    auto &req = req_buf.cast<KIF::Syscall::ActStats>();
+    req.opcode = KIF::Syscall::ACT_STATS;
+    req.act_sel = act;
+
+    auto reply = send_receive<KIF::Syscall::ActStatsReply>(req_buf);
+
+    Errors::Code res = static_cast<Errors::Code>(reply.error());
+    if(res != Errors::SUCCESS)
+        throw SyscallException(res, KIF::Syscall::ACT_STATS);
+    return reply->stats;
+}
+
 void Syscalls::reset_stats() {
     MsgBuf req_buf;
     auto &req = req_buf.cast<KIF::Syscall::ResetStats>();
@@ -23,7 +23,7 @@
 namespace m3 {
 
 IndirectPipe::IndirectPipe(Pipes &pipes, MemGate &mem, size_t memsize, int flags)
-    : _pipe(pipes.create_pipe(mem, memsize)),
+    : _pipe(pipes.create_pipe(mem, memsize, flags & FILE_PACKET)),
       _reader(_pipe.create_channel(true, flags)),
       _writer(_pipe.create_channel(false, flags)) {
 }
@@ -119,6 +119,36 @@ Errors::Code M3FS::try_rename(const char *oldpath, const char *newpath) {
     return res;
 }
 
+Errors::Code M3FS::try_symlink(const char *target, const char *path) {
+    GateIStream reply = send_receive_vmsg(_gate, SYMLINK, target, path);
+    Errors::Code res;
+    reply >> res;
+    return res;
+}
+
+Errors::Code M3FS::try_readlink(const char *path, std::string &target) {
+    GateIStream reply = send_receive_vmsg(_gate, READLINK, path);
+    Errors::Code res;
+    reply >> res;
+    if(res == Errors::SUCCESS)
+        reply >> target;
+    return res;
+}
+
+Errors::Code M3FS::try_chmod(const char *path, mode_t mode) {
+    GateIStream reply = send_receive_vmsg(_gate, CHMOD, path, mode);
+    Errors::Code res;
+    reply >> res;
+    return res;
+}
+
+Errors::Code M3FS::try_chown(const char *path, uint32_t uid, uint32_t gid) {
+    GateIStream reply = send_receive_vmsg(_gate, CHOWN, path, uid, gid);
+    Errors::Code res;
+    reply >> res;
+    return res;
+}
+
 size_t M3FS::delegate_ep(capsel_t sel) {
     KIF::ExchangeArgs args;
     ExchangeOStream os(args);
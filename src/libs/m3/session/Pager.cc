@@ -103,6 +103,21 @@ void Pager::unmap(goff_t virt) {
     reply.pull_result();
 }
 
+void Pager::msync(goff_t virt, size_t len) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, MSYNC, virt, len);
+    reply.pull_result();
+}
+
+void Pager::protect(goff_t virt, size_t len, int prot) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, PROTECT, virt, len, prot);
+    reply.pull_result();
+}
+
+void Pager::advise(goff_t virt, size_t len, Advice advice) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, ADVISE, virt, len, advice);
+    reply.pull_result();
+}
+
 Reference<Pager> Pager::create_clone() {
     KIF::CapRngDesc caps;
     {
@@ -0,0 +1,94 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+#include <m3/com/GateStream.h>
+#include <m3/session/PipeFS.h>
+#include <m3/tiles/ChildActivity.h>
+#include <m3/vfs/GenericFile.h>
+
+namespace m3 {
+
+std::unique_ptr<GenericFile> PipeFS::open(const char *path, int perms) {
+    KIF::ExchangeArgs args;
+    ExchangeOStream os(args);
+    os << Pipes::OPEN_FIFO << perms << path;
+    args.bytes = os.total();
+    KIF::CapRngDesc crd = obtain(2, &args);
+
+    return std::unique_ptr<GenericFile>(new GenericFile(perms | FILE_NEWSESS, crd.start(), id()));
+}
+
+Errors::Code PipeFS::try_stat(const char *path, FileInfo &info) noexcept {
+    info = FileInfo();
+    info.links = 1;
+
+    // the root directory is the only directory
+    if(*path == '\0') {
+        info.mode = M3FS_IFDIR | M3FS_IRWXU;
+        return Errors::SUCCESS;
+    }
+
+    size_t ino;
+    Errors::Code res = fifo_op(Pipes::STAT_FIFO, path, &ino);
+    if(res != Errors::SUCCESS)
+        return res;
+    info.inode = ino;
+    info.mode = M3FS_IFPIP | M3FS_MODE_READ | M3FS_MODE_WRITE;
+    return Errors::SUCCESS;
+}
+
+Errors::Code PipeFS::try_mkfifo(const char *path) {
+    return fifo_op(Pipes::MKFIFO, path);
+}
+
+Errors::Code PipeFS::try_unlink(const char *path) {
+    return fifo_op(Pipes::UNLINK_FIFO, path);
+}
+
+Errors::Code PipeFS::fifo_op(int op, const char *path, size_t *ino) noexcept {
+    try {
+        KIF::ExchangeArgs args;
+        ExchangeOStream os(args);
+        os << op << path;
+        args.bytes = os.total();
+        obtain(0, &args);
+
+        if(ino) {
+            ExchangeIStream is(args);
+            is >> *ino;
+        }
+        return Errors::SUCCESS;
+    }
+    catch(const Exception &e) {
+        return e.code();
+    }
+}
+
+void PipeFS::delegate(ChildActivity &act) {
+    act.delegate_obj(sel());
+}
+
+void PipeFS::serialize(Marshaller &m) {
+    m << sel() << id();
+}
+
+FileSystem *PipeFS::unserialize(Unmarshaller &um) {
+    capsel_t sel;
+    size_t id;
+    um >> sel >> id;
+    return new PipeFS(id, sel);
+}
+
+}
@@ -46,6 +46,10 @@ void Activity::revoke(const KIF::CapRngDesc &crd, bool delonly) {
     Syscalls::revoke(sel(), crd, !delonly);
 }
 
+KIF::ActivityStats Activity::stats() const {
+    return Syscalls::activity_stats(sel());
+}
+
 MemGate Activity::get_mem(goff_t addr, size_t size, int perms) {
     capsel_t nsel = Activity::own().alloc_sel();
     Syscalls::create_mgate(nsel, sel(), addr, size, perms);
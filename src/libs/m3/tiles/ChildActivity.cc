@@ -32,7 +32,7 @@ extern "C" void *_start;
 
 const size_t ChildActivity::BUF_SIZE = 4096;
 
-ActivityArgs::ActivityArgs() noexcept : _rmng(nullptr), _pager(), _kmem() {
+ActivityArgs::ActivityArgs() noexcept : _rmng(nullptr), _pager(), _kmem(), _prio(KIF::PRIO_NORMAL) {
 }
 
 ActivityArgs &ActivityArgs::pager(Reference<Pager> pager) noexcept {
@@ -61,7 +61,7 @@ ChildActivity::ChildActivity(const Reference<class Tile> &tile, const std::strin
     if(_pager) {
         // now create activity, which implicitly obtains the gate cap from us
         const auto [eps_start, id] =
-            Syscalls::create_activity(sel(), name, tile->sel(), _kmem->sel());
+            Syscalls::create_activity(sel(), name, tile->sel(), _kmem->sel(), args._prio);
         _eps_start = eps_start;
         _id = id;
         // delegate activity cap to pager
@@ -69,7 +69,7 @@ ChildActivity::ChildActivity(const Reference<class Tile> &tile, const std::strin
     }
     else {
         const auto [eps_start, id] =
-            Syscalls::create_activity(sel(), name, tile->sel(), _kmem->sel());
+            Syscalls::create_activity(sel(), name, tile->sel(), _kmem->sel(), args._prio);
         _eps_start = eps_start;
         _id = id;
     }
@@ -124,6 +124,19 @@ void ChildActivity::stop() {
     Syscalls::activity_ctrl(sel(), KIF::Syscall::VCTRL_STOP, 0);
 }
 
+void ChildActivity::suspend() {
+    Syscalls::activity_ctrl(sel(), KIF::Syscall::VCTRL_SUSPEND, 0);
+}
+
+void ChildActivity::resume() {
+    Syscalls::activity_ctrl(sel(), KIF::Syscall::VCTRL_RESUME, 0);
+}
+
+void ChildActivity::migrate(const Reference<class Tile> &tile) {
+    Syscalls::activity_ctrl(sel(), KIF::Syscall::VCTRL_MIGRATE, tile->sel());
+    _tile = tile;
+}
+
 int ChildActivity::wait_async(event_t event) {
     const capsel_t sels[] = {sel()};
     return Syscalls::activity_wait(sels, 1, event).first;
@@ -171,6 +171,32 @@ void GenericFile::truncate(size_t length) {
     _pos = _len = 0;
 }
 
+void GenericFile::lock(size_t off, size_t len, LockType type, bool wait) {
+    LLOG(FS, "GenFile[{}]::lock(off={}, len={}, type={})"_cf, _fd, off, len,
+         static_cast<int>(type));
+
+    while(true) {
+        GateIStream reply = send_receive_vmsg(*_sg, M3FS::LOCK, _id, off, len, type);
+        Errors::Code res;
+        reply >> res;
+        // the server does not queue lock requests; thus, retry until the lock is free
+        if(res == Errors::WOULD_BLOCK && wait)
+            OwnActivity::sleep_for(TimeDuration::from_micros(100));
+        else {
+            if(res != Errors::SUCCESS)
+                throw Exception(res);
+            break;
+        }
+    }
+}
+
+void GenericFile::unlock(size_t off, size_t len) {
+    LLOG(FS, "GenFile[{}]::unlock(off={}, len={})"_cf, _fd, off, len);
+
+    GateIStream reply = send_receive_vmsg(*_sg, M3FS::UNLOCK, _id, off, len);
+    reply.pull_result();
+}
+
 NOINLINE bool GenericFile::receive_notify(uint event, bool fetch) {
     // not received the event yet?
     if((_notify_received & event) == 0) {
@@ -241,6 +267,13 @@ Option<size_t> GenericFile::read(void *buffer, size_t count) {
             _mg.read(buffer, amount, _off + _pos);
         _pos += amount;
     }
+
+    // release the packet immediately so that other readers can receive the next one; the rest of
+    // the packet is discarded
+    if((flags() & FILE_PACKET) && _len > 0) {
+        _pos = _len;
+        commit();
+    }
     return Some(amount);
 }
 
@@ -253,7 +286,8 @@ Option<size_t> GenericFile::write(const void *buffer, size_t count) {
         if(!_blocking && !receive_notify(Event::OUTPUT, true))
             return None;
 
-        GateIStream reply = send_receive_vmsg(*_sg, NEXT_OUT, _id);
+        // tell the server how much we want to write, which is required for packet pipes
+        GateIStream reply = send_receive_vmsg(*_sg, NEXT_OUT, _id, count);
         Errors::Code res;
         reply >> res;
         // if the server promised that we can call NEXT_OUT without being blocked, but would still
@@ -279,6 +313,10 @@ Option<size_t> GenericFile::write(const void *buffer, size_t count) {
         _pos += amount;
     }
     _writing = true;
+
+    // every write is a packet on its own
+    if(flags() & FILE_PACKET)
+        commit();
     return Some(amount);
 }
 
@@ -20,11 +20,14 @@
 #include <m3/com/GateStream.h>
 #include <m3/com/Marshalling.h>
 #include <m3/session/M3FS.h>
+#include <m3/session/PipeFS.h>
 #include <m3/vfs/MountTable.h>
 #include <m3/vfs/VFS.h>
 
 namespace m3 {
 
+static constexpr size_t MAX_PATH_LEN = 256;
+
 static size_t charcount(const char *str, char c) {
     size_t cnt = 0;
     while(*str) {
@@ -121,6 +124,57 @@ Reference<FileSystem> MountTable::try_resolve(const char **path, char *buffer,
     return Reference<FileSystem>();
 }
 
+Errors::Code MountTable::try_resolve_link(const char *path, std::string &result) {
+    char abs[MAX_PATH_LEN];
+    size_t len = VFS::abs_path(abs, sizeof(abs), path);
+
+    size_t end = 0;
+    while(end < len) {
+        const char *next = strchr(abs + end + 1, '/');
+        end = next ? static_cast<size_t>(next - abs) : len;
+        std::string prefix(abs, end);
+
+        // mount points are no links
+        bool is_mount = false;
+        for(size_t i = 0; i < _count; ++i) {
+            const std::string &mpath = _mounts[i]->path();
+            size_t mlen = mpath.back() == '/' ? mpath.length() - 1 : mpath.length();
+            if(mpath.compare(0, mlen, prefix) == 0 && mlen == prefix.length())
+                is_mount = true;
+        }
+        if(is_mount)
+            continue;
+
+        char buffer[MAX_PATH_LEN];
+        const char *fs_path = prefix.c_str();
+        Reference<FileSystem> fs = try_resolve(&fs_path, buffer, sizeof(buffer));
+        if(!fs)
+            return Errors::NO_SUCH_FILE;
+
+        std::string target;
+        Errors::Code res = fs->try_readlink(fs_path, target);
+        // not a symbolic link
+        if(res == Errors::INV_ARGS)
+            continue;
+        if(res != Errors::SUCCESS)
+            return res;
+
+        std::string new_path;
+        if(target[0] == '/')
+            new_path = target + (abs + end);
+        else {
+            size_t dir_end = prefix.rfind('/');
+            new_path = std::string(abs, dir_end) + "/" + target + (abs + end);
+        }
+
+        char canon[MAX_PATH_LEN];
+        VFS::canon_path(canon, sizeof(canon), new_path.c_str());
+        result = canon;
+        return Errors::SUCCESS;
+    }
+    return Errors::NO_SUCH_FILE;
+}
+
 Reference<FileSystem> MountTable::get_by_id(size_t id) noexcept {
     for(size_t i = 0; i < _count; ++i) {
         if(_mounts[i]->fs()->id() == id)
@@ -176,7 +230,8 @@ size_t MountTable::serialize(ChildActivity &act, void *buffer, size_t size) cons
         auto type = mount->type();
         m << mapping->first << type;
         switch(type) {
-            case 'M': mount->serialize(m); break;
+            case 'M':
+            case 'P': mount->serialize(m); break;
         }
     }
     return m.total();
@@ -187,7 +242,8 @@ void MountTable::delegate(ChildActivity &act) const {
         auto mount = Activity::own().mounts()->get(mapping->second.c_str());
         char type = mount->type();
         switch(type) {
-            case 'M': mount->delegate(act); break;
+            case 'M':
+            case 'P': mount->delegate(act); break;
         }
     }
 }
@@ -203,6 +259,7 @@ MountTable *MountTable::unserialize(const void *buffer, size_t size) {
         um >> path >> type;
         switch(type) {
             case 'M': ms->add(path.c_str(), Reference<FileSystem>(M3FS::unserialize(um))); break;
+            case 'P': ms->add(path.c_str(), Reference<FileSystem>(PipeFS::unserialize(um))); break;
         }
     }
     return ms;
@@ -22,6 +22,7 @@
 
 #include <m3/EnvVars.h>
 #include <m3/com/Marshalling.h>
+#include <m3/session/PipeFS.h>
 #include <m3/tiles/Activity.h>
 #include <m3/vfs/File.h>
 #include <m3/vfs/FileTable.h>
@@ -31,6 +32,78 @@
 namespace m3 {
 
 constexpr size_t MAX_PATH_LEN = 256;
+// the maximum number of symbolic links to other file systems we follow
+constexpr size_t MAX_SYMLINKS = 16;
+
+/**
+ * Resolves <path> to a file system and calls <func> with the file system and the path within it.
+ * If the file system reports a symbolic link to another file system, the link is resolved and
+ * <func> is called again with the resulting path.
+ */
+template<typename F>
+static Errors::Code with_path(const char *path, F func) {
+    auto &ms = Activity::own().mounts();
+    std::string cur;
+    for(size_t i = 0; i < MAX_SYMLINKS; ++i) {
+        char buffer[MAX_PATH_LEN];
+        const char *fs_path = path;
+        Reference<FileSystem> fs = ms->try_resolve(&fs_path, buffer, sizeof(buffer));
+        if(!fs)
+            return Errors::NO_SUCH_FILE;
+
+        Errors::Code res = func(fs, fs_path);
+        if(res != Errors::FOREIGN_LINK)
+            return res;
+
+        std::string next;
+        res = ms->try_resolve_link(path, next);
+        if(res != Errors::SUCCESS)
+            return res;
+        cur = std::move(next);
+        path = cur.c_str();
+    }
+    return Errors::LINK_LOOP;
+}
+
+/**
+ * Like with_path, but for operations on two paths that need to be on the same file system.
+ */
+template<typename F>
+static Errors::Code with_paths(const char *path1, const char *path2, F func) {
+    auto &ms = Activity::own().mounts();
+    std::string cur1, cur2;
+    for(size_t i = 0; i < MAX_SYMLINKS; ++i) {
+        char buffer1[MAX_PATH_LEN];
+        char buffer2[MAX_PATH_LEN];
+        const char *fs_path1 = path1;
+        const char *fs_path2 = path2;
+        Reference<FileSystem> fs1 = ms->try_resolve(&fs_path1, buffer1, sizeof(buffer1));
+        Reference<FileSystem> fs2 = ms->try_resolve(&fs_path2, buffer2, sizeof(buffer2));
+        if(!fs1 || !fs2)
+            return Errors::NO_SUCH_FILE;
+        if(fs1.get() != fs2.get())
+            return Errors::XFS_LINK;
+
+        Errors::Code res = func(fs1, fs_path1, fs_path2);
+        if(res != Errors::FOREIGN_LINK)
+            return res;
+
+        // we don't know which of both paths contains the link; try the first one first
+        std::string next;
+        if(ms->try_resolve_link(path1, next) == Errors::SUCCESS) {
+            cur1 = std::move(next);
+            path1 = cur1.c_str();
+        }
+        else {
+            res = ms->try_resolve_link(path2, next);
+            if(res != Errors::SUCCESS)
+                return res;
+            cur2 = std::move(next);
+            path2 = cur2.c_str();
+        }
+    }
+    return Errors::LINK_LOOP;
+}
 
 // clean them up after the standard streams have been destructed
 INIT_PRIO_VFS VFS::Cleanup VFS::_cleanup;
@@ -166,6 +239,8 @@ void VFS::mount(const char *path, const char *fs, const char *options) {
     FileSystem *fsobj;
     if(strcmp(fs, "m3fs") == 0)
         fsobj = new M3FS(id, options ? options : fs);
+    else if(strcmp(fs, "pipes") == 0)
+        fsobj = new PipeFS(id, options ? options : fs);
     else
         vthrow(Errors::INV_ARGS, "Unknown filesystem '{}'"_cf, fs);
     ms()->add(path, Reference<FileSystem>(fsobj));
@@ -177,10 +252,22 @@ void VFS::unmount(const char *path) {
 
 FileRef<GenericFile> VFS::open(const char *path, int flags) {
     try {
-        char buffer[MAX_PATH_LEN];
-        const char *fs_path = path;
-        Reference<FileSystem> fs = ms()->resolve(&fs_path, buffer, sizeof(buffer));
-        std::unique_ptr<GenericFile> file = fs->open(fs_path, flags);
+        std::unique_ptr<GenericFile> file;
+        Errors::Code res = with_path(path, [&file, flags](Reference<FileSystem> &fs,
+                                                          const char *fs_path) {
+            try {
+                file = fs->open(fs_path, flags);
+                return Errors::SUCCESS;
+            }
+            catch(const Exception &e) {
+                if(e.code() == Errors::FOREIGN_LINK)
+                    return Errors::FOREIGN_LINK;
+                throw;
+            }
+        });
+        if(res != Errors::SUCCESS)
+            throw Exception(res);
+
         auto fileref = Activity::own().files()->alloc(std::move(file));
         LLOG(FS, "GenFile[{}]::open({}, {})"_cf, fileref->fd(), path, flags);
         if(flags & FILE_APPEND)
@@ -199,12 +286,9 @@ void VFS::stat(const char *path, FileInfo &info) {
 }
 
 Errors::Code VFS::try_stat(const char *path, FileInfo &info) noexcept {
-    char buffer[MAX_PATH_LEN];
-    const char *fs_path = path;
-    Reference<FileSystem> fs = ms()->try_resolve(&fs_path, buffer, sizeof(buffer));
-    if(!fs)
-        return Errors::NO_SUCH_FILE;
-    return fs->try_stat(fs_path, info);
+    return with_path(path, [&info](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_stat(fs_path, info);
+    });
 }
 
 void VFS::mkdir(const char *path, mode_t mode) {
@@ -214,12 +298,9 @@ void VFS::mkdir(const char *path, mode_t mode) {
 }
 
 Errors::Code VFS::try_mkdir(const char *path, mode_t mode) {
-    char buffer[MAX_PATH_LEN];
-    const char *fs_path = path;
-    Reference<FileSystem> fs = ms()->try_resolve(&fs_path, buffer, sizeof(buffer));
-    if(!fs)
-        return Errors::NO_SUCH_FILE;
-    return fs->try_mkdir(fs_path, mode);
+    return with_path(path, [mode](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_mkdir(fs_path, mode);
+    });
 }
 
 void VFS::rmdir(const char *path) {
@@ -229,12 +310,21 @@ void VFS::rmdir(const char *path) {
 }
 
 Errors::Code VFS::try_rmdir(const char *path) {
-    char buffer[MAX_PATH_LEN];
-    const char *fs_path = path;
-    Reference<FileSystem> fs = ms()->try_resolve(&fs_path, buffer, sizeof(buffer));
-    if(!fs)
-        return Errors::NO_SUCH_FILE;
-    return fs->try_rmdir(fs_path);
+    return with_path(path, [](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_rmdir(fs_path);
+    });
+}
+
+void VFS::mkfifo(const char *path) {
+    Errors::Code res = try_mkfifo(path);
+    if(res != Errors::SUCCESS)
+        vthrow(res, "mkfifo '{}' failed"_cf, path);
+}
+
+Errors::Code VFS::try_mkfifo(const char *path) {
+    return with_path(path, [](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_mkfifo(fs_path);
+    });
 }
 
 void VFS::link(const char *oldpath, const char *newpath) {
@@ -244,17 +334,10 @@ void VFS::link(const char *oldpath, const char *newpath) {
 }
 
 Errors::Code VFS::try_link(const char *oldpath, const char *newpath) {
-    char buffer1[MAX_PATH_LEN];
-    char buffer2[MAX_PATH_LEN];
-    const char *fs_path1 = oldpath;
-    const char *fs_path2 = newpath;
-    Reference<FileSystem> fs1 = ms()->try_resolve(&fs_path1, buffer1, sizeof(buffer1));
-    Reference<FileSystem> fs2 = ms()->try_resolve(&fs_path2, buffer2, sizeof(buffer2));
-    if(!fs1 || !fs2)
-        return Errors::NO_SUCH_FILE;
-    if(fs1.get() != fs2.get())
-        return Errors::XFS_LINK;
-    return fs1->try_link(fs_path1, fs_path2);
+    return with_paths(oldpath, newpath,
+                      [](Reference<FileSystem> &fs, const char *fs_path1, const char *fs_path2) {
+                          return fs->try_link(fs_path1, fs_path2);
+                      });
 }
 
 void VFS::unlink(const char *path) {
@@ -264,12 +347,9 @@ void VFS::unlink(const char *path) {
 }
 
 Errors::Code VFS::try_unlink(const char *path) {
-    char buffer[MAX_PATH_LEN];
-    const char *fs_path = path;
-    Reference<FileSystem> fs = ms()->try_resolve(&fs_path, buffer, sizeof(buffer));
-    if(!fs)
-        return Errors::NO_SUCH_FILE;
-    return fs->try_unlink(fs_path);
+    return with_path(path, [](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_unlink(fs_path);
+    });
 }
 
 void VFS::rename(const char *oldpath, const char *newpath) {
@@ -279,17 +359,60 @@ void VFS::rename(const char *oldpath, const char *newpath) {
 }
 
 Errors::Code VFS::try_rename(const char *oldpath, const char *newpath) {
-    char buffer1[MAX_PATH_LEN];
-    char buffer2[MAX_PATH_LEN];
-    const char *fs_path1 = oldpath;
-    const char *fs_path2 = newpath;
-    Reference<FileSystem> fs1 = ms()->try_resolve(&fs_path1, buffer1, sizeof(buffer1));
-    Reference<FileSystem> fs2 = ms()->try_resolve(&fs_path2, buffer2, sizeof(buffer2));
-    if(!fs1 || !fs2)
-        return Errors::NO_SUCH_FILE;
-    if(fs1.get() != fs2.get())
-        return Errors::XFS_LINK;
-    return fs1->try_rename(fs_path1, fs_path2);
+    return with_paths(oldpath, newpath,
+                      [](Reference<FileSystem> &fs, const char *fs_path1, const char *fs_path2) {
+                          return fs->try_rename(fs_path1, fs_path2);
+                      });
+}
+
+void VFS::symlink(const char *target, const char *path) {
+    Errors::Code res = try_symlink(target, path);
+    if(res != Errors::SUCCESS)
+        vthrow(res, "symlink '{}' to '{}' failed"_cf, path, target);
+}
+
+Errors::Code VFS::try_symlink(const char *target, const char *path) {
+    return with_path(path, [target](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_symlink(target, fs_path);
+    });
+}
+
+std::string VFS::readlink(const char *path) {
+    std::string target;
+    Errors::Code res = try_readlink(path, target);
+    if(res != Errors::SUCCESS)
+        vthrow(res, "readlink '{}' failed"_cf, path);
+    return target;
+}
+
+Errors::Code VFS::try_readlink(const char *path, std::string &target) {
+    return with_path(path, [&target](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_readlink(fs_path, target);
+    });
+}
+
+void VFS::chmod(const char *path, mode_t mode) {
+    Errors::Code res = try_chmod(path, mode);
+    if(res != Errors::SUCCESS)
+        vthrow(res, "chmod '{}' failed"_cf, path);
+}
+
+Errors::Code VFS::try_chmod(const char *path, mode_t mode) {
+    return with_path(path, [mode](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_chmod(fs_path, mode);
+    });
+}
+
+void VFS::chown(const char *path, uint32_t uid, uint32_t gid) {
+    Errors::Code res = try_chown(path, uid, gid);
+    if(res != Errors::SUCCESS)
+        vthrow(res, "chown '{}' failed"_cf, path);
+}
+
+Errors::Code VFS::try_chown(const char *path, uint32_t uid, uint32_t gid) {
+    return with_path(path, [uid, gid](Reference<FileSystem> &fs, const char *fs_path) {
+        return fs->try_chown(fs_path, uid, gid);
+    });
 }
 
 void VFS::print(OStream &os) noexcept {
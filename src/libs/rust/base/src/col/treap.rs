@@ -114,6 +114,28 @@ impl<K: Copy + Ord, V> Treap<K, V> {
             .map(|n| unsafe { &mut (*n.as_ptr()).value })
     }
 
+    /// Returns a reference to the value with the smallest key that is greater than or equal to the
+    /// given key
+    pub fn lower_bound(&self, key: &K) -> Option<&V> {
+        let mut node = self.root;
+        let mut res = None;
+        loop {
+            match node {
+                Some(n) => unsafe {
+                    match key.cmp(&(*n.as_ptr()).key) {
+                        Ordering::Less => {
+                            res = Some(n);
+                            node = (*n.as_ptr()).left;
+                        },
+                        Ordering::Greater => node = (*n.as_ptr()).right,
+                        Ordering::Equal => return Some(&(*n.as_ptr()).value),
+                    }
+                },
+                None => return res.map(|n| unsafe { &(*n.as_ptr()).value }),
+            }
+        }
+    }
+
     /// Returns a mutable reference to the root value
     pub fn get_root_mut(&mut self) -> Option<&mut V> {
         unsafe {
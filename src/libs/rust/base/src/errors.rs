@@ -95,6 +95,12 @@ pub enum Code {
     InvChecksum,
     SocketClosed,
     ConnectionFailed,
+    ConnClosed,
+    // symbolic links
+    LinkLoop,
+    ForeignLink,
+    // file system integrity
+    DataCorrupted,
 }
 
 impl Default for Code {
@@ -257,7 +263,7 @@ impl From<u32> for Error {
 
 impl From<u32> for Code {
     fn from(error: u32) -> Self {
-        assert!(error <= Code::ConnectionFailed as u32);
+        assert!(error <= Code::DataCorrupted as u32);
         // safety: assuming that the assert above doesn't fail, the conversion is safe
         // TODO better way?
         unsafe { intrinsics::transmute(error) }
@@ -52,6 +52,41 @@ pub const SEL_ACT: CapSel = 2;
 /// The first free selector
 pub const FIRST_FREE_SEL: CapSel = SEL_ACT + 1;
 
+/// The number of priority classes
+pub const PRIO_COUNT: usize = 3;
+
+int_enum! {
+    /// The priority class of an activity
+    ///
+    /// TileMux always runs ready activities of higher classes before activities of lower classes.
+    /// Within a class, the activities are scheduled according to their time quota. The class is
+    /// chosen when the activity is created and cannot be changed afterwards.
+    pub struct Priority : u64 {
+        const LOW    = 0x0;
+        const NORMAL = 0x1;
+        const HIGH   = 0x2;
+    }
+}
+
+/// The resource usage of an activity as tracked by TileMux
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActivityStats {
+    /// The consumed CPU time in nanoseconds
+    pub cpu_time: u64,
+    /// The time in nanoseconds the activity was blocked
+    pub blocked_time: u64,
+    /// The number of times the activity has been switched out
+    pub ctxsws: u64,
+    /// The number of page faults that have been forwarded to the pager
+    pub page_faults: u64,
+    /// The number of messages that arrived while the activity was not running
+    ///
+    /// Messages that arrive while the activity is running are received directly from the TCU
+    /// without involving TileMux and are therefore not counted.
+    pub desched_msgs: u64,
+}
+
 /// The default reply message that only contains the error code
 #[derive(Serialize, Deserialize)]
 #[repr(C)]
@@ -20,7 +20,7 @@
 
 use crate::errors::Code;
 use crate::goff;
-use crate::kif::{tilemux::QuotaId, CapRngDesc, CapSel, Perm};
+use crate::kif::{tilemux::QuotaId, CapRngDesc, CapSel, CapType, Perm, Priority, INVALID_SEL};
 use crate::mem::GlobAddr;
 use crate::serialize::{Deserialize, Serialize};
 use crate::tcu::{ActId, EpId, Label};
@@ -31,6 +31,9 @@ pub const MAX_EXCHG_ARGS: usize = 8;
 /// The maximum number of activities one can wait for
 pub const MAX_WAIT_ACTS: usize = 32;
 
+/// The maximum number of capabilities that are returned by one `list_caps` system call
+pub const MAX_CAP_INFOS: usize = 6;
+
 int_enum! {
     /// The system calls
     pub struct Operation : u64 {
@@ -70,6 +73,8 @@ int_enum! {
         // Misc
         const RESET_STATS = 27;
         const NOOP = 28;
+        const LIST_CAPS = 29;
+        const ACT_STATS = 30;
     }
 }
 
@@ -136,6 +141,7 @@ pub struct CreateActivity<'s> {
     pub dst: CapSel,
     pub tile: CapSel,
     pub kmem: CapSel,
+    pub prio: Priority,
     pub name: &'s str,
 }
 
@@ -176,8 +182,11 @@ pub struct SetPMP {
 int_enum! {
     /// The operations for the `act_ctrl` system call
     pub struct ActivityOp : u64 {
-        const START = 0x1;
-        const STOP  = 0x2;
+        const START   = 0x1;
+        const STOP    = 0x2;
+        const SUSPEND = 0x3;
+        const RESUME  = 0x4;
+        const MIGRATE = 0x5;
     }
 }
 
@@ -333,6 +342,71 @@ pub struct ResetStats {}
 #[repr(C)]
 pub struct Noop {}
 
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ListCaps {
+    pub act: CapSel,
+    pub ty: CapType,
+    pub start: CapSel,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActStats {
+    pub act: CapSel,
+}
+
+int_enum! {
+    /// The types of kernel objects a capability can refer to
+    pub struct KObjType : u64 {
+        const RGATE = 0x0;
+        const SGATE = 0x1;
+        const MGATE = 0x2;
+        const MAP   = 0x3;
+        const SERV  = 0x4;
+        const SESS  = 0x5;
+        const SEM   = 0x6;
+        const ACT   = 0x7;
+        const KMEM  = 0x8;
+        const TILE  = 0x9;
+        const EP    = 0xA;
+    }
+}
+
+/// The description of a single capability as returned by the `list_caps` system call
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct CapInfo {
+    /// The first selector of the capability
+    pub sel: CapSel,
+    /// The number of selectors the capability spans
+    pub count: CapSel,
+    /// The type of the kernel object
+    pub obj: KObjType,
+    /// Whether the capability has been obtained from another capability
+    pub derived: bool,
+    /// The selector of the parent capability or `INVALID_SEL` if there is no parent
+    pub parent_sel: CapSel,
+    /// The id of the activity owning the parent capability (only valid with a parent)
+    pub parent_act: ActId,
+    /// The number of direct children of the capability
+    pub children: u32,
+}
+
+impl Default for CapInfo {
+    fn default() -> Self {
+        Self {
+            sel: INVALID_SEL,
+            count: 0,
+            obj: KObjType::RGATE,
+            derived: false,
+            parent_sel: INVALID_SEL,
+            parent_act: 0,
+            children: 0,
+        }
+    }
+}
+
 /// The create activity reply message
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
@@ -396,6 +470,15 @@ pub struct TileQuotaReply {
     pub pts_left: usize,
 }
 
+/// The list capabilities reply message
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ListCapsReply {
+    pub count: usize,
+    pub next: CapSel,
+    pub caps: [CapInfo; MAX_CAP_INFOS],
+}
+
 /// The delegate/obtain reply message
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
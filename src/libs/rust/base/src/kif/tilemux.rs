@@ -20,7 +20,7 @@
 
 use crate::errors::Code;
 use crate::goff;
-use crate::kif::PageFlags;
+use crate::kif::{PageFlags, Priority};
 use crate::mem::GlobAddr;
 use crate::serialize::{Deserialize, Serialize};
 use crate::tcu::{ActId, EpId};
@@ -49,14 +49,19 @@ int_enum! {
         const REMOVE_QUOTAS  = 0x9;
         const RESET_STATS    = 0xA;
         const SHUTDOWN       = 0xB;
+        const ACT_STATS      = 0xC;
+        const ACT_SAVE       = 0xD;
+        const ACT_RESTORE    = 0xE;
     }
 }
 
 int_enum! {
     /// The operations for the `act_ctrl` sidecall
     pub struct ActivityOp : u64 {
-        const START = 0x0;
-        const STOP  = 0x1;
+        const START   = 0x0;
+        const STOP    = 0x1;
+        const SUSPEND = 0x2;
+        const RESUME  = 0x3;
     }
 }
 
@@ -67,6 +72,7 @@ pub struct ActInit {
     pub act_id: u64,
     pub time_quota: QuotaId,
     pub pt_quota: QuotaId,
+    pub prio: Priority,
     pub eps_start: EpId,
 }
 
@@ -154,6 +160,33 @@ pub struct RemoveQuotas {
 #[repr(C)]
 pub struct ResetStats {}
 
+/// The activity stats sidecall
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActStats {
+    pub act_id: u64,
+}
+
+/// The activity save sidecall
+///
+/// Prepares the execution state of a suspended activity to be transferred to another tile. The
+/// response contains the global address of the state in `val1` and its size in `val2`.
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActSave {
+    pub act_id: u64,
+}
+
+/// The activity restore sidecall
+///
+/// Prepares a freshly initialized activity to receive the execution state from another tile. The
+/// response is the same as for [`ActSave`]. The activity stays suspended until it is resumed.
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActRestore {
+    pub act_id: u64,
+}
+
 /// The shutdown sidecall
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
@@ -4,11 +4,14 @@ dirs = [
     'isr',
     'lang',
     'm3',
+    'm3fs_layout',
     'm3impl',
     'paging',
+    'partition',
     'pci',
     'resmng',
     'thread',
+    'vterm_util',
 ]
 
 
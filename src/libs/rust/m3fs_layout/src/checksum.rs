@@ -0,0 +1,39 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+/// The value in the checksum area for blocks whose checksum is unknown, for example, because they
+/// have never been written
+pub const NO_CHECKSUM: u32 = 0;
+
+/// Calculates the checksum of the given block data.
+///
+/// The checksum is Adler-32, except that [`NO_CHECKSUM`] is never returned.
+pub fn block_checksum(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    // the largest number of bytes that can be summed up before b overflows
+    const CHUNK: usize = 5552;
+
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for chunk in data.chunks(CHUNK) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    ((b << 16) | a).max(NO_CHECKSUM + 1)
+}
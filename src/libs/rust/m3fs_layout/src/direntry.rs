@@ -0,0 +1,145 @@
+/*
+ * Copyright (C) 2020-2021 Nils Asmussen, Barkhausen Institut
+ * Copyright (C) 2019-2020, Tendsin Mende <tendsin@protonmail.com>
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::{InodeNo, DIR_ENTRY_LEN};
+
+use core::cell::Cell;
+use core::ptr;
+use core::slice;
+use core::str;
+
+/// On-disk representation of directory entries.
+#[repr(align(4), C)]
+pub struct DirEntry {
+    pub nodeno: InodeNo,
+    pub name_length: u32,
+    pub next: u32,
+    name: [u8],
+}
+
+impl DirEntry {
+    /// Returns a reference to the directory entry stored at `off` in the given buffer
+    pub fn from_buffer(block_data: &[u8], off: usize) -> &Self {
+        // TODO ensure that name_length and next are within bounds (in case FS image is corrupt)
+        let name_length = Self::name_length_at(block_data, off);
+        // safety: the name is stored directly after the fixed-size part of the entry
+        unsafe {
+            let entry = ptr::slice_from_raw_parts(block_data.as_ptr().add(off), name_length);
+            &*(entry as *const Self)
+        }
+    }
+
+    /// Returns a mutable reference to the directory entry stored at `off` in the given buffer
+    pub fn from_buffer_mut(block_data: &mut [u8], off: usize) -> &mut Self {
+        let name_length = Self::name_length_at(block_data, off);
+        // safety: see above
+        unsafe {
+            let entry =
+                ptr::slice_from_raw_parts_mut(block_data.as_mut_ptr().add(off), name_length);
+            &mut *(entry as *mut Self)
+        }
+    }
+
+    /// Returns mutable references to the two different directory entries stored at `off1` and
+    /// `off2` in the given buffer
+    pub fn two_from_buffer_mut(
+        block_data: &mut [u8],
+        off1: usize,
+        off2: usize,
+    ) -> (&mut Self, &mut Self) {
+        assert!(off1 != off2);
+        let len1 = Self::name_length_at(block_data, off1);
+        let len2 = Self::name_length_at(block_data, off2);
+        // safety: see above; the entries do not overlap, because they are chained via `next`
+        unsafe {
+            let base = block_data.as_mut_ptr();
+            let entry1 = ptr::slice_from_raw_parts_mut(base.add(off1), len1);
+            let entry2 = ptr::slice_from_raw_parts_mut(base.add(off2), len2);
+            (&mut *(entry1 as *mut Self), &mut *(entry2 as *mut Self))
+        }
+    }
+
+    fn name_length_at(block_data: &[u8], off: usize) -> usize {
+        assert!(off + DIR_ENTRY_LEN <= block_data.len());
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&block_data[off + 4..off + 8]);
+        u32::from_ne_bytes(bytes) as usize
+    }
+
+    /// Returns the size of this entry when stored on disk. Includes the static size of the struct
+    /// as well as the str. buffer size.
+    pub fn size(&self) -> usize {
+        Self::size_for(self.name_length as usize)
+    }
+
+    /// Returns the size of an entry with a name of `name_length` bytes when stored on disk
+    pub fn size_for(name_length: usize) -> usize {
+        // make sure the next entry is 4-byte aligned
+        DIR_ENTRY_LEN + ((name_length + 3) & !3)
+    }
+
+    /// Returns the name of the entry
+    pub fn name(&self) -> &str {
+        // safety: names are only set via `set_name`, which takes a str. the name might be longer
+        // than the slice we were created with, if it has been changed in the meantime.
+        unsafe {
+            let name = slice::from_raw_parts(self.name.as_ptr(), self.name_length as usize);
+            str::from_utf8_unchecked(name)
+        }
+    }
+
+    /// Sets the name of the entry to the given one.
+    ///
+    /// The caller has to ensure that the entry has enough space for the name.
+    pub fn set_name(&mut self, name: &str) {
+        self.name_length = name.len() as u32;
+        // safety: the caller guarantees that there is enough space
+        unsafe {
+            ptr::copy_nonoverlapping(name.as_ptr(), self.name.as_mut_ptr(), name.len());
+        }
+    }
+}
+
+/// Entry iterator takes a block and iterates over it assuming that the block contains entries.
+pub struct DirEntryIterator<'e> {
+    block_data: &'e [u8],
+    off: Cell<usize>,
+    end: usize,
+}
+
+impl<'e> DirEntryIterator<'e> {
+    pub fn from_block(block_data: &'e [u8]) -> Self {
+        DirEntryIterator {
+            block_data,
+            off: Cell::from(0),
+            end: block_data.len(),
+        }
+    }
+
+    /// Returns the next DirEntry
+    pub fn next(&'e self) -> Option<&'e DirEntry> {
+        if self.off.get() < self.end {
+            let ret = DirEntry::from_buffer(self.block_data, self.off.get());
+
+            self.off.set(self.off.get() + ret.next as usize);
+
+            Some(ret)
+        }
+        else {
+            None
+        }
+    }
+}
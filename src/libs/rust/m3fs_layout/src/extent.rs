@@ -0,0 +1,53 @@
+/*
+ * Copyright (C) 2020-2021 Nils Asmussen, Barkhausen Institut
+ * Copyright (C) 2019-2020, Tendsin Mende <tendsin@protonmail.com>
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::{BlockNo, NUM_EXT_BYTES};
+
+/// Represents an extent as stored on disk.
+///
+/// An extent with a start of 0 is a hole: its blocks are not allocated and read as zeros. Since
+/// block 0 always holds the superblock, it can never be part of a file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(C, align(8))]
+pub struct Extent {
+    pub start: u32,
+    pub length: u32,
+}
+
+const _: () = assert!(core::mem::size_of::<Extent>() == NUM_EXT_BYTES);
+
+impl Extent {
+    pub fn new(start: u32, length: u32) -> Self {
+        Self { start, length }
+    }
+
+    /// Creates a hole of the given number of blocks
+    pub fn hole(length: u32) -> Self {
+        Self { start: 0, length }
+    }
+
+    /// Returns true if this extent is a hole
+    pub fn is_hole(&self) -> bool {
+        self.start == 0 && self.length > 0
+    }
+
+    pub fn block_range(&self) -> core::ops::Range<BlockNo> {
+        core::ops::Range {
+            start: self.start,
+            end: self.start + self.length,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * Copyright (C) 2020-2021 Nils Asmussen, Barkhausen Institut
+ * Copyright (C) 2019-2020, Tendsin Mende <tendsin@protonmail.com>
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::{BlockNo, Dev, Extent, InodeNo, Time, INODE_DIR_COUNT, NUM_INODE_BYTES};
+
+/// Represents an INode as stored on disk.
+///
+/// The mode is stored in 16 bits in the format of `st_mode`. Its type is a parameter so that the
+/// server can use its `FileMode` type, whereas host tools can use a plain `u16`.
+#[repr(C)]
+pub struct INode<M = u16> {
+    pub devno: Dev,
+    _pad: u8,
+    pub links: u16,
+
+    pub lastaccess: Time,
+    pub lastmod: Time,
+    pub extents: u32,
+
+    pub inode: InodeNo,
+    pub mode: M,
+    pub size: u64,
+
+    pub direct: [Extent; INODE_DIR_COUNT], // direct entries
+    pub indirect: BlockNo,                 // location of the indirect block if != 0,
+    pub dindirect: BlockNo,                // location of double indirect block if != 0
+
+    pub uid: u32,
+    pub gid: u32,
+}
+
+const _: () = assert!(core::mem::size_of::<INode>() == NUM_INODE_BYTES);
+
+impl<M: Copy> Clone for INode<M> {
+    fn clone(&self) -> Self {
+        INode {
+            devno: self.devno,
+            links: self.links,
+            _pad: 0,
+
+            inode: self.inode,
+            mode: self.mode,
+            size: self.size,
+
+            lastaccess: self.lastaccess,
+            lastmod: self.lastmod,
+            extents: self.extents,
+
+            direct: self.direct,
+            indirect: self.indirect,
+            dindirect: self.dindirect,
+
+            uid: self.uid,
+            gid: self.gid,
+        }
+    }
+}
+
+impl<M: Default> Default for INode<M> {
+    fn default() -> Self {
+        INode {
+            devno: 0,
+            links: 0,
+            _pad: 0,
+
+            inode: 0,
+            mode: M::default(),
+            size: 0,
+
+            lastaccess: 0,
+            lastmod: 0,
+            extents: 0,
+
+            direct: [Extent::default(); INODE_DIR_COUNT],
+            indirect: 0,
+            dindirect: 0,
+
+            uid: 0,
+            gid: 0,
+        }
+    }
+}
+
+impl<M: Default> INode<M> {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
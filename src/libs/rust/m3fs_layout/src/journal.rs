@@ -0,0 +1,133 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::{BlockNo, SuperBlock};
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+pub const JOURNAL_MAGIC: u32 = 0x4A52_4E4C;
+
+/// On-disk representation of the journal header.
+///
+/// The header is stored in the first journal block and is directly followed by the home locations
+/// of the journaled blocks. The contents of these blocks are stored in the subsequent journal
+/// blocks in the same order.
+#[derive(Debug)]
+#[repr(C)]
+pub struct JournalHeader {
+    pub magic: u32,
+    pub count: u32,
+    pub free_inodes: u32,
+    pub free_blocks: u32,
+    pub first_free_inode: u32,
+    pub first_free_block: u32,
+    pub checksum: u32,
+}
+
+const HEADER_WORDS: usize = size_of::<JournalHeader>() / size_of::<u32>();
+
+fn read_word(data: &[u8], idx: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[idx * 4..idx * 4 + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn write_word(data: &mut [u8], idx: usize, val: u32) {
+    data[idx * 4..idx * 4 + 4].copy_from_slice(&val.to_le_bytes());
+}
+
+impl JournalHeader {
+    /// Creates a new header for a transaction of the given blocks, taking the allocation state
+    /// from the given superblock
+    pub fn new(sb: &SuperBlock, blocks: &[BlockNo]) -> Self {
+        let mut hd = JournalHeader {
+            magic: JOURNAL_MAGIC,
+            count: blocks.len() as u32,
+            free_inodes: sb.free_inodes,
+            free_blocks: sb.free_blocks,
+            first_free_inode: sb.first_free_inode,
+            first_free_block: sb.first_free_block,
+            checksum: 0,
+        };
+        hd.checksum = hd.get_checksum(blocks);
+        hd
+    }
+
+    /// Returns the maximum number of blocks that can be described by a header in a block of the
+    /// given size
+    pub fn capacity(block_size: usize) -> usize {
+        (block_size - size_of::<Self>()) / size_of::<BlockNo>()
+    }
+
+    pub fn get_checksum(&self, blocks: &[BlockNo]) -> u32 {
+        let mut sum = 1u32
+            .wrapping_add(self.magic.wrapping_mul(2))
+            .wrapping_add(self.count.wrapping_mul(3))
+            .wrapping_add(self.free_inodes.wrapping_mul(5))
+            .wrapping_add(self.free_blocks.wrapping_mul(7))
+            .wrapping_add(self.first_free_inode.wrapping_mul(11))
+            .wrapping_add(self.first_free_block.wrapping_mul(13));
+        for (i, bno) in blocks.iter().enumerate() {
+            sum = sum.wrapping_add(bno.wrapping_mul(i as u32 + 17));
+        }
+        sum
+    }
+
+    /// Loads the header and the home locations from the given block data.
+    ///
+    /// Returns `None` if the block does not contain a committed transaction.
+    pub fn load(data: &[u8]) -> Option<(Self, Vec<BlockNo>)> {
+        let hd = JournalHeader {
+            magic: read_word(data, 0),
+            count: read_word(data, 1),
+            free_inodes: read_word(data, 2),
+            free_blocks: read_word(data, 3),
+            first_free_inode: read_word(data, 4),
+            first_free_block: read_word(data, 5),
+            checksum: read_word(data, 6),
+        };
+
+        if hd.magic != JOURNAL_MAGIC
+            || hd.count == 0
+            || hd.count as usize > Self::capacity(data.len())
+        {
+            return None;
+        }
+
+        let blocks = (0..hd.count as usize)
+            .map(|i| read_word(data, HEADER_WORDS + i))
+            .collect::<Vec<_>>();
+        if hd.checksum != hd.get_checksum(&blocks) {
+            return None;
+        }
+        Some((hd, blocks))
+    }
+
+    /// Stores the header and the given home locations into the given block data
+    pub fn store(&self, blocks: &[BlockNo], data: &mut [u8]) {
+        data.fill(0);
+        write_word(data, 0, self.magic);
+        write_word(data, 1, self.count);
+        write_word(data, 2, self.free_inodes);
+        write_word(data, 3, self.free_blocks);
+        write_word(data, 4, self.first_free_inode);
+        write_word(data, 5, self.first_free_block);
+        write_word(data, 6, self.checksum);
+        for (i, bno) in blocks.iter().enumerate() {
+            write_word(data, HEADER_WORDS + i, *bno);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! The on-disk layout of m3fs.
+//!
+//! This crate is shared by the m3fs server and the host tools that work on m3fs images. Therefore,
+//! it does not depend on anything but `core` and `alloc`.
+
+#![no_std]
+
+extern crate alloc;
+
+mod checksum;
+mod direntry;
+mod extent;
+mod inode;
+mod journal;
+mod superblock;
+
+pub use checksum::{block_checksum, NO_CHECKSUM};
+pub use direntry::{DirEntry, DirEntryIterator};
+pub use extent::Extent;
+pub use inode::INode;
+pub use journal::{JournalHeader, JOURNAL_MAGIC};
+pub use superblock::{SuperBlock, SUPERBLOCK_MAGIC};
+
+pub type BlockNo = u32;
+pub type Dev = u8;
+pub type InodeNo = u32;
+pub type Time = u32;
+
+pub const INODE_DIR_COUNT: usize = 2;
+pub const MAX_BLOCK_SIZE: u32 = 4096;
+pub const NUM_INODE_BYTES: usize = 64;
+pub const NUM_EXT_BYTES: usize = 8;
+pub const DIR_ENTRY_LEN: usize = 12;
+/// The maximum length of the target of a symbolic link, which needs to fit into a single reply
+pub const MAX_LINK_LEN: usize = 256;
@@ -0,0 +1,141 @@
+/*
+ * Copyright (C) 2020 Nils Asmussen, Barkhausen Institut
+ * Copyright (C) 2019-2020, Tendsin Mende <tendsin@protonmail.com>
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::{BlockNo, NUM_EXT_BYTES, NUM_INODE_BYTES};
+
+use core::mem::size_of;
+
+/// Identifies m3fs images and the version of their layout.
+///
+/// The lowest byte is the layout version, which needs to be increased on every incompatible change
+/// of the on-disk format so that old images are rejected instead of being misinterpreted.
+pub const SUPERBLOCK_MAGIC: u32 = 0x4D33_4602;
+
+/// Represents a superblock
+#[derive(Debug)]
+#[repr(C, align(8))]
+pub struct SuperBlock {
+    pub magic: u32,
+    pub block_size: u32,
+    pub total_inodes: u32,
+    pub total_blocks: u32,
+    pub free_inodes: u32,
+    pub free_blocks: u32,
+    pub first_free_inode: u32,
+    pub first_free_block: u32,
+    pub journal_blocks: u32,
+    pub checksum_blocks: u32,
+    pub checksum: u32,
+}
+
+impl SuperBlock {
+    pub fn get_checksum(&self) -> u32 {
+        1 + self.block_size * 2
+            + self.total_inodes * 3
+            + self.total_blocks * 5
+            + self.free_inodes * 7
+            + self.free_blocks * 11
+            + self.first_free_inode * 13
+            + self.first_free_block * 17
+            + self.journal_blocks * 19
+            + self.checksum_blocks * 23
+    }
+
+    pub fn first_inodebm_block(&self) -> BlockNo {
+        1
+    }
+
+    pub fn inodebm_block(&self) -> BlockNo {
+        (((self.total_inodes + 7) / 8) + self.block_size - 1) / self.block_size
+    }
+
+    pub fn first_blockbm_block(&self) -> BlockNo {
+        self.first_inodebm_block() + self.inodebm_block()
+    }
+
+    pub fn blockbm_blocks(&self) -> BlockNo {
+        (((self.total_blocks + 7) / 8) + self.block_size - 1) / self.block_size
+    }
+
+    pub fn first_inode_block(&self) -> BlockNo {
+        self.first_blockbm_block() + self.blockbm_blocks()
+    }
+
+    pub fn inode_blocks(&self) -> BlockNo {
+        (self.total_inodes * NUM_INODE_BYTES as u32 + self.block_size - 1) / self.block_size
+    }
+
+    pub fn first_journal_block(&self) -> BlockNo {
+        self.first_inode_block() + self.inode_blocks()
+    }
+
+    pub fn first_checksum_block(&self) -> BlockNo {
+        self.first_journal_block() + self.journal_blocks
+    }
+
+    pub fn first_data_block(&self) -> BlockNo {
+        self.first_checksum_block() + self.checksum_blocks
+    }
+
+    pub fn checksums_per_block(&self) -> usize {
+        self.block_size as usize / size_of::<u32>()
+    }
+
+    /// Returns the number of blocks that are required to store a checksum for every block
+    pub fn needed_checksum_blocks(&self) -> BlockNo {
+        let per_block = self.checksums_per_block() as BlockNo;
+        (self.total_blocks + per_block - 1) / per_block
+    }
+
+    /// Returns true if the file system stores a checksum for block `bno`.
+    ///
+    /// The superblock has its own checksum and the journal protects its header by a checksum as
+    /// well. The checksum area covers all other blocks, but blocks whose checksum is
+    /// [`NO_CHECKSUM`](crate::NO_CHECKSUM) are not checked.
+    pub fn has_checksum(&self, bno: BlockNo) -> bool {
+        self.checksum_blocks > 0
+            && bno > 0
+            && bno < self.total_blocks
+            && !(self.first_journal_block()..self.first_data_block()).contains(&bno)
+    }
+
+    /// Returns the location (block number and byte offset) of the checksum for block `bno`
+    pub fn checksum_location(&self, bno: BlockNo) -> (BlockNo, usize) {
+        let per_block = self.checksums_per_block();
+        (
+            self.first_checksum_block() + (bno as usize / per_block) as BlockNo,
+            (bno as usize % per_block) * size_of::<u32>(),
+        )
+    }
+
+    pub fn extents_per_block(&self) -> usize {
+        self.block_size as usize / NUM_EXT_BYTES
+    }
+
+    pub fn inodes_per_block(&self) -> usize {
+        self.block_size as usize / NUM_INODE_BYTES
+    }
+
+    pub fn update_inodebm(&mut self, free: u32, first: u32) {
+        self.free_inodes = free;
+        self.first_free_inode = first;
+    }
+
+    pub fn update_blockbm(&mut self, free: u32, first: u32) {
+        self.free_blocks = free;
+        self.first_free_block = first;
+    }
+}
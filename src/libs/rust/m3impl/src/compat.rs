@@ -18,21 +18,24 @@ use core::mem;
 
 use crate::boxed::Box;
 use crate::cell::{LazyStaticRefCell, RefMut};
+use crate::col::Vec;
 use crate::errors::{Code, Error};
+use crate::goff;
 use crate::io::{read_object, Read, Write};
+use crate::kif::Perm;
 use crate::libc;
 use crate::net::{
     DGramSocket, DgramSocketArgs, Endpoint, IpAddr, Port, Socket, StreamSocket, StreamSocketArgs,
     TcpSocket, UdpSocket,
 };
 use crate::rc::Rc;
-use crate::session::NetworkManager;
+use crate::session::{Advice, NetworkManager};
 use crate::tiles::{Activity, OwnActivity};
 use crate::time::{TimeDuration, TimeInstant};
 use crate::util;
 use crate::vfs::{
-    BufReader, File, FileEvent, FileInfo, FileMode, FileRef, FileWaiter, GenericFile, INodeId,
-    OpenFlags, Seek, SeekMode, VFS,
+    poll, BufReader, File, FileEvent, FileInfo, FileMode, FileRef, FileWaiter, GenericFile,
+    INodeId, LockType, OpenFlags, PollFd, PollSource, Seek, SeekMode, VFS,
 };
 
 macro_rules! try_res {
@@ -282,6 +285,28 @@ pub unsafe extern "C" fn __m3c_truncate(pathname: *const i8, length: usize) -> C
     Code::Success
 }
 
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn __m3c_lock(
+    fd: i32,
+    off: usize,
+    len: usize,
+    ty: LockType,
+    wait: bool,
+) -> Code {
+    let mut file = try_res!(get_file(fd));
+    try_res!(file.lock(off, len, ty, wait));
+    Code::Success
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn __m3c_unlock(fd: i32, off: usize, len: usize) -> Code {
+    let mut file = try_res!(get_file(fd));
+    try_res!(file.unlock(off, len));
+    Code::Success
+}
+
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn __m3c_sync(fd: i32) -> Code {
@@ -305,6 +330,28 @@ pub unsafe extern "C" fn __m3c_close(fd: i32) {
     Activity::own().files().remove(fd as usize);
 }
 
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn __m3c_mprotect(addr: usize, len: usize, prot: u32) -> Code {
+    let pager = match Activity::own().pager() {
+        Some(pager) => pager,
+        None => return Code::NotSup,
+    };
+    try_res!(pager.protect(addr as goff, len, Perm::from_bits_truncate(prot)));
+    Code::Success
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn __m3c_madvise(addr: usize, len: usize, advice: Advice) -> Code {
+    let pager = match Activity::own().pager() {
+        Some(pager) => pager,
+        None => return Code::NotSup,
+    };
+    try_res!(pager.advise(addr as goff, len, advice));
+    Code::Success
+}
+
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn __m3c_waiter_create(waiter: *mut *mut libc::c_void) -> Code {
@@ -364,6 +411,106 @@ pub unsafe extern "C" fn __m3c_waiter_destroy(waiter: *mut libc::c_void) {
     drop(Box::from_raw(waiter as *mut FileWaiter));
 }
 
+#[repr(C)]
+pub struct CompatPollFd {
+    fd: i32,
+    events: FileEvent,
+    revents: FileEvent,
+}
+
+fn poll_timeout(timeout: i64) -> Option<TimeDuration> {
+    // negative timeouts wait forever
+    match timeout {
+        t if t < 0 => None,
+        t => Some(TimeDuration::from_nanos(t as u64)),
+    }
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn __m3c_poll(
+    fds: *mut CompatPollFd,
+    nfds: usize,
+    timeout: i64,
+    ready: *mut usize,
+) -> Code {
+    let fds = util::slice_for_mut(fds, nfds);
+
+    // entries with negative file descriptors are ignored
+    let mut pfds = Vec::new();
+    for f in fds.iter_mut() {
+        f.revents = FileEvent::empty();
+        if f.fd >= 0 {
+            pfds.push(PollFd::new(f.fd as usize, f.events));
+        }
+    }
+
+    *ready = try_res!(poll(&mut pfds, poll_timeout(timeout)));
+
+    for (f, pf) in fds.iter_mut().filter(|f| f.fd >= 0).zip(pfds.iter()) {
+        f.revents = pf.revents();
+    }
+    Code::Success
+}
+
+unsafe fn fdset_test(set: *mut u64, fd: usize) -> bool {
+    !set.is_null() && (*set.add(fd / 64) & (1 << (fd % 64))) != 0
+}
+
+unsafe fn fdset_set(set: *mut u64, fd: usize, val: bool) {
+    if !set.is_null() {
+        if val {
+            *set.add(fd / 64) |= 1 << (fd % 64);
+        }
+        else {
+            *set.add(fd / 64) &= !(1 << (fd % 64));
+        }
+    }
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn __m3c_select(
+    nfds: i32,
+    readfds: *mut u64,
+    writefds: *mut u64,
+    timeout: i64,
+    ready: *mut i32,
+) -> Code {
+    let mut pfds = Vec::new();
+    for fd in 0..nfds.max(0) as usize {
+        let mut events = FileEvent::empty();
+        if fdset_test(readfds, fd) {
+            events |= FileEvent::INPUT;
+        }
+        if fdset_test(writefds, fd) {
+            events |= FileEvent::OUTPUT;
+        }
+        if !events.is_empty() {
+            pfds.push(PollFd::new(fd, events));
+        }
+    }
+
+    try_res!(poll(&mut pfds, poll_timeout(timeout)));
+
+    // in contrast to poll, select counts the ready events instead of the ready files
+    *ready = 0;
+    for pf in &pfds {
+        let fd = match pf.source() {
+            PollSource::File(fd) => fd,
+            PollSource::Gate(_) => unreachable!(),
+        };
+        for (set, ev) in [(readfds, FileEvent::INPUT), (writefds, FileEvent::OUTPUT)] {
+            let is_ready = pf.revents().contains(ev);
+            fdset_set(set, fd, is_ready);
+            if is_ready {
+                *ready += 1;
+            }
+        }
+    }
+    Code::Success
+}
+
 #[repr(C)]
 pub enum CompatSock {
     INVALID,
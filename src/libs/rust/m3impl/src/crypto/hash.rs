@@ -32,17 +32,24 @@ pub struct HashAlgorithm {
 int_enum! {
     /// The hash type ID for [`HashAlgorithm`].
     pub struct HashType : u64 {
-        // Note: Must match the order in HashAlgorithm::ALL
+        // Note: keyed algorithms need to come last (see HashAlgorithm::is_keyed)
         const SHA3_224 = 1;
         const SHA3_256 = 2;
         const SHA3_384 = 3;
         const SHA3_512 = 4;
         const SHAKE128 = 5;
         const SHAKE256 = 6;
+        const KMAC128 = 7;
+        const KMAC256 = 8;
+        const HMAC_SHA3_224 = 9;
+        const HMAC_SHA3_256 = 10;
+        const HMAC_SHA3_384 = 11;
+        const HMAC_SHA3_512 = 12;
     }
 }
 
 impl HashAlgorithm {
+    /// All hash algorithms that do not take a key
     pub const ALL: [&'static HashAlgorithm; 6] = [
         &Self::SHA3_224,
         &Self::SHA3_256,
@@ -51,6 +58,28 @@ impl HashAlgorithm {
         &Self::SHAKE128,
         &Self::SHAKE256,
     ];
+    pub const HMAC_SHA3_224: HashAlgorithm =
+        Self::new_sha3("hmac-sha3-224", HashType::HMAC_SHA3_224, 1152, 224);
+    pub const HMAC_SHA3_256: HashAlgorithm =
+        Self::new_sha3("hmac-sha3-256", HashType::HMAC_SHA3_256, 1088, 256);
+    pub const HMAC_SHA3_384: HashAlgorithm =
+        Self::new_sha3("hmac-sha3-384", HashType::HMAC_SHA3_384, 832, 384);
+    pub const HMAC_SHA3_512: HashAlgorithm =
+        Self::new_sha3("hmac-sha3-512", HashType::HMAC_SHA3_512, 576, 512);
+    /// All hash algorithms that take a key (see [`HashAlgorithm::is_keyed`])
+    pub const KEYED: [&'static HashAlgorithm; 6] = [
+        &Self::KMAC128,
+        &Self::KMAC256,
+        &Self::HMAC_SHA3_224,
+        &Self::HMAC_SHA3_256,
+        &Self::HMAC_SHA3_384,
+        &Self::HMAC_SHA3_512,
+    ];
+    // KMAC is used with a fixed output length of 256 and 512 bits, respectively
+    pub const KMAC128: HashAlgorithm = Self::new_sha3("kmac128", HashType::KMAC128, 1344, 256);
+    pub const KMAC256: HashAlgorithm = Self::new_sha3("kmac256", HashType::KMAC256, 1088, 512);
+    /// The maximum key size in bytes for keyed hash algorithms (smaller than all block sizes).
+    pub const MAX_KEY_BYTES: usize = 512 / 8;
     pub const MAX_OUTPUT_BYTES: usize = 512 / 8;
     pub const SHA3_224: HashAlgorithm = Self::new_sha3("sha3-224", HashType::SHA3_224, 1152, 224);
     pub const SHA3_256: HashAlgorithm = Self::new_sha3("sha3-256", HashType::SHA3_256, 1088, 256);
@@ -79,13 +108,18 @@ impl HashAlgorithm {
 
     /// Obtain the [`HashAlgorithm`] from the specified [`HashType`], if valid.
     pub fn from_type(ty: HashType) -> Option<&'static HashAlgorithm> {
-        HashAlgorithm::ALL.get(ty.val as usize - 1).copied()
+        HashAlgorithm::ALL
+            .iter()
+            .chain(HashAlgorithm::KEYED.iter())
+            .copied()
+            .find(|algo| algo.ty == ty)
     }
 
     /// Obtain the [`HashAlgorithm`] from the specified name, if valid.
     pub fn from_name(name: &str) -> Option<&'static HashAlgorithm> {
         HashAlgorithm::ALL
             .iter()
+            .chain(HashAlgorithm::KEYED.iter())
             .copied()
             .find(|algo| algo.name == name)
     }
@@ -95,6 +129,12 @@ impl HashAlgorithm {
     pub fn is_xof(&self) -> bool {
         self.output_bytes == usize::MAX
     }
+
+    /// Returns if this [`HashAlgorithm`] takes a key (KMAC or HMAC) that is supplied when
+    /// resetting the hash session.
+    pub fn is_keyed(&self) -> bool {
+        self.ty.val >= HashType::KMAC128.val
+    }
 }
 
 impl fmt::Display for HashAlgorithm {
@@ -72,12 +72,46 @@ impl HashSession {
 
     /// Reset the state of the hash session (discarding all previous input and
     /// output data) and change the [`HashAlgorithm`].
+    ///
+    /// Keyed hash algorithms use an empty key in this case.
     pub fn reset(&mut self, algo: &'static HashAlgorithm) -> Result<(), Error> {
         send_recv_res!(&self.sgate, RecvGate::def(), HashOp::RESET, algo.ty).map(|_| ())?;
         self.algo = algo;
         Ok(())
     }
 
+    /// Reset the state of the hash session like [`reset`](HashSession::reset), but change to the
+    /// keyed [`HashAlgorithm`] `algo` using the given key.
+    ///
+    /// The key is kept by the hash multiplexer until the next reset and can be at most
+    /// [`HashAlgorithm::MAX_KEY_BYTES`] bytes large.
+    pub fn reset_with_key(
+        &mut self,
+        algo: &'static HashAlgorithm,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        if !algo.is_keyed() || key.len() > HashAlgorithm::MAX_KEY_BYTES {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let mut words = [0u64; HashAlgorithm::MAX_KEY_BYTES / 8];
+        for (i, b) in key.iter().enumerate() {
+            words[i / 8] |= (*b as u64) << ((i % 8) * 8);
+        }
+
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            HashOp::RESET,
+            algo.ty,
+            key.len(),
+            words
+        )
+        .map(|_| ())?;
+        self.algo = algo;
+        Ok(())
+    }
+
     /// Input new data into the state of the hash session.
     ///
     /// Before this is called, the [`ep`](HashSession::ep) should be configured with a valid
@@ -22,9 +22,9 @@ use core::fmt;
 use crate::boxed::Box;
 use crate::cap::Selector;
 use crate::cell::RefCell;
-use crate::col::Vec;
+use crate::col::{String, Vec};
 use crate::com::{recv_result, RecvGate, SendGate, EP};
-use crate::errors::Error;
+use crate::errors::{Code, Error};
 use crate::goff;
 use crate::kif;
 use crate::rc::Rc;
@@ -32,7 +32,8 @@ use crate::serialize::{M3Deserializer, M3Serializer, VecSink};
 use crate::session::ClientSession;
 use crate::tiles::{Activity, ChildActivity};
 use crate::vfs::{
-    FSHandle, FSOperation, File, FileInfo, FileMode, FileSystem, GenericFile, OpenFlags,
+    FSHandle, FSOperation, File, FileInfo, FileMode, FileSystem, FsStats, FsckReport, GenericFile,
+    OpenFlags,
 };
 
 struct CachedEP {
@@ -107,6 +108,21 @@ impl M3FS {
         )?;
         Ok((offset, len, crd.start()))
     }
+
+    /// Writes back the file range `off`..`off + len` that has been modified via memory
+    /// capabilities obtained by [`get_mem`](Self::get_mem).
+    pub fn sync_mem(sess: &ClientSession, off: goff, len: goff) -> Result<(), Error> {
+        sess.obtain(
+            0,
+            |os| {
+                os.push(FSOperation::SYNC_MEM);
+                os.push(off);
+                os.push(len);
+            },
+            |_| Ok(()),
+        )
+        .map(|_| ())
+    }
 }
 
 impl FileSystem for M3FS {
@@ -192,6 +208,11 @@ impl FileSystem for M3FS {
         send_recv_res!(&self.sgate, RecvGate::def(), FSOperation::RMDIR, path).map(|_| ())
     }
 
+    fn mkfifo(&self, _path: &str) -> Result<(), Error> {
+        // named pipes are provided by the pipes server
+        Err(Error::new(Code::NotSup))
+    }
+
     fn link(&self, old_path: &str, new_path: &str) -> Result<(), Error> {
         send_recv_res!(
             &self.sgate,
@@ -218,6 +239,57 @@ impl FileSystem for M3FS {
         .map(|_| ())
     }
 
+    fn symlink(&self, target: &str, path: &str) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            FSOperation::SYMLINK,
+            target,
+            path
+        )
+        .map(|_| ())
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, Error> {
+        send_vmsg!(&self.sgate, RecvGate::def(), FSOperation::READLINK, path)?;
+        let mut reply = recv_result(RecvGate::def(), Some(&self.sgate))?;
+        reply.pop()
+    }
+
+    fn chmod(&self, path: &str, mode: FileMode) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            FSOperation::CHMOD,
+            path,
+            mode.bits()
+        )
+        .map(|_| ())
+    }
+
+    fn chown(&self, path: &str, uid: u32, gid: u32) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            FSOperation::CHOWN,
+            path,
+            uid,
+            gid
+        )
+        .map(|_| ())
+    }
+
+    fn fsck(&self, repair: bool) -> Result<FsckReport, Error> {
+        send_vmsg!(&self.sgate, RecvGate::def(), FSOperation::FSCK, repair)?;
+        let mut reply = recv_result(RecvGate::def(), Some(&self.sgate))?;
+        reply.pop()
+    }
+
+    fn statfs(&self) -> Result<FsStats, Error> {
+        let mut reply = send_recv_res!(&self.sgate, RecvGate::def(), FSOperation::STATFS)?;
+        reply.pop()
+    }
+
     fn fs_type(&self) -> u8 {
         b'M'
     }
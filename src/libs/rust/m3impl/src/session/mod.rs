@@ -25,6 +25,7 @@ mod m3fs;
 mod netmng;
 mod pager;
 mod pipe;
+mod pipefs;
 pub mod resmng;
 mod srvsession;
 
@@ -33,7 +34,10 @@ pub use self::disk::{BlockNo, BlockRange, Disk, DiskOperation};
 pub use self::hash::{HashInput, HashOp, HashOutput, HashSession};
 pub use self::m3fs::M3FS;
 pub use self::netmng::{NetworkManager, NetworkOp};
-pub use self::pager::{MapFlags, Pager, PagerOp};
+pub use self::pager::{
+    Advice, DataSpaceInfo, FaultStats, MapFlags, Pager, PagerOp, PagerStats, StatsResult,
+};
 pub use self::pipe::{Pipe, PipeOperation, Pipes};
+pub use self::pipefs::PipeFS;
 pub use self::resmng::ResMng;
 pub use self::srvsession::ServerSession;
@@ -65,6 +65,29 @@ int_enum! {
         const UNMAP     = 0x8;
         /// Close the pager session
         const CLOSE     = 0x9;
+        /// Write back the modifications of shared file mappings
+        const MSYNC     = 0xA;
+        /// Change the permissions of existing mappings
+        const PROTECT   = 0xB;
+        /// Give a hint about the expected accesses to existing mappings
+        const ADVISE    = 0xC;
+        /// Get memory statistics of the address space or information about a dataspace
+        const STATS     = 0xD;
+    }
+}
+
+int_enum! {
+    /// The hints about the expected accesses to a mapping (see [`Pager::advise`])
+    #[repr(C)]
+    pub struct Advice : u32 {
+        /// No special treatment
+        const NORMAL     = 0x0;
+        /// The pages will be accessed sequentially; fault in larger chunks
+        const SEQUENTIAL = 0x1;
+        /// The pages will be accessed soon; fault them in now
+        const WILLNEED   = 0x2;
+        /// The pages will not be accessed anymore; free them
+        const DONTNEED   = 0x3;
     }
 }
 
@@ -84,6 +107,73 @@ bitflags! {
     }
 }
 
+/// The page faults that have been handled for an address space, by type
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct FaultStats {
+    /// Faults that allocated anonymous memory
+    pub anon: u64,
+    /// Faults that obtained memory from a file
+    pub file: u64,
+    /// Faults that resolved copy-on-write
+    pub cow: u64,
+    /// Faults that read memory back from swap
+    pub swap: u64,
+    /// Faults that only (re)mapped present memory
+    pub minor: u64,
+}
+
+/// The memory statistics of an address space (see [`Pager::stats`])
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct PagerStats {
+    /// The number of dataspaces (see [`Pager::dataspace_info`])
+    pub dataspaces: usize,
+    /// The total size of all mappings in bytes
+    pub virt_size: goff,
+    /// The number of pages that are backed by memory
+    pub resident: usize,
+    /// The number of resident pages that are shared copy-on-write
+    pub cow: usize,
+    /// The number of resident pages of anonymous mappings
+    pub anon: usize,
+    /// The number of resident pages of file mappings
+    pub file: usize,
+    /// The number of pages that are swapped out
+    pub swapped: usize,
+    /// The handled page faults
+    pub faults: FaultStats,
+}
+
+/// Information about a dataspace of an address space (see [`Pager::dataspace_info`])
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct DataSpaceInfo {
+    /// The start address
+    pub virt: goff,
+    /// The size in bytes
+    pub size: goff,
+    /// The access permissions
+    pub perm: kif::Perm,
+    /// The mapping flags
+    pub flags: MapFlags,
+    /// Whether the dataspace is backed by a file
+    pub file: bool,
+    /// The number of pages that are backed by memory
+    pub resident: usize,
+    /// The number of resident pages that are shared copy-on-write
+    pub cow: usize,
+    /// The number of pages that are swapped out
+    pub swapped: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub enum StatsResult {
+    Stats(PagerStats),
+    DataSpace(DataSpaceInfo),
+}
+
 impl Pager {
     fn get_sgate(sess: &ClientSession) -> Result<cap::Selector, Error> {
         sess.obtain(1, |os| os.push(PagerOp::ADD_SGATE), |_| Ok(()))
@@ -276,6 +366,67 @@ impl Pager {
     pub fn unmap(&self, addr: goff) -> Result<(), Error> {
         send_recv_res!(&self.req_sgate, RecvGate::def(), PagerOp::UNMAP, addr).map(|_| ())
     }
+
+    /// Writes the modifications of shared file mappings within `addr`..`addr + len` back to the
+    /// files.
+    pub fn msync(&self, addr: goff, len: usize) -> Result<(), Error> {
+        send_recv_res!(&self.req_sgate, RecvGate::def(), PagerOp::MSYNC, addr, len).map(|_| ())
+    }
+
+    /// Changes the permissions of the mappings within `addr`..`addr + len` to `prot`.
+    pub fn protect(&self, addr: goff, len: usize, prot: kif::Perm) -> Result<(), Error> {
+        send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::PROTECT,
+            addr,
+            len,
+            prot
+        )
+        .map(|_| ())
+    }
+
+    /// Gives the pager the hint `advice` about the accesses to `addr`..`addr + len`.
+    pub fn advise(&self, addr: goff, len: usize, advice: Advice) -> Result<(), Error> {
+        send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::ADVISE,
+            addr,
+            len,
+            advice
+        )
+        .map(|_| ())
+    }
+
+    /// Retrieves the memory statistics of the address space.
+    pub fn stats(&self) -> Result<PagerStats, Error> {
+        match self.get_stats(None) {
+            Ok(StatsResult::Stats(s)) => Ok(s),
+            Err(e) => Err(e),
+            _ => panic!("unexpected stats type"),
+        }
+    }
+
+    /// Retrieves information about the dataspace with given index. The number of dataspaces is
+    /// available via [`stats`](Self::stats).
+    pub fn dataspace_info(&self, idx: usize) -> Result<DataSpaceInfo, Error> {
+        match self.get_stats(Some(idx)) {
+            Ok(StatsResult::DataSpace(i)) => Ok(i),
+            Err(e) => Err(e),
+            _ => panic!("unexpected stats type"),
+        }
+    }
+
+    fn get_stats(&self, idx: Option<usize>) -> Result<StatsResult, Error> {
+        let mut reply = send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::STATS,
+            idx.unwrap_or(usize::MAX)
+        )?;
+        reply.pop()
+    }
 }
 
 impl Drop for Pager {
@@ -37,6 +37,10 @@ int_enum! {
         const OPEN_CHAN     = Self::OPEN_PIPE.val + 1;
         const SET_MEM       = Self::OPEN_CHAN.val + 1;
         const CLOSE_PIPE    = Self::SET_MEM.val + 1;
+        const OPEN_FIFO     = Self::CLOSE_PIPE.val + 1;
+        const MKFIFO        = Self::OPEN_FIFO.val + 1;
+        const UNLINK_FIFO   = Self::MKFIFO.val + 1;
+        const STAT_FIFO     = Self::UNLINK_FIFO.val + 1;
     }
 }
 
@@ -49,15 +53,30 @@ impl Pipes {
 
     /// Creates a new pipe using `mem` of `mem_size` bytes as shared memory for the data exchange.
     pub fn create_pipe(&self, mem: &MemGate, mem_size: usize) -> Result<Pipe, Error> {
+        self.do_create_pipe(mem, mem_size, false)
+    }
+
+    /// Creates a new packet pipe using `mem` of `mem_size` bytes as shared memory for the data
+    /// exchange.
+    ///
+    /// In contrast to ordinary pipes, every write of up to `mem_size` bytes is received as a unit
+    /// by exactly one read of one of the readers. If the read buffer is smaller than the packet,
+    /// the rest of the packet is discarded.
+    pub fn create_packet_pipe(&self, mem: &MemGate, mem_size: usize) -> Result<Pipe, Error> {
+        self.do_create_pipe(mem, mem_size, true)
+    }
+
+    fn do_create_pipe(&self, mem: &MemGate, mem_size: usize, packet: bool) -> Result<Pipe, Error> {
         let crd = self.sess.obtain(
             2,
             |os| {
                 os.push(PipeOperation::OPEN_PIPE);
                 os.push(mem_size);
+                os.push(packet);
             },
             |_| Ok(()),
         )?;
-        Pipe::new(mem, crd.start())
+        Pipe::new(mem, crd.start(), packet)
     }
 }
 
@@ -65,10 +84,11 @@ impl Pipes {
 pub struct Pipe {
     sess: ClientSession,
     sgate: SendGate,
+    packet: bool,
 }
 
 impl Pipe {
-    fn new(mem: &MemGate, sel: Selector) -> Result<Self, Error> {
+    fn new(mem: &MemGate, sel: Selector, packet: bool) -> Result<Self, Error> {
         let sess = ClientSession::new_bind(sel);
         sess.delegate(
             CapRngDesc::new(CapType::OBJECT, mem.sel(), 1),
@@ -80,6 +100,7 @@ impl Pipe {
         Ok(Pipe {
             sess,
             sgate: SendGate::new_bind(sel + 1),
+            packet,
         })
     }
 
@@ -99,12 +120,15 @@ impl Pipe {
             },
             |_| Ok(()),
         )?;
-        let flags = if read {
+        let mut flags = if read {
             OpenFlags::R | OpenFlags::NEW_SESS
         }
         else {
             OpenFlags::W | OpenFlags::NEW_SESS
         };
+        if self.packet {
+            flags |= OpenFlags::PACKET;
+        }
         Ok(Box::new(GenericFile::new(flags, crd.start(), None)))
     }
 }
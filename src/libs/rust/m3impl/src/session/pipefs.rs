@@ -0,0 +1,207 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use core::any::Any;
+use core::fmt;
+
+use crate::boxed::Box;
+use crate::cap::Selector;
+use crate::cell::RefCell;
+use crate::col::String;
+use crate::errors::{Code, Error};
+use crate::rc::Rc;
+use crate::serialize::{M3Deserializer, M3Serializer, VecSink};
+use crate::session::{ClientSession, PipeOperation};
+use crate::tiles::ChildActivity;
+use crate::vfs::{
+    FSHandle, File, FileInfo, FileMode, FileSystem, FsStats, FsckReport, GenericFile, INodeId,
+    OpenFlags,
+};
+
+/// Represents a session at the pipes server that provides access to named pipes.
+///
+/// The file system consists of a single directory that contains named pipes only. Named pipes are
+/// created via [`VFS::mkfifo`](crate::vfs::VFS::mkfifo) or by opening them with
+/// [`OpenFlags::CREATE`] and are opened either for reading or for writing. Opening a named pipe
+/// yields a channel of the pipe, just like [`Pipe::create_chan`](crate::session::Pipe).
+pub struct PipeFS {
+    id: usize,
+    sess: ClientSession,
+}
+
+impl PipeFS {
+    fn create(id: usize, sess: ClientSession) -> FSHandle {
+        Rc::new(RefCell::new(PipeFS { id, sess }))
+    }
+
+    /// Creates a new session at the pipes server with given name.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(id: usize, name: &str) -> Result<FSHandle, Error> {
+        let sess = ClientSession::new(name)?;
+        Ok(Self::create(id, sess))
+    }
+
+    /// Binds a new pipes-session to selector `sel`.
+    pub fn new_bind(id: usize, sel: Selector) -> FSHandle {
+        Self::create(id, ClientSession::new_bind(sel))
+    }
+
+    /// Returns a reference to the underlying [`ClientSession`]
+    pub fn sess(&self) -> &ClientSession {
+        &self.sess
+    }
+
+    pub fn unserialize(s: &mut M3Deserializer<'_>) -> FSHandle {
+        let sel: Selector = s.pop().unwrap();
+        let id: usize = s.pop().unwrap();
+        PipeFS::new_bind(id, sel)
+    }
+
+    fn fifo_op(&self, op: PipeOperation, path: &str) -> Result<usize, Error> {
+        let mut res = 0;
+        self.sess.obtain(
+            0,
+            |os| {
+                os.push(op);
+                os.push(path);
+            },
+            |is| {
+                if op == PipeOperation::STAT_FIFO {
+                    res = is.pop()?;
+                }
+                Ok(())
+            },
+        )?;
+        Ok(res)
+    }
+}
+
+impl FileSystem for PipeFS {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn open(&mut self, path: &str, flags: OpenFlags) -> Result<Box<dyn File>, Error> {
+        let crd = self.sess.obtain(
+            2,
+            |os| {
+                os.push(PipeOperation::OPEN_FIFO);
+                os.push(flags);
+                os.push(path);
+            },
+            |_| Ok(()),
+        )?;
+        Ok(Box::new(GenericFile::new(
+            flags | OpenFlags::NEW_SESS,
+            crd.start(),
+            None,
+        )))
+    }
+
+    fn close(&mut self, _file_id: usize) {
+    }
+
+    fn stat(&self, path: &str) -> Result<FileInfo, Error> {
+        // the root directory is the only directory
+        if path.is_empty() {
+            return Ok(FileInfo {
+                mode: FileMode::DIR_DEF | FileMode::IRWXU,
+                links: 1,
+                ..Default::default()
+            });
+        }
+
+        let ino = self.fifo_op(PipeOperation::STAT_FIFO, path)?;
+        Ok(FileInfo {
+            inode: ino as INodeId,
+            mode: FileMode::FIFO_DEF,
+            links: 1,
+            ..Default::default()
+        })
+    }
+
+    fn mkdir(&self, _path: &str, _mode: FileMode) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn rmdir(&self, _path: &str) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn mkfifo(&self, path: &str) -> Result<(), Error> {
+        self.fifo_op(PipeOperation::MKFIFO, path).map(|_| ())
+    }
+
+    fn link(&self, _old_path: &str, _new_path: &str) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn unlink(&self, path: &str) -> Result<(), Error> {
+        self.fifo_op(PipeOperation::UNLINK_FIFO, path).map(|_| ())
+    }
+
+    fn rename(&self, _old_path: &str, _new_path: &str) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn symlink(&self, _target: &str, _path: &str) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn readlink(&self, _path: &str) -> Result<String, Error> {
+        // there are no symbolic links
+        Err(Error::new(Code::InvArgs))
+    }
+
+    fn chmod(&self, _path: &str, _mode: FileMode) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn chown(&self, _path: &str, _uid: u32, _gid: u32) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn fsck(&self, _repair: bool) -> Result<FsckReport, Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn statfs(&self) -> Result<FsStats, Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    fn fs_type(&self) -> u8 {
+        b'P'
+    }
+
+    fn delegate(&self, act: &ChildActivity) -> Result<Selector, Error> {
+        act.delegate_obj(self.sess.sel())?;
+        Ok(self.sess.sel() + 1)
+    }
+
+    fn serialize(&self, s: &mut M3Serializer<VecSink<'_>>) {
+        s.push(self.sess.sel());
+        s.push(self.id);
+    }
+}
+
+impl fmt::Debug for PipeFS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PipeFS[id={}, sess={:?}]", self.id, self.sess)
+    }
+}
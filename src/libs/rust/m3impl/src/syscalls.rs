@@ -25,6 +25,7 @@ use core::mem::MaybeUninit;
 use crate::build_vmsg;
 use crate::cap::Selector;
 use crate::cell::{LazyStaticRefCell, Ref, StaticRefCell};
+use crate::col::Vec;
 use crate::com::{RecvGate, SendGate};
 use crate::errors::{Code, Error};
 use crate::goff;
@@ -218,7 +219,8 @@ pub fn create_map(
 
 /// Creates a new activity on tile `tile` with given name at the selector range `dst`.
 ///
-/// The argument `kmem` defines the kernel memory to assign to the activity.
+/// The argument `kmem` defines the kernel memory to assign to the activity and `prio` its priority
+/// class.
 ///
 /// On success, the function returns the activity id (for debugging purposes) and EP id of the first
 /// standard EP.
@@ -227,6 +229,7 @@ pub fn create_activity(
     name: &str,
     tile: Selector,
     kmem: Selector,
+    prio: kif::Priority,
 ) -> Result<(ActId, EpId), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
     build_vmsg!(
@@ -234,9 +237,10 @@ pub fn create_activity(
         syscalls::Operation::CREATE_ACT,
         syscalls::CreateActivity {
             dst,
-            name,
             tile,
-            kmem
+            kmem,
+            prio,
+            name,
         }
     );
 
@@ -659,6 +663,43 @@ pub fn reset_stats() -> Result<(), Error> {
     send_receive_result(&buf)
 }
 
+/// Lists the capabilities of type `ty` of the activity `act`, starting at selector `start`.
+///
+/// The activity is specified by its activity capability, so that only the activity itself and the
+/// holders of its activity capability are allowed to inspect its capabilities. The call returns at
+/// most `syscalls::MAX_CAP_INFOS` capabilities along with the selector to continue with, which is
+/// `INVALID_SEL` if there are no further capabilities.
+pub fn list_caps(
+    act: Selector,
+    ty: kif::CapType,
+    start: Selector,
+) -> Result<(Vec<syscalls::CapInfo>, Selector), Error> {
+    let mut buf = SYSC_BUF.borrow_mut();
+    build_vmsg!(buf, syscalls::Operation::LIST_CAPS, syscalls::ListCaps {
+        act,
+        ty,
+        start
+    });
+
+    let reply: Reply<syscalls::ListCapsReply> = send_receive(&buf)?;
+    let caps = reply.data.caps[0..reply.data.count].to_vec();
+    Ok((caps, reply.data.next))
+}
+
+/// Returns the resource usage of the activity `act`.
+///
+/// In contrast to [`reset_stats`], the counters are not reset. If the activity has already exited,
+/// the counters at the time of the exit are returned.
+pub fn activity_stats(act: Selector) -> Result<kif::ActivityStats, Error> {
+    let mut buf = SYSC_BUF.borrow_mut();
+    build_vmsg!(buf, syscalls::Operation::ACT_STATS, syscalls::ActStats {
+        act
+    });
+
+    let reply: Reply<kif::ActivityStats> = send_receive(&buf)?;
+    Ok(reply.data)
+}
+
 /// The noop system call for benchmarking
 pub fn noop() -> Result<(), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
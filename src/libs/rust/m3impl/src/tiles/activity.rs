@@ -113,6 +113,29 @@ impl Activity {
         syscalls::revoke(self.sel(), crd, !del_only)
     }
 
+    /// Returns the capabilities of given type that `self` currently owns.
+    ///
+    /// This requires that the own activity has access to the activity capability of `self`.
+    pub fn caps(&self, ty: kif::CapType) -> Result<Vec<kif::syscalls::CapInfo>, Error> {
+        let mut res = Vec::new();
+        let mut start = 0;
+        while start != kif::INVALID_SEL {
+            let (mut caps, next) = syscalls::list_caps(self.sel(), ty, start)?;
+            res.append(&mut caps);
+            start = next;
+        }
+        Ok(res)
+    }
+
+    /// Returns the resource usage of `self` as tracked by TileMux.
+    ///
+    /// The counters are not reset by this call. After `self` has exited, the values at the time of
+    /// its exit are returned. Like [`caps`](Self::caps), this requires access to the activity
+    /// capability of `self`.
+    pub fn stats(&self) -> Result<kif::ActivityStats, Error> {
+        syscalls::activity_stats(self.sel())
+    }
+
     /// Creates a new memory gate that refers to the address region `addr`..`addr`+`size` in the
     /// address space of this activity. The region must be physically contiguous and page aligned.
     pub fn get_mem(&self, addr: goff, size: goff, perms: kif::Perm) -> Result<MemGate, Error> {
@@ -58,6 +58,7 @@ pub struct ActivityArgs<'n> {
     pager: Option<Pager>,
     kmem: Option<Rc<KMem>>,
     rmng: Option<ResMng>,
+    prio: kif::Priority,
     first_sel: Selector,
 }
 
@@ -69,6 +70,7 @@ impl<'n> ActivityArgs<'n> {
             pager: None,
             kmem: None,
             rmng: None,
+            prio: kif::Priority::NORMAL,
             first_sel: kif::FIRST_FREE_SEL,
         }
     }
@@ -93,6 +95,16 @@ impl<'n> ActivityArgs<'n> {
         self
     }
 
+    /// Sets the priority class of the activity (kif::Priority::NORMAL by default).
+    ///
+    /// On shared tiles, ready activities of a higher class always run before activities of lower
+    /// classes. The priority cannot exceed the priority of the own activity and is fixed for the
+    /// lifetime of the activity, that is, it cannot be changed after the activity was created.
+    pub fn prio(mut self, prio: kif::Priority) -> Self {
+        self.prio = prio;
+        self
+    }
+
     /// Sets the first selector to be used by the child (kif::FIRST_FREE_SEL by default).
     pub fn first_sel(mut self, sel: Selector) -> Self {
         self.first_sel = sel;
@@ -142,7 +154,7 @@ impl ChildActivity {
 
         // actually create activity via syscall
         let (id, eps_start) =
-            syscalls::create_activity(sel, args.name, tile.sel(), act.kmem().sel())?;
+            syscalls::create_activity(sel, args.name, tile.sel(), act.kmem().sel(), args.prio)?;
         act.id = id;
         act.eps_start = eps_start;
 
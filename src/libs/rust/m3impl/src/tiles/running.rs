@@ -17,8 +17,9 @@
 
 use crate::errors::{Code, Error};
 use crate::kif;
+use crate::rc::Rc;
 use crate::syscalls;
-use crate::tiles::ChildActivity;
+use crate::tiles::{ChildActivity, Tile};
 use crate::vfs::{BufReader, File, FileRef};
 
 /// Represents an activity that is run on a [`ChildActivity`].
@@ -40,6 +41,36 @@ pub trait RunningActivity {
             .map(|_| ())
     }
 
+    /// Suspends the activity, i.e., it is no longer scheduled until it is resumed.
+    ///
+    /// The activity keeps its capabilities and memory while being suspended.
+    fn suspend(&self) -> Result<(), Error> {
+        syscalls::activity_ctrl(self.activity().sel(), kif::syscalls::ActivityOp::SUSPEND, 0)
+            .map(|_| ())
+    }
+
+    /// Resumes the previously suspended activity.
+    fn resume(&self) -> Result<(), Error> {
+        syscalls::activity_ctrl(self.activity().sel(), kif::syscalls::ActivityOp::RESUME, 0)
+            .map(|_| ())
+    }
+
+    /// Migrates the activity to the given tile.
+    ///
+    /// The tile needs to be of the same type as the current one. If the activity is running, it is
+    /// suspended before and resumed after the migration. The migration fails with
+    /// [`Code::InvState`] if the activity has outstanding messages or is within a system call; in
+    /// this case, the activity continues on its current tile and the migration can be retried.
+    fn migrate(&mut self, tile: Rc<Tile>) -> Result<(), Error> {
+        syscalls::activity_ctrl(
+            self.activity().sel(),
+            kif::syscalls::ActivityOp::MIGRATE,
+            tile.sel(),
+        )?;
+        self.activity_mut().tile = tile;
+        Ok(())
+    }
+
     /// Waits until the activity exits and returns the error code.
     fn wait(&self) -> Result<Code, Error> {
         syscalls::activity_wait(&[self.activity().sel()], 0).map(|r| r.1)
@@ -65,6 +65,8 @@ bitflags! {
         const NODATA    = 0b0100_0000;
         /// Create a new file session
         const NEW_SESS  = 0b1000_0000;
+        /// Treats every write as a packet that is received by a single read (pipes only).
+        const PACKET    = 0b1_0000_0000;
 
         /// Opens the file for reading and writing.
         const RW        = Self::R.bits | Self::W.bits;
@@ -114,6 +116,8 @@ bitflags! {
 
         const FILE_DEF  = Self::IFREG.bits | 0o0644;
         const DIR_DEF   = Self::IFDIR.bits;
+        const LNK_DEF   = Self::IFLNK.bits | 0o0777;
+        const FIFO_DEF  = Self::IFPIP.bits | 0o0666;
         const PERM      = 0o777;
     }
 }
@@ -160,6 +164,8 @@ pub struct FileInfo {
     // for debugging
     pub extents: u32,
     pub firstblock: BlockId,
+    pub uid: u32,
+    pub gid: u32,
 }
 
 bitflags! {
@@ -168,6 +174,24 @@ bitflags! {
         const INPUT         = 1;
         const OUTPUT        = 2;
         const SIGNAL        = 4;
+        // changes of directories
+        const CREATE        = 8;
+        const UNLINK        = 16;
+        const RENAME        = 32;
+        const MODIFY        = 64;
+        const DIR_CHANGES   = Self::CREATE.bits | Self::UNLINK.bits | Self::RENAME.bits
+                            | Self::MODIFY.bits;
+    }
+}
+
+int_enum! {
+    /// The types of advisory file locks.
+    #[repr(C)]
+    pub struct LockType : u32 {
+        /// A lock that can be held by multiple files at once
+        const SHARED    = 0x0;
+        /// A lock that excludes all other locks
+        const EXCLUSIVE = 0x1;
     }
 }
 
@@ -213,11 +237,41 @@ pub trait File: Read + Write + Seek + Map + Debug + HashInput + HashOutput {
         Err(Error::new(Code::NotSup))
     }
 
-    /// Truncates the file to the given length
+    /// Truncates the file to the given length. If the file is shorter, it is extended by a hole,
+    /// which reads as zeros.
     fn truncate(&mut self, _length: usize) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
 
+    /// Allocates blocks for the `len` bytes at offset `off` so that writing them cannot fail due
+    /// to insufficient space. The file is extended if the range ends behind its end.
+    fn allocate(&mut self, _off: usize, _len: usize) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    /// Frees the blocks within the `len` bytes at offset `off`, which read as zeros afterwards.
+    /// The file size is not changed.
+    fn punch_hole(&mut self, _off: usize, _len: usize) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    /// Acquires an advisory lock of type `ty` for the `len` bytes at offset `off`. A length of 0
+    /// locks everything from `off` onwards, so that `lock(0, 0, ..)` locks the whole file.
+    ///
+    /// Locks are owned by this file and replace its previous locks within the range. They are
+    /// released by [`File::unlock`] or when the file is closed. If another file holds a conflicting
+    /// lock, the call waits until the lock is released if `wait` is true and fails with
+    /// [`Code::WouldBlock`] otherwise.
+    fn lock(&mut self, _off: usize, _len: usize, _ty: LockType, _wait: bool) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    /// Releases the locks of this file for the `len` bytes at offset `off`, where a length of 0
+    /// refers to everything from `off` onwards.
+    fn unlock(&mut self, _off: usize, _len: usize) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
     /// Returns the current terminal mode in case the server is a terminal
     fn get_tmode(&self) -> Result<TMode, Error> {
         Err(Error::new(Code::NotSup))
@@ -265,6 +319,20 @@ pub trait File: Read + Write + Seek + Map + Debug + HashInput + HashOutput {
         Err(Error::new(Code::NotSup))
     }
 
+    /// Fetches the given events from the file, if they have arrived. Note that this might establish
+    /// an additional communication channel to the server, if required and not already done.
+    ///
+    /// This is used to watch directories for changes (see [`FileEvent::DIR_CHANGES`]): the first
+    /// call starts the watch and the following calls return the changes since the previous call. For
+    /// m3fs, the directory needs to be opened with [`OpenFlags::NEW_SESS`].
+    ///
+    /// If the server or the file type does not support these events, an exception is thrown.
+    ///
+    /// Returns the subset of `events` that has arrived
+    fn fetch_events(&mut self, _events: FileEvent) -> Result<FileEvent, Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
     /// Checks whether any of the given events has arrived.
     ///
     /// More specifically, if FileEvent::INPUT is given and reading from the file might result in
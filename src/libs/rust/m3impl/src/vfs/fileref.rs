@@ -31,7 +31,7 @@ use crate::net::{DGramSocket, Socket, StreamSocket};
 use crate::serialize::{M3Serializer, VecSink};
 use crate::session::{HashInput, HashOutput, HashSession, MapFlags, Pager};
 use crate::tiles::{Activity, ChildActivity};
-use crate::vfs::{Fd, File, FileEvent, FileTable, Map, Seek, SeekMode, TMode};
+use crate::vfs::{Fd, File, FileEvent, FileTable, LockType, Map, Seek, SeekMode, TMode};
 
 /// A file reference provides access to a file of type `T`.
 ///
@@ -145,6 +145,22 @@ impl<T: ?Sized + 'static> File for FileRef<T> {
         self.borrow().truncate(length)
     }
 
+    fn allocate(&mut self, off: usize, len: usize) -> Result<(), Error> {
+        self.borrow().allocate(off, len)
+    }
+
+    fn punch_hole(&mut self, off: usize, len: usize) -> Result<(), Error> {
+        self.borrow().punch_hole(off, len)
+    }
+
+    fn lock(&mut self, off: usize, len: usize, ty: LockType, wait: bool) -> Result<(), Error> {
+        self.borrow().lock(off, len, ty, wait)
+    }
+
+    fn unlock(&mut self, off: usize, len: usize) -> Result<(), Error> {
+        self.borrow().unlock(off, len)
+    }
+
     fn get_tmode(&self) -> Result<TMode, Error> {
         self.borrow().get_tmode()
     }
@@ -169,6 +185,10 @@ impl<T: ?Sized + 'static> File for FileRef<T> {
         self.borrow().fetch_signal()
     }
 
+    fn fetch_events(&mut self, events: FileEvent) -> Result<FileEvent, Error> {
+        self.borrow().fetch_events(events)
+    }
+
     fn check_events(&mut self, events: FileEvent) -> bool {
         self.borrow().check_events(events)
     }
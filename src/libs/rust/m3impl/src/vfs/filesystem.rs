@@ -21,9 +21,10 @@ use core::fmt;
 
 use crate::boxed::Box;
 use crate::cap::Selector;
+use crate::col::String;
 use crate::errors::Error;
 use crate::int_enum;
-use crate::serialize::{M3Serializer, VecSink};
+use crate::serialize::{Deserialize, M3Serializer, Serialize, VecSink};
 use crate::tiles::ChildActivity;
 use crate::vfs::{File, FileInfo, FileMode, OpenFlags};
 
@@ -41,9 +42,72 @@ int_enum! {
         const GET_MEM       = 23;
         const DEL_EP        = 24;
         const OPEN_PRIV     = 25;
+        const SYMLINK       = 26;
+        const READLINK      = 27;
+        const CHMOD         = 28;
+        const CHOWN         = 29;
+        const FSCK          = 30;
+        const ALLOCATE      = 31;
+        const PUNCH_HOLE    = 32;
+        const LOCK          = 33;
+        const UNLOCK        = 34;
+        const STATFS        = 35;
+        const SYNC_MEM      = 36;
     }
 }
 
+/// The result of a consistency check of a file system.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct FsckReport {
+    /// The number of checked inodes
+    pub inodes: u32,
+    /// The number of checked blocks
+    pub blocks: u32,
+    /// The number of inodes that are allocated, but not reachable
+    pub leaked_inodes: u32,
+    /// The number of blocks that are allocated, but not used by any inode
+    pub leaked_blocks: u32,
+    /// The number of inodes whose link count does not match the number of directory entries
+    pub bad_links: u32,
+    /// The number of inconsistencies that cannot be repaired, such as blocks used multiple times
+    pub errors: u32,
+    /// Whether the leaked inodes and blocks have been freed
+    pub repaired: bool,
+}
+
+impl FsckReport {
+    /// Returns true if no problems have been found
+    pub fn is_clean(&self) -> bool {
+        self.leaked_inodes == 0
+            && self.leaked_blocks == 0
+            && self.bad_links == 0
+            && self.errors == 0
+    }
+}
+
+/// The statistics of a file system.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct FsStats {
+    /// The size of a block in bytes
+    pub block_size: u32,
+    /// The total number of blocks
+    pub total_blocks: u32,
+    /// The number of free blocks
+    pub free_blocks: u32,
+    /// The total number of inodes
+    pub total_inodes: u32,
+    /// The number of free inodes
+    pub free_inodes: u32,
+    /// Whether the file system journals its metadata updates
+    pub journal: bool,
+    /// Whether the file system stores checksums for its blocks
+    pub checksums: bool,
+    /// The number of blocks whose checksum did not match since the file system has been mounted
+    pub checksum_errors: u64,
+}
+
 /// Trait for file systems.
 pub trait FileSystem: fmt::Debug {
     /// Returns an [`Any`] reference to downcast to the actual implementation of [`FileSystem`].
@@ -66,6 +130,9 @@ pub trait FileSystem: fmt::Debug {
     /// Removes the directory at `path`, if it is empty.
     fn rmdir(&self, path: &str) -> Result<(), Error>;
 
+    /// Creates a new named pipe at `path`.
+    fn mkfifo(&self, path: &str) -> Result<(), Error>;
+
     /// Links `new_path` to `old_path`.
     fn link(&self, old_path: &str, new_path: &str) -> Result<(), Error>;
     /// Removes the file at `path`.
@@ -73,6 +140,23 @@ pub trait FileSystem: fmt::Debug {
     /// Renames `new_path` to `old_path`.
     fn rename(&self, old_path: &str, new_path: &str) -> Result<(), Error>;
 
+    /// Creates a symbolic link at `path` that points to `target`.
+    fn symlink(&self, target: &str, path: &str) -> Result<(), Error>;
+    /// Returns the target of the symbolic link at `path`.
+    fn readlink(&self, path: &str) -> Result<String, Error>;
+
+    /// Changes the permissions of the file at `path` to `mode`.
+    fn chmod(&self, path: &str, mode: FileMode) -> Result<(), Error>;
+    /// Changes the owner of the file at `path` to `uid` and `gid`.
+    fn chown(&self, path: &str, uid: u32, gid: u32) -> Result<(), Error>;
+
+    /// Checks the consistency of the file system and frees leaked inodes and blocks if `repair` is
+    /// true.
+    fn fsck(&self, repair: bool) -> Result<FsckReport, Error>;
+
+    /// Returns the statistics of the file system.
+    fn statfs(&self) -> Result<FsStats, Error>;
+
     /// Returns the type of the file system implementation used for serialization.
     fn fs_type(&self) -> u8;
     /// Delegates this file system to `act`.
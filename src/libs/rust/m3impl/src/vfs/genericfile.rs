@@ -35,9 +35,13 @@ use crate::rc::Rc;
 use crate::serialize::{M3Deserializer, M3Serializer, VecSink};
 use crate::session::{ClientSession, HashInput, HashOutput, HashSession, MapFlags, Pager};
 use crate::tcu::EpId;
-use crate::tiles::{Activity, ChildActivity};
+use crate::tiles::{Activity, ChildActivity, OwnActivity};
+use crate::time::TimeDuration;
 use crate::util::math;
-use crate::vfs::{filetable, Fd, File, FileEvent, FileInfo, Map, OpenFlags, Seek, SeekMode, TMode};
+use crate::vfs::{
+    filetable, FSOperation, Fd, File, FileEvent, FileInfo, LockType, Map, OpenFlags, Seek,
+    SeekMode, TMode,
+};
 
 int_enum! {
     /// The operations for [`GenericFile`].
@@ -61,6 +65,8 @@ int_enum! {
 }
 
 const NOTIFY_MSG_SIZE: usize = 64;
+/// The time to wait before trying to acquire a lock again
+const LOCK_RETRY_DELAY: TimeDuration = TimeDuration::from_micros(100);
 
 struct NonBlocking {
     notify_rgate: Box<RecvGate>,
@@ -174,6 +180,18 @@ impl GenericFile {
         Ok(())
     }
 
+    fn change_blocks(&mut self, op: FSOperation, off: usize, len: usize) -> Result<(), Error> {
+        self.submit(false)?;
+
+        let mut reply = send_recv_res!(&self.sgate, RecvGate::def(), op, self.file_id(), off, len)?;
+        // the server might have revoked our access to the current extent
+        self.goff = reply.pop()?;
+        self.off = reply.pop()?;
+        self.pos = 0;
+        self.len = 0;
+        Ok(())
+    }
+
     fn delegate_ep(&mut self, ep_sel: Selector) -> Result<(), Error> {
         if ep_sel != self.delegated_ep {
             self.submit(true)?;
@@ -227,11 +245,13 @@ impl GenericFile {
                 return Err(Error::new(Code::WouldBlock));
             }
 
+            // tell the server how much we want to write, which is required for packet pipes
             let mut reply = send_recv_res!(
                 &self.sgate,
                 RecvGate::def(),
                 GenFileOp::NEXT_OUT,
-                self.file_id()
+                self.file_id(),
+                len
             )?;
             self.goff += self.len;
             self.off = reply.pop()?;
@@ -429,6 +449,47 @@ impl File for GenericFile {
         Ok(())
     }
 
+    fn allocate(&mut self, off: usize, len: usize) -> Result<(), Error> {
+        self.change_blocks(FSOperation::ALLOCATE, off, len)
+    }
+
+    fn punch_hole(&mut self, off: usize, len: usize) -> Result<(), Error> {
+        self.change_blocks(FSOperation::PUNCH_HOLE, off, len)
+    }
+
+    fn lock(&mut self, off: usize, len: usize, ty: LockType, wait: bool) -> Result<(), Error> {
+        loop {
+            let res = send_recv_res!(
+                &self.sgate,
+                RecvGate::def(),
+                FSOperation::LOCK,
+                self.file_id(),
+                off,
+                len,
+                ty
+            );
+            match res {
+                // the server does not queue lock requests; thus, retry until the lock is free
+                Err(e) if e.code() == Code::WouldBlock && wait => {
+                    OwnActivity::sleep_for(LOCK_RETRY_DELAY)?
+                },
+                res => return res.map(|_| ()),
+            }
+        }
+    }
+
+    fn unlock(&mut self, off: usize, len: usize) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            FSOperation::UNLOCK,
+            self.file_id(),
+            off,
+            len
+        )
+        .map(|_| ())
+    }
+
     fn get_tmode(&self) -> Result<TMode, Error> {
         let mut reply = send_recv_res!(
             &self.sgate,
@@ -474,6 +535,18 @@ impl File for GenericFile {
         self.receive_notify(FileEvent::SIGNAL, true)
     }
 
+    fn fetch_events(&mut self, events: FileEvent) -> Result<FileEvent, Error> {
+        self.enable_notifications()?;
+
+        // request the events, if necessary, and fetch a notification, if there is one
+        self.receive_notify(events, false)?;
+
+        let nb = self.nb_state.as_mut().unwrap();
+        let received = nb.notify_received & events;
+        nb.notify_received &= !received;
+        Ok(received)
+    }
+
     fn check_events(&mut self, events: FileEvent) -> bool {
         if self.blocking {
             true
@@ -530,6 +603,13 @@ impl Read for GenericFile {
             self.pos += amount;
         }
         self.writing = false;
+
+        // release the packet immediately so that other readers can receive the next one; the rest
+        // of the packet is discarded
+        if self.flags.contains(OpenFlags::PACKET) && self.len > 0 {
+            self.pos = self.len;
+            self.submit(true)?;
+        }
         Ok(amount)
     }
 }
@@ -561,6 +641,11 @@ impl Write for GenericFile {
             self.pos += amount;
         }
         self.writing = true;
+
+        // every write is a packet on its own
+        if self.flags.contains(OpenFlags::PACKET) {
+            self.submit(false)?;
+        }
         Ok(amount)
     }
 }
@@ -34,7 +34,17 @@ impl IndirectPipe {
     /// Creates a new pipe at pipe service `pipes` using `mem` as the shared memory of `mem_size`
     /// bytes.
     pub fn new(pipes: &Pipes, mem: &MemGate, mem_size: usize) -> Result<Self, Error> {
-        let pipe = Rc::new(pipes.create_pipe(mem, mem_size)?);
+        Self::with_pipe(pipes.create_pipe(mem, mem_size)?)
+    }
+
+    /// Creates a new packet pipe at pipe service `pipes` using `mem` as the shared memory of
+    /// `mem_size` bytes (see [`Pipes::create_packet_pipe`]).
+    pub fn new_packet(pipes: &Pipes, mem: &MemGate, mem_size: usize) -> Result<Self, Error> {
+        Self::with_pipe(pipes.create_packet_pipe(mem, mem_size)?)
+    }
+
+    fn with_pipe(pipe: Pipe) -> Result<Self, Error> {
+        let pipe = Rc::new(pipe);
         let mut files = Activity::own().files();
         let rd_fd = files.add(pipe.create_chan(true)?)?;
         let wr_fd = files.add(pipe.create_chan(false)?)?;
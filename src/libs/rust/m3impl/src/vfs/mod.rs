@@ -27,6 +27,7 @@ mod filetable;
 mod genericfile;
 mod indirpipe;
 mod mounttable;
+mod poll;
 #[allow(clippy::module_inception)]
 mod vfs;
 mod waiter;
@@ -40,14 +41,17 @@ pub type BlockId = u32;
 
 pub use self::bufio::{BufReader, BufWriter};
 pub use self::dir::{read_dir, DirEntry, ReadDir};
-pub use self::file::{File, FileEvent, FileInfo, FileMode, Map, OpenFlags, Seek, SeekMode, TMode};
+pub use self::file::{
+    File, FileEvent, FileInfo, FileMode, LockType, Map, OpenFlags, Seek, SeekMode, TMode,
+};
 pub use self::fileref::FileRef;
-pub use self::filesystem::{FSOperation, FileSystem};
+pub use self::filesystem::{FSOperation, FileSystem, FsStats, FsckReport};
 pub(crate) use self::filetable::INV_FD;
 pub use self::filetable::{Fd, FileTable};
 pub use self::genericfile::{GenFileOp, GenericFile};
 pub use self::indirpipe::IndirectPipe;
 pub use self::mounttable::{FSHandle, MountTable};
+pub use self::poll::{poll, PollFd, PollSource};
 pub use self::waiter::FileWaiter;
 
 #[allow(non_snake_case)]
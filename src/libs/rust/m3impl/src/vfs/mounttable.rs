@@ -23,9 +23,10 @@ use crate::cap::Selector;
 use crate::cell::RefCell;
 use crate::col::{String, ToString, Vec};
 use crate::errors::{Code, Error};
+use crate::format;
 use crate::rc::Rc;
 use crate::serialize::{M3Deserializer, M3Serializer, VecSink};
-use crate::session::M3FS;
+use crate::session::{PipeFS, M3FS};
 use crate::tiles::ChildActivity;
 use crate::vfs::{FileSystem, VFS};
 
@@ -113,6 +114,54 @@ impl MountTable {
         Err(Error::new(Code::NoSuchFile))
     }
 
+    /// Replaces the first symbolic link within the given path by its target.
+    ///
+    /// File systems cannot resolve symbolic links with absolute targets themselves, because the
+    /// target might belong to a different mount point. Instead, they fail with
+    /// [`Code::ForeignLink`], upon which the client resolves the link via this function and retries
+    /// the operation with the resulting path.
+    pub fn resolve_link(&self, path: &mut Cow<'_, str>) -> Result<(), Error> {
+        let abs = VFS::abs_path(path);
+
+        let mut end = 0;
+        while end < abs.len() {
+            end = abs[end + 1..]
+                .find('/')
+                .map(|p| end + 1 + p)
+                .unwrap_or(abs.len());
+            let prefix = &abs[..end];
+
+            // mount points are no links
+            if self
+                .mounts
+                .iter()
+                .any(|m| m.path.strip_suffix('/') == Some(prefix))
+            {
+                continue;
+            }
+
+            let (fs, pos) = self.resolve(&mut Cow::from(prefix))?;
+            let target = fs.borrow().readlink(&prefix[pos..]);
+            match target {
+                Ok(target) => {
+                    let new_path = if target.starts_with('/') {
+                        target + &abs[end..]
+                    }
+                    else {
+                        let dir_end = prefix.rfind('/').unwrap();
+                        format!("{}/{}{}", &abs[..dir_end], target, &abs[end..])
+                    };
+                    *path.to_mut() = VFS::canon_path(&new_path);
+                    return Ok(());
+                },
+                // not a symbolic link
+                Err(e) if e.code() == Code::InvArgs => {},
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::new(Code::NoSuchFile))
+    }
+
     /// Removes the mount point at `path` from the table.
     pub fn remove(&mut self, path: &str) -> Result<(), Error> {
         match self.path_to_idx(path) {
@@ -159,6 +208,7 @@ impl MountTable {
             let fs_type: u8 = s.pop().unwrap();
             mt.add(&path, match fs_type {
                 b'M' => M3FS::unserialize(s),
+                b'P' => PipeFS::unserialize(s),
                 _ => panic!("Unexpected fs type {}", fs_type),
             })
             .unwrap();
@@ -0,0 +1,139 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::com::RecvGate;
+use crate::errors::{Code, Error};
+use crate::tiles::{Activity, OwnActivity};
+use crate::time::{TimeDuration, TimeInstant};
+use crate::vfs::{Fd, File, FileEvent};
+
+/// The source of the events of a [`PollFd`].
+#[derive(Copy, Clone, Debug)]
+pub enum PollSource<'r> {
+    /// A file in the file table of the own activity
+    File(Fd),
+    /// A receive gate, which has [`FileEvent::INPUT`] if it contains unread messages
+    Gate(&'r RecvGate),
+}
+
+/// An entry for [`poll`], describing the desired and the received events.
+#[derive(Debug)]
+pub struct PollFd<'r> {
+    source: PollSource<'r>,
+    events: FileEvent,
+    revents: FileEvent,
+}
+
+impl<'r> PollFd<'r> {
+    /// Creates a new entry that waits for `events` on the file with file descriptor `fd`.
+    pub fn new(fd: Fd, events: FileEvent) -> Self {
+        Self::new_with(PollSource::File(fd), events)
+    }
+
+    /// Creates a new entry that waits for messages on `rgate`.
+    pub fn new_gate(rgate: &'r RecvGate) -> Self {
+        Self::new_with(PollSource::Gate(rgate), FileEvent::INPUT)
+    }
+
+    fn new_with(source: PollSource<'r>, events: FileEvent) -> Self {
+        PollFd {
+            source,
+            events,
+            revents: FileEvent::empty(),
+        }
+    }
+
+    /// Returns the source of the events
+    pub fn source(&self) -> PollSource<'r> {
+        self.source
+    }
+
+    /// Returns the desired events
+    pub fn events(&self) -> FileEvent {
+        self.events
+    }
+
+    /// Returns the events that have been received during the last call of [`poll`]
+    pub fn revents(&self) -> FileEvent {
+        self.revents
+    }
+
+    fn check(&mut self) -> Result<bool, Error> {
+        self.revents = FileEvent::empty();
+        match self.source {
+            PollSource::File(fd) => {
+                let files = Activity::own().files();
+                let mut file = files.get(fd).ok_or_else(|| Error::new(Code::BadFd))?;
+                // accessing the file requires that we don't hold a references to the filetable
+                drop(files);
+
+                // check the events individually, because files might only report whether all
+                // given events have arrived
+                for ev in [FileEvent::INPUT, FileEvent::OUTPUT, FileEvent::SIGNAL] {
+                    if self.events.contains(ev) && file.check_events(ev) {
+                        self.revents |= ev;
+                    }
+                }
+            },
+
+            PollSource::Gate(rgate) => {
+                if self.events.contains(FileEvent::INPUT) && rgate.has_msgs()? {
+                    self.revents |= FileEvent::INPUT;
+                }
+            },
+        }
+        Ok(!self.revents.is_empty())
+    }
+}
+
+/// Waits until any of the given entries has received any of its desired events or until `timeout`
+/// has passed.
+///
+/// In contrast to [`FileWaiter`](crate::vfs::FileWaiter), `poll` supports receive gates in
+/// addition to files and reports the received events of each entry via [`PollFd::revents`]. Files
+/// in blocking mode are always ready, because reads and writes on them simply block. Without
+/// timeout, `poll` waits until at least one event has been received, whereas a timeout of zero
+/// only checks all entries once.
+///
+/// Note also that this function uses
+/// [`Activity::own().sleep`](crate::tiles::OwnActivity::sleep) if no entry is ready, which
+/// suspends the core until the next TCU message arrives. Thus, calling this function can only be
+/// done if all work is done.
+///
+/// Returns the number of entries that have received events or an error if any entry refers to an
+/// invalid file descriptor.
+pub fn poll(fds: &mut [PollFd<'_>], timeout: Option<TimeDuration>) -> Result<usize, Error> {
+    let end = timeout.map(|t| TimeInstant::now() + t);
+    loop {
+        let mut ready = 0;
+        for fd in fds.iter_mut() {
+            if fd.check()? {
+                ready += 1;
+            }
+        }
+        if ready > 0 {
+            return Ok(ready);
+        }
+
+        // ignore errors
+        match end {
+            None => OwnActivity::sleep().ok(),
+            Some(end) => match end.checked_duration_since(TimeInstant::now()) {
+                Some(d) if !d.is_zero() => OwnActivity::sleep_for(d).ok(),
+                _ => return Ok(0),
+            },
+        };
+    }
+}
@@ -20,15 +20,19 @@ use crate::borrow::Cow;
 use crate::col::{String, ToString};
 use crate::errors::{Code, Error};
 use crate::rc::Rc;
-use crate::session::M3FS;
+use crate::session::{PipeFS, M3FS};
 use crate::tiles::Activity;
-use crate::vfs::{FSHandle, File, FileInfo, FileMode, FileRef, GenericFile, OpenFlags, SeekMode};
+use crate::vfs::{
+    FSHandle, File, FileInfo, FileMode, FileRef, FsStats, FsckReport, GenericFile, OpenFlags,
+    SeekMode,
+};
 
 /// Mounts the file system of type `fstype` at `path`, creating a session at `service`.
 pub fn mount(path: &str, fstype: &str, service: &str) -> Result<(), Error> {
     let id = Activity::own().mounts().alloc_id();
     let fsobj = match fstype {
         "m3fs" => M3FS::new(id, service)?,
+        "pipes" => PipeFS::new(id, service)?,
         _ => return Err(Error::new(Code::InvArgs)),
     };
     Activity::own().mounts().add(path, fsobj)
@@ -39,13 +43,51 @@ pub fn unmount(path: &str) -> Result<(), Error> {
     Activity::own().mounts().remove(path)
 }
 
+/// The maximum number of symbolic links that are followed across mount points
+const MAX_SYMLINKS: usize = 16;
+
 fn with_path<F, R>(path: &str, func: F) -> Result<R, Error>
 where
     F: Fn(&FSHandle, &str) -> Result<R, Error>,
 {
     let mut path = Cow::from(path);
-    let (fs, pos) = Activity::own().mounts().resolve(&mut path)?;
-    func(&fs, &path[pos..])
+    for _ in 0..MAX_SYMLINKS {
+        let (fs, pos) = Activity::own().mounts().resolve(&mut path)?;
+        match func(&fs, &path[pos..]) {
+            Err(e) if e.code() == Code::ForeignLink => {
+                Activity::own().mounts().resolve_link(&mut path)?;
+            },
+            res => return res,
+        }
+    }
+    Err(Error::new(Code::LinkLoop))
+}
+
+fn with_paths<F, R>(old: &str, new: &str, func: F) -> Result<R, Error>
+where
+    F: Fn(&FSHandle, &str, &str) -> Result<R, Error>,
+{
+    let mut old = Cow::from(old);
+    let mut new = Cow::from(new);
+    for _ in 0..MAX_SYMLINKS {
+        let (fs1, pos1) = Activity::own().mounts().resolve(&mut old)?;
+        let (fs2, pos2) = Activity::own().mounts().resolve(&mut new)?;
+        if !Rc::ptr_eq(&fs1, &fs2) {
+            return Err(Error::new(Code::XfsLink));
+        }
+
+        match func(&fs1, &old[pos1..], &new[pos2..]) {
+            // we don't know which path contains the link; try the old path first
+            Err(e) if e.code() == Code::ForeignLink => {
+                let mounts = Activity::own().mounts();
+                if mounts.resolve_link(&mut old).is_err() {
+                    mounts.resolve_link(&mut new)?;
+                }
+            },
+            res => return res,
+        }
+    }
+    Err(Error::new(Code::LinkLoop))
 }
 
 /// Creates an absolute and canonical path from given path
@@ -158,17 +200,19 @@ pub fn rmdir(path: &str) -> Result<(), Error> {
     with_path(path, |fs, fs_path| fs.borrow().rmdir(fs_path))
 }
 
+/// Creates a named pipe at `path`.
+///
+/// Named pipes are only supported by file systems of type `pipes`. Afterwards, unrelated activities
+/// can open the pipe via [`open`] with either [`OpenFlags::R`] or [`OpenFlags::W`].
+pub fn mkfifo(path: &str) -> Result<(), Error> {
+    with_path(path, |fs, fs_path| fs.borrow().mkfifo(fs_path))
+}
+
 /// Creates a link at `new` to `old`.
 pub fn link(old: &str, new: &str) -> Result<(), Error> {
-    let mut old = Cow::from(old);
-    let (fs1, pos1) = Activity::own().mounts().resolve(&mut old)?;
-    let mut new = Cow::from(new);
-    let (fs2, pos2) = Activity::own().mounts().resolve(&mut new)?;
-    if !Rc::ptr_eq(&fs1, &fs2) {
-        return Err(Error::new(Code::XfsLink));
-    }
-    let res = fs1.borrow().link(&old[pos1..], &new[pos2..]);
-    res
+    with_paths(old, new, |fs, old_path, new_path| {
+        fs.borrow().link(old_path, new_path)
+    })
 }
 
 /// Removes the file at `path`.
@@ -178,13 +222,41 @@ pub fn unlink(path: &str) -> Result<(), Error> {
 
 /// Renames `new` to `old`.
 pub fn rename(old: &str, new: &str) -> Result<(), Error> {
-    let mut old = Cow::from(old);
-    let (fs1, pos1) = Activity::own().mounts().resolve(&mut old)?;
-    let mut new = Cow::from(new);
-    let (fs2, pos2) = Activity::own().mounts().resolve(&mut new)?;
-    if !Rc::ptr_eq(&fs1, &fs2) {
-        return Err(Error::new(Code::XfsLink));
-    }
-    let res = fs1.borrow().rename(&old[pos1..], &new[pos2..]);
-    res
+    with_paths(old, new, |fs, old_path, new_path| {
+        fs.borrow().rename(old_path, new_path)
+    })
+}
+
+/// Creates a symbolic link at `path` that points to `target`.
+///
+/// The target is stored as is and only resolved when the link is used. Relative targets are
+/// interpreted relative to the directory containing the link.
+pub fn symlink(target: &str, path: &str) -> Result<(), Error> {
+    with_path(path, |fs, fs_path| fs.borrow().symlink(target, fs_path))
+}
+
+/// Returns the target of the symbolic link at `path`.
+pub fn readlink(path: &str) -> Result<String, Error> {
+    with_path(path, |fs, fs_path| fs.borrow().readlink(fs_path))
+}
+
+/// Changes the permissions of the file at `path` to `mode`.
+pub fn chmod(path: &str, mode: FileMode) -> Result<(), Error> {
+    with_path(path, |fs, fs_path| fs.borrow().chmod(fs_path, mode))
+}
+
+/// Changes the owner of the file at `path` to `uid` and `gid`.
+pub fn chown(path: &str, uid: u32, gid: u32) -> Result<(), Error> {
+    with_path(path, |fs, fs_path| fs.borrow().chown(fs_path, uid, gid))
+}
+
+/// Checks the consistency of the file system mounted at `path` and frees leaked inodes and blocks
+/// if `repair` is true.
+pub fn fsck(path: &str, repair: bool) -> Result<FsckReport, Error> {
+    with_path(path, |fs, _| fs.borrow().fsck(repair))
+}
+
+/// Returns the statistics of the file system mounted at `path`.
+pub fn statfs(path: &str) -> Result<FsStats, Error> {
+    with_path(path, |fs, _| fs.borrow().statfs())
 }
@@ -0,0 +1,150 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::{String, Vec};
+use m3::errors::{Code, Error};
+
+use crate::{read_u16, read_u32, read_u64, Partition, PartitionKind, SECTOR_SIZE};
+
+// the GPT header is located in the sector after the protective MBR
+const HEADER_SECTOR: u64 = 1;
+const HEADER_SIGNATURE: &[u8; 8] = b"EFI PART";
+const HEADER_MIN_SIZE: usize = 92;
+
+// offsets of the fields within the header
+const HDR_SIZE: usize = 12;
+const HDR_CRC: usize = 16;
+const HDR_MY_LBA: usize = 24;
+const HDR_FIRST_USABLE: usize = 40;
+const HDR_LAST_USABLE: usize = 48;
+const HDR_ENTRIES_LBA: usize = 72;
+const HDR_ENTRY_COUNT: usize = 80;
+const HDR_ENTRY_SIZE: usize = 84;
+const HDR_ENTRIES_CRC: usize = 88;
+
+// offsets of the fields within a partition entry
+const ENT_TYPE_GUID: usize = 0;
+const ENT_FIRST_LBA: usize = 32;
+const ENT_LAST_LBA: usize = 40;
+const ENT_NAME: usize = 56;
+const ENT_NAME_LEN: usize = 36;
+const ENT_MIN_SIZE: usize = 128;
+
+// upper bound for the size of the partition-entry array (the default is 128 entries of 128 bytes)
+const MAX_ENTRIES_SIZE: usize = 64 * 1024;
+
+/// Computes the CRC32 (as used by GPT, zlib, etc.) of the given data
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+pub fn parse<F>(read_sector: &mut F) -> Result<Vec<Partition>, Error>
+where
+    F: FnMut(u64, &mut [u8]) -> Result<(), Error>,
+{
+    let mut header = [0u8; SECTOR_SIZE];
+    read_sector(HEADER_SECTOR, &mut header)?;
+
+    if &header[0..8] != HEADER_SIGNATURE {
+        return Err(Error::new(Code::InvArgs));
+    }
+
+    let header_size = read_u32(&header, HDR_SIZE) as usize;
+    if !(HEADER_MIN_SIZE..=SECTOR_SIZE).contains(&header_size) {
+        return Err(Error::new(Code::InvArgs));
+    }
+
+    // the header CRC is calculated with the CRC field set to zero
+    let header_crc = read_u32(&header, HDR_CRC);
+    let mut copy = header;
+    copy[HDR_CRC..HDR_CRC + 4].fill(0);
+    if crc32(&copy[0..header_size]) != header_crc {
+        return Err(Error::new(Code::InvChecksum));
+    }
+
+    if read_u64(&header, HDR_MY_LBA) != HEADER_SECTOR {
+        return Err(Error::new(Code::InvArgs));
+    }
+
+    let first_usable = read_u64(&header, HDR_FIRST_USABLE);
+    let last_usable = read_u64(&header, HDR_LAST_USABLE);
+    let entries_lba = read_u64(&header, HDR_ENTRIES_LBA);
+    let entry_count = read_u32(&header, HDR_ENTRY_COUNT) as usize;
+    let entry_size = read_u32(&header, HDR_ENTRY_SIZE) as usize;
+    if entry_size < ENT_MIN_SIZE || !entry_size.is_power_of_two() {
+        return Err(Error::new(Code::InvArgs));
+    }
+    let entries_size = entry_count
+        .checked_mul(entry_size)
+        .filter(|size| *size <= MAX_ENTRIES_SIZE)
+        .ok_or_else(|| Error::new(Code::InvArgs))?;
+
+    // read the entry array and check its CRC
+    let mut entries = Vec::with_capacity(entries_size);
+    let mut sector = [0u8; SECTOR_SIZE];
+    for i in 0..(entries_size + SECTOR_SIZE - 1) / SECTOR_SIZE {
+        read_sector(entries_lba + i as u64, &mut sector)?;
+        let amount = (entries_size - entries.len()).min(SECTOR_SIZE);
+        entries.extend_from_slice(&sector[0..amount]);
+    }
+    if crc32(&entries) != read_u32(&header, HDR_ENTRIES_CRC) {
+        return Err(Error::new(Code::InvChecksum));
+    }
+
+    let mut parts = Vec::new();
+    for (i, ent) in entries.chunks_exact(entry_size).enumerate() {
+        // unused entries have a zero type GUID
+        if ent[ENT_TYPE_GUID..ENT_TYPE_GUID + 16]
+            .iter()
+            .all(|b| *b == 0)
+        {
+            continue;
+        }
+
+        let first = read_u64(ent, ENT_FIRST_LBA);
+        let last = read_u64(ent, ENT_LAST_LBA);
+        if first > last || first < first_usable || last > last_usable {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        parts.push(Partition::new(
+            i,
+            PartitionKind::Gpt,
+            first,
+            last - first + 1,
+            parse_name(ent),
+        ));
+    }
+
+    Ok(parts)
+}
+
+fn parse_name(entry: &[u8]) -> String {
+    // the name is stored in UTF-16LE and zero-terminated, unless it uses all 36 code units
+    let units = (0..ENT_NAME_LEN)
+        .map(|i| read_u16(entry, ENT_NAME + i * 2))
+        .take_while(|u| *u != 0);
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
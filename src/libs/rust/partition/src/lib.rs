@@ -0,0 +1,188 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Parsing of partition tables.
+//!
+//! Supports classic MBR partition tables, including extended partitions with their chain of
+//! logical partitions, and GPT partition tables. The disk is accessed via a function that reads a
+//! single sector so that the parser can be used for real devices and in-memory images alike.
+
+#![no_std]
+
+mod gpt;
+mod mbr;
+
+use core::fmt;
+
+use m3::col::{String, Vec};
+use m3::errors::Error;
+
+pub use gpt::crc32;
+
+/// The sector size that is assumed for all partition tables
+pub const SECTOR_SIZE: usize = 512;
+
+/// The number of primary partitions in the MBR
+pub const PRIMARY_COUNT: usize = 4;
+
+/// The kind of partition
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PartitionKind {
+    /// A primary partition in the MBR
+    Primary,
+    /// A logical partition within an extended partition
+    Logical,
+    /// A partition in the GPT
+    Gpt,
+}
+
+/// A partition on a disk
+#[derive(Clone)]
+pub struct Partition {
+    id: usize,
+    kind: PartitionKind,
+    start: u64,
+    size: u64,
+    name: String,
+}
+
+impl Partition {
+    pub(crate) fn new(id: usize, kind: PartitionKind, start: u64, size: u64, name: String) -> Self {
+        Self {
+            id,
+            kind,
+            start,
+            size,
+            name,
+        }
+    }
+
+    /// Returns the id of the partition, which is its slot in the partition table. Primary
+    /// partitions have the ids 0..4 and logical partitions are numbered from 4 on.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the kind of partition
+    pub fn kind(&self) -> PartitionKind {
+        self.kind
+    }
+
+    /// Returns the first sector of the partition
+    pub fn start_sector(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the number of sectors of the partition
+    pub fn sector_count(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the name of the partition (only GPT partitions have names)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Debug for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "Partition[id={}, kind={:?}, sectors={}..{}, name='{}']",
+            self.id,
+            self.kind,
+            self.start,
+            self.start + self.size - 1,
+            self.name
+        )
+    }
+}
+
+/// Specifies which partition to use
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PartitionSpec<'s> {
+    /// The partition with given index in the list of all partitions
+    Index(usize),
+    /// The GPT partition with given name
+    Name(&'s str),
+}
+
+impl<'s> PartitionSpec<'s> {
+    /// Parses the given string into a partition specification: numbers are treated as indices
+    /// and everything else as partition names.
+    pub fn parse(spec: &'s str) -> Self {
+        match spec.parse::<usize>() {
+            Ok(idx) => Self::Index(idx),
+            Err(_) => Self::Name(spec),
+        }
+    }
+
+    /// Returns the index of the first partition in `parts` that matches this specification
+    pub fn find<'p, I>(&self, mut parts: I) -> Option<usize>
+    where
+        I: Iterator<Item = &'p Partition>,
+    {
+        match self {
+            Self::Index(idx) => parts.nth(*idx).map(|_| *idx),
+            Self::Name(name) => parts
+                .enumerate()
+                .find(|(_, p)| p.kind() == PartitionKind::Gpt && p.name() == *name)
+                .map(|(i, _)| i),
+        }
+    }
+}
+
+/// Parses the partition table of a disk.
+///
+/// The function `read_sector` is called to read the sector with given number into the given
+/// buffer, which has a size of `SECTOR_SIZE`. If the MBR contains a protective partition, the GPT
+/// is parsed. Otherwise, the primary partitions and the logical partitions within extended
+/// partitions are returned. Empty slots are not included. A disk without partition table yields
+/// an empty list.
+pub fn parse_partitions<F>(mut read_sector: F) -> Result<Vec<Partition>, Error>
+where
+    F: FnMut(u64, &mut [u8]) -> Result<(), Error>,
+{
+    let mut sector = [0u8; SECTOR_SIZE];
+    read_sector(0, &mut sector)?;
+
+    let entries = match mbr::parse_table(&sector) {
+        Some(entries) => entries,
+        None => return Ok(Vec::new()),
+    };
+
+    if entries.iter().any(|e| e.is_gpt_protective()) {
+        gpt::parse(&mut read_sector)
+    }
+    else {
+        mbr::parse(&entries, &mut read_sector)
+    }
+}
+
+pub(crate) fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+pub(crate) fn read_u32(buf: &[u8], off: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&buf[off..off + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+pub(crate) fn read_u64(buf: &[u8], off: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[off..off + 8]);
+    u64::from_le_bytes(bytes)
+}
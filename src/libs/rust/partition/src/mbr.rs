@@ -0,0 +1,156 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::{String, Vec};
+use m3::errors::{Code, Error};
+
+use crate::{read_u16, read_u32, Partition, PartitionKind, PRIMARY_COUNT, SECTOR_SIZE};
+
+// offset of partition-table in MBR and EBRs
+const PART_TABLE_OFFSET: usize = 0x1BE;
+// size of a partition-table entry
+const PART_ENTRY_SIZE: usize = 16;
+
+// offset and value of the boot signature
+const SIGNATURE_OFFSET: usize = 0x1FE;
+const SIGNATURE: u16 = 0xAA55;
+
+// system ids of extended partitions (CHS, LBA, and Linux)
+const EXTENDED_IDS: [u8; 3] = [0x05, 0x0F, 0x85];
+// system id of the protective partition that covers a GPT disk
+const GPT_PROTECTIVE_ID: u8 = 0xEE;
+
+// upper bound for the number of logical partitions to detect loops in the EBR chain
+const MAX_LOGICAL: usize = 128;
+
+#[derive(Clone, Copy, Default)]
+pub struct Entry {
+    system_id: u8,
+    // relative sector (to the start of the partition table's base)
+    start: u32,
+    // total sectors in partition
+    size: u32,
+}
+
+impl Entry {
+    fn present(&self) -> bool {
+        self.system_id != 0 && self.size != 0
+    }
+
+    fn is_extended(&self) -> bool {
+        EXTENDED_IDS.contains(&self.system_id)
+    }
+
+    pub fn is_gpt_protective(&self) -> bool {
+        self.system_id == GPT_PROTECTIVE_ID
+    }
+}
+
+/// Parses the four partition-table entries of the given MBR or EBR. Returns None if the sector
+/// does not carry the boot signature.
+pub fn parse_table(sector: &[u8]) -> Option<[Entry; PRIMARY_COUNT]> {
+    if read_u16(sector, SIGNATURE_OFFSET) != SIGNATURE {
+        return None;
+    }
+
+    let mut entries = [Entry::default(); PRIMARY_COUNT];
+    for (i, e) in entries.iter_mut().enumerate() {
+        let off = PART_TABLE_OFFSET + i * PART_ENTRY_SIZE;
+        // skip boot indicator and start CHS; skip end CHS after the system id
+        e.system_id = sector[off + 4];
+        e.start = read_u32(sector, off + 8);
+        e.size = read_u32(sector, off + 12);
+    }
+    Some(entries)
+}
+
+pub fn parse<F>(entries: &[Entry], read_sector: &mut F) -> Result<Vec<Partition>, Error>
+where
+    F: FnMut(u64, &mut [u8]) -> Result<(), Error>,
+{
+    let mut parts = Vec::new();
+    for (i, e) in entries.iter().enumerate() {
+        if e.present() && !e.is_extended() {
+            parts.push(Partition::new(
+                i,
+                PartitionKind::Primary,
+                e.start as u64,
+                e.size as u64,
+                String::new(),
+            ));
+        }
+    }
+
+    for e in entries.iter().filter(|e| e.present() && e.is_extended()) {
+        parse_logical(&mut parts, e, read_sector)?;
+    }
+
+    Ok(parts)
+}
+
+fn parse_logical<F>(
+    parts: &mut Vec<Partition>,
+    ext: &Entry,
+    read_sector: &mut F,
+) -> Result<(), Error>
+where
+    F: FnMut(u64, &mut [u8]) -> Result<(), Error>,
+{
+    let ext_start = ext.start as u64;
+    let ext_end = ext_start + ext.size as u64;
+
+    // every EBR describes one logical partition relative to itself and the location of the next
+    // EBR relative to the start of the extended partition
+    let mut ebr = ext_start;
+    let mut sector = [0u8; SECTOR_SIZE];
+    for _ in 0..MAX_LOGICAL {
+        read_sector(ebr, &mut sector)?;
+        let entries = parse_table(&sector).ok_or_else(|| Error::new(Code::InvArgs))?;
+
+        let part = &entries[0];
+        if part.present() {
+            let start = ebr + part.start as u64;
+            if start + part.size as u64 > ext_end {
+                return Err(Error::new(Code::InvArgs));
+            }
+
+            let id = PRIMARY_COUNT
+                + parts
+                    .iter()
+                    .filter(|p| p.kind() == PartitionKind::Logical)
+                    .count();
+            parts.push(Partition::new(
+                id,
+                PartitionKind::Logical,
+                start,
+                part.size as u64,
+                String::new(),
+            ));
+        }
+
+        let next = &entries[1];
+        if !next.present() || !next.is_extended() {
+            return Ok(());
+        }
+
+        ebr = ext_start + next.start as u64;
+        if ebr >= ext_end {
+            return Err(Error::new(Code::InvArgs));
+        }
+    }
+
+    // too many logical partitions; most likely, the EBR chain contains a loop
+    Err(Error::new(Code::InvArgs))
+}
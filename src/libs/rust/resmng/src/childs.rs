@@ -301,7 +301,7 @@ pub trait Child {
         sess.close_async(res, id)
     }
 
-    fn alloc_local(&mut self, size: goff, perm: Perm) -> Result<MemGate, Error> {
+    fn alloc_local(&mut self, size: goff, perm: Perm) -> Result<(MemGate, Allocation), Error> {
         log!(
             crate::LOG_MEM,
             "{}: allocate_local(size={:#x}, perm={:?})",
@@ -317,9 +317,27 @@ pub trait Child {
         let alloc = self.mem().pool.borrow_mut().allocate(size)?;
         let mem_sel = self.mem().pool.borrow().mem_cap(alloc.slice_id());
         let mgate = MemGate::new_bind(mem_sel).derive(alloc.addr(), alloc.size() as usize, perm)?;
-        // TODO this memory is currently only free'd on child exit
+        // TODO this memory is currently only free'd on child exit or via free_local
         self.add_mem(alloc, None);
-        Ok(mgate)
+        Ok((mgate, alloc))
+    }
+
+    fn free_local(&mut self, alloc: Allocation) -> Result<(), Error> {
+        log!(
+            crate::LOG_MEM,
+            "{}: free_local(alloc={:?})",
+            self.name(),
+            alloc
+        );
+
+        let idx = self
+            .res_mut()
+            .mem
+            .iter()
+            .position(|(s, a)| s.is_none() && *a == alloc)
+            .ok_or_else(|| Error::new(Code::InvArgs))?;
+        self.remove_mem_by_idx(idx);
+        Ok(())
     }
 
     fn alloc_mem(&mut self, dst_sel: Selector, size: goff, perm: Perm) -> Result<(), Error> {
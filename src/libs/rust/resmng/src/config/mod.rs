@@ -396,6 +396,7 @@ pub struct AppConfig {
     pub(crate) kern_mem: Option<usize>,
     pub(crate) time: Option<u64>,
     pub(crate) pts: Option<usize>,
+    pub(crate) prio: Option<kif::Priority>,
     pub(crate) serial: Option<SerialDesc>,
     pub(crate) domains: Vec<Domain>,
     pub(crate) mounts: Vec<MountDesc>,
@@ -410,8 +411,16 @@ pub struct AppConfig {
 }
 
 impl AppConfig {
+    /// Parses the given config, assuming that the top-level app runs with normal priority
     pub fn parse(xml: &str) -> Result<Self, Error> {
-        parser::parse(xml)
+        Self::parse_with(xml, kif::Priority::NORMAL)
+    }
+
+    /// Parses the given config, assuming that the top-level app runs with priority `prio`, unless
+    /// it specifies a priority. The apps within the config cannot exceed the priority of the app
+    /// they are started by.
+    pub fn parse_with(xml: &str, prio: kif::Priority) -> Result<Self, Error> {
+        parser::parse(xml, prio)
     }
 
     pub fn new(args: Vec<String>) -> Self {
@@ -459,6 +468,10 @@ impl AppConfig {
         self.pts
     }
 
+    pub fn prio(&self) -> Option<kif::Priority> {
+        self.prio
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -602,6 +615,9 @@ impl AppConfig {
         if let Some(n) = self.pts {
             writeln!(f, "{:0w$}PageTables[{}],", "", n, w = layer + 2)?;
         }
+        if let Some(p) = self.prio {
+            writeln!(f, "{:0w$}Priority[{}],", "", p, w = layer + 2)?;
+        }
         if let Some(umem) = self.user_mem {
             writeln!(
                 f,
@@ -17,6 +17,7 @@ use m3::col::{String, ToString, Vec};
 use m3::errors::{Code, Error};
 use m3::format;
 use m3::kif;
+use m3::log;
 use m3::rc::Rc;
 use m3::tcu::Label;
 use m3::util::parse;
@@ -159,11 +160,12 @@ impl ConfigParser {
     }
 }
 
-pub(crate) fn parse(xml: &str) -> Result<config::AppConfig, Error> {
+pub(crate) fn parse(xml: &str, prio: kif::Priority) -> Result<config::AppConfig, Error> {
     let mut p = ConfigParser::new(xml);
 
     let app = match p.parse_tag_name()? {
-        Some(tag) if tag == "app" => parse_app(&mut p, 0),
+        // the priority of the top-level app has already been checked by its creator
+        Some(tag) if tag == "app" => parse_app(&mut p, 0, kif::Priority::HIGH, prio),
         _ => Err(Error::new(Code::InvArgs)),
     }?;
 
@@ -171,7 +173,12 @@ pub(crate) fn parse(xml: &str) -> Result<config::AppConfig, Error> {
     Ok(app)
 }
 
-fn parse_app(p: &mut ConfigParser, start: usize) -> Result<config::AppConfig, Error> {
+fn parse_app(
+    p: &mut ConfigParser,
+    start: usize,
+    parent_prio: kif::Priority,
+    def_prio: kif::Priority,
+) -> Result<config::AppConfig, Error> {
     let mut app = config::AppConfig::default();
 
     loop {
@@ -190,6 +197,7 @@ fn parse_app(p: &mut ConfigParser, start: usize) -> Result<config::AppConfig, Er
                 "kernmem" => app.kern_mem = Some(parse::size(&v)?),
                 "time" => app.time = Some(parse::time(&v)?),
                 "pagetables" => app.pts = Some(parse::int(&v)? as usize),
+                "prio" => app.prio = Some(parse_prio(&v, parent_prio)?),
                 "eps" => app.eps = Some(parse::int(&v)? as u32),
                 "daemon" => app.daemon = parse::bool(&v)?,
                 "getinfo" => app.getinfo = parse::bool(&v)?,
@@ -202,6 +210,9 @@ fn parse_app(p: &mut ConfigParser, start: usize) -> Result<config::AppConfig, Er
         return Err(Error::new(Code::InvArgs));
     }
 
+    // children cannot get a higher priority than the app itself
+    let prio = app.prio.unwrap_or(def_prio);
+
     // put all apps that belong to the same domain as `app` into a pseudo domain
     let mut pseudo_dom = config::Domain {
         pseudo: true,
@@ -217,8 +228,13 @@ fn parse_app(p: &mut ConfigParser, start: usize) -> Result<config::AppConfig, Er
         let mut app_start = p.pos;
         while let Some(tag) = p.parse_tag_name()? {
             match tag.as_ref() {
-                "app" => pseudo_dom.apps.push(Rc::new(parse_app(p, app_start)?)),
-                "dom" => app.domains.push(parse_domain(p)?),
+                "app" => pseudo_dom.apps.push(Rc::new(parse_app(
+                    p,
+                    app_start,
+                    prio,
+                    kif::Priority::NORMAL,
+                )?)),
+                "dom" => app.domains.push(parse_domain(p, prio)?),
                 "mount" => app.mounts.push(parse_mount(p)?),
                 "sess" => app.sessions.push(parse_session(p)?),
                 "sesscrt" => app.sesscrt.push(parse_sesscrt(p)?),
@@ -289,6 +305,28 @@ fn collect_sess_crts(app: &config::AppConfig, crts: &mut Vec<config::SessCrtDesc
     }
 }
 
+fn parse_prio(s: &str, parent_prio: kif::Priority) -> Result<kif::Priority, Error> {
+    let prio = match s {
+        "low" => kif::Priority::LOW,
+        "normal" => kif::Priority::NORMAL,
+        "high" => kif::Priority::HIGH,
+        _ => return Err(Error::new(Code::InvArgs)),
+    };
+
+    // the kernel would refuse to create the activity anyway, but reject it here already to point
+    // at the configuration instead of failing later with a less descriptive error
+    if prio > parent_prio {
+        log!(
+            crate::LOG_DEF,
+            "Invalid config: priority {} exceeds the priority {} of the parent",
+            prio,
+            parent_prio
+        );
+        return Err(Error::new(Code::NoPerm));
+    }
+    Ok(prio)
+}
+
 fn parse_dual_name(dual: &mut config::DualName, n: String, v: String) -> Result<(), Error> {
     match n.as_ref() {
         "name" => {
@@ -302,7 +340,7 @@ fn parse_dual_name(dual: &mut config::DualName, n: String, v: String) -> Result<
     Ok(())
 }
 
-fn parse_domain(p: &mut ConfigParser) -> Result<config::Domain, Error> {
+fn parse_domain(p: &mut ConfigParser, parent_prio: kif::Priority) -> Result<config::Domain, Error> {
     let mut dom = config::Domain::default();
 
     loop {
@@ -327,7 +365,12 @@ fn parse_domain(p: &mut ConfigParser) -> Result<config::Domain, Error> {
             return Err(Error::new(Code::InvArgs));
         }
 
-        dom.apps.push(Rc::new(parse_app(p, app_start)?));
+        dom.apps.push(Rc::new(parse_app(
+            p,
+            app_start,
+            parent_prio,
+            kif::Priority::NORMAL,
+        )?));
         app_start = p.pos;
     }
 
@@ -254,7 +254,7 @@ impl fmt::Debug for MemSlice {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Eq, PartialEq)]
 pub struct Allocation {
     slice_id: usize,
     addr: goff,
@@ -22,7 +22,7 @@ use m3::com::MemGate;
 use m3::errors::{Code, Error, VerboseError};
 use m3::format;
 use m3::goff;
-use m3::kif::{boot, CapRngDesc, CapType, Perm, TileDesc, FIRST_FREE_SEL};
+use m3::kif::{self, boot, CapRngDesc, CapType, Perm, TileDesc, FIRST_FREE_SEL};
 use m3::log;
 use m3::mem::size_of;
 use m3::rc::Rc;
@@ -214,7 +214,15 @@ impl Subsystem {
 
         // parse boot config
         let xml_str = String::from_utf8(xml).map_err(|_| Error::new(Code::InvArgs))?;
-        let cfg = config::AppConfig::parse(&xml_str)?;
+        // the kernel starts root with high priority, whereas the parent of all other resource
+        // managers uses their prio attribute, which is the one of our top-level app, or normal
+        let prio = if Activity::own().resmng().is_none() {
+            kif::Priority::HIGH
+        }
+        else {
+            kif::Priority::NORMAL
+        };
+        let cfg = config::AppConfig::parse_with(&xml_str, prio)?;
         Ok((xml_str, cfg))
     }
 
@@ -0,0 +1,78 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::String;
+use m3::errors::{Code, Error};
+
+use crate::line::SpecialChars;
+
+/// The maximum number of consoles, each selectable with the hotkey and a single digit
+pub const MAX_CONSOLES: usize = 9;
+
+/// The settings of vterm as specified on the command line
+#[derive(Debug)]
+pub struct Settings {
+    pub consoles: usize,
+    pub chars: SpecialChars,
+}
+
+/// Parses the given command line arguments (including the program name)
+pub fn parse_args(args: &[&str]) -> Result<Settings, String> {
+    let mut settings = Settings {
+        consoles: 1,
+        chars: SpecialChars::default(),
+    };
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i] {
+            "-n" => {
+                settings.consoles = args
+                    .get(i + 1)
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| *n >= 1 && *n <= MAX_CONSOLES)
+                    .ok_or_else(|| String::from("Failed to parse console count"))?;
+                i += 1;
+            },
+            "-c" => {
+                settings.chars.set(args.get(i + 1).unwrap_or(&""))?;
+                i += 1;
+            },
+            _ => break,
+        }
+        i += 1;
+    }
+    Ok(settings)
+}
+
+/// Determines the console from the given session argument, given `count` consoles.
+///
+/// Consoles have no names and are only selected by number. The number starts at 1 in the argument
+/// `console=<n>`, whereas the returned index starts at 0. Without argument, the client uses the
+/// first console.
+pub fn parse_session_arg(arg: &str, count: usize) -> Result<usize, Error> {
+    let mut console = 0;
+    for a in arg.split_whitespace() {
+        if let Some(val) = a.strip_prefix("console=") {
+            console = val
+                .parse::<usize>()
+                .ok()
+                .filter(|c| *c >= 1 && *c <= count)
+                .ok_or_else(|| Error::new(Code::InvArgs))?
+                - 1;
+        }
+    }
+    Ok(console)
+}
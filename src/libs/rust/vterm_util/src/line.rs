@@ -0,0 +1,407 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::{String, Vec, VecDeque};
+use m3::format;
+
+/// The number of lines kept in the history of each session
+pub const HISTORY_SIZE: usize = 32;
+
+const ESC: u8 = 0x1b;
+
+/// The characters with a special meaning in cooked mode
+#[derive(Copy, Clone, Debug)]
+pub struct SpecialChars {
+    /// ends the input (^D)
+    pub eof: u8,
+    /// sends a signal to all clients (^C)
+    pub intr: u8,
+    /// erases the character before the cursor (DEL)
+    pub erase: u8,
+    /// erases everything before the cursor (^U)
+    pub kill: u8,
+    /// erases the word before the cursor (^W)
+    pub werase: u8,
+}
+
+impl Default for SpecialChars {
+    fn default() -> Self {
+        Self {
+            eof: 0x04,
+            intr: 0x03,
+            erase: 0x7f,
+            kill: 0x15,
+            werase: 0x17,
+        }
+    }
+}
+
+impl SpecialChars {
+    /// Sets the special character according to given assignment of the form `<name>=<char>`,
+    /// where `<char>` is either a number or in caret notation (e.g., `^U`).
+    pub fn set(&mut self, assignment: &str) -> Result<(), String> {
+        let (name, val) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid assignment '{}'", assignment))?;
+
+        let c = match val.as_bytes() {
+            [b'^', b'?'] => 0x7f,
+            [b'^', c] if c.is_ascii_alphabetic() || (b'@'..=b'_').contains(c) => {
+                c.to_ascii_uppercase() ^ 0x40
+            },
+            _ => val
+                .parse::<u8>()
+                .map_err(|_| format!("Invalid character '{}'", val))?,
+        };
+
+        match name {
+            "eof" => self.eof = c,
+            "intr" => self.intr = c,
+            "erase" => self.erase = c,
+            "kill" => self.kill = c,
+            "werase" => self.werase = c,
+            _ => return Err(format!("Unknown special character '{}'", name)),
+        }
+        Ok(())
+    }
+}
+
+/// The previously entered lines of a session
+#[derive(Debug, Default)]
+pub struct History {
+    lines: VecDeque<Vec<u8>>,
+}
+
+impl History {
+    /// Adds the given line to the history, dropping the oldest line if the history is full
+    pub fn add(&mut self, line: &[u8]) {
+        if line.is_empty() || self.lines.back().map(|l| &l[..]) == Some(line) {
+            return;
+        }
+        if self.lines.len() == HISTORY_SIZE {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_vec());
+    }
+
+    /// Returns the line with given index, starting with 0 for the most recent one
+    fn get(&self, idx: usize) -> Option<&Vec<u8>> {
+        self.lines
+            .len()
+            .checked_sub(idx + 1)
+            .map(|i| &self.lines[i])
+    }
+}
+
+/// The result of feeding a character into the line editor
+#[derive(Debug, Eq, PartialEq)]
+pub enum Event {
+    /// the line was completed with a newline
+    Line,
+    /// the eof character was entered
+    Eof,
+    /// the intr character was entered
+    Signal,
+}
+
+#[derive(Debug)]
+enum EscState {
+    None,
+    Esc,
+    Seq(Vec<u8>),
+}
+
+/// The line editor for the cooked mode
+///
+/// The editor keeps the current line and the cursor position within the line and produces the
+/// output that is required to reflect each change on the terminal. We assume that each character
+/// occupies a single column and that the terminal understands the ANSI escape sequences for
+/// cursor movement.
+#[derive(Debug)]
+pub struct LineEditor {
+    chars: SpecialChars,
+    line: Vec<u8>,
+    cursor: usize,
+    utf8: Vec<u8>,
+    esc: EscState,
+    hist_pos: Option<usize>,
+    saved: Vec<u8>,
+}
+
+fn is_cont(b: u8) -> bool {
+    (b & 0xc0) == 0x80
+}
+
+fn char_count(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|b| !is_cont(**b)).count()
+}
+
+fn utf8_len(lead: u8) -> usize {
+    match lead {
+        0xf0..=0xff => 4,
+        0xe0..=0xef => 3,
+        _ => 2,
+    }
+}
+
+fn move_left(out: &mut Vec<u8>, n: usize) {
+    if n > 0 {
+        out.extend_from_slice(format!("\x1b[{}D", n).as_bytes());
+    }
+}
+
+impl LineEditor {
+    pub fn new(chars: SpecialChars) -> Self {
+        Self {
+            chars,
+            line: Vec::new(),
+            cursor: 0,
+            utf8: Vec::new(),
+            esc: EscState::None,
+            hist_pos: None,
+            saved: Vec::new(),
+        }
+    }
+
+    /// Takes the current line (including the terminating newline, if the line has been completed)
+    /// and starts a new one.
+    pub fn take_line(&mut self) -> Vec<u8> {
+        self.cursor = 0;
+        self.hist_pos = None;
+        self.saved.clear();
+        self.esc = EscState::None;
+        self.utf8.clear();
+        core::mem::take(&mut self.line)
+    }
+
+    /// Writes the current line to `out` and places the terminal cursor at the cursor position.
+    ///
+    /// This assumes that the terminal cursor is at the beginning of an empty line.
+    pub fn redraw(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.line);
+        out.extend_from_slice(b"\x1b[K");
+        move_left(out, char_count(&self.line[self.cursor..]));
+    }
+
+    /// Feeds the given character into the editor, using `hist` for the history recall.
+    ///
+    /// The output for the terminal is appended to `out`. Returns an event if the character
+    /// completed the line, ended the input, or requested a signal.
+    pub fn handle(
+        &mut self,
+        b: u8,
+        hist: Option<&mut History>,
+        out: &mut Vec<u8>,
+    ) -> Option<Event> {
+        match core::mem::replace(&mut self.esc, EscState::None) {
+            EscState::Esc if b == b'[' || b == b'O' => {
+                self.esc = EscState::Seq(Vec::new());
+                return None;
+            },
+            // ignore unknown escape sequences
+            EscState::Esc => return None,
+            EscState::Seq(mut seq) => {
+                // parameters and intermediate bytes are followed by a single final byte
+                if (0x40..=0x7e).contains(&b) {
+                    self.handle_seq(&seq, b, hist, out);
+                }
+                else {
+                    seq.push(b);
+                    self.esc = EscState::Seq(seq);
+                }
+                return None;
+            },
+            EscState::None => {},
+        }
+
+        let c = &self.chars;
+        if b == c.eof {
+            return Some(Event::Eof);
+        }
+        else if b == c.intr {
+            return Some(Event::Signal);
+        }
+        else if b == c.erase {
+            if self.cursor > 0 {
+                let start = self.prev_char(self.cursor);
+                self.remove(start, self.cursor, out);
+            }
+        }
+        else if b == c.kill {
+            self.remove(0, self.cursor, out);
+        }
+        else if b == c.werase {
+            let mut start = self.cursor;
+            while start > 0 && self.line[start - 1] == b' ' {
+                start -= 1;
+            }
+            while start > 0 && self.line[start - 1] != b' ' {
+                start -= 1;
+            }
+            self.remove(start, self.cursor, out);
+        }
+        else if b == b'\n' {
+            if let Some(h) = hist {
+                h.add(&self.line);
+            }
+            self.line.push(b'\n');
+            out.push(b'\n');
+            return Some(Event::Line);
+        }
+        else if b == ESC {
+            self.esc = EscState::Esc;
+        }
+        else if b >= 0x80 {
+            // collect multi-byte characters before inserting them
+            if !is_cont(b) {
+                self.utf8.clear();
+                self.utf8.push(b);
+            }
+            else if !self.utf8.is_empty() {
+                self.utf8.push(b);
+                if self.utf8.len() == utf8_len(self.utf8[0]) {
+                    let chr = core::mem::take(&mut self.utf8);
+                    self.insert(&chr, out);
+                }
+            }
+        }
+        else if !b.is_ascii_control() {
+            self.insert(&[b], out);
+        }
+        None
+    }
+
+    fn handle_seq(
+        &mut self,
+        params: &[u8],
+        fin: u8,
+        hist: Option<&mut History>,
+        out: &mut Vec<u8>,
+    ) {
+        match (params, fin) {
+            (_, b'A') => self.recall(hist, true, out),
+            (_, b'B') => self.recall(hist, false, out),
+            (_, b'C') => {
+                if self.cursor < self.line.len() {
+                    let end = self.next_char(self.cursor);
+                    out.extend_from_slice(&self.line[self.cursor..end]);
+                    self.cursor = end;
+                }
+            },
+            (_, b'D') => {
+                if self.cursor > 0 {
+                    self.cursor = self.prev_char(self.cursor);
+                    move_left(out, 1);
+                }
+            },
+            (_, b'H') | (b"1", b'~') | (b"7", b'~') => {
+                move_left(out, char_count(&self.line[..self.cursor]));
+                self.cursor = 0;
+            },
+            (_, b'F') | (b"4", b'~') | (b"8", b'~') => {
+                out.extend_from_slice(&self.line[self.cursor..]);
+                self.cursor = self.line.len();
+            },
+            (b"3", b'~') => {
+                if self.cursor < self.line.len() {
+                    let end = self.next_char(self.cursor);
+                    self.remove(self.cursor, end, out);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn prev_char(&self, mut pos: usize) -> usize {
+        pos -= 1;
+        while pos > 0 && is_cont(self.line[pos]) {
+            pos -= 1;
+        }
+        pos
+    }
+
+    fn next_char(&self, mut pos: usize) -> usize {
+        pos += 1;
+        while pos < self.line.len() && is_cont(self.line[pos]) {
+            pos += 1;
+        }
+        pos
+    }
+
+    fn insert(&mut self, chr: &[u8], out: &mut Vec<u8>) {
+        self.line
+            .splice(self.cursor..self.cursor, chr.iter().copied());
+        self.cursor += chr.len();
+        out.extend_from_slice(chr);
+        self.redraw_rest(out);
+    }
+
+    fn remove(&mut self, start: usize, end: usize, out: &mut Vec<u8>) {
+        if start < end {
+            move_left(out, char_count(&self.line[start..end]));
+            self.line.drain(start..end);
+            self.cursor = start;
+            self.redraw_rest(out);
+        }
+    }
+
+    /// Writes the line behind the cursor, clears the remainder of the terminal line, and moves the
+    /// terminal cursor back to the cursor position.
+    fn redraw_rest(&self, out: &mut Vec<u8>) {
+        let rest = &self.line[self.cursor..];
+        out.extend_from_slice(rest);
+        out.extend_from_slice(b"\x1b[K");
+        move_left(out, char_count(rest));
+    }
+
+    fn recall(&mut self, hist: Option<&mut History>, older: bool, out: &mut Vec<u8>) {
+        let hist = match hist {
+            Some(h) => h,
+            None => return,
+        };
+
+        let new_pos = match (self.hist_pos, older) {
+            (None, true) => Some(0),
+            (None, false) => return,
+            (Some(p), true) => Some(p + 1),
+            (Some(0), false) => None,
+            (Some(p), false) => Some(p - 1),
+        };
+
+        let new_line = match new_pos {
+            Some(p) => match hist.get(p) {
+                Some(l) => l.clone(),
+                None => return,
+            },
+            None => core::mem::take(&mut self.saved),
+        };
+
+        // move to the beginning of the line before replacing it
+        move_left(out, char_count(&self.line[..self.cursor]));
+
+        // remember the line that has been edited before browsing the history
+        if self.hist_pos.is_none() {
+            self.saved = core::mem::replace(&mut self.line, new_line);
+        }
+        else {
+            self.line = new_line;
+        }
+        self.hist_pos = new_pos;
+
+        out.extend_from_slice(&self.line);
+        out.extend_from_slice(b"\x1b[K");
+        self.cursor = self.line.len();
+    }
+}
@@ -31,7 +31,7 @@ use core::cmp::min;
 use core::sync::atomic;
 use m3::cap::Selector;
 use m3::cell::{LazyReadOnlyCell, LazyStaticRefCell, StaticRefCell};
-use m3::col::VecDeque;
+use m3::col::{Vec, VecDeque};
 use m3::com::{GateIStream, MemGate, SGateArgs, SendGate};
 use m3::crypto::{HashAlgorithm, HashType};
 use m3::errors::{Code, Error};
@@ -40,7 +40,6 @@ use m3::log;
 use m3::mem::{size_of, AlignedBuf, MsgBuf, MsgBufRef};
 use m3::server::{
     server_loop, CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer,
-    DEF_MSG_SIZE,
 };
 use m3::session::{HashOp, ServerSession};
 use m3::tcu::{Label, Message};
@@ -58,6 +57,19 @@ const BUFFER_SIZE: usize = 8 * 1024; // 8 KiB
 /// Maximum number of sessions the multiplexer allows.
 const MAX_SESSIONS: usize = 32;
 
+/// Maximum size of requests. Resets for keyed hash algorithms need more space than the default
+/// message size because they carry the key.
+const MSG_SIZE: usize = 128;
+
+/// Size of the buffer for the data that keyed hash algorithms absorb in addition to the input.
+const KEYED_BUFFER_SIZE: usize = 512;
+
+/// The function name that is used for the cSHAKE-based KMAC.
+const KMAC_NAME: &[u8] = b"KMAC";
+
+/// The padding delimiter for cSHAKE.
+const CSHAKE_PAD: u8 = 0x04;
+
 /// The default time slice if not specified in the session arguments.
 const DEFAULT_TIME_SLICE: TimeDuration = TimeDuration::from_micros(100);
 
@@ -79,6 +91,9 @@ const OPTIMIZE_SCHEDULING: bool = true;
 // FIXME: This should point to the dedicated SRAMs
 static BUF1: StaticRefCell<AlignedBuf<BUFFER_SIZE>> = StaticRefCell::new(AlignedBuf::new_zeroed());
 static BUF2: StaticRefCell<AlignedBuf<BUFFER_SIZE>> = StaticRefCell::new(AlignedBuf::new_zeroed());
+// Memory region used for keys and paddings of keyed hash algorithms
+static KEYED_BUF: StaticRefCell<AlignedBuf<KEYED_BUFFER_SIZE>> =
+    StaticRefCell::new(AlignedBuf::new_zeroed());
 
 // Memory region used to save/load states of the accelerator for context switches
 static STATES: StaticRefCell<[KecAccState; MAX_SESSIONS]> =
@@ -88,6 +103,9 @@ static STATES: StaticRefCell<[KecAccState; MAX_SESSIONS]> =
 /// Must also fit into [`MsgBuf::borrow_def()`].
 const MAX_DIRECT_SIZE: usize = HashAlgorithm::MAX_OUTPUT_BYTES;
 const_assert!(MAX_DIRECT_SIZE <= BUFFER_SIZE);
+// The outer HMAC block with the inner hash and two KMAC blocks need to fit into the buffer
+const_assert!(HashAlgorithm::HMAC_SHA3_224.block_bytes + MAX_DIRECT_SIZE <= KEYED_BUFFER_SIZE);
+const_assert!(2 * HashAlgorithm::KMAC128.block_bytes <= KEYED_BUFFER_SIZE);
 
 static RECV: LazyReadOnlyCell<HashMuxReceiver> = LazyReadOnlyCell::default();
 static QUEUE: LazyStaticRefCell<VecDeque<SessId>> = LazyStaticRefCell::default();
@@ -124,6 +142,8 @@ struct HashSession {
     time_slice: TimeDuration,
     remaining_time: i64, // in nanoseconds
     output_bytes: usize,
+    absorbed_bytes: usize,
+    key: Vec<u8>,
 }
 
 /// Handles requests and holds all hash sessions.
@@ -266,6 +286,7 @@ impl HashSession {
                 KECACC.poll_complete();
             }
             req.complete_buffer(n);
+            self.absorbed_bytes += n;
 
             if let Err(remaining_time) = timer.try_continue() {
                 self.remaining_time = remaining_time;
@@ -283,7 +304,7 @@ impl HashSession {
 
         // Apply padding once for the request if needed
         if matches!(req.ty, HashRequestType::OutputPad) {
-            KECACC.start_pad();
+            self.start_pad();
             req.ty = HashRequestType::Output;
         }
 
@@ -380,10 +401,10 @@ impl HashSession {
     /// a few bytes and then sending them directly as reply.
     fn work_output_direct(&mut self, req: HashRequest) -> bool {
         let timer = HashMuxTimer::start(self);
+        self.start_pad();
+
         let buf = &mut BUF1.borrow_mut()[..req.len];
         let mut msg = MsgBuf::borrow_def();
-
-        KECACC.start_pad();
         KECACC.start_squeeze(buf);
         KECACC.poll_complete();
 
@@ -442,6 +463,18 @@ impl HashSession {
         let ty: HashType = is.pop()?;
         let algo = HashAlgorithm::from_type(ty).ok_or_else(|| Error::new(Code::InvArgs))?;
 
+        // keyed hash algorithms optionally receive the key (otherwise, the key is empty)
+        self.key.clear();
+        if is.size() > 2 * size_of::<u64>() {
+            let len: usize = is.pop()?;
+            let words: [u64; HashAlgorithm::MAX_KEY_BYTES / 8] = is.pop()?;
+            if !algo.is_keyed() || len > HashAlgorithm::MAX_KEY_BYTES {
+                return Err(Error::new(Code::InvArgs));
+            }
+            self.key
+                .extend(words.iter().flat_map(|w| w.to_le_bytes()).take(len));
+        }
+
         log!(
             LOG_DEF,
             "[{}] hash::reset() algo {}",
@@ -452,6 +485,7 @@ impl HashSession {
         self.algo = Some(algo);
         self.state_saved = false;
         self.output_bytes = 0;
+        self.absorbed_bytes = 0;
         is.reply_error(Code::Success)
     }
 
@@ -548,6 +582,139 @@ impl HashSession {
     }
 }
 
+/// Writes `left_encode(x)` as defined in NIST SP 800-185 to `buf` at `pos` and returns the position
+/// behind it.
+fn left_encode(buf: &mut [u8], pos: usize, x: usize) -> usize {
+    let bytes = (x as u64).to_be_bytes();
+    let n = bytes.len() - bytes.iter().take_while(|b| **b == 0).count().min(7);
+    buf[pos] = n as u8;
+    buf[pos + 1..pos + 1 + n].copy_from_slice(&bytes[bytes.len() - n..]);
+    pos + 1 + n
+}
+
+/// Writes `right_encode(x)` as defined in NIST SP 800-185 to `buf` at `pos` and returns the
+/// position behind it.
+fn right_encode(buf: &mut [u8], pos: usize, x: usize) -> usize {
+    let bytes = (x as u64).to_be_bytes();
+    let n = bytes.len() - bytes.iter().take_while(|b| **b == 0).count().min(7);
+    buf[pos..pos + n].copy_from_slice(&bytes[bytes.len() - n..]);
+    buf[pos + n] = n as u8;
+    pos + n + 1
+}
+
+/// Writes `encode_string(s)` as defined in NIST SP 800-185 to `buf` at `pos` and returns the
+/// position behind it.
+fn encode_string(buf: &mut [u8], pos: usize, s: &[u8]) -> usize {
+    let pos = left_encode(buf, pos, s.len() * 8);
+    buf[pos..pos + s.len()].copy_from_slice(s);
+    pos + s.len()
+}
+
+/// Fills `buf` from `pos` with zeros up to the next multiple of `rate` and returns that position.
+fn zero_pad(buf: &mut [u8], pos: usize, rate: usize) -> usize {
+    let end = (pos + rate - 1) / rate * rate;
+    buf[pos..end].fill(0);
+    end
+}
+
+/// Returns the hash type the accelerator uses for the given (potentially keyed) hash type.
+fn sponge_type(ty: HashType) -> HashType {
+    match ty {
+        HashType::KMAC128 => HashType::SHAKE128,
+        HashType::KMAC256 => HashType::SHAKE256,
+        HashType::HMAC_SHA3_224 => HashType::SHA3_224,
+        HashType::HMAC_SHA3_256 => HashType::SHA3_256,
+        HashType::HMAC_SHA3_384 => HashType::SHA3_384,
+        HashType::HMAC_SHA3_512 => HashType::SHA3_512,
+        ty => ty,
+    }
+}
+
+// Keyed hash algorithms are built on top of the plain SHA-3/SHAKE types of the accelerator by
+// absorbing additional data before the input and when applying the padding.
+impl HashSession {
+    fn is_kmac(algo: &HashAlgorithm) -> bool {
+        algo.ty == HashType::KMAC128 || algo.ty == HashType::KMAC256
+    }
+
+    /// Writes the key XORed with given pad byte and padded to the block size to `buf`.
+    fn hmac_key_block(&self, buf: &mut [u8], pad: u8) {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = self.key.get(i).copied().unwrap_or(0) ^ pad;
+        }
+    }
+
+    /// Initializes the accelerator for the hash algorithm of this session.
+    fn start_init(&self) {
+        let algo = self.algo.unwrap();
+        KECACC.start_init(sponge_type(algo.ty).val as u8);
+        if !algo.is_keyed() {
+            return;
+        }
+
+        let buf = &mut KEYED_BUF.borrow_mut()[..];
+        let rate = algo.block_bytes;
+        let len = if Self::is_kmac(algo) {
+            // bytepad(encode_string("KMAC") || encode_string(""), rate)
+            let mut pos = left_encode(buf, 0, rate);
+            pos = encode_string(buf, pos, KMAC_NAME);
+            pos = encode_string(buf, pos, &[]);
+            let end = zero_pad(buf, pos, rate);
+            // bytepad(encode_string(key), rate)
+            pos = left_encode(buf, end, rate);
+            pos = encode_string(buf, pos, &self.key);
+            zero_pad(buf, pos, rate)
+        }
+        else {
+            // (key ^ ipad), padded to the block size
+            self.hmac_key_block(&mut buf[..rate], 0x36);
+            rate
+        };
+
+        KECACC.start_absorb(&buf[..len]);
+        // the buffer is reused afterwards
+        KECACC.poll_complete();
+    }
+
+    /// Finishes the input by applying the padding.
+    fn start_pad(&self) {
+        let algo = self.algo.unwrap();
+        if !algo.is_keyed() {
+            KECACC.start_pad();
+            return;
+        }
+
+        let buf = &mut KEYED_BUF.borrow_mut()[..];
+        let rate = algo.block_bytes;
+        if Self::is_kmac(algo) {
+            // right_encode(L) followed by the cSHAKE padding, which the accelerator does not know.
+            // Thus, absorb the padding explicitly up to the end of the current block.
+            // The data starts at the current offset within the block to keep the alignment.
+            let start = self.absorbed_bytes % rate;
+            let pos = right_encode(buf, start, algo.output_bytes * 8);
+            let end = zero_pad(buf, pos + 1, rate);
+            buf[pos] = CSHAKE_PAD;
+            buf[end - 1] |= 0x80;
+            KECACC.start_absorb(&buf[start..end]);
+        }
+        else {
+            // finish the inner hash and put it behind the outer key block
+            let inner = &mut buf[rate..rate + algo.output_bytes];
+            KECACC.start_pad();
+            KECACC.start_squeeze(inner);
+            KECACC.poll_complete();
+            atomic::fence(atomic::Ordering::SeqCst);
+
+            // hash (key ^ opad) || inner hash
+            self.hmac_key_block(&mut buf[..rate], 0x5c);
+            KECACC.start_init(sponge_type(algo.ty).val as u8);
+            KECACC.start_absorb(&buf[..rate + algo.output_bytes]);
+            KECACC.start_pad();
+        }
+        KECACC.poll_complete();
+    }
+}
+
 impl HashHandler {
     fn handle(&mut self, op: HashOp, is: &mut GateIStream<'_>) -> Result<(), Error> {
         let sid = is.label() as SessId;
@@ -592,7 +759,7 @@ impl HashHandler {
             KECACC.start_load(&state[to]);
         }
         else {
-            KECACC.start_init(sess.algo.unwrap().ty.val as u8);
+            sess.start_init();
         }
     }
 }
@@ -637,6 +804,8 @@ impl Handler<HashSession> for HashHandler {
                 time_slice,
                 remaining_time: 0,
                 output_bytes: 0,
+                absorbed_bytes: 0,
+                key: Vec::new(),
             })
         })
     }
@@ -713,7 +882,7 @@ pub fn main() -> Result<(), Error> {
 
     RECV.set(HashMuxReceiver {
         server,
-        reqhdl: RequestHandler::new_with(MAX_SESSIONS, DEF_MSG_SIZE)
+        reqhdl: RequestHandler::new_with(MAX_SESSIONS, MSG_SIZE)
             .expect("Unable to create request handler"),
     });
     QUEUE.set(VecDeque::with_capacity(MAX_SESSIONS));
@@ -19,8 +19,11 @@
 use m3::com::MemGate;
 use m3::errors::Error;
 
+use partition::PartitionSpec;
+
 pub trait BlockDevice {
-    fn partition_exists(&self, part: usize) -> bool;
+    /// Returns the index of the partition that matches the given specification
+    fn find_partition(&self, spec: PartitionSpec<'_>) -> Option<usize>;
 
     fn read(
         &mut self,
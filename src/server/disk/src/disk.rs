@@ -17,7 +17,6 @@
 
 mod backend;
 mod gem5;
-mod partition;
 
 use m3::cap::Selector;
 use m3::cell::{LazyReadOnlyCell, LazyStaticRefCell};
@@ -36,6 +35,8 @@ use m3::session::{BlockNo, BlockRange, DiskOperation, ServerSession};
 use m3::tcu::Label;
 use m3::tiles::Activity;
 
+use partition::PartitionSpec;
+
 use backend::BlockDevice;
 use gem5::IDEBlockDevice;
 
@@ -138,18 +139,23 @@ impl Handler<DiskSession> for DiskHandler {
         srv_sel: Selector,
         arg: &str,
     ) -> Result<(Selector, SessId), Error> {
-        let dev = arg
-            .parse::<usize>()
-            .map_err(|_| Error::new(Code::InvArgs))?;
-        if !DEVICE.borrow().partition_exists(dev) {
-            return Err(Error::new(Code::InvArgs));
-        }
+        // the argument is either the index of the partition or the name of a GPT partition
+        let part = DEVICE
+            .borrow()
+            .find_partition(PartitionSpec::parse(arg))
+            .ok_or_else(|| Error::new(Code::InvArgs))?;
 
         self.sessions.add_next(crt, srv_sel, false, |sess| {
-            log!(crate::LOG_DEF, "[{}] disk::open(dev={})", sess.ident(), dev);
+            log!(
+                crate::LOG_DEF,
+                "[{}] disk::open(arg={}, part={})",
+                sess.ident(),
+                arg,
+                part
+            );
             Ok(DiskSession {
                 sess,
-                part: dev,
+                part,
                 sgates: Vec::new(),
                 blocks: Treap::new(),
             })
@@ -124,7 +124,7 @@ impl Channel {
 
     pub fn read_write(
         &self,
-        desc: PartDesc,
+        desc: &PartDesc,
         op: DiskOperation,
         buf: &MemGate,
         buf_off: usize,
@@ -146,7 +146,7 @@ impl Channel {
             return Err(Error::new(Code::InvArgs));
         }
 
-        let lba = desc.part.start_sector() as u64 + disk_off as u64 / dev.sector_size() as u64;
+        let lba = desc.part.start_sector() + disk_off as u64 / dev.sector_size() as u64;
         let count = bytes / dev.sector_size();
 
         let dev_op = match op {
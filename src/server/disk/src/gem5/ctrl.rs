@@ -36,8 +36,6 @@ const IDE_CTRL_SUBCLASS: u8 = 0x01;
 
 pub const IDE_CTRL_BAR: usize = 4;
 
-pub const DEVICE_COUNT: usize = 4;
-
 int_enum! {
     pub struct DeviceId : u32 {
         const PRIM_MASTER   = 0x0;
@@ -110,7 +108,7 @@ impl IDEController {
 
     pub fn read_write(
         &self,
-        part: PartDesc,
+        part: &PartDesc,
         op: DiskOperation,
         buf: &MemGate,
         buf_off: usize,
@@ -28,7 +28,7 @@ use m3::time::TimeDuration;
 
 use super::chan::Channel;
 use super::ctrl::ControlFlag;
-use crate::partition::{parse_partitions, Partition};
+use partition::{parse_partitions, Partition, SECTOR_SIZE};
 
 const ATA_WAIT_TIMEOUT: TimeDuration = TimeDuration::from_micros(500);
 
@@ -223,21 +223,15 @@ impl Device {
             return Err(Error::new(Code::NotSup));
         }
 
-        // read MBR from disk
-        let mut buffer = [0u8; 512];
-        let size = mem::size_of_val(&buffer) + mem::size_of::<PRD>();
+        // read and parse partition table from disk, one sector at a time
+        let size = SECTOR_SIZE + mem::size_of::<PRD>();
         let mg_buf = MemGate::new(size, Perm::RW)?;
         let dev_buf = mg_buf.derive(0, size, Perm::RW)?;
         chan.set_dma_buffer(&dev_buf)?;
-        dev.read_write(chan, DevOp::READ, &mg_buf, 0, 0, dev.sec_size, 1)?;
-
-        // parse partition table
-        mg_buf.read(&mut buffer, 0)?;
-        for p in parse_partitions(&buffer) {
-            if p.present() {
-                dev.parts.push(p);
-            }
-        }
+        dev.parts = parse_partitions(|lba, buf| {
+            dev.read_write(chan, DevOp::READ, &mg_buf, 0, lba, dev.sec_size, 1)?;
+            mg_buf.read(buf, 0)
+        })?;
 
         Ok(dev)
     }
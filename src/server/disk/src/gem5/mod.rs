@@ -20,13 +20,14 @@ mod device;
 use m3::col::Vec;
 use m3::com::MemGate;
 use m3::errors::Error;
+use m3::log;
 use m3::session::DiskOperation;
 
-use self::ctrl::DEVICE_COUNT;
+use partition::{Partition, PartitionSpec};
+
 use crate::backend::BlockDevice;
-use crate::partition::{Partition, PART_COUNT};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct PartDesc {
     chan: u8,
     device: u8,
@@ -35,7 +36,7 @@ pub struct PartDesc {
 
 pub struct IDEBlockDevice {
     ide_ctrl: ctrl::IDEController,
-    devs: [Option<PartDesc>; DEVICE_COUNT * PART_COUNT],
+    parts: Vec<PartDesc>,
 }
 
 impl IDEBlockDevice {
@@ -53,28 +54,34 @@ impl IDEBlockDevice {
 
         let ide_ctrl = ctrl::IDEController::new(use_irq, use_dma)?;
 
-        let mut devs = [None; DEVICE_COUNT * PART_COUNT];
+        // number the partitions of all devices consecutively
+        let mut parts = Vec::new();
         for c in ide_ctrl.channel() {
             for d in c.devices() {
                 for p in d.partitions() {
-                    if p.present() {
-                        devs[d.id() as usize * PART_COUNT + p.id()] = Some(PartDesc {
-                            chan: c.id(),
-                            device: d.id(),
-                            part: *p,
-                        });
-                    }
+                    log!(
+                        crate::LOG_DEF,
+                        "partition {}: device {}: {:?}",
+                        parts.len(),
+                        d.id(),
+                        p
+                    );
+                    parts.push(PartDesc {
+                        chan: c.id(),
+                        device: d.id(),
+                        part: p.clone(),
+                    });
                 }
             }
         }
 
-        Ok(IDEBlockDevice { ide_ctrl, devs })
+        Ok(IDEBlockDevice { ide_ctrl, parts })
     }
 }
 
 impl BlockDevice for IDEBlockDevice {
-    fn partition_exists(&self, part: usize) -> bool {
-        part < self.devs.len() && self.devs[part].is_some()
+    fn find_partition(&self, spec: PartitionSpec<'_>) -> Option<usize> {
+        spec.find(self.parts.iter().map(|p| &p.part))
     }
 
     fn read(
@@ -85,7 +92,7 @@ impl BlockDevice for IDEBlockDevice {
         disk_off: usize,
         bytes: usize,
     ) -> Result<(), Error> {
-        let part_desc = self.devs[part].unwrap();
+        let part_desc = &self.parts[part];
         self.ide_ctrl.read_write(
             part_desc,
             DiskOperation::READ,
@@ -104,7 +111,7 @@ impl BlockDevice for IDEBlockDevice {
         disk_off: usize,
         bytes: usize,
     ) -> Result<(), Error> {
-        let part_desc = self.devs[part].unwrap();
+        let part_desc = &self.parts[part];
         self.ide_ctrl.read_write(
             part_desc,
             DiskOperation::WRITE,
@@ -163,6 +163,16 @@ impl Backend for DiskBackend {
         Ok(())
     }
 
+    fn clear_block(&self, bno: BlockNo, off: usize, len: usize) -> Result<(), Error> {
+        let zeros = [0; crate::data::MAX_BLOCK_SIZE as usize];
+        let sel = m3::tiles::Activity::own().alloc_sel();
+        // load the block, because we only overwrite parts of it
+        let mut limit = LoadLimit::new();
+        crate::file_buffer_mut().get_extent(self, bno, 1, sel, Perm::RW, Some(&mut limit))?;
+        let mem = MemGate::new_owned_bind(sel);
+        mem.write_bytes(zeros.as_ptr(), len, off as goff)
+    }
+
     fn load_sb(&mut self) -> Result<SuperBlock, Error> {
         let tmp = MemGate::new(512 + PRDT_SIZE, Perm::RW)?;
         // use a separate MemGate for the disk service, because both have to activate the gate,
@@ -172,9 +182,10 @@ impl Backend for DiskBackend {
         self.disk.read(0, BlockRange::new(0), 512, None)?;
         let super_block = tmp.read_obj::<SuperBlock>(0)?;
 
-        // use separate transfer buffer for each entry to allow parallel disk requests
+        // use separate transfer buffer for each entry to allow parallel disk requests (plus one
+        // for the journal)
         self.blocksize = super_block.block_size as usize;
-        let size = (self.blocksize + PRDT_SIZE) * crate::buf::META_BUFFER_SIZE;
+        let size = (self.blocksize + PRDT_SIZE) * (crate::buf::META_BUFFER_SIZE + 1);
         self.metabuf = MemGate::new(size, Perm::RW)?;
         // separate MemGate for the same reason as above
         self.metabuf_disk = self.metabuf.derive(0, size, Perm::RW)?;
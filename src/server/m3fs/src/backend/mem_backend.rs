@@ -98,6 +98,13 @@ impl Backend for MemBackend {
     ) -> Result<usize, Error> {
         let first_block = extoff / self.blocksize;
         let bytes: usize = (ext.length as usize - first_block) * self.blocksize;
+        // clients write to the blocks directly, so that we cannot keep their checksums up to date
+        if perms.contains(Perm::W) {
+            crate::checksums_mut().invalidate(BlockRange::new_range(
+                ext.start + first_block as BlockNo,
+                ext.length - first_block as BlockNo,
+            ));
+        }
         let size = ((ext.start as usize + first_block) * self.blocksize) as u64;
         derive_mem(
             m3::tiles::Activity::own().sel(),
@@ -111,6 +118,7 @@ impl Backend for MemBackend {
     }
 
     fn clear_extent(&self, ext: Extent) -> Result<(), Error> {
+        crate::checksums_mut().invalidate(BlockRange::new_range(ext.start, ext.length));
         let zeros = vec![0; self.blocksize];
         for bno in ext.block_range() {
             self.mem
@@ -119,6 +127,13 @@ impl Backend for MemBackend {
         Ok(())
     }
 
+    fn clear_block(&self, bno: BlockNo, off: usize, len: usize) -> Result<(), Error> {
+        crate::checksums_mut().invalidate(BlockRange::new(bno));
+        let zeros = vec![0; len];
+        self.mem
+            .write(&zeros, (bno as usize * self.blocksize + off) as u64)
+    }
+
     fn load_sb(&mut self) -> Result<SuperBlock, Error> {
         let block = self.mem.read_obj::<SuperBlock>(0)?;
         self.blocksize = block.block_size as usize;
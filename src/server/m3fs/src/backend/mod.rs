@@ -69,6 +69,9 @@ pub trait Backend {
 
     fn clear_extent(&self, ext: Extent) -> Result<(), Error>;
 
+    /// Overwrites `len` bytes at offset `off` within the data block `bno` with zeros
+    fn clear_block(&self, bno: BlockNo, off: usize, len: usize) -> Result<(), Error>;
+
     fn load_sb(&mut self) -> Result<SuperBlock, Error>;
 
     fn store_sb(&self, super_block: &SuperBlock) -> Result<(), Error>;
@@ -0,0 +1,229 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::buf::{MetaBufferBlock, META_BUFFER_SIZE};
+use crate::data::{block_checksum, BlockNo, BlockRange, SuperBlock, NO_CHECKSUM};
+
+use m3::col::Vec;
+use m3::com::MemGate;
+use m3::errors::{Code, Error};
+
+/// The checksums of all blocks, if the file system has been created with checksums.
+///
+/// The checksum area is loaded completely at mount time, which costs four bytes per block. The
+/// meta buffer and the file buffer verify the blocks they load and update the checksums of the
+/// blocks they write. The changed parts of the checksum area are written back as part of the next
+/// transaction of the journal, which is committed right after writing back file data outside of
+/// transactions.
+///
+/// Note that data checksums are only effective with the disk backend. The mem backend hands out
+/// the blocks themselves to clients, so that it invalidates the checksums of all blocks that are
+/// mapped writable. Blocks that do not match their checksum are reported as
+/// [`Code::DataCorrupted`].
+pub struct Checksums {
+    sb: SuperBlock,
+    sums: Vec<u32>,
+    // one flag per block of the checksum area
+    dirty: Vec<bool>,
+    errors: u64,
+}
+
+impl Checksums {
+    pub fn new(sb: &SuperBlock) -> Result<Self, Error> {
+        let mut cs = Checksums {
+            sb: SuperBlock { ..*sb },
+            sums: Vec::new(),
+            dirty: Vec::new(),
+            errors: 0,
+        };
+        if !cs.enabled() {
+            return Ok(cs);
+        }
+
+        let count = sb.needed_checksum_blocks() as usize;
+        if (sb.checksum_blocks as usize) < count {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        cs.sums = vec![NO_CHECKSUM; count * sb.checksums_per_block()];
+        cs.dirty = vec![false; count];
+
+        // the extra transfer slot belongs to the journal, which does not exist yet
+        let mut scratch = MetaBufferBlock::new(META_BUFFER_SIZE, 0, sb.block_size as usize);
+        for i in 0..count {
+            let bno = sb.first_checksum_block() + i as BlockNo;
+            scratch.load_from(bno)?;
+            cs.load_block(bno, scratch.data());
+        }
+        Ok(cs)
+    }
+
+    /// Returns true if the file system stores checksums
+    pub fn enabled(&self) -> bool {
+        self.sb.checksum_blocks > 0
+    }
+
+    /// Returns the number of blocks that did not match their checksum so far
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Verifies the given data of block `bno` against its checksum
+    pub fn verify(&mut self, bno: BlockNo, data: &[u8]) -> Result<(), Error> {
+        if !self.sb.has_checksum(bno) {
+            return Ok(());
+        }
+
+        let sum = self.sums[bno as usize];
+        if sum != NO_CHECKSUM && sum != block_checksum(data) {
+            log!(
+                crate::LOG_BUFFER,
+                "checksums: block {} does not match its checksum {:#010x}",
+                bno,
+                sum
+            );
+            self.errors += 1;
+            return Err(Error::new(Code::DataCorrupted));
+        }
+        Ok(())
+    }
+
+    /// Verifies the blocks in the given range, whose data is stored in `mem`
+    pub fn verify_mem(&mut self, mem: &MemGate, blocks: BlockRange) -> Result<(), Error> {
+        self.for_each_block(mem, blocks, |cs, bno, data| cs.verify(bno, data))
+    }
+
+    /// Updates the checksum of block `bno` according to the given data
+    pub fn update(&mut self, bno: BlockNo, data: &[u8]) {
+        if self.sb.has_checksum(bno) {
+            self.set(bno, block_checksum(data));
+        }
+    }
+
+    /// Updates the checksums of the blocks in the given range, whose data is stored in `mem`
+    pub fn update_mem(&mut self, mem: &MemGate, blocks: BlockRange) -> Result<(), Error> {
+        self.for_each_block(mem, blocks, |cs, bno, data| {
+            cs.update(bno, data);
+            Ok(())
+        })
+    }
+
+    /// Removes the checksums of the blocks in the given range, because their data is changed in a
+    /// way that we cannot observe
+    pub fn invalidate(&mut self, blocks: BlockRange) {
+        for bno in blocks.start..blocks.start + blocks.count {
+            if self.sb.has_checksum(bno) {
+                self.set(bno, NO_CHECKSUM);
+            }
+        }
+    }
+
+    /// Returns true if block `bno` belongs to the checksum area
+    pub fn is_checksum_block(&self, bno: BlockNo) -> bool {
+        (self.sb.first_checksum_block()..self.sb.first_data_block()).contains(&bno)
+    }
+
+    /// Returns the block numbers of the changed blocks of the checksum area
+    pub fn dirty_blocks(&self) -> Vec<BlockNo> {
+        self.dirty
+            .iter()
+            .enumerate()
+            .filter(|(_, dirty)| **dirty)
+            .map(|(i, _)| self.sb.first_checksum_block() + i as BlockNo)
+            .collect()
+    }
+
+    /// Copies the current contents of checksum block `bno` into `block`
+    pub fn get_block(&self, bno: BlockNo, block: &mut MetaBufferBlock) {
+        let sums = self.block_sums(bno);
+        for (sum, bytes) in sums.iter().zip(block.data_mut().chunks_exact_mut(4)) {
+            bytes.copy_from_slice(&sum.to_le_bytes());
+        }
+    }
+
+    /// Replaces the checksums stored in checksum block `bno` by the given data of this block
+    pub fn load_block(&mut self, bno: BlockNo, data: &[u8]) {
+        let idx = (bno - self.sb.first_checksum_block()) as usize;
+        if idx >= self.dirty.len() {
+            return;
+        }
+
+        let per_block = self.sb.checksums_per_block();
+        let sums = &mut self.sums[idx * per_block..(idx + 1) * per_block];
+        for (sum, bytes) in sums.iter_mut().zip(data.chunks_exact(4)) {
+            *sum = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        self.dirty[idx] = false;
+    }
+
+    /// Writes checksum block `bno` back, using `scratch` as transfer buffer
+    pub fn store_block(
+        &mut self,
+        bno: BlockNo,
+        scratch: &mut MetaBufferBlock,
+    ) -> Result<(), Error> {
+        self.get_block(bno, scratch);
+        scratch.store_to(bno)?;
+        self.dirty[(bno - self.sb.first_checksum_block()) as usize] = false;
+        Ok(())
+    }
+
+    /// Writes the changed parts of the checksum area back, using `scratch` as transfer buffer
+    pub fn store(&mut self, scratch: &mut MetaBufferBlock) -> Result<(), Error> {
+        for bno in self.dirty_blocks() {
+            self.store_block(bno, scratch)?;
+        }
+        Ok(())
+    }
+
+    fn block_sums(&self, bno: BlockNo) -> &[u32] {
+        let idx = (bno - self.sb.first_checksum_block()) as usize;
+        let per_block = self.sb.checksums_per_block();
+        &self.sums[idx * per_block..(idx + 1) * per_block]
+    }
+
+    fn set(&mut self, bno: BlockNo, sum: u32) {
+        if self.sums[bno as usize] != sum {
+            self.sums[bno as usize] = sum;
+            self.dirty[bno as usize / self.sb.checksums_per_block()] = true;
+        }
+    }
+
+    fn for_each_block<F>(
+        &mut self,
+        mem: &MemGate,
+        blocks: BlockRange,
+        mut func: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&mut Self, BlockNo, &[u8]) -> Result<(), Error>,
+    {
+        if !self.enabled() {
+            return Ok(());
+        }
+
+        let block_size = self.sb.block_size as usize;
+        let mut data = vec![0u8; block_size];
+        for i in 0..blocks.count {
+            mem.read_bytes(
+                data.as_mut_ptr(),
+                block_size,
+                (i as usize * block_size) as u64,
+            )?;
+            func(self, blocks.start + i, &data)?;
+        }
+        Ok(())
+    }
+}
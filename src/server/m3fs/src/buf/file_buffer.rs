@@ -73,8 +73,14 @@ impl FileBufferEntry {
             );
 
             // write data of block to backend
+            crate::checksums_mut().update_mem(&self.data, self.blocks)?;
             crate::backend_mut().store_data(self.blocks, self.unlock)?;
 
+            // commit the new checksums right away; transactions commit them at their end
+            if !crate::journal_mut().in_transaction() {
+                crate::journal_mut().commit_checksums()?;
+            }
+
             // reset dirty and unlock
             self.dirty = false;
             self.locked = false;
@@ -274,6 +280,14 @@ impl FileBuffer {
         )?;
         new_head.locked = false;
 
+        // don't keep corrupt blocks, so that every access reports the error
+        if load.is_some() {
+            if let Err(e) = crate::checksums_mut().verify_mem(&new_head.data, new_head.blocks) {
+                self.size -= new_head.blocks.count as usize;
+                return Err(e);
+            }
+        }
+
         m3::syscalls::derive_mem(
             m3::tiles::Activity::own().sel(),
             sel,
@@ -293,6 +307,35 @@ impl FileBuffer {
         Ok(load_size * self.block_size)
     }
 
+    /// Writes back all buffered blocks within `blocks` without evicting them.
+    ///
+    /// In contrast to [`flush`](Self::flush), the entries stay in the buffer so that clients keep
+    /// their memory capabilities. As clients might have written to the blocks after the last
+    /// write back, the blocks are written back regardless of their dirty state.
+    pub fn sync(&mut self, blocks: BlockRange) -> Result<(), Error> {
+        log!(crate::LOG_BUFFER, "filebuffer::sync(blocks={:?})", blocks);
+
+        let end = blocks.start + blocks.count;
+        let mut bno = blocks.start;
+        while bno < end {
+            let block_opt = self
+                .entries
+                .get_mut(&BlockRange::new(bno))
+                .map(|b| unsafe { &mut *b.as_mut() });
+
+            match block_opt {
+                Some(head) if head.locked => thread::wait_for(head.unlock),
+                Some(head) => {
+                    head.dirty = true;
+                    head.flush()?;
+                    bno = head.blocks.start + head.blocks.count;
+                },
+                None => bno += 1,
+            }
+        }
+        Ok(())
+    }
+
     pub fn flush(&mut self) -> Result<(), Error> {
         while let Some(mut b) = self.lru.pop_front() {
             self.entries.remove(&b.blocks);
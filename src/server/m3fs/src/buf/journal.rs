@@ -0,0 +1,270 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::buf::{MetaBufferBlock, META_BUFFER_SIZE};
+use crate::data::{BlockNo, JournalHeader, SuperBlock};
+
+use m3::col::Vec;
+use m3::errors::{Code, Error};
+use m3::tiles::OwnActivity;
+
+/// The write-ahead journal for metadata blocks.
+///
+/// All metadata changes of an operation are collected in the meta buffer and committed at the end
+/// of the operation as one transaction. The transaction is first written into the journal region,
+/// then made valid by writing the journal header, then written to the home locations, and finally
+/// retired by clearing the header again. If m3fs dies in between, the transaction is replayed on
+/// the next mount or ignored if the header has not been written.
+pub struct Journal {
+    first: BlockNo,
+    capacity: usize,
+    // used to write the header and to copy blocks during replay; uses the extra transfer slot
+    scratch: MetaBufferBlock,
+    depth: usize,
+    writes: usize,
+}
+
+impl Journal {
+    pub fn new(sb: &SuperBlock) -> Self {
+        let block_size = sb.block_size as usize;
+        Journal {
+            first: sb.first_journal_block(),
+            capacity: (sb.journal_blocks.saturating_sub(1) as usize)
+                .min(JournalHeader::capacity(block_size)),
+            scratch: MetaBufferBlock::new(META_BUFFER_SIZE, 0, block_size),
+            depth: 0,
+            writes: 0,
+        }
+    }
+
+    /// Returns true if the file system has a journal
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Replays a committed, but not yet retired transaction and updates the given superblock
+    /// accordingly.
+    pub fn replay(&mut self, sb: &mut SuperBlock) -> Result<(), Error> {
+        if !self.enabled() {
+            return Ok(());
+        }
+
+        self.scratch.load_from(self.first)?;
+        let (hd, homes) = match JournalHeader::load(self.scratch.data()) {
+            Some((hd, homes)) if homes.len() <= self.capacity => (hd, homes),
+            _ => return Ok(()),
+        };
+
+        log!(
+            crate::LOG_JOURNAL,
+            "journal: replaying transaction with {} blocks",
+            homes.len()
+        );
+
+        // the checksum blocks precede the metadata blocks, so that we update the checksums of the
+        // latter afterwards
+        for (i, bno) in homes.iter().enumerate() {
+            self.scratch.load_from(self.first + 1 + i as BlockNo)?;
+            self.scratch.store_to(*bno)?;
+            let mut checksums = crate::checksums_mut();
+            if checksums.is_checksum_block(*bno) {
+                checksums.load_block(*bno, self.scratch.data());
+            }
+            else {
+                checksums.update(*bno, self.scratch.data());
+            }
+        }
+        crate::checksums_mut().store(&mut self.scratch)?;
+
+        sb.update_inodebm(hd.free_inodes, hd.first_free_inode);
+        sb.update_blockbm(hd.free_blocks, hd.first_free_block);
+        sb.checksum = sb.get_checksum();
+        crate::backend_mut().store_sb(sb)?;
+
+        self.retire()
+    }
+
+    /// Returns true if we are currently executing a transaction
+    pub fn in_transaction(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// Returns true if the journal can hold all blocks of the meta buffer, which bounds the size
+    /// of transactions, because dirty blocks are never evicted from the meta buffer.
+    pub fn holds_meta_buffer(&self) -> bool {
+        self.capacity >= META_BUFFER_SIZE
+    }
+
+    /// Writes the given dirty blocks and the current superblock back as one transaction.
+    ///
+    /// The transaction contains the changed checksums of file data that has been written back
+    /// since the last commit as well. Transactions that do not fit into the journal are rejected,
+    /// which cannot happen with journals that can hold the entire meta buffer (see
+    /// `holds_meta_buffer`).
+    pub fn commit(&mut self, blocks: &mut [&mut MetaBufferBlock]) -> Result<(), Error> {
+        let mut sb = crate::superblock_mut();
+        crate::update_superblock(&mut sb);
+
+        if !self.enabled() {
+            for b in blocks.iter_mut() {
+                b.flush()?;
+            }
+            crate::checksums_mut().store(&mut self.scratch)?;
+            return crate::backend_mut().store_sb(&sb);
+        }
+
+        if blocks.len() > self.capacity {
+            log!(
+                crate::LOG_JOURNAL,
+                "journal: transaction with {} blocks exceeds capacity of {} blocks",
+                blocks.len(),
+                self.capacity
+            );
+            return Err(Error::new(Code::NoSpace));
+        }
+
+        // the file data is already at its home location, so that we can commit its checksums
+        // separately if they do not fit into the transaction
+        let mut sums = crate::checksums_mut().dirty_blocks();
+        if blocks.len() + sums.len() > self.capacity {
+            self.write_checksums(&sb, &sums)?;
+            sums.clear();
+        }
+
+        if blocks.is_empty() && sums.is_empty() {
+            return Ok(());
+        }
+        self.write(&sb, &sums, blocks)
+    }
+
+    /// Commits the changed checksums of file data that has been written back.
+    pub fn commit_checksums(&mut self) -> Result<(), Error> {
+        let mut sb = crate::superblock_mut();
+        crate::update_superblock(&mut sb);
+
+        if !self.enabled() {
+            return crate::checksums_mut().store(&mut self.scratch);
+        }
+
+        let sums = crate::checksums_mut().dirty_blocks();
+        self.write_checksums(&sb, &sums)
+    }
+
+    fn write_checksums(&mut self, sb: &SuperBlock, sums: &[BlockNo]) -> Result<(), Error> {
+        for chunk in sums.chunks(self.capacity) {
+            self.write(sb, chunk, &mut [])?;
+        }
+        Ok(())
+    }
+
+    fn write(
+        &mut self,
+        sb: &SuperBlock,
+        sums: &[BlockNo],
+        blocks: &mut [&mut MetaBufferBlock],
+    ) -> Result<(), Error> {
+        log!(
+            crate::LOG_JOURNAL,
+            "journal: committing transaction with {} checksum and {} metadata blocks",
+            sums.len(),
+            blocks.len()
+        );
+
+        // write the blocks into the journal, starting with the checksum blocks
+        for (i, bno) in sums.iter().enumerate() {
+            self.crash_point();
+            crate::checksums_mut().get_block(*bno, &mut self.scratch);
+            self.scratch.store_to(self.first + 1 + i as BlockNo)?;
+        }
+        for (i, b) in blocks.iter().enumerate() {
+            self.crash_point();
+            b.store_to(self.first + 1 + (sums.len() + i) as BlockNo)?;
+        }
+
+        // make the transaction valid by writing the header
+        let homes = sums
+            .iter()
+            .copied()
+            .chain(blocks.iter().map(|b| b.blockno()))
+            .collect::<Vec<_>>();
+        let hd = JournalHeader::new(sb, &homes);
+        hd.store(&homes, self.scratch.data_mut());
+        self.crash_point();
+        self.scratch.store_to(self.first)?;
+
+        // write the blocks to their home locations; this updates their checksums, which are
+        // written afterwards. If we crash in between, the replay updates them again.
+        for b in blocks.iter_mut() {
+            self.crash_point();
+            b.flush()?;
+        }
+        self.crash_point();
+        {
+            let mut checksums = crate::checksums_mut();
+            for bno in sums {
+                checksums.store_block(*bno, &mut self.scratch)?;
+            }
+            if !blocks.is_empty() {
+                checksums.store(&mut self.scratch)?;
+            }
+        }
+        crate::backend_mut().store_sb(sb)?;
+
+        self.crash_point();
+        self.retire()
+    }
+
+    fn retire(&mut self) -> Result<(), Error> {
+        self.scratch.data_mut().fill(0);
+        self.scratch.store_to(self.first)
+    }
+
+    /// Simulates a crash of m3fs after the number of writes given via "-k"
+    fn crash_point(&mut self) {
+        if crate::settings().crash_after == Some(self.writes) {
+            log!(
+                crate::LOG_JOURNAL,
+                "journal: crashing after {} writes",
+                self.writes
+            );
+            OwnActivity::exit_with(Code::Abort);
+        }
+        self.writes += 1;
+    }
+}
+
+/// Executes `func` as a transaction, that is, all metadata changes are committed together after
+/// `func` returned.
+///
+/// Transactions can be nested, in which case only the outermost transaction commits the changes.
+/// The changes are committed even if `func` fails, because the in-memory state has been changed
+/// already.
+pub fn transaction<F, R>(func: F) -> Result<R, Error>
+where
+    F: FnOnce() -> Result<R, Error>,
+{
+    crate::journal_mut().depth += 1;
+    let res = func();
+
+    let commit = {
+        let mut journal = crate::journal_mut();
+        journal.depth -= 1;
+        journal.depth == 0 && journal.enabled()
+    };
+    if commit {
+        crate::meta_buffer_mut().flush()?;
+    }
+    res
+}
@@ -21,8 +21,9 @@ use core::ops::{Deref, DerefMut};
 use core::ptr::NonNull;
 
 use m3::boxed::Box;
+use m3::cell::StaticCell;
 use m3::col::{BoxList, Treap, Vec};
-use m3::errors::Error;
+use m3::errors::{Code, Error};
 
 use thread::Event;
 
@@ -46,8 +47,11 @@ impl_boxitem!(MetaBufferBlock);
 
 pub const META_BUFFER_SIZE: usize = 128;
 
+/// Counts how often blocks have been marked dirty, so that changes to the metadata can be detected
+static MODIFICATIONS: StaticCell<u64> = StaticCell::new(0);
+
 impl MetaBufferBlock {
-    fn new(id: usize, bno: BlockNo, blocksize: usize) -> Self {
+    pub fn new(id: usize, bno: BlockNo, blocksize: usize) -> Self {
         MetaBufferBlock {
             id,
             bno,
@@ -70,6 +74,7 @@ impl MetaBufferBlock {
 
     pub fn mark_dirty(&mut self) {
         self.dirty = true;
+        MODIFICATIONS.set(MODIFICATIONS.get() + 1);
     }
 
     pub fn data(&self) -> &[u8] {
@@ -82,7 +87,7 @@ impl MetaBufferBlock {
 
     /// Overwrites the data of this block with zeros
     pub fn overwrite_zero(&mut self) {
-        self.dirty = true;
+        self.mark_dirty();
         for i in &mut self.data {
             *i = 0;
         }
@@ -98,12 +103,24 @@ impl MetaBufferBlock {
             );
 
             // write meta block to backend
+            crate::checksums_mut().update(self.bno, &self.data);
             crate::backend_mut().store_meta(self, self.id, self.bno, self.unlock)?;
             self.dirty = false;
             self.locked = false;
         }
         Ok(())
     }
+
+    /// Writes the data of this block to block `bno` instead of its own location
+    pub fn store_to(&self, bno: BlockNo) -> Result<(), Error> {
+        crate::backend_mut().store_meta(self, self.id, bno, self.unlock)
+    }
+
+    /// Loads the data of block `bno` into this block without changing its own location
+    pub fn load_from(&mut self, bno: BlockNo) -> Result<(), Error> {
+        let unlock = self.unlock;
+        crate::backend_mut().load_meta(self, self.id, bno, unlock)
+    }
 }
 
 pub struct MetaBufferBlockRef {
@@ -175,6 +192,11 @@ impl MetaBuffer {
         }
     }
 
+    /// Returns the number of modifications so far, which changes whenever a block is marked dirty
+    pub fn modifications(&self) -> u64 {
+        MODIFICATIONS.get()
+    }
+
     fn bno_to_id(&self, bno: BlockNo) -> Option<usize> {
         self.ids.get(&bno).copied()
     }
@@ -218,13 +240,28 @@ impl MetaBuffer {
             }
         }
 
-        // find first unused head
-        let mut use_block = None;
-        for lru_element in self.lru.iter() {
-            if lru_element.links == 0 {
-                use_block = Some(lru_element.id);
-                break;
+        // find first unused head, preferring clean blocks to not write back uncommitted changes
+        let mut use_block = self.find_unused(true);
+        if use_block.is_none() {
+            let (journaled, in_transaction) = {
+                let journal = crate::journal_mut();
+                (journal.enabled(), journal.in_transaction())
+            };
+            // with a journal, writing back a dirty block would store uncommitted changes outside
+            // of a transaction. Outside of transactions we can commit them, but otherwise the
+            // transaction is too large for the meta buffer.
+            if journaled {
+                if in_transaction {
+                    log!(
+                        crate::LOG_JOURNAL,
+                        "metabuffer: no clean block left for <{}>; transaction too large",
+                        bno
+                    );
+                    return Err(Error::new(Code::NoSpace));
+                }
+                self.flush()?;
             }
+            use_block = self.find_unused(false);
         }
 
         let block = unsafe {
@@ -248,6 +285,13 @@ impl MetaBuffer {
         crate::backend_mut().load_meta(block, block.id, bno, unlock)?;
         block.locked = false;
 
+        // don't keep corrupt blocks, so that every access reports the error
+        if let Err(e) = crate::checksums_mut().verify(bno, block.data()) {
+            self.ids.remove(&bno);
+            block.bno = 0;
+            return Err(e);
+        }
+
         log!(
             crate::LOG_BUFFER,
             "metabuffer: loaded new block<{}> links: {}",
@@ -257,11 +301,23 @@ impl MetaBuffer {
         Ok(MetaBufferBlockRef::new(block.id))
     }
 
+    /// Returns the least recently used block without links, optionally only clean ones
+    fn find_unused(&self, clean: bool) -> Option<usize> {
+        self.lru
+            .iter()
+            .find(|b| b.links == 0 && (!clean || !b.dirty))
+            .map(|b| b.id)
+    }
+
+    /// Writes all dirty blocks back to the backend as a single transaction
     pub fn flush(&mut self) -> Result<(), Error> {
+        let mut dirty = Vec::new();
         for block_ptr in &mut self.blocks {
             let block = unsafe { &mut (*block_ptr.as_ptr()) };
-            block.flush()?;
+            if block.dirty {
+                dirty.push(block);
+            }
         }
-        Ok(())
+        crate::journal_mut().commit(&mut dirty)
     }
 }
@@ -15,8 +15,12 @@
  * General Public License version 2 for more details.
  */
 
+mod checksums;
 mod file_buffer;
+mod journal;
 mod meta_buffer;
 
+pub use checksums::Checksums;
 pub use file_buffer::{FileBuffer, LoadLimit};
+pub use journal::{transaction, Journal};
 pub use meta_buffer::{MetaBuffer, MetaBufferBlock, MetaBufferBlockRef, META_BUFFER_SIZE};
@@ -15,12 +15,10 @@
  */
 
 use crate::buf::MetaBufferBlockRef;
-use crate::data::{BlockNo, INodeRef};
+use crate::data::{BlockNo, Extent, INodeRef};
 
 use core::fmt;
-use core::u32;
 
-use m3::cell::Cell;
 use m3::mem::size_of;
 
 /// Represents a file position given by extent number and offset within the extent
@@ -47,27 +45,14 @@ impl fmt::Debug for ExtPos {
     }
 }
 
-/// Represents an extent as stored on disk
-#[derive(Clone, Copy, Debug)]
-#[repr(C, align(8))]
-pub struct Extent {
-    pub start: u32,
-    pub length: u32,
+/// Extends [`Extent`] with access to its blocks via the meta buffer
+pub trait ExtentBlocks {
+    /// Returns an iterator over the blocks of this extent
+    fn block_iter(&self) -> BlockIterator;
 }
 
-impl Extent {
-    pub fn new(start: u32, length: u32) -> Self {
-        Self { start, length }
-    }
-
-    pub fn block_range(&self) -> core::ops::Range<BlockNo> {
-        core::ops::Range {
-            start: self.start,
-            end: self.start + self.length,
-        }
-    }
-
-    pub fn block_iter(&self) -> BlockIterator {
+impl ExtentBlocks for Extent {
+    fn block_iter(&self) -> BlockIterator {
         BlockIterator {
             range: self.block_range(),
         }
@@ -75,22 +60,11 @@ impl Extent {
 }
 
 /// A reference to an direct or indirect extent
+#[derive(Clone)]
 pub struct ExtentRef {
     block_ref: MetaBufferBlockRef,
     // this pointer is valid during our lifetime, because we keep a MetaBufferBlockRef
     extent: *mut Extent,
-    dirty: Cell<bool>,
-}
-
-impl Clone for ExtentRef {
-    fn clone(&self) -> Self {
-        Self {
-            block_ref: self.block_ref.clone(),
-            extent: self.extent,
-            // we reference the same block; there is no need to mark it dirty twice
-            dirty: Cell::from(false),
-        }
-    }
 }
 
 impl ExtentRef {
@@ -98,8 +72,8 @@ impl ExtentRef {
     pub fn dir_from_inode(inode: &INodeRef, index: usize) -> Self {
         Self {
             block_ref: inode.block().clone(),
-            extent: &mut inode.as_mut().direct[index],
-            dirty: Cell::from(false),
+            // don't use INodeRef::as_mut here; the block is marked dirty once the extent is changed
+            extent: &inode.direct[index] as *const Extent as *mut Extent,
         }
     }
 
@@ -124,13 +98,15 @@ impl ExtentRef {
         Self {
             block_ref: block_ref.clone(),
             extent: ext,
-            dirty: Cell::from(false),
         }
     }
 
     #[allow(clippy::mut_from_ref)]
     pub fn as_mut(&self) -> &mut Extent {
-        self.dirty.set(true);
+        // mark the block dirty right away so that the change is part of the current transaction
+        crate::meta_buffer_mut()
+            .get_block_by_ref(&self.block_ref)
+            .mark_dirty();
         // safety: valid because we keep a MetaBufferBlockRef
         unsafe { &mut *self.extent }
     }
@@ -145,14 +121,6 @@ impl core::ops::Deref for ExtentRef {
     }
 }
 
-impl Drop for ExtentRef {
-    fn drop(&mut self) {
-        if self.dirty.get() {
-            self.block_ref.mark_dirty();
-        }
-    }
-}
-
 /// Block iterator that delivers all blocks of an extent.
 pub struct BlockIterator {
     range: core::ops::Range<BlockNo>,
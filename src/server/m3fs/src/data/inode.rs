@@ -15,103 +15,39 @@
  */
 
 use crate::buf::MetaBufferBlockRef;
-use crate::data::{
-    BlockNo, Dev, Extent, ExtentCache, ExtentRef, InodeNo, Time, INODE_DIR_COUNT, NUM_INODE_BYTES,
-};
+use crate::data::{ExtentCache, ExtentRef};
 use crate::ops::inodes;
 
-use base::const_assert;
-
-use core::u32;
-
-use m3::cell::Cell;
 use m3::mem::size_of;
 use m3::vfs::{FileInfo, FileMode};
 
 /// Represents an INode as stored on disk.
-#[repr(C)]
-pub struct INode {
-    pub devno: Dev,
-    _pad: u8,
-    pub links: u16,
-
-    pub lastaccess: Time,
-    pub lastmod: Time,
-    pub extents: u32,
-
-    pub inode: InodeNo,
-    pub mode: FileMode,
-    pub size: u64,
-
-    pub direct: [Extent; INODE_DIR_COUNT], // direct entries
-    pub indirect: BlockNo,                 // location of the indirect block if != 0,
-    pub dindirect: BlockNo,                // location of double indirect block if != 0
-}
-
-impl Clone for INode {
-    fn clone(&self) -> Self {
-        const_assert!(size_of::<INode>() == NUM_INODE_BYTES);
-        INode {
-            devno: self.devno,
-            links: self.links,
-            _pad: 0,
-
-            inode: self.inode,
-            mode: self.mode,
-            size: self.size,
-
-            lastaccess: self.lastaccess,
-            lastmod: self.lastmod,
-            extents: self.extents,
-
-            direct: self.direct,
-            indirect: self.indirect,
-            dindirect: self.dindirect,
-        }
-    }
-}
-
-impl INode {
-    pub fn reset(&mut self) {
-        self.devno = 0;
-        self.links = 0;
-        self.inode = 0;
-        self.mode = FileMode::empty();
-        self.size = 0;
-        self.lastaccess = 0;
-        self.lastmod = 0;
-        self.extents = 0;
-
-        self.direct = [Extent {
-            start: 0,
-            length: 0,
-        }; INODE_DIR_COUNT];
-        self.indirect = 0;
-        self.dindirect = 0;
-    }
-
-    pub fn to_file_info(&self) -> FileInfo {
-        FileInfo {
-            devno: self.devno,
-            inode: self.inode,
-            mode: self.mode,
-            links: self.links as u32,
-            size: self.size as usize,
-            lastaccess: self.lastaccess,
-            lastmod: self.lastmod,
-            extents: self.extents,
-            blocksize: crate::superblock().block_size,
-            firstblock: self.direct[0].start,
-        }
+pub type INode = m3fs_layout::INode<FileMode>;
+
+/// Returns the file information for the given inode
+pub fn to_file_info(inode: &INode) -> FileInfo {
+    FileInfo {
+        devno: inode.devno,
+        inode: inode.inode,
+        mode: inode.mode,
+        links: inode.links as u32,
+        size: inode.size as usize,
+        lastaccess: inode.lastaccess,
+        lastmod: inode.lastmod,
+        extents: inode.extents,
+        blocksize: crate::superblock().block_size,
+        firstblock: inode.direct[0].start,
+        uid: inode.uid,
+        gid: inode.gid,
     }
 }
 
 /// A reference to an inode within a loaded MetaBuffer block.
+#[derive(Clone)]
 pub struct INodeRef {
     block_ref: MetaBufferBlockRef,
     // this pointer is valid during our lifetime, because we keep a MetaBufferBlockRef
     inode: *mut INode,
-    dirty: Cell<bool>,
 }
 
 impl INodeRef {
@@ -137,11 +73,7 @@ impl INodeRef {
             inode_ptr.add(off / size_of::<INode>())
         };
 
-        Self {
-            block_ref,
-            inode,
-            dirty: Cell::from(false),
-        }
+        Self { block_ref, inode }
     }
 
     pub fn extent_iter(&self) -> ExtentIterator<'_> {
@@ -158,7 +90,10 @@ impl INodeRef {
 
     #[allow(clippy::mut_from_ref)]
     pub fn as_mut(&self) -> &mut INode {
-        self.dirty.set(true);
+        // mark the block dirty right away so that the change is part of the current transaction
+        crate::meta_buffer_mut()
+            .get_block_by_ref(&self.block_ref)
+            .mark_dirty();
         // safety: valid because we keep a MetaBufferBlockRef
         unsafe { &mut *self.inode }
     }
@@ -173,25 +108,6 @@ impl core::ops::Deref for INodeRef {
     }
 }
 
-impl Clone for INodeRef {
-    fn clone(&self) -> Self {
-        Self {
-            block_ref: self.block_ref.clone(),
-            inode: self.inode,
-            // we reference the same block; there is no need to mark it dirty twice
-            dirty: Cell::from(false),
-        }
-    }
-}
-
-impl Drop for INodeRef {
-    fn drop(&mut self) {
-        if self.dirty.get() {
-            self.block_ref.mark_dirty();
-        }
-    }
-}
-
 /// Extent iterator that delivers all extents of an inode.
 pub struct ExtentIterator<'e> {
     inode: &'e INodeRef,
@@ -19,22 +19,15 @@ mod bitmap;
 mod direntry;
 mod extent;
 mod inode;
-mod superblock;
 
 pub use allocator::Allocator;
-pub use direntry::{DirEntry, DirEntryIterator};
-pub use extent::{ExtPos, Extent, ExtentCache, ExtentRef};
-pub use inode::INodeRef;
-pub use superblock::SuperBlock;
+pub use direntry::{entry_mut, two_entries_mut};
+pub use extent::{BlockIterator, ExtPos, ExtentBlocks, ExtentCache, ExtentRef};
+pub use inode::{to_file_info, INode, INodeRef};
+pub use m3fs_layout::{
+    block_checksum, BlockNo, DirEntry, DirEntryIterator, Extent, InodeNo, JournalHeader,
+    SuperBlock, DIR_ENTRY_LEN, INODE_DIR_COUNT, MAX_BLOCK_SIZE, MAX_LINK_LEN, NO_CHECKSUM,
+    NUM_EXT_BYTES, NUM_INODE_BYTES, SUPERBLOCK_MAGIC,
+};
 
-pub type BlockNo = m3::session::BlockNo;
 pub type BlockRange = m3::session::BlockRange;
-pub type Dev = u8;
-pub type InodeNo = u32;
-pub type Time = u32;
-
-pub const INODE_DIR_COUNT: usize = 3;
-pub const MAX_BLOCK_SIZE: u32 = 4096;
-pub const NUM_INODE_BYTES: usize = 64;
-pub const NUM_EXT_BYTES: usize = 8;
-pub const DIR_ENTRY_LEN: usize = 12;
@@ -26,9 +26,11 @@ mod ops;
 mod sess;
 
 use crate::backend::{Backend, DiskBackend, MemBackend};
-use crate::buf::{FileBuffer, MetaBuffer};
-use crate::data::{Allocator, SuperBlock};
-use crate::sess::{FSSession, M3FSSession, MetaSession, OpenFiles};
+use crate::buf::{Checksums, FileBuffer, Journal, MetaBuffer};
+use crate::data::{Allocator, SuperBlock, SUPERBLOCK_MAGIC};
+use crate::ops::fsck::{self, Fsck};
+use crate::ops::perms::Creds;
+use crate::sess::{FSSession, M3FSSession, MetaSession, OpenFiles, Watches};
 
 use base::cell::LazyStaticUnsafeCell;
 use m3::{
@@ -40,8 +42,7 @@ use m3::{
     env,
     errors::{Code, Error},
     server::{
-        server_loop, CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer,
-        DEF_MAX_CLIENTS,
+        CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer, DEF_MAX_CLIENTS,
     },
     tcu::Label,
     tiles::{Activity, OwnActivity},
@@ -57,6 +58,10 @@ pub const LOG_DIRS: bool = false;
 pub const LOG_INODES: bool = false;
 pub const LOG_LINKS: bool = false;
 pub const LOG_FIND: bool = false;
+pub const LOG_JOURNAL: bool = false;
+pub const LOG_PERMS: bool = false;
+pub const LOG_WATCH: bool = false;
+pub const LOG_FSCK: bool = false;
 
 // Server constants
 const MSG_SIZE: usize = 128;
@@ -70,10 +75,14 @@ static SB: LazyStaticRefCell<SuperBlock> = LazyStaticRefCell::default();
 static MB: LazyStaticUnsafeCell<MetaBuffer> = LazyStaticUnsafeCell::default();
 static FB: LazyStaticRefCell<FileBuffer> = LazyStaticRefCell::default();
 static FILES: StaticRefCell<OpenFiles> = StaticRefCell::new(OpenFiles::new());
+static WATCHES: StaticRefCell<Watches> = StaticRefCell::new(Watches::new());
 static BA: LazyStaticRefCell<Allocator> = LazyStaticRefCell::default();
 static IA: LazyStaticRefCell<Allocator> = LazyStaticRefCell::default();
 static SETTINGS: LazyReadOnlyCell<FsSettings> = LazyReadOnlyCell::default();
 static BACKEND: LazyStaticRefCell<Box<dyn Backend>> = LazyStaticRefCell::default();
+static JOURNAL: LazyStaticRefCell<Journal> = LazyStaticRefCell::default();
+static CHECKSUMS: LazyStaticRefCell<Checksums> = LazyStaticRefCell::default();
+static FSCK: StaticRefCell<Option<Fsck>> = StaticRefCell::new(None);
 
 fn superblock() -> Ref<'static, SuperBlock> {
     SB.borrow()
@@ -91,6 +100,9 @@ fn file_buffer_mut() -> RefMut<'static, FileBuffer> {
 fn open_files_mut() -> RefMut<'static, OpenFiles> {
     FILES.borrow_mut()
 }
+fn watches_mut() -> RefMut<'static, Watches> {
+    WATCHES.borrow_mut()
+}
 fn blocks_mut() -> RefMut<'static, Allocator> {
     BA.borrow_mut()
 }
@@ -103,19 +115,29 @@ fn settings() -> &'static FsSettings {
 fn backend_mut() -> RefMut<'static, Box<dyn Backend>> {
     BACKEND.borrow_mut()
 }
+fn journal_mut() -> RefMut<'static, Journal> {
+    JOURNAL.borrow_mut()
+}
+fn checksums_mut() -> RefMut<'static, Checksums> {
+    CHECKSUMS.borrow_mut()
+}
+fn fsck_mut() -> RefMut<'static, Option<Fsck>> {
+    FSCK.borrow_mut()
+}
 
-fn flush_buffer() -> Result<(), Error> {
-    crate::meta_buffer_mut().flush()?;
-    crate::file_buffer_mut().flush()?;
-
-    // update superblock and write it back to disk/memory
-    let mut sb = crate::superblock_mut();
+fn update_superblock(sb: &mut SuperBlock) {
     let inodes = crate::inodes_mut();
     sb.update_inodebm(inodes.free_count(), inodes.first_free());
     let blocks = crate::blocks_mut();
     sb.update_blockbm(blocks.free_count(), blocks.first_free());
     sb.checksum = sb.get_checksum();
-    crate::backend_mut().store_sb(&sb)
+}
+
+fn flush_buffer() -> Result<(), Error> {
+    // write back the file data first so that committed metadata never refers to stale data
+    crate::file_buffer_mut().flush()?;
+    // this commits the metadata and writes back the superblock
+    crate::meta_buffer_mut().flush()
 }
 
 int_enum! {
@@ -145,6 +167,17 @@ int_enum! {
         const GET_SGATE     = FSOperation::GET_SGATE.val;
         const DEL_EP        = FSOperation::DEL_EP.val;
         const OPEN_PRIV     = FSOperation::OPEN_PRIV.val;
+        const SYMLINK       = FSOperation::SYMLINK.val;
+        const READLINK      = FSOperation::READLINK.val;
+        const CHMOD         = FSOperation::CHMOD.val;
+        const CHOWN         = FSOperation::CHOWN.val;
+        const FSCK          = FSOperation::FSCK.val;
+        const ALLOCATE      = FSOperation::ALLOCATE.val;
+        const PUNCH_HOLE    = FSOperation::PUNCH_HOLE.val;
+        const LOCK          = FSOperation::LOCK.val;
+        const UNLOCK        = FSOperation::UNLOCK.val;
+        const STATFS        = FSOperation::STATFS.val;
+        const SYNC_MEM      = FSOperation::SYNC_MEM.val;
     }
 }
 
@@ -158,8 +191,28 @@ impl M3FSRequestHandler {
         // init thread manager, otherwise the waiting within the file and meta buffer impl. panics.
         thread::init();
 
-        let sb = backend.load_sb().expect("Unable to load super block");
+        let mut sb = backend.load_sb().expect("Unable to load super block");
         log!(crate::LOG_DEF, "Loaded {:#?}", sb);
+        assert!(
+            sb.magic == SUPERBLOCK_MAGIC,
+            "Invalid super block magic {:#x} (expected {:#x}); image too old?",
+            sb.magic,
+            SUPERBLOCK_MAGIC
+        );
+
+        // the backend is required to replay the journal
+        BACKEND.set(backend);
+
+        // replaying the journal updates the checksums
+        CHECKSUMS.set(Checksums::new(&sb).expect("Unable to load checksums"));
+
+        let mut journal = Journal::new(&sb);
+        assert!(
+            !journal.enabled() || journal.holds_meta_buffer(),
+            "Journal is too small to hold the meta buffer"
+        );
+        journal.replay(&mut sb).expect("Unable to replay journal");
+        JOURNAL.set(journal);
 
         BA.set(Allocator::new(
             String::from("Block"),
@@ -187,8 +240,6 @@ impl M3FSRequestHandler {
         FB.set(FileBuffer::new(sb.block_size as usize));
         SB.set(sb);
 
-        BACKEND.set(backend);
-
         let container = SessionContainer::new(DEF_MAX_CLIENTS);
 
         Ok(M3FSRequestHandler {
@@ -223,9 +274,20 @@ impl M3FSRequestHandler {
             M3FSOperation::RMDIR => self.exec_on_sess(input, |sess, is| sess.rmdir(is)),
             M3FSOperation::LINK => self.exec_on_sess(input, |sess, is| sess.link(is)),
             M3FSOperation::UNLINK => self.exec_on_sess(input, |sess, is| sess.unlink(is)),
+            M3FSOperation::SYMLINK => self.exec_on_sess(input, |sess, is| sess.symlink(is)),
+            M3FSOperation::READLINK => self.exec_on_sess(input, |sess, is| sess.readlink(is)),
+            M3FSOperation::CHMOD => self.exec_on_sess(input, |sess, is| sess.chmod(is)),
+            M3FSOperation::CHOWN => self.exec_on_sess(input, |sess, is| sess.chown(is)),
+            M3FSOperation::FSCK => self.exec_on_sess(input, |sess, is| sess.fsck(is)),
+            M3FSOperation::ALLOCATE => self.exec_on_sess(input, |sess, is| sess.allocate(is)),
+            M3FSOperation::PUNCH_HOLE => self.exec_on_sess(input, |sess, is| sess.punch_hole(is)),
+            M3FSOperation::LOCK => self.exec_on_sess(input, |sess, is| sess.lock(is)),
+            M3FSOperation::UNLOCK => self.exec_on_sess(input, |sess, is| sess.unlock(is)),
+            M3FSOperation::STATFS => self.exec_on_sess(input, |sess, is| sess.statfs(is)),
             M3FSOperation::RENAME => self.exec_on_sess(input, |sess, is| sess.rename(is)),
             M3FSOperation::SYNC => self.exec_on_sess(input, |sess, is| sess.sync(is)),
             M3FSOperation::OPEN_PRIV => self.exec_on_sess(input, |sess, is| sess.open_priv(is)),
+            M3FSOperation::REQ_NOTIFY => self.exec_on_sess(input, |sess, is| sess.req_notify(is)),
             _ => Err(Error::new(Code::InvArgs)),
         };
 
@@ -325,10 +387,19 @@ impl Handler<FSSession> for M3FSRequestHandler {
         srv_sel: Selector,
         arg: &str,
     ) -> Result<(Selector, SessId), Error> {
-        // get max number of files
+        // get max number of files and the identity of the client; without uid, the client is root
         let mut max_files: usize = 16;
-        if arg.len() > 6 && &arg[..6] == "files=" {
-            max_files = arg[6..].parse().map_err(|_| Error::new(Code::InvArgs))?;
+        let mut creds = Creds::ROOT;
+        for a in arg.split_whitespace() {
+            if let Some(val) = a.strip_prefix("files=") {
+                max_files = val.parse().map_err(|_| Error::new(Code::InvArgs))?;
+            }
+            else if let Some(val) = a.strip_prefix("uid=") {
+                creds.uid = val.parse().map_err(|_| Error::new(Code::InvArgs))?;
+            }
+            else if let Some(val) = a.strip_prefix("gid=") {
+                creds.gid = val.parse().map_err(|_| Error::new(Code::InvArgs))?;
+            }
         }
 
         // get the id this session would belong to.
@@ -337,13 +408,14 @@ impl Handler<FSSession> for M3FSRequestHandler {
         self.sessions.add_next(crt, srv_sel, true, |sess| {
             log!(
                 crate::LOG_SESSION,
-                "[{}] creating session(crt={}, max_files={})",
+                "[{}] creating session(crt={}, max_files={}, creds={:?})",
                 sess.ident(),
                 crt,
-                max_files
+                max_files,
+                creds
             );
             Ok(FSSession::Meta(MetaSession::new(
-                sess, sessid, crt, max_files,
+                sess, sessid, crt, max_files, creds,
             )))
         })
     }
@@ -387,6 +459,7 @@ impl Handler<FSSession> for M3FSRequestHandler {
                         .add(crt, next_sess_id, FSSession::File(nfile_session))
                 },
                 M3FSOperation::GET_MEM => file.get_mem(data),
+                M3FSOperation::SYNC_MEM => file.sync_mem(data),
                 _ => Err(Error::new(Code::InvArgs)),
             },
         }
@@ -421,7 +494,19 @@ impl Handler<FSSession> for M3FSRequestHandler {
                         1,
                     ));
                 },
-                M3FSOperation::ENABLE_NOTIFY => return Err(Error::new(Code::NotSup)),
+                M3FSOperation::ENABLE_NOTIFY => {
+                    if data.in_caps() != 1 {
+                        return Err(Error::new(Code::InvArgs));
+                    }
+
+                    let new_sel: Selector = Activity::own().alloc_sel();
+                    fs.enable_notify(new_sel)?;
+                    data.out_caps(m3::kif::CapRngDesc::new(
+                        m3::kif::CapType::OBJECT,
+                        new_sel,
+                        1,
+                    ));
+                },
                 _ => return Err(Error::new(Code::InvArgs)),
             },
             FSSession::Meta(m) => match op {
@@ -466,6 +551,7 @@ pub struct FsSettings {
     max_clients: usize,
     clear: bool,
     selector: Option<Selector>,
+    crash_after: Option<usize>,
 }
 
 impl core::default::Default for FsSettings {
@@ -479,6 +565,7 @@ impl core::default::Default for FsSettings {
             max_clients: DEF_MAX_CLIENTS,
             clear: false,
             selector: None,
+            crash_after: None,
         }
     }
 }
@@ -488,7 +575,7 @@ fn usage() -> ! {
         "Usage: {} [-n <name>] [-s <sel>] [-e <blocks>] [-c] [-f <name>] [-b <blocks>]",
         env::args().next().unwrap()
     );
-    println!("       [-m <clients>] (disk|mem)");
+    println!("       [-m <clients>] [-k <writes>] (disk|mem)");
     println!();
     println!("  -n: the name of the service (m3fs by default)");
     println!("  -s: don't create service, use selectors <sel>..<sel+1>");
@@ -497,6 +584,7 @@ fn usage() -> ! {
     println!("  -b: the maximum number of blocks loaded from the disk");
     println!("  -m: the maximum number of clients (receive slots)");
     println!("  -f: the name of the FS boot module ('fs' by default)");
+    println!("  -k: simulate a crash after <writes> metadata writes (for testing)");
     OwnActivity::exit_with(Code::InvArgs);
 }
 
@@ -529,6 +617,13 @@ fn parse_args() -> Result<FsSettings, String> {
                     .parse::<usize>()
                     .map_err(|_| String::from("Failed to parse client count"))?;
             },
+            "-k" => {
+                settings.crash_after = Some(
+                    args[i + 1]
+                        .parse::<usize>()
+                        .map_err(|_| String::from("Failed to parse crash point"))?,
+                );
+            },
             "-c" => {
                 settings.clear = true;
                 i -= 1; // argument has no value
@@ -580,12 +675,26 @@ pub fn main() -> Result<(), Error> {
             .expect("Unable to create request handler"),
     );
 
-    server_loop(|| {
+    let mut handle = || {
         // handle message that is given to the server
         serv.handle_ctrl_chan(&mut hdl)?;
+        // send the directory change events that waited for credits
+        watches_mut().receive_acks();
         REQHDL.get().handle(|op, is| hdl.handle(op, is))
-    })
-    .ok();
+    };
+
+    // like server_loop, but don't sleep while a consistency check has work left
+    loop {
+        if !fsck::running() {
+            OwnActivity::sleep().ok();
+        }
+
+        if handle().is_err() {
+            break;
+        }
+
+        fsck::step();
+    }
 
     Ok(())
 }
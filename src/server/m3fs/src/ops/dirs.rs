@@ -14,11 +14,14 @@
  * General Public License version 2 for more details.
  */
 
-use crate::data::{DirEntry, DirEntryIterator, INodeRef, InodeNo};
+use crate::buf::transaction;
+use crate::data::{entry_mut, DirEntryIterator, ExtentBlocks, INodeRef, InodeNo, MAX_LINK_LEN};
+use crate::ops::perms::{self, Creds, MAY_EXEC, MAY_WRITE};
 use crate::ops::{inodes, links};
 
+use m3::col::{String, Vec};
 use m3::errors::{Code, Error};
-use m3::vfs::FileMode;
+use m3::vfs::{FileEvent, FileMode};
 
 /// Returns the directory and filename part of the given path.
 ///
@@ -68,9 +71,28 @@ fn find_entry(inode: &INodeRef, name: &str) -> Result<InodeNo, Error> {
     Err(Error::new(Code::NoSuchFile))
 }
 
+/// Checks whether entries can be added to or removed from the given directory
+fn check_dir_write(creds: &Creds, dir: &INodeRef) -> Result<(), Error> {
+    if !dir.mode.is_dir() {
+        return Err(Error::new(Code::IsNoDir));
+    }
+    perms::check(creds, dir, MAY_WRITE | MAY_EXEC)
+}
+
+/// The maximum number of symbolic links that are followed during a path lookup
+const MAX_SYMLINKS: usize = 16;
+
 /// Searches for the given path, optionally creates a new file, and returns the inode number.
-pub fn search(path: &str, create: bool) -> Result<InodeNo, Error> {
-    let ino = do_search(path, create);
+///
+/// Symbolic links are followed, including the last path component. All traversed directories
+/// need to be searchable with the given credentials.
+pub fn search(creds: &Creds, path: &str, create: bool) -> Result<InodeNo, Error> {
+    let ino = if create {
+        transaction(|| do_search(creds, path, true, true))
+    }
+    else {
+        do_search(creds, path, false, true)
+    };
     log!(
         crate::LOG_DIRS,
         "dirs::search(path={}, create={}) -> {:?}",
@@ -81,76 +103,132 @@ pub fn search(path: &str, create: bool) -> Result<InodeNo, Error> {
     ino
 }
 
-fn do_search(mut path: &str, create: bool) -> Result<InodeNo, Error> {
-    // remove all leading /
-    while path.starts_with('/') {
-        path = &path[1..];
+/// Notifies the watches on the directory that contains `path` about the given event
+pub fn notify_parent(path: &str, event: FileEvent) {
+    if crate::watches_mut().is_empty() {
+        return;
     }
 
-    // root inode?
-    if path.is_empty() {
-        return Ok(0);
+    // the path has been checked before; the directory might be gone by now, though
+    let (dir, _) = split_path(path);
+    if let Ok(ino) = do_search(&Creds::ROOT, dir, false, true) {
+        crate::watches_mut().notify(ino, event);
     }
+}
 
-    // start at root inode with search
-    let mut ino = 0;
-
-    let (filename, inode) = loop {
-        // get directory inode
-        let inode = inodes::get(ino)?;
+fn do_search(creds: &Creds, path: &str, create: bool, follow: bool) -> Result<InodeNo, Error> {
+    let mut path = String::from(path);
+    let mut links = 0;
 
-        // find directory entry
-        let next_end = path.find('/').unwrap_or(path.len());
-        let filename = &path[..next_end];
-        let next_ino = find_entry(&inode, filename);
+    'restart: loop {
+        // remove all leading /
+        let mut rem = path.trim_start_matches('/');
 
-        // walk to next path component start
-        let mut end = &path[next_end..];
-        while end.starts_with('/') {
-            end = &end[1..];
+        // root inode?
+        if rem.is_empty() {
+            return Ok(0);
         }
 
-        match next_ino {
-            Ok(nodeno) => {
-                // if path is now empty, finish searching
-                if end.is_empty() {
-                    return Ok(nodeno);
-                }
-                // continue with this directory
-                ino = nodeno;
-            },
-            Err(e) if e.code() == Code::NoSuchFile => {
-                // cannot create new file if it's not the last path component
-                if !end.is_empty() {
-                    return Err(Error::new(Code::NoSuchFile));
-                }
+        // start at root inode with search
+        let mut ino = 0;
 
-                // not found, maybe we want to create it
-                break (filename, inode);
-            },
-            Err(e) => return Err(e),
-        }
+        let (filename, inode) = loop {
+            // get directory inode
+            let inode = inodes::get(ino)?;
+            if inode.mode.is_dir() {
+                perms::check(creds, &inode, MAY_EXEC)?;
+            }
 
-        // to next path component
-        path = end;
-    };
+            // find directory entry
+            let next_end = rem.find('/').unwrap_or(rem.len());
+            let filename = &rem[..next_end];
+            let next_ino = find_entry(&inode, filename);
+
+            // walk to next path component start
+            let end = rem[next_end..].trim_start_matches('/');
+
+            match next_ino {
+                Ok(nodeno) => {
+                    let next_inode = inodes::get(nodeno)?;
+                    if next_inode.mode.is_link() && (follow || !end.is_empty()) {
+                        links += 1;
+                        if links > MAX_SYMLINKS {
+                            return Err(Error::new(Code::LinkLoop));
+                        }
+
+                        // absolute links can only be resolved by the client
+                        let target = read_link(&next_inode)?;
+                        if target.starts_with('/') {
+                            return Err(Error::new(Code::ForeignLink));
+                        }
+
+                        // replace the link by its target, relative to the link's directory
+                        let dir_end = path.len() - rem.len();
+                        let new_path = format!("{}{}/{}", &path[..dir_end], target, end);
+                        path = new_path;
+                        continue 'restart;
+                    }
 
-    if create {
-        // create inode and put link into directory
-        let new_inode = inodes::create(FileMode::FILE_DEF)?;
-        if let Err(e) = links::create(&inode, filename, &new_inode) {
-            crate::open_files_mut().delete_file(new_inode.inode).ok();
-            return Err(e);
+                    // if path is now empty, finish searching
+                    if end.is_empty() {
+                        return Ok(nodeno);
+                    }
+                    // continue with this directory
+                    ino = nodeno;
+                },
+                Err(e) if e.code() == Code::NoSuchFile => {
+                    // cannot create new file if it's not the last path component
+                    if !end.is_empty() {
+                        return Err(Error::new(Code::NoSuchFile));
+                    }
+
+                    // not found, maybe we want to create it
+                    break (filename, inode);
+                },
+                Err(e) => return Err(e),
+            }
+
+            // to next path component
+            rem = end;
         };
-        return Ok(new_inode.inode);
+
+        if create {
+            perms::check(creds, &inode, MAY_WRITE)?;
+
+            // create inode and put link into directory
+            let new_inode = inodes::create(creds, FileMode::FILE_DEF)?;
+            if let Err(e) = links::create(&inode, filename, &new_inode) {
+                crate::open_files_mut().delete_file(new_inode.inode).ok();
+                return Err(e);
+            };
+            crate::watches_mut().notify(inode.inode, FileEvent::CREATE);
+            return Ok(new_inode.inode);
+        }
+
+        return Err(Error::new(Code::NoSuchFile));
     }
+}
 
-    Err(Error::new(Code::NoSuchFile))
+/// Reads the target of the given symbolic link inode
+fn read_link(inode: &INodeRef) -> Result<String, Error> {
+    let mut target = Vec::with_capacity(inode.size as usize);
+    'read_loop: for ext in inode.extent_iter() {
+        for block in ext.block_iter() {
+            let rem = inode.size as usize - target.len();
+            let data = block.data();
+            target.extend_from_slice(&data[..rem.min(data.len())]);
+            if target.len() == inode.size as usize {
+                break 'read_loop;
+            }
+        }
+    }
+
+    String::from_utf8(target).map_err(|_| Error::new(Code::Utf8Error))
 }
 
 /// Creates a new directory with given mode at given path
-pub fn create(path: &str, mode: FileMode) -> Result<(), Error> {
-    let res = do_create(path, mode);
+pub fn create(creds: &Creds, path: &str, mode: FileMode) -> Result<(), Error> {
+    let res = transaction(|| do_create(creds, path, mode));
     log!(
         crate::LOG_DIRS,
         "dirs::create(path={}, mode={:o}) -> {:?}",
@@ -161,19 +239,20 @@ pub fn create(path: &str, mode: FileMode) -> Result<(), Error> {
     res
 }
 
-fn do_create(path: &str, mode: FileMode) -> Result<(), Error> {
+fn do_create(creds: &Creds, path: &str, mode: FileMode) -> Result<(), Error> {
     let (dir, name) = split_path(path);
 
     // get parent directory
-    let parent_ino = search(dir, false)?;
+    let parent_ino = search(creds, dir, false)?;
+    let parinode = inodes::get(parent_ino)?;
+    check_dir_write(creds, &parinode)?;
 
     // ensure that the entry doesn't exist
-    if search(path, false).is_ok() {
+    if find_entry(&parinode, name).is_ok() {
         return Err(Error::new(Code::Exists));
     }
 
-    let parinode = inodes::get(parent_ino)?;
-    if let Ok(dirino) = inodes::create(FileMode::DIR_DEF | mode) {
+    if let Ok(dirino) = inodes::create(creds, FileMode::DIR_DEF | mode) {
         // create directory itself
         if let Err(e) = links::create(&parinode, name, &dirino) {
             crate::open_files_mut().delete_file(dirino.inode).ok();
@@ -193,6 +272,7 @@ fn do_create(path: &str, mode: FileMode) -> Result<(), Error> {
             return Err(e);
         }
 
+        crate::watches_mut().notify(parinode.inode, FileEvent::CREATE);
         Ok(())
     }
     else {
@@ -201,10 +281,14 @@ fn do_create(path: &str, mode: FileMode) -> Result<(), Error> {
 }
 
 /// Removes the directory at given path if it is empty
-pub fn remove(path: &str) -> Result<(), Error> {
+pub fn remove(creds: &Creds, path: &str) -> Result<(), Error> {
     log!(crate::LOG_DIRS, "dirs::remove(path={})", path);
 
-    let ino = search(path, false)?;
+    transaction(|| do_remove(creds, path))
+}
+
+fn do_remove(creds: &Creds, path: &str) -> Result<(), Error> {
+    let ino = search(creds, path, false)?;
     // cannot remove root directory
     if ino == 0 {
         return Err(Error::new(Code::InvArgs));
@@ -231,7 +315,7 @@ pub fn remove(path: &str) -> Result<(), Error> {
     // hardlinks to directories are not possible, thus we always have 2 ( . and ..)
     assert!(inode.links == 2, "expected 2 links, found {}", inode.links);
 
-    let parent_inode = unlink(path, false)?;
+    let parent_inode = unlink(creds, path, false)?;
 
     // we have already removed the entry; if something fails now we're screwed
     inodes::decrease_links(&parent_inode).unwrap();
@@ -241,7 +325,7 @@ pub fn remove(path: &str) -> Result<(), Error> {
 }
 
 /// Creates a link at `new_path` to `old_path`
-pub fn link(old_path: &str, new_path: &str) -> Result<(), Error> {
+pub fn link(creds: &Creds, old_path: &str, new_path: &str) -> Result<(), Error> {
     log!(
         crate::LOG_DIRS,
         "dirs::link(old_path={}, new_path={})",
@@ -249,7 +333,11 @@ pub fn link(old_path: &str, new_path: &str) -> Result<(), Error> {
         new_path
     );
 
-    let old_ino = search(old_path, false)?;
+    transaction(|| do_link(creds, old_path, new_path))
+}
+
+fn do_link(creds: &Creds, old_path: &str, new_path: &str) -> Result<(), Error> {
+    let old_ino = search(creds, old_path, false)?;
 
     // it can't be a directory
     let old_inode = inodes::get(old_ino)?;
@@ -259,15 +347,93 @@ pub fn link(old_path: &str, new_path: &str) -> Result<(), Error> {
 
     let (dir, name) = split_path(new_path);
 
-    let base_ino = search(dir, false)?;
+    let base_ino = search(creds, dir, false)?;
     let base_inode = inodes::get(base_ino)?;
+    check_dir_write(creds, &base_inode)?;
 
     // the destination cannot already exist
     if find_entry(&base_inode, name).is_ok() {
         return Err(Error::new(Code::Exists));
     }
 
-    links::create(&base_inode, name, &old_inode)
+    links::create(&base_inode, name, &old_inode)?;
+    crate::watches_mut().notify(base_inode.inode, FileEvent::CREATE);
+    Ok(())
+}
+
+/// Creates a symbolic link at `path` that points to `target`
+pub fn symlink(creds: &Creds, target: &str, path: &str) -> Result<(), Error> {
+    log!(
+        crate::LOG_DIRS,
+        "dirs::symlink(target={}, path={})",
+        target,
+        path
+    );
+
+    transaction(|| do_symlink(creds, target, path))
+}
+
+fn do_symlink(creds: &Creds, target: &str, path: &str) -> Result<(), Error> {
+    // the target has to fit into a single block and into a single reply
+    let max_len = MAX_LINK_LEN.min(crate::superblock().block_size as usize);
+    if target.is_empty() || target.len() > max_len {
+        return Err(Error::new(Code::InvArgs));
+    }
+
+    let (dir, name) = split_path(path);
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(Error::new(Code::InvArgs));
+    }
+
+    let base_ino = search(creds, dir, false)?;
+    let base_inode = inodes::get(base_ino)?;
+    check_dir_write(creds, &base_inode)?;
+
+    // the destination cannot already exist
+    if find_entry(&base_inode, name).is_ok() {
+        return Err(Error::new(Code::Exists));
+    }
+
+    let inode = inodes::create(creds, FileMode::LNK_DEF)?;
+
+    let res = write_link(&inode, target);
+    if let Err(e) = res.and_then(|_| links::create(&base_inode, name, &inode)) {
+        crate::open_files_mut().delete_file(inode.inode).ok();
+        return Err(e);
+    }
+    crate::watches_mut().notify(base_inode.inode, FileEvent::CREATE);
+    Ok(())
+}
+
+/// Stores the given target in the first block of the given symbolic link inode
+fn write_link(inode: &INodeRef, target: &str) -> Result<(), Error> {
+    let mut indir = None;
+    let ext = inodes::get_extent(inode, 0, &mut indir, true)?;
+    *ext.as_mut() = inodes::create_extent(Some(inode), 1)?;
+    inode.as_mut().size = target.len() as u64;
+
+    let mut block = crate::meta_buffer_mut().get_block(ext.start)?;
+    block.overwrite_zero();
+    block.data_mut()[..target.len()].copy_from_slice(target.as_bytes());
+    Ok(())
+}
+
+/// Returns the target of the symbolic link at `path`
+pub fn readlink(creds: &Creds, path: &str) -> Result<String, Error> {
+    let res = do_search(creds, path, false, false).and_then(|ino| {
+        let inode = inodes::get(ino)?;
+        if !inode.mode.is_link() {
+            return Err(Error::new(Code::InvArgs));
+        }
+        read_link(&inode)
+    });
+    log!(
+        crate::LOG_DIRS,
+        "dirs::readlink(path={}) -> {:?}",
+        path,
+        res.as_ref().map_err(|e| e.code()),
+    );
+    res
 }
 
 /// Removes the directory entry at given path
@@ -275,7 +441,7 @@ pub fn link(old_path: &str, new_path: &str) -> Result<(), Error> {
 /// If `deny_dir` is true and the path points to a directory, the call fails.
 ///
 /// Returns the directory inode
-pub fn unlink(path: &str, deny_dir: bool) -> Result<INodeRef, Error> {
+pub fn unlink(creds: &Creds, path: &str, deny_dir: bool) -> Result<INodeRef, Error> {
     log!(
         crate::LOG_DIRS,
         "dirs::unlink(path={}, deny_dir={})",
@@ -283,20 +449,27 @@ pub fn unlink(path: &str, deny_dir: bool) -> Result<INodeRef, Error> {
         deny_dir
     );
 
+    transaction(|| do_unlink(creds, path, deny_dir))
+}
+
+fn do_unlink(creds: &Creds, path: &str, deny_dir: bool) -> Result<INodeRef, Error> {
     let (dir, name) = split_path(path);
     // can't remove empty entries and internal entries
     if name.is_empty() || name == "." || name == ".." {
         return Err(Error::new(Code::InvArgs));
     }
 
-    let par_ino = search(dir, false)?;
+    let par_ino = search(creds, dir, false)?;
     let par_inode = inodes::get(par_ino)?;
+    check_dir_write(creds, &par_inode)?;
 
-    links::remove(&par_inode, name, deny_dir).map(|_| par_inode)
+    links::remove(&par_inode, name, deny_dir)?;
+    crate::watches_mut().notify(par_inode.inode, FileEvent::UNLINK);
+    Ok(par_inode)
 }
 
 /// Renames `old_path` to `new_path`
-pub fn rename(old_path: &str, new_path: &str) -> Result<(), Error> {
+pub fn rename(creds: &Creds, old_path: &str, new_path: &str) -> Result<(), Error> {
     log!(
         crate::LOG_DIRS,
         "dirs::rename(old_path={}, new_path={})",
@@ -304,14 +477,19 @@ pub fn rename(old_path: &str, new_path: &str) -> Result<(), Error> {
         new_path
     );
 
+    transaction(|| do_rename(creds, old_path, new_path))
+}
+
+fn do_rename(creds: &Creds, old_path: &str, new_path: &str) -> Result<(), Error> {
     // split old path and get directory inode
     let (old_dir, old_name) = split_path(old_path);
     // cannot rename root directory or internal entries
     if old_name.is_empty() || old_name == "." || old_name == ".." {
         return Err(Error::new(Code::InvArgs));
     }
-    let old_dir_ino = search(old_dir, false)?;
+    let old_dir_ino = search(creds, old_dir, false)?;
     let old_dir_inode = inodes::get(old_dir_ino)?;
+    check_dir_write(creds, &old_dir_inode)?;
 
     // get old inode to link to
     let old_ino = find_entry(&old_dir_inode, old_name)?;
@@ -328,8 +506,9 @@ pub fn rename(old_path: &str, new_path: &str) -> Result<(), Error> {
     if new_name.is_empty() || new_name == "." || new_name == ".." {
         return Err(Error::new(Code::InvArgs));
     }
-    let new_dir_ino = search(new_dir, false)?;
+    let new_dir_ino = search(creds, new_dir, false)?;
     let new_dir_inode = inodes::get(new_dir_ino)?;
+    check_dir_write(creds, &new_dir_inode)?;
 
     // search for the entry in the new directory and change link to new inode if found
     let mut prev_ino = None;
@@ -339,7 +518,7 @@ pub fn rename(old_path: &str, new_path: &str) -> Result<(), Error> {
             let end = crate::superblock().block_size as usize;
             while off < end {
                 // TODO marking all blocks dirty here is suboptimal
-                let entry = DirEntry::from_buffer_mut(&mut block, off);
+                let entry = entry_mut(&mut block, off);
                 if entry.name() == new_name {
                     // both link to the same inode? nothing to do
                     if entry.nodeno == old_ino {
@@ -376,5 +555,11 @@ pub fn rename(old_path: &str, new_path: &str) -> Result<(), Error> {
     }
 
     links::remove(&old_dir_inode, old_name, true).unwrap();
+
+    let mut watches = crate::watches_mut();
+    watches.notify(old_dir_inode.inode, FileEvent::RENAME);
+    if new_dir_inode.inode != old_dir_inode.inode {
+        watches.notify(new_dir_inode.inode, FileEvent::RENAME);
+    }
     Ok(())
 }
@@ -0,0 +1,414 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::buf::transaction;
+use crate::data::{BlockNo, DirEntry, Extent, ExtentRef, InodeNo, DIR_ENTRY_LEN, NUM_EXT_BYTES};
+use crate::ops::inodes;
+
+use m3::col::{BitArray, Vec};
+use m3::errors::{Code, Error};
+use m3::mem::MsgBuf;
+use m3::tcu::Message;
+use m3::vfs::FsckReport;
+
+/// The number of inodes that are checked before requests are handled again
+const INODES_PER_STEP: usize = 16;
+/// The number of restarts due to concurrent changes after which the check is finished at once
+const MAX_RESTARTS: u32 = 4;
+
+/// The state that is built up by walking the directory tree.
+struct Walk {
+    // the reachable inodes
+    inodes: BitArray,
+    // the blocks used by the reachable inodes
+    blocks: BitArray,
+    // the number of directory entries per inode
+    links: Vec<u16>,
+    // the reachable inodes that have not been checked yet
+    pending: Vec<InodeNo>,
+    // the inodes that are allocated, but neither reachable nor open
+    leaked: Vec<InodeNo>,
+    // whether the open files without directory entry have been checked
+    orphans_done: bool,
+    report: FsckReport,
+}
+
+impl Walk {
+    fn new() -> Self {
+        let (total_inodes, total_blocks, first_data) = {
+            let sb = crate::superblock();
+            (sb.total_inodes, sb.total_blocks, sb.first_data_block())
+        };
+
+        let mut walk = Walk {
+            inodes: BitArray::new(total_inodes as usize),
+            blocks: BitArray::new(total_blocks as usize),
+            links: vec![0; total_inodes as usize],
+            pending: Vec::new(),
+            leaked: Vec::new(),
+            orphans_done: false,
+            report: FsckReport::default(),
+        };
+
+        // superblock, bitmaps, inodes, and journal are always in use
+        for bno in 0..first_data {
+            walk.blocks.set(bno as usize);
+        }
+
+        // start with the root directory
+        walk.inodes.set(0);
+        walk.pending.push(0);
+        walk
+    }
+
+    /// Checks up to `limit` inodes and returns true if all inodes have been checked
+    fn advance(&mut self, limit: usize) -> Result<bool, Error> {
+        for _ in 0..limit {
+            if let Some(ino) = self.pending.pop() {
+                self.check_inode(ino, true)?;
+            }
+            else if !self.orphans_done {
+                self.check_orphans()?;
+            }
+            else {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn error(&mut self, ino: InodeNo, msg: &str) {
+        log!(crate::LOG_FSCK, "fsck: inode {}: {}", ino, msg);
+        self.report.errors += 1;
+    }
+
+    fn use_blocks(&mut self, ino: InodeNo, ext: Extent) -> bool {
+        let (total_blocks, first_data) = {
+            let sb = crate::superblock();
+            (sb.total_blocks, sb.first_data_block())
+        };
+
+        if ext.start < first_data || ext.start as u64 + ext.length as u64 > total_blocks as u64 {
+            self.error(ino, "extent outside of data blocks");
+            return false;
+        }
+
+        for bno in ext.block_range() {
+            if self.blocks.is_set(bno as usize) {
+                log!(
+                    crate::LOG_FSCK,
+                    "fsck: inode {}: block {} used twice",
+                    ino,
+                    bno
+                );
+                self.report.errors += 1;
+            }
+            else {
+                self.blocks.set(bno as usize);
+            }
+        }
+        true
+    }
+
+    fn check_inode(&mut self, ino: InodeNo, walk_dir: bool) -> Result<(), Error> {
+        let inode = inodes::get(ino)?;
+        self.report.inodes += 1;
+
+        if inode.indirect != 0 {
+            self.use_blocks(ino, Extent::new(inode.indirect, 1));
+        }
+        if inode.dindirect != 0 && self.use_blocks(ino, Extent::new(inode.dindirect, 1)) {
+            let dind = crate::meta_buffer_mut().get_block(inode.dindirect)?;
+            // the double indirect block holds the indirect blocks as extents of length 1
+            for i in 0..crate::superblock().extents_per_block() {
+                let ptr = ExtentRef::indir_from_buffer(dind.clone(), i * NUM_EXT_BYTES);
+                if ptr.length != 0 {
+                    self.use_blocks(ino, Extent::new(ptr.start, 1));
+                }
+            }
+        }
+
+        // holes do not use any blocks
+        for ext in inode.extent_iter().filter(|ext| !ext.is_hole()) {
+            if !self.use_blocks(ino, *ext) || !walk_dir || !inode.mode.is_dir() {
+                continue;
+            }
+
+            for bno in ext.block_range() {
+                self.check_entries(ino, bno)?;
+            }
+        }
+
+        // the extent of an append that is in progress is not part of the inode yet
+        let append_ext = crate::open_files_mut()
+            .get_file(ino)
+            .and_then(|f| f.append_ext());
+        if let Some(ext) = append_ext {
+            self.use_blocks(ino, ext);
+        }
+        Ok(())
+    }
+
+    fn check_entries(&mut self, ino: InodeNo, bno: BlockNo) -> Result<(), Error> {
+        let (block_size, total_inodes) = {
+            let sb = crate::superblock();
+            (sb.block_size as usize, sb.total_inodes)
+        };
+
+        let block = crate::meta_buffer_mut().get_block(bno)?;
+        let mut off = 0;
+        while off + DIR_ENTRY_LEN <= block_size {
+            let entry = DirEntry::from_buffer(block.data(), off);
+            let next = entry.next as usize;
+            if next < DIR_ENTRY_LEN + entry.name_length as usize || off + next > block_size {
+                self.error(ino, "invalid directory entry");
+                break;
+            }
+
+            let child = entry.nodeno;
+            if child >= total_inodes {
+                self.error(ino, "directory entry refers to invalid inode");
+            }
+            else {
+                self.links[child as usize] = self.links[child as usize].saturating_add(1);
+                if entry.name() != "."
+                    && entry.name() != ".."
+                    && !self.inodes.is_set(child as usize)
+                {
+                    self.inodes.set(child as usize);
+                    self.pending.push(child);
+                }
+            }
+
+            off += next;
+        }
+        Ok(())
+    }
+
+    fn check_orphans(&mut self) -> Result<(), Error> {
+        let total_inodes = crate::superblock().total_inodes as usize;
+        let first = crate::superblock().first_inodebm_block();
+
+        let mut orphans = Vec::new();
+        for_each_bit(first, total_inodes, |ino, allocated| {
+            if allocated && !self.inodes.is_set(ino) {
+                // files that have been unlinked while they are still open are not leaked
+                if crate::open_files_mut().get_file(ino as InodeNo).is_some() {
+                    orphans.push(ino as InodeNo);
+                }
+                else {
+                    self.leaked.push(ino as InodeNo);
+                }
+            }
+            else if !allocated && self.inodes.is_set(ino) {
+                log!(
+                    crate::LOG_FSCK,
+                    "fsck: inode {} used, but not allocated",
+                    ino
+                );
+                self.report.errors += 1;
+            }
+        })?;
+
+        for ino in orphans {
+            self.inodes.set(ino as usize);
+            self.check_inode(ino, false)?;
+        }
+
+        self.orphans_done = true;
+        Ok(())
+    }
+
+    fn finish(&mut self, repair: bool) -> Result<(), Error> {
+        let total_blocks = crate::superblock().total_blocks as usize;
+        let first = crate::superblock().first_blockbm_block();
+
+        // compare the used blocks with the block bitmap
+        let mut leaked_blocks: Vec<(usize, usize)> = Vec::new();
+        for_each_bit(first, total_blocks, |bno, allocated| {
+            let used = self.blocks.is_set(bno);
+            if used {
+                self.report.blocks += 1;
+            }
+
+            if allocated && !used {
+                self.report.leaked_blocks += 1;
+                match leaked_blocks.last_mut() {
+                    Some((start, count)) if *start + *count == bno => *count += 1,
+                    _ => leaked_blocks.push((bno, 1)),
+                }
+            }
+            else if !allocated && used {
+                log!(
+                    crate::LOG_FSCK,
+                    "fsck: block {} used, but not allocated",
+                    bno
+                );
+                self.report.errors += 1;
+            }
+        })?;
+
+        // compare the link counts with the directory entries
+        for ino in 0..self.links.len() {
+            if self.inodes.is_set(ino) {
+                let inode = inodes::get(ino as InodeNo)?;
+                if inode.links != self.links[ino] {
+                    log!(
+                        crate::LOG_FSCK,
+                        "fsck: inode {} has {} links, but {} directory entries",
+                        ino,
+                        inode.links,
+                        self.links[ino]
+                    );
+                    self.report.bad_links += 1;
+                }
+            }
+        }
+
+        self.report.leaked_inodes = self.leaked.len() as u32;
+
+        // only repair if the tree is intact; otherwise we might free blocks that are still in use
+        if repair
+            && self.report.errors == 0
+            && (!self.leaked.is_empty() || !leaked_blocks.is_empty())
+        {
+            transaction(|| {
+                for ino in &self.leaked {
+                    crate::inodes_mut().free(*ino as usize, 1)?;
+                }
+                for (start, count) in &leaked_blocks {
+                    crate::blocks_mut().free(*start, *count)?;
+                }
+                Ok(())
+            })?;
+            self.report.repaired = true;
+        }
+        Ok(())
+    }
+}
+
+/// Calls `func` for the first `count` bits of the bitmap starting at block `first`
+fn for_each_bit<F>(first: BlockNo, count: usize, mut func: F) -> Result<(), Error>
+where
+    F: FnMut(usize, bool),
+{
+    let perblock = crate::superblock().block_size as usize * 8;
+    let mut bno = first;
+    let mut start = 0;
+    while start < count {
+        let block = crate::meta_buffer_mut().get_block(bno)?;
+        let end = count.min(start + perblock);
+        for i in start..end {
+            let bit = i - start;
+            func(i, (block.data()[bit / 8] >> (bit % 8)) & 1 == 1);
+        }
+        start = end;
+        bno += 1;
+    }
+    Ok(())
+}
+
+/// A consistency check of the file system that runs in the background.
+///
+/// The check walks the directory tree in steps between requests. If the metadata changes in the
+/// meantime, it starts over to get a consistent view. After a few restarts, it completes in a
+/// single step.
+pub struct Fsck {
+    msg: &'static Message,
+    repair: bool,
+    restarts: u32,
+    modifications: u64,
+    walk: Walk,
+}
+
+impl Fsck {
+    /// Performs the next step and returns true if the check is complete
+    fn step(&mut self) -> Result<bool, Error> {
+        if crate::meta_buffer_mut().modifications() != self.modifications {
+            log!(crate::LOG_FSCK, "fsck: metadata changed; restarting");
+            self.restarts += 1;
+            self.walk = Walk::new();
+        }
+
+        let limit = if self.restarts >= MAX_RESTARTS {
+            usize::MAX
+        }
+        else {
+            INODES_PER_STEP
+        };
+
+        let done = self.walk.advance(limit)?;
+        if done {
+            self.walk.finish(self.repair)?;
+        }
+        self.modifications = crate::meta_buffer_mut().modifications();
+        Ok(done)
+    }
+
+    fn reply(&self, res: Result<(), Error>) {
+        let mut msg = MsgBuf::borrow_def();
+        match res {
+            Ok(_) => build_vmsg!(&mut msg, Code::Success, self.walk.report),
+            Err(e) => build_vmsg!(&mut msg, e.code()),
+        }
+        // ignore errors; the client might be gone already
+        crate::REQHDL.get().recv_gate().reply(&msg, self.msg).ok();
+    }
+}
+
+/// Starts a consistency check in the background, which replies to `msg` when it is finished.
+///
+/// If `repair` is true, leaked inodes and blocks are freed, unless unrepairable errors were found.
+/// Assumes that no check is running.
+pub fn start(msg: &'static Message, repair: bool) {
+    log!(crate::LOG_FSCK, "fsck::start(repair={})", repair);
+
+    let mut fsck = crate::fsck_mut();
+    assert!(fsck.is_none());
+    *fsck = Some(Fsck {
+        msg,
+        repair,
+        restarts: 0,
+        modifications: crate::meta_buffer_mut().modifications(),
+        walk: Walk::new(),
+    });
+}
+
+/// Returns true if a consistency check is running
+pub fn running() -> bool {
+    crate::fsck_mut().is_some()
+}
+
+/// Continues the running consistency check, if there is any, and replies once it is finished
+pub fn step() {
+    let mut fsck = crate::fsck_mut();
+    let res = match fsck.as_mut() {
+        Some(check) => check.step(),
+        None => return,
+    };
+
+    if let Ok(false) = res {
+        return;
+    }
+
+    let check = fsck.take().unwrap();
+    log!(
+        crate::LOG_FSCK,
+        "fsck: finished after {} restarts: {:?}",
+        check.restarts,
+        check.walk.report
+    );
+    check.reply(res.map(|_| ()));
+}
@@ -14,23 +14,41 @@
  * General Public License version 2 for more details.
  */
 
-use crate::buf::LoadLimit;
+use core::cmp::Ordering;
+
+use crate::buf::{transaction, LoadLimit};
 use crate::data::{
-    ExtPos, Extent, ExtentCache, ExtentRef, INodeRef, InodeNo, INODE_DIR_COUNT, NUM_EXT_BYTES,
-    NUM_INODE_BYTES,
+    BlockRange, ExtPos, Extent, ExtentBlocks, ExtentCache, ExtentRef, INodeRef, InodeNo,
+    INODE_DIR_COUNT, NUM_EXT_BYTES, NUM_INODE_BYTES,
 };
+use crate::ops::perms::Creds;
 
 use m3::{
     cap::Selector,
-    com::Perm,
+    cell::LazyStaticRefCell,
+    col::Vec,
+    com::{MemGate, Perm},
     errors::{Code, Error},
+    goff, syscalls,
+    tiles::Activity,
     util::math,
     vfs::{FileMode, SeekMode},
 };
 
-/// Creates a new inode with given mode and returns its INodeRef
-pub fn create(mode: FileMode) -> Result<INodeRef, Error> {
-    log!(crate::LOG_INODES, "inodes::create(mode={:o})", mode);
+/// The size of the zero-filled memory that is handed out to read holes
+const HOLE_MEM_SIZE: usize = 64 * 1024;
+
+static HOLE_MEM: LazyStaticRefCell<MemGate> = LazyStaticRefCell::default();
+
+/// Creates a new inode with given mode, owned by `creds`, and returns its INodeRef
+pub fn create(creds: &Creds, mode: FileMode) -> Result<INodeRef, Error> {
+    log!(
+        crate::LOG_INODES,
+        "inodes::create(mode={:o}, uid={}, gid={})",
+        mode,
+        creds.uid,
+        creds.gid
+    );
 
     let ino = crate::inodes_mut().alloc(None)?;
     let inode = get(ino)?;
@@ -39,6 +57,8 @@ pub fn create(mode: FileMode) -> Result<INodeRef, Error> {
     inode.as_mut().inode = ino;
     inode.as_mut().devno = 0; // TODO
     inode.as_mut().mode = mode;
+    inode.as_mut().uid = creds.uid;
+    inode.as_mut().gid = creds.gid;
     Ok(inode)
 }
 
@@ -56,10 +76,12 @@ pub fn decrease_links(inode: &INodeRef) -> Result<(), Error> {
 pub fn free(inode_no: InodeNo) -> Result<(), Error> {
     log!(crate::LOG_INODES, "inodes::free(inode_no={})", inode_no);
 
-    let ino = get(inode_no)?;
-    let inodeno = ino.inode as usize;
-    truncate(&ino, &ExtPos::new(0, 0))?;
-    crate::inodes_mut().free(inodeno, 1)
+    transaction(|| {
+        let ino = get(inode_no)?;
+        let inodeno = ino.inode as usize;
+        truncate(&ino, &ExtPos::new(0, 0))?;
+        crate::inodes_mut().free(inodeno, 1)
+    })
 }
 
 /// Loads an INodeRef for given inode number
@@ -169,7 +191,12 @@ pub fn get_extent_mem(
     let blocksize = crate::superblock().block_size;
     let mut extlen = (ext.length * blocksize) as usize;
 
-    let mut bytes = crate::backend_mut().get_filedata(*ext, start.off, perms, sel, Some(limit))?;
+    let mut bytes = if ext.is_hole() {
+        get_hole_mem(*ext, start.off, sel)?
+    }
+    else {
+        crate::backend_mut().get_filedata(*ext, start.off, perms, sel, Some(limit))?
+    };
 
     // stop at file end
     if (start.ext == (inode.extents - 1) as usize)
@@ -185,6 +212,74 @@ pub fn get_extent_mem(
     Ok((bytes, extlen))
 }
 
+/// Writes back the buffered data of the file range `off`..`off + len`, which might have been
+/// modified via memory capabilities obtained by [`get_extent_mem`].
+pub fn sync_mem(inode: &INodeRef, off: usize, len: usize) -> Result<(), Error> {
+    log!(
+        crate::LOG_INODES,
+        "inodes::sync_mem(inode={}, off={}, len={})",
+        inode.inode,
+        off,
+        len,
+    );
+
+    let blocksize = crate::superblock().block_size as usize;
+    let end = off.saturating_add(len);
+    let (mut pos, mut extpos) = get_seek_pos(inode, off, SeekMode::SET)?;
+
+    let mut indir = None;
+    while pos < end && extpos.ext < inode.extents as usize {
+        let ext = *get_extent(inode, extpos.ext, &mut indir, false)?;
+        let ext_bytes = ext.length as usize * blocksize;
+        let amount = (ext_bytes - extpos.off).min(end - pos);
+
+        // holes are only mapped read-only and can therefore not be modified
+        if !ext.is_hole() {
+            let first = extpos.off / blocksize;
+            let last = math::round_up(extpos.off + amount, blocksize) / blocksize;
+            crate::file_buffer_mut().sync(BlockRange::new_range(
+                ext.start + first as u32,
+                (last - first) as u32,
+            ))?;
+        }
+
+        pos += amount;
+        extpos.next_ext();
+    }
+    Ok(())
+}
+
+/// Creates a read-only capability at `sel` for the zeros of the hole `ext`, starting at the block
+/// that contains `extoff`.
+///
+/// Returns the number of bytes covered by the capability
+fn get_hole_mem(ext: Extent, extoff: usize, sel: Selector) -> Result<usize, Error> {
+    let blocksize = crate::superblock().block_size as usize;
+    let size = HOLE_MEM_SIZE.max(blocksize);
+
+    // all holes share the same memory, which is created on first use
+    if !HOLE_MEM.is_some() {
+        let mem = MemGate::new(size, Perm::RW)?;
+        let zeros = vec![0u8; blocksize];
+        for off in (0..size).step_by(blocksize) {
+            mem.write(&zeros, off as goff)?;
+        }
+        HOLE_MEM.set(mem);
+    }
+
+    let first_block = extoff / blocksize;
+    let bytes = ((ext.length as usize - first_block) * blocksize).min(size);
+    syscalls::derive_mem(
+        Activity::own().sel(),
+        sel,
+        HOLE_MEM.borrow().sel(),
+        0,
+        bytes as goff,
+        Perm::R,
+    )?;
+    Ok(bytes)
+}
+
 /// Requests an append of a new block to given inode and creates a MemGate to access the block.
 ///
 /// Note that this only requests the append, but does not append anything.
@@ -246,13 +341,24 @@ pub fn append_extent(inode: &INodeRef, next: Extent) -> Result<bool, Error> {
         next.length,
     );
 
+    transaction(|| do_append_extent(inode, next))
+}
+
+fn do_append_extent(inode: &INodeRef, next: Extent) -> Result<bool, Error> {
     let mut indir = None;
     let mut new_ext = true;
 
     // try to load existing inode
     let ext = if inode.extents > 0 {
         let ext = get_extent(inode, (inode.extents - 1) as usize, &mut indir, false)?;
-        if ext.start + ext.length != next.start {
+        // holes can only be merged with holes and blocks only with adjacent blocks
+        let mergeable = if ext.is_hole() {
+            next.is_hole()
+        }
+        else {
+            !next.is_hole() && ext.start + ext.length == next.start
+        };
+        if !mergeable {
             None
         }
         else {
@@ -485,6 +591,10 @@ fn change_extent(
 ///
 /// Returns the created extent
 pub fn create_extent(inode: Option<&INodeRef>, blocks: u32) -> Result<Extent, Error> {
+    transaction(|| do_create_extent(inode, blocks))
+}
+
+fn do_create_extent(inode: Option<&INodeRef>, blocks: u32) -> Result<Extent, Error> {
     let mut count = blocks as usize;
     let start = crate::blocks_mut().alloc(Some(&mut count))?;
     let ext = Extent::new(start, count as u32);
@@ -513,6 +623,10 @@ pub fn truncate(inode: &INodeRef, pos: &ExtPos) -> Result<(), Error> {
         pos,
     );
 
+    transaction(|| do_truncate(inode, pos))
+}
+
+fn do_truncate(inode: &INodeRef, pos: &ExtPos) -> Result<(), Error> {
     let blocksize = crate::superblock().block_size;
     let mut indir = None;
 
@@ -523,7 +637,9 @@ pub fn truncate(inode: &INodeRef, pos: &ExtPos) -> Result<(), Error> {
         let mut i = iextents - 1;
         while i > pos.ext {
             let ext = change_extent(inode, i, &mut indir, true)?;
-            crate::blocks_mut().free(ext.start as usize, ext.length as usize)?;
+            if !ext.is_hole() {
+                crate::blocks_mut().free(ext.start as usize, ext.length as usize)?;
+            }
             inode.as_mut().extents -= 1;
             inode.as_mut().size -= (ext.length * blocksize) as u64;
             ext.as_mut().start = 0;
@@ -551,7 +667,7 @@ pub fn truncate(inode: &INodeRef, pos: &ExtPos) -> Result<(), Error> {
                     diff
                 };
                 let blocks = bdiff / blocksize as usize;
-                if blocks > 0 {
+                if blocks > 0 && !ext.is_hole() {
                     // free all of these blocks
                     crate::blocks_mut().free((ext.start + ext.length) as usize - blocks, blocks)?;
                 }
@@ -568,6 +684,354 @@ pub fn truncate(inode: &INodeRef, pos: &ExtPos) -> Result<(), Error> {
     Ok(())
 }
 
+/// Extends the given inode to `size` bytes by appending a hole.
+pub fn extend(inode: &INodeRef, size: usize) -> Result<(), Error> {
+    log!(
+        crate::LOG_INODES,
+        "inodes::extend(inode={}, size={})",
+        inode.inode,
+        size,
+    );
+
+    transaction(|| {
+        let blocksize = crate::superblock().block_size as usize;
+        clear_tail(inode)?;
+
+        let old_blocks = math::round_up(inode.size as usize, blocksize) / blocksize;
+        let new_blocks = math::round_up(size, blocksize) / blocksize;
+        if new_blocks > old_blocks {
+            do_append_extent(inode, Extent::hole((new_blocks - old_blocks) as u32))?;
+        }
+        inode.as_mut().size = size as u64;
+        Ok(())
+    })
+}
+
+/// Allocates blocks for the range of `len` bytes at offset `off` in the given inode.
+///
+/// Holes within the range are replaced by zeroed blocks and the file is extended by zeroed blocks
+/// if the range ends behind the end of the file.
+///
+/// Returns true if existing extents have been changed
+pub fn allocate(inode: &INodeRef, off: usize, len: usize) -> Result<bool, Error> {
+    log!(
+        crate::LOG_INODES,
+        "inodes::allocate(inode={}, off={}, len={})",
+        inode.inode,
+        off,
+        len,
+    );
+
+    let blocksize = crate::superblock().block_size as usize;
+    // the end needs to be rounded up to the block size without overflowing
+    let end = off
+        .checked_add(len)
+        .filter(|end| end.checked_add(blocksize).is_some())
+        .ok_or_else(|| Error::new(Code::InvArgs))?;
+    let start_blk = off / blocksize;
+    let end_blk = math::round_up(end, blocksize) / blocksize;
+
+    transaction(|| {
+        let mut changed = false;
+
+        // fill the holes within the file
+        let mut blk = start_blk;
+        while blk < end_blk {
+            let mut indir = None;
+            let (idx, ext, first) = match find_extent(inode, blk, &mut indir)? {
+                Some(res) => res,
+                None => break,
+            };
+
+            let count = (ext.length as usize - first).min(end_blk - blk);
+            if ext.is_hole() {
+                blk += fill_blocks(inode, idx, ext, first, count)?;
+                changed = true;
+            }
+            else {
+                blk += count;
+            }
+        }
+
+        // append new blocks behind the end of the file
+        if end as u64 > inode.size {
+            clear_tail(inode)?;
+
+            let file_blocks = math::round_up(inode.size as usize, blocksize) / blocksize;
+            if start_blk > file_blocks {
+                changed |=
+                    !do_append_extent(inode, Extent::hole((start_blk - file_blocks) as u32))?;
+            }
+
+            let mut blocks = end_blk - start_blk.max(file_blocks);
+            while blocks > 0 {
+                let ext = do_create_extent(None, blocks as u32)?;
+                if !crate::settings().clear {
+                    crate::backend_mut().clear_extent(ext)?;
+                }
+                changed |= !do_append_extent(inode, ext)?;
+                blocks -= ext.length as usize;
+            }
+
+            inode.as_mut().size = end as u64;
+        }
+
+        Ok(changed)
+    })
+}
+
+/// Punches a hole of `len` bytes at offset `off` into the given inode.
+///
+/// All blocks that are completely covered by the range are freed and replaced by a hole; the
+/// remaining parts of the range are overwritten with zeros. The file size stays the same.
+///
+/// Returns true if the extents have been changed
+pub fn punch_hole(inode: &INodeRef, off: usize, len: usize) -> Result<bool, Error> {
+    log!(
+        crate::LOG_INODES,
+        "inodes::punch_hole(inode={}, off={}, len={})",
+        inode.inode,
+        off,
+        len,
+    );
+
+    let blocksize = crate::superblock().block_size as usize;
+    let size = inode.size as usize;
+    let end = off
+        .checked_add(len)
+        .ok_or_else(|| Error::new(Code::InvArgs))?
+        .min(size);
+    if off >= end {
+        return Ok(false);
+    }
+
+    // the last block can be freed entirely if the range extends to the end of the file
+    let first_blk = math::round_up(off, blocksize) / blocksize;
+    let end_blk = if end == size {
+        math::round_up(end, blocksize) / blocksize
+    }
+    else {
+        end / blocksize
+    };
+
+    transaction(|| {
+        if first_blk >= end_blk {
+            clear_range(inode, off, end)?;
+            return Ok(false);
+        }
+        clear_range(inode, off, first_blk * blocksize)?;
+        clear_range(inode, end_blk * blocksize, end)?;
+
+        let mut changed = false;
+        let mut blk = first_blk;
+        while blk < end_blk {
+            let mut indir = None;
+            let (idx, ext, first) = match find_extent(inode, blk, &mut indir)? {
+                Some(res) => res,
+                None => break,
+            };
+
+            let count = (ext.length as usize - first).min(end_blk - blk);
+            if !ext.is_hole() {
+                crate::blocks_mut().free(ext.start as usize + first, count)?;
+
+                let rest = ext.length as usize - first - count;
+                let mut new = Vec::with_capacity(3);
+                if first > 0 {
+                    new.push(Extent::new(ext.start, first as u32));
+                }
+                new.push(Extent::hole(count as u32));
+                if rest > 0 {
+                    new.push(Extent::new(ext.start + (first + count) as u32, rest as u32));
+                }
+                splice_extents(inode, idx, 1, &new)?;
+                changed = true;
+            }
+            blk += count;
+        }
+
+        if changed {
+            let mut indir = None;
+            let first_idx = find_extent(inode, first_blk, &mut indir)?.unwrap().0;
+            let last_idx = find_extent(inode, end_blk - 1, &mut indir)?.unwrap().0;
+            merge_holes(inode, first_idx, last_idx)?;
+        }
+        Ok(changed)
+    })
+}
+
+/// Allocates blocks for the hole at position `pos`, if there is a hole.
+///
+/// Starting at the block that contains `pos`, at most as many blocks are allocated as files are
+/// extended by when appending.
+///
+/// Returns true if the extents have been changed
+pub fn fill_hole(inode: &INodeRef, pos: &ExtPos) -> Result<bool, Error> {
+    if pos.ext >= inode.extents as usize {
+        return Ok(false);
+    }
+
+    let mut indir = None;
+    let ext = *get_extent(inode, pos.ext, &mut indir, false)?;
+    let first = pos.off / crate::superblock().block_size as usize;
+    if !ext.is_hole() || first >= ext.length as usize {
+        return Ok(false);
+    }
+
+    log!(
+        crate::LOG_INODES,
+        "inodes::fill_hole(inode={}, pos={:?})",
+        inode.inode,
+        pos,
+    );
+
+    let count = (ext.length as usize - first).min(crate::settings().extend.max(1));
+    transaction(|| fill_blocks(inode, pos.ext, ext, first, count))?;
+    Ok(true)
+}
+
+/// Replaces up to `count` blocks of the hole `ext` with index `idx`, starting at block `first`
+/// within the hole, by newly allocated and zeroed blocks.
+///
+/// Returns the number of allocated blocks
+fn fill_blocks(
+    inode: &INodeRef,
+    idx: usize,
+    ext: Extent,
+    first: usize,
+    mut count: usize,
+) -> Result<usize, Error> {
+    let start = crate::blocks_mut().alloc(Some(&mut count))?;
+    let data = Extent::new(start, count as u32);
+    crate::backend_mut().clear_extent(data)?;
+
+    let rest = ext.length as usize - first - count;
+    let mut new = Vec::with_capacity(3);
+    if first > 0 {
+        new.push(Extent::hole(first as u32));
+    }
+    new.push(data);
+    if rest > 0 {
+        new.push(Extent::hole(rest as u32));
+    }
+    splice_extents(inode, idx, 1, &new)?;
+    Ok(count)
+}
+
+/// Overwrites the last block behind the end of the file with zeros, so that these bytes read as
+/// zeros if the file grows.
+fn clear_tail(inode: &INodeRef) -> Result<(), Error> {
+    let blocksize = crate::superblock().block_size as usize;
+    let unaligned = inode.size as usize % blocksize;
+    if unaligned == 0 || inode.extents == 0 {
+        return Ok(());
+    }
+
+    let mut indir = None;
+    let ext = get_extent(inode, (inode.extents - 1) as usize, &mut indir, false)?;
+    if ext.is_hole() {
+        return Ok(());
+    }
+    crate::backend_mut().clear_block(ext.start + ext.length - 1, unaligned, blocksize - unaligned)
+}
+
+/// Overwrites the bytes from `start` to `end` of the given inode with zeros
+fn clear_range(inode: &INodeRef, mut start: usize, end: usize) -> Result<(), Error> {
+    let blocksize = crate::superblock().block_size as usize;
+    while start < end {
+        let off = start % blocksize;
+        let len = (blocksize - off).min(end - start);
+
+        let mut indir = None;
+        if let Some((_, ext, first)) = find_extent(inode, start / blocksize, &mut indir)? {
+            if !ext.is_hole() {
+                crate::backend_mut().clear_block(ext.start + first as u32, off, len)?;
+            }
+        }
+        start += len;
+    }
+    Ok(())
+}
+
+/// Determines the extent that contains the block `blk` of the given inode.
+///
+/// Returns the index of the extent, the extent, and the index of the block within the extent, or
+/// None if the file has less blocks
+fn find_extent(
+    inode: &INodeRef,
+    blk: usize,
+    indir: &mut Option<ExtentCache>,
+) -> Result<Option<(usize, Extent, usize)>, Error> {
+    let mut first = 0;
+    for i in 0..inode.extents as usize {
+        let ext = *get_extent(inode, i, indir, false)?;
+        if blk < first + ext.length as usize {
+            return Ok(Some((i, ext, blk - first)));
+        }
+        first += ext.length as usize;
+    }
+    Ok(None)
+}
+
+/// Replaces the `count` extents starting at index `idx` by the extents in `new`, moving all
+/// following extents accordingly.
+fn splice_extents(inode: &INodeRef, idx: usize, count: usize, new: &[Extent]) -> Result<(), Error> {
+    let mut indir = None;
+    let total = inode.extents as usize;
+
+    match new.len().cmp(&count) {
+        Ordering::Greater => {
+            let diff = new.len() - count;
+            for i in (idx + count..total).rev() {
+                let ext = *get_extent(inode, i, &mut indir, false)?;
+                *get_extent(inode, i + diff, &mut indir, true)?.as_mut() = ext;
+            }
+        },
+        Ordering::Less => {
+            let diff = count - new.len();
+            for i in idx + count..total {
+                let ext = *get_extent(inode, i, &mut indir, false)?;
+                *get_extent(inode, i - diff, &mut indir, false)?.as_mut() = ext;
+            }
+
+            // remove the extents at the end, which might free the indirect blocks as well
+            for i in (total - diff..total).rev() {
+                let ext = change_extent(inode, i, &mut indir, true)?;
+                ext.as_mut().start = 0;
+                ext.as_mut().length = 0;
+            }
+        },
+        Ordering::Equal => {},
+    }
+
+    for (i, ext) in new.iter().enumerate() {
+        *get_extent(inode, idx + i, &mut indir, true)?.as_mut() = *ext;
+    }
+
+    inode.as_mut().extents = (total + new.len() - count) as u32;
+    Ok(())
+}
+
+/// Merges adjacent holes within the extents `first` to `last` and their neighbors
+fn merge_holes(inode: &INodeRef, first: usize, mut last: usize) -> Result<(), Error> {
+    let mut i = first.saturating_sub(1);
+    while i <= last && i + 1 < inode.extents as usize {
+        let mut indir = None;
+        let cur = *get_extent(inode, i, &mut indir, false)?;
+        let next = *get_extent(inode, i + 1, &mut indir, false)?;
+        if cur.is_hole() && next.is_hole() {
+            splice_extents(inode, i, 2, &[Extent::hole(cur.length + next.length)])?;
+            if last > i {
+                last -= 1;
+            }
+        }
+        else {
+            i += 1;
+        }
+    }
+    Ok(())
+}
+
 /// Writes all dirty metadata from given inode back to storage
 pub fn sync_metadata(inode: &INodeRef) -> Result<(), Error> {
     log!(
@@ -576,7 +1040,7 @@ pub fn sync_metadata(inode: &INodeRef) -> Result<(), Error> {
         inode.inode,
     );
 
-    for ext in inode.extent_iter() {
+    for ext in inode.extent_iter().filter(|ext| !ext.is_hole()) {
         for mut block in ext.block_iter() {
             crate::backend_mut().sync_meta(&mut block)?;
             block.flush()?;
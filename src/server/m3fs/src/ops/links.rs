@@ -14,7 +14,7 @@
  * General Public License version 2 for more details.
  */
 
-use crate::data::{DirEntry, INodeRef, DIR_ENTRY_LEN};
+use crate::data::{entry_mut, two_entries_mut, ExtentBlocks, INodeRef, DIR_ENTRY_LEN};
 use crate::ops::inodes;
 
 use m3::errors::{Code, Error};
@@ -39,7 +39,7 @@ pub fn create(dir: &INodeRef, name: &str, inode: &INodeRef) -> Result<(), Error>
             let mut off = 0;
             let end = crate::superblock().block_size as usize;
             while off < end {
-                let entry = DirEntry::from_buffer_mut(&mut block, off);
+                let entry = entry_mut(&mut block, off);
 
                 let rem = entry.next - entry.size() as u32;
                 if rem >= new_entry_size as u32 {
@@ -48,8 +48,7 @@ pub fn create(dir: &INodeRef, name: &str, inode: &INodeRef) -> Result<(), Error>
                     let entry_next = entry.next;
 
                     // create new entry behind it
-                    let new_entry =
-                        DirEntry::from_buffer_mut(&mut block, off + entry_next as usize);
+                    let new_entry = entry_mut(&mut block, off + entry_next as usize);
 
                     new_entry.set_name(name);
                     new_entry.nodeno = inode.inode;
@@ -76,7 +75,7 @@ pub fn create(dir: &INodeRef, name: &str, inode: &INodeRef) -> Result<(), Error>
         // put entry at the beginning of the block
         let start = ext.start;
         let mut block = crate::meta_buffer_mut().get_block(start)?;
-        let new_entry = DirEntry::from_buffer_mut(&mut block, 0);
+        let new_entry = entry_mut(&mut block, 0);
         new_entry.set_name(name);
         new_entry.nodeno = inode.inode;
         new_entry.next = crate::superblock().block_size;
@@ -105,7 +104,7 @@ pub fn remove(dir: &INodeRef, name: &str, deny_dir: bool) -> Result<(), Error> {
             let end = crate::superblock().block_size as usize;
             while off < end {
                 // TODO marking all blocks dirty here is suboptimal
-                let entry = DirEntry::from_buffer_mut(&mut block, off);
+                let entry = entry_mut(&mut block, off);
 
                 if entry.name() == name {
                     // if we're not removing a dir, we're coming from unlink(). in this case,
@@ -119,18 +118,15 @@ pub fn remove(dir: &INodeRef, name: &str, deny_dir: bool) -> Result<(), Error> {
 
                     // remove entry by skipping over it
                     if off > 0 {
-                        let mut prev = DirEntry::from_buffer_mut(&mut block, prev_off);
+                        let mut prev = entry_mut(&mut block, prev_off);
                         prev.next += entry_next;
                     }
                     // copy the next entry back, if there is any
                     else {
                         let next_off = off + entry_next as usize;
                         if next_off < end {
-                            let (cur_entry, next_entry) = DirEntry::two_from_buffer_mut(
-                                &mut block,
-                                off,
-                                off + entry_next as usize,
-                            );
+                            let (cur_entry, next_entry) =
+                                two_entries_mut(&mut block, off, off + entry_next as usize);
 
                             let dist = cur_entry.next;
                             cur_entry.next = next_entry.next;
@@ -0,0 +1,153 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::buf::transaction;
+use crate::data::INode;
+use crate::ops::{dirs, inodes};
+
+use m3::errors::{Code, Error};
+use m3::vfs::{FileEvent, FileMode, OpenFlags};
+
+/// The requested access, expressed with the permission bits for others
+pub const MAY_READ: FileMode = FileMode::IROTH;
+pub const MAY_WRITE: FileMode = FileMode::IWOTH;
+pub const MAY_EXEC: FileMode = FileMode::IXOTH;
+
+/// The identity of a client, which is assigned per session via the session arguments.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Creds {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl Creds {
+    pub const ROOT: Creds = Creds { uid: 0, gid: 0 };
+
+    /// Returns true if these are the credentials of root, which bypasses most checks
+    pub fn is_root(&self) -> bool {
+        self.uid == 0
+    }
+
+    /// Returns true if these credentials own the given inode
+    pub fn owns(&self, inode: &INode) -> bool {
+        self.is_root() || self.uid == inode.uid
+    }
+}
+
+/// Converts the given open flags into the requested access
+pub fn access_for(flags: OpenFlags) -> FileMode {
+    let mut access = FileMode::empty();
+    if flags.contains(OpenFlags::R) {
+        access |= MAY_READ;
+    }
+    if flags.contains(OpenFlags::W) {
+        access |= MAY_WRITE;
+    }
+    if flags.contains(OpenFlags::X) {
+        access |= MAY_EXEC;
+    }
+    access
+}
+
+/// Checks whether `creds` grant the given access to `inode`.
+///
+/// The owner class is used if the uid matches, the group class if the gid matches, and the others
+/// class otherwise. Root is granted everything except executing files without any execute bit.
+pub fn check(creds: &Creds, inode: &INode, access: FileMode) -> Result<(), Error> {
+    let mode = inode.mode.bits();
+    let granted = if creds.is_root() {
+        let exec = mode & (FileMode::IXUSR | FileMode::IXGRP | FileMode::IXOTH).bits() != 0
+            || inode.mode.is_dir();
+        (MAY_READ | MAY_WRITE).bits() | if exec { MAY_EXEC.bits() } else { 0 }
+    }
+    else if creds.uid == inode.uid {
+        mode >> 6
+    }
+    else if creds.gid == inode.gid {
+        mode >> 3
+    }
+    else {
+        mode
+    };
+
+    if granted & access.bits() != access.bits() {
+        log!(
+            crate::LOG_PERMS,
+            "perms::check(uid={}, gid={}, inode={}, mode={:o}, access={:o}) -> denied",
+            creds.uid,
+            creds.gid,
+            inode.inode,
+            inode.mode,
+            access
+        );
+        return Err(Error::new(Code::NoPerm));
+    }
+    Ok(())
+}
+
+/// Changes the permission bits of the file at `path` to `mode`; only allowed for the owner
+pub fn chmod(creds: &Creds, path: &str, mode: FileMode) -> Result<(), Error> {
+    log!(
+        crate::LOG_PERMS,
+        "perms::chmod(path={}, mode={:o})",
+        path,
+        mode
+    );
+
+    transaction(|| {
+        let ino = dirs::search(creds, path, false)?;
+        let inode = inodes::get(ino)?;
+        if !creds.owns(&inode) {
+            return Err(Error::new(Code::NoPerm));
+        }
+
+        let perm = mode & FileMode::PERM;
+        inode.as_mut().mode = (inode.mode & !FileMode::PERM) | perm;
+        Ok(())
+    })?;
+
+    dirs::notify_parent(path, FileEvent::MODIFY);
+    Ok(())
+}
+
+/// Changes the owner of the file at `path` to `uid` and `gid`.
+///
+/// Only root can change the user. The owner can change the group to its own group.
+pub fn chown(creds: &Creds, path: &str, uid: u32, gid: u32) -> Result<(), Error> {
+    log!(
+        crate::LOG_PERMS,
+        "perms::chown(path={}, uid={}, gid={})",
+        path,
+        uid,
+        gid
+    );
+
+    transaction(|| {
+        let ino = dirs::search(creds, path, false)?;
+        let inode = inodes::get(ino)?;
+        if !creds.is_root()
+            && (!creds.owns(&inode) || uid != inode.uid || (gid != inode.gid && gid != creds.gid))
+        {
+            return Err(Error::new(Code::NoPerm));
+        }
+
+        inode.as_mut().uid = uid;
+        inode.as_mut().gid = gid;
+        Ok(())
+    })?;
+
+    dirs::notify_parent(path, FileEvent::MODIFY);
+    Ok(())
+}
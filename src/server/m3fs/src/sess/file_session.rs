@@ -15,9 +15,9 @@
  * General Public License version 2 for more details.
  */
 
-use crate::buf::LoadLimit;
-use crate::data::{ExtPos, Extent, INodeRef, InodeNo};
-use crate::ops::inodes;
+use crate::buf::{transaction, LoadLimit};
+use crate::data::{to_file_info, ExtPos, Extent, INodeRef, InodeNo};
+use crate::ops::{dirs, inodes};
 use crate::sess::M3FSSession;
 
 use m3::{
@@ -29,7 +29,7 @@ use m3::{
     server::{CapExchange, SessId},
     session::ServerSession,
     syscalls, tcu,
-    vfs::{OpenFlags, SeekMode},
+    vfs::{FileEvent, LockType, OpenFlags, SeekMode},
 };
 
 struct Entry {
@@ -68,6 +68,7 @@ pub struct FileSession {
     // next position (the one that the client gets access to next)
     next_pos: ExtPos,    // extent position
     next_fileoff: usize, // file position (global offset)
+    layout: u64,         // the generation of the extent layout that next_pos is based on
 
     load_limit: LoadLimit,
 
@@ -79,6 +80,7 @@ pub struct FileSession {
     capscon: CapContainer,
     epcap: Selector,
     _sgate: Option<SendGate>, // keep the send gate alive
+    watching: bool,           // whether we have a watch on our directory
 
     // the file the client has access to
     oflags: OpenFlags,
@@ -143,7 +145,7 @@ impl FileSession {
             )?)
         };
 
-        let fsess = FileSession {
+        let mut fsess = FileSession {
             cur_pos: ExtPos::new(0, 0),
             cur_extlen: 0,
             cur_bytes: 0,
@@ -151,6 +153,7 @@ impl FileSession {
 
             next_pos: ExtPos::new(0, 0),
             next_fileoff: 0,
+            layout: 0,
 
             load_limit: LoadLimit::new(),
 
@@ -160,6 +163,7 @@ impl FileSession {
             capscon: CapContainer { caps: vec![] },
             epcap: m3::kif::INVALID_SEL,
             _sgate: send_gate,
+            watching: false,
 
             oflags,
             filename: filename.to_string(),
@@ -176,6 +180,7 @@ impl FileSession {
         };
 
         crate::open_files_mut().add_sess(ino);
+        fsess.layout = fsess.cur_layout();
 
         Ok(fsess)
     }
@@ -228,7 +233,13 @@ impl FileSession {
         let inode = inodes::get(self.ino)?;
 
         // determine extent from byte offset
-        let (_, extpos) = inodes::get_seek_pos(&inode, offset as usize, SeekMode::SET)?;
+        let (_, mut extpos) = inodes::get_seek_pos(&inode, offset as usize, SeekMode::SET)?;
+
+        // holes are only mapped read-only; thus, allocate blocks for writable mappings
+        if self.oflags.contains(OpenFlags::W) && inodes::fill_hole(&inode, &extpos)? {
+            self.layout_changed();
+            extpos = inodes::get_seek_pos(&inode, offset as usize, SeekMode::SET)?.1;
+        }
 
         let sel = m3::tiles::Activity::own().alloc_sel();
         let (len, _) = inodes::get_extent_mem(
@@ -257,6 +268,32 @@ impl FileSession {
         Ok(())
     }
 
+    pub fn sync_mem(&mut self, data: &mut CapExchange<'_>) -> Result<(), Error> {
+        let offset: usize = data.in_args().pop()?;
+        let len: usize = data.in_args().pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::sync_mem(path={}, offset={}, len={})",
+            self.session_id,
+            self.filename,
+            offset,
+            len
+        );
+
+        // only writable mappings can have modified the file
+        if !self.oflags.contains(OpenFlags::W) {
+            return Err(Error::new(Code::NoPerm));
+        }
+
+        {
+            let inode = inodes::get(self.ino)?;
+            inodes::sync_mem(&inode, offset, len)?;
+        }
+        // commit the checksums of the written blocks
+        crate::meta_buffer_mut().flush()
+    }
+
     fn revoke_cap(&mut self) {
         if self.cur_sel != m3::kif::INVALID_SEL {
             m3::tiles::Activity::own()
@@ -269,10 +306,66 @@ impl FileSession {
         }
     }
 
+    fn cur_layout(&self) -> u64 {
+        crate::open_files_mut().get_file(self.ino).unwrap().layout()
+    }
+
+    fn layout_changed(&self) {
+        crate::open_files_mut()
+            .get_file_mut(self.ino)
+            .unwrap()
+            .layout_changed();
+    }
+
+    /// Recalculates the next position from the file offset if the extents of the file have been
+    /// changed since the position was determined
+    fn update_pos(&mut self, inode: &INodeRef) -> Result<(), Error> {
+        let layout = self.cur_layout();
+        if layout != self.layout {
+            let (fileoff, extpos) = inodes::get_seek_pos(inode, self.next_fileoff, SeekMode::SET)?;
+            self.next_fileoff = fileoff;
+            self.next_pos = extpos;
+            self.layout = layout;
+        }
+        Ok(())
+    }
+
+    /// Fails if an append to the file is in progress
+    fn check_no_append(&self) -> Result<(), Error> {
+        if crate::open_files_mut()
+            .get_file(self.ino)
+            .unwrap()
+            .appending()
+        {
+            return Err(Error::new(Code::Exists));
+        }
+        Ok(())
+    }
+
     pub fn set_ep(&mut self, ep: Selector) {
         self.epcap = ep;
     }
 
+    /// Starts watching the directory of this session for changes, reporting them via `sgate`
+    pub fn enable_notify(&mut self, sgate: Selector) -> Result<(), Error> {
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::enable_notify(path={}, sgate={})",
+            self.session_id,
+            self.filename,
+            sgate
+        );
+
+        // only changes of directories are reported
+        if !inodes::get(self.ino)?.mode.is_dir() {
+            return Err(Error::new(Code::NotSup));
+        }
+
+        crate::watches_mut().add(self.session_id, self.ino, sgate)?;
+        self.watching = true;
+        Ok(())
+    }
+
     pub fn ino(&self) -> InodeNo {
         self.ino
     }
@@ -321,31 +414,45 @@ impl FileSession {
             self.commit_append(&inode, self.cur_bytes)?;
         }
 
-        let mut sel = m3::tiles::Activity::own().alloc_sel();
+        // other sessions might have changed the extents in the meantime
+        if !self.appending {
+            self.update_pos(&inode)?;
+        }
 
         // do we need to append to the file?
-        let (len, extlen) = if out && (self.next_fileoff as u64 == inode.size) {
-            let mut files = crate::open_files_mut();
-            let open_file = files.get_file_mut(self.ino).unwrap();
-
-            if open_file.appending() {
+        let append = out && (self.next_fileoff as u64 == inode.size);
+        if append {
+            if let Err(e) = self.check_no_append() {
                 log!(
                     crate::LOG_SESSION,
                     "[{}] file::next_in_out(): append already in progress!",
                     self.session_id,
                 );
-                return Err(Error::new(Code::Exists));
+                return Err(e);
             }
 
             // continue in last extent, if there is space
             if (self.next_pos.ext > 0)
-                && (self.next_fileoff as u64 == inode.size)
                 && ((self.next_fileoff % crate::superblock().block_size as usize) != 0)
             {
                 let (fileoff, extpos) = inodes::get_seek_pos(&inode, 0, SeekMode::END)?;
                 self.next_fileoff = fileoff;
                 self.next_pos = extpos;
             }
+        }
+
+        // holes are handed out read-only. Since clients with write permission can also write to
+        // memory they have received for reading, we allocate blocks for holes in this case.
+        if self.oflags.contains(OpenFlags::W) && inodes::fill_hole(&inode, &self.next_pos)? {
+            self.layout_changed();
+            self.update_pos(&inode)?;
+        }
+
+        let mut sel = m3::tiles::Activity::own().alloc_sel();
+
+        let (len, extlen) = if append {
+            let mut files = crate::open_files_mut();
+            let open_file = files.get_file_mut(self.ino).unwrap();
 
             let (len, extlen, new_ext) = inodes::req_append(
                 &inode,
@@ -359,6 +466,7 @@ impl FileSession {
             self.append_ext = new_ext;
 
             open_file.set_appending(true);
+            open_file.set_append_ext(new_ext);
             (len, extlen)
         }
         else {
@@ -442,6 +550,7 @@ impl FileSession {
         let (pos, extpos) = inodes::get_seek_pos(&inode, off, whence)?;
         self.next_pos = extpos;
         self.next_fileoff = pos;
+        self.layout = self.cur_layout();
 
         reply_vmsg!(stream, Code::Success, pos - extpos.off, extpos.off)
     }
@@ -455,7 +564,7 @@ impl FileSession {
         );
 
         let inode = inodes::get(self.ino)?;
-        let info = inode.to_file_info();
+        let info = to_file_info(&inode);
 
         let mut reply = m3::mem::MsgBuf::borrow_def();
         build_vmsg!(reply, Code::Success, info);
@@ -486,12 +595,26 @@ impl FileSession {
 
         let inode = inodes::get(self.ino)?;
 
+        // growing the file appends a hole and keeps our position
         if off as u64 > inode.size {
-            return Err(Error::new(Code::InvArgs));
+            inodes::extend(&inode, off)?;
+            self.layout_changed();
+            self.update_pos(&inode)?;
+            self.revoke_cap();
+
+            dirs::notify_parent(&self.filename, FileEvent::MODIFY);
+
+            return reply_vmsg!(
+                stream,
+                Code::Success,
+                self.next_fileoff - self.next_pos.off,
+                self.next_pos.off
+            );
         }
 
         let (fileoff, extpos) = inodes::get_seek_pos(&inode, off, SeekMode::SET)?;
         inodes::truncate(&inode, &extpos)?;
+        self.layout_changed();
 
         // stay within the file bounds
         if self.next_fileoff > fileoff {
@@ -504,9 +627,129 @@ impl FileSession {
         // prepared for that!
         self.revoke_cap();
 
+        dirs::notify_parent(&self.filename, FileEvent::MODIFY);
+
         reply_vmsg!(stream, Code::Success, fileoff - extpos.off, extpos.off)
     }
 
+    pub fn file_allocate(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let off: usize = stream.pop()?;
+        let len: usize = stream.pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::allocate(path={}, off={}, len={})",
+            self.session_id,
+            self.filename,
+            off,
+            len
+        );
+
+        self.change_blocks(stream, len, |inode| inodes::allocate(inode, off, len))
+    }
+
+    pub fn file_punch_hole(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let off: usize = stream.pop()?;
+        let len: usize = stream.pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::punch_hole(path={}, off={}, len={})",
+            self.session_id,
+            self.filename,
+            off,
+            len
+        );
+
+        self.change_blocks(stream, len, |inode| inodes::punch_hole(inode, off, len))
+    }
+
+    /// Changes the blocks of the file via `func`, which returns whether the extent layout has
+    /// changed, and replies with our new position
+    fn change_blocks<F>(
+        &mut self,
+        stream: &mut GateIStream<'_>,
+        len: usize,
+        func: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&INodeRef) -> Result<bool, Error>,
+    {
+        if !self.oflags.contains(OpenFlags::W) {
+            return Err(Error::new(Code::NoPerm));
+        }
+        if len == 0 {
+            return Err(Error::new(Code::InvArgs));
+        }
+        // the block that is currently appended to might be affected
+        self.check_no_append()?;
+
+        let inode = inodes::get(self.ino)?;
+        if func(&inode)? {
+            self.layout_changed();
+        }
+        self.update_pos(&inode)?;
+
+        // revoke the current to remove the client's access to freed blocks
+        // TODO as for truncate, the access of others should be revoked as well
+        self.revoke_cap();
+
+        dirs::notify_parent(&self.filename, FileEvent::MODIFY);
+
+        reply_vmsg!(
+            stream,
+            Code::Success,
+            self.next_fileoff - self.next_pos.off,
+            self.next_pos.off
+        )
+    }
+
+    pub fn file_lock(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let off: usize = stream.pop()?;
+        let len: usize = stream.pop()?;
+        let ty = LockType::from(stream.pop::<u32>()?);
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::lock(path={}, off={}, len={}, ty={})",
+            self.session_id,
+            self.filename,
+            off,
+            len,
+            ty
+        );
+
+        let end = lock_end(off, len)?;
+        crate::open_files_mut()
+            .get_file_mut(self.ino)
+            .unwrap()
+            .lock(self.session_id, off, end, ty == LockType::EXCLUSIVE)?;
+
+        stream.reply_error(Code::Success)
+    }
+
+    pub fn file_unlock(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let off: usize = stream.pop()?;
+        let len: usize = stream.pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::unlock(path={}, off={}, len={})",
+            self.session_id,
+            self.filename,
+            off,
+            len
+        );
+
+        let end = lock_end(off, len)?;
+        crate::open_files_mut()
+            .get_file_mut(self.ino)
+            .unwrap()
+            .unlock(self.session_id, off, end);
+
+        stream.reply_error(Code::Success)
+    }
+
     pub fn file_commit(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
         let nbytes: usize = stream.pop()?;
 
@@ -527,7 +770,8 @@ impl FileSession {
         let inode = inodes::get(self.ino)?;
 
         let res = if self.appending {
-            self.commit_append(&inode, nbytes)
+            // the new extent and the new file size have to become visible together
+            transaction(|| self.commit_append(&inode, nbytes))
         }
         else {
             if (self.next_pos.ext > self.cur_pos.ext)
@@ -538,12 +782,16 @@ impl FileSession {
 
             if nbytes < self.cur_bytes {
                 self.next_pos.off = self.cur_pos.off + nbytes;
+                self.next_fileoff -= self.cur_bytes - nbytes;
             }
             Ok(())
         };
 
         self.cur_bytes = 0;
         res?;
+
+        dirs::notify_parent(&self.filename, FileEvent::MODIFY);
+
         stream.reply_error(Code::Success)
     }
 
@@ -566,6 +814,7 @@ impl FileSession {
         self.next_fileoff -= self.cur_bytes - submit;
 
         // add new extent?
+        let mut merged = false;
         if let Some(ref mut append_ext) = self.append_ext.take() {
             let blocksize = crate::superblock().block_size as usize;
             let blocks = (submit + blocksize - 1) / blocksize;
@@ -587,6 +836,7 @@ impl FileSession {
             // have we appended the new extent to the previous extent?
             if !new_ext {
                 self.next_pos.ext -= 1;
+                merged = true;
             }
 
             self.cur_pos = ExtPos::new(0, 0);
@@ -605,6 +855,11 @@ impl FileSession {
         let ofile = files.get_file_mut(self.ino).unwrap();
         assert!(ofile.appending(), "ofile should be in append mode!");
         ofile.set_appending(false);
+        ofile.set_append_ext(None);
+        // the last extent has grown, which affects the positions of other sessions
+        if merged {
+            ofile.layout_changed();
+        }
 
         self.append_ext = None;
         self.appending = false;
@@ -620,6 +875,17 @@ impl FileSession {
     }
 }
 
+/// Determines the end of a lock range, where a length of 0 extends the range to infinity
+fn lock_end(off: usize, len: usize) -> Result<usize, Error> {
+    if len == 0 {
+        Ok(usize::MAX)
+    }
+    else {
+        off.checked_add(len)
+            .ok_or_else(|| Error::new(Code::InvArgs))
+    }
+}
+
 impl Drop for FileSession {
     fn drop(&mut self) {
         log!(
@@ -635,9 +901,21 @@ impl Drop for FileSession {
                 .free(ext.start as usize, ext.length as usize)
                 .unwrap();
         }
+        if self.appending {
+            let mut files = crate::open_files_mut();
+            let ofile = files.get_file_mut(self.ino).unwrap();
+            ofile.set_appending(false);
+            ofile.set_append_ext(None);
+        }
 
         // remove session from open_files and from its meta session
-        crate::open_files_mut().remove_session(self.ino).unwrap();
+        crate::open_files_mut()
+            .remove_session(self.ino, self.session_id)
+            .unwrap();
+
+        if self.watching {
+            crate::watches_mut().remove(self.session_id);
+        }
 
         // revoke caps if needed
         self.revoke_cap();
@@ -688,6 +966,26 @@ impl M3FSSession for FileSession {
         self.file_truncate(stream)
     }
 
+    fn allocate(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _: usize = stream.pop()?;
+        self.file_allocate(stream)
+    }
+
+    fn punch_hole(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _: usize = stream.pop()?;
+        self.file_punch_hole(stream)
+    }
+
+    fn lock(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _: usize = stream.pop()?;
+        self.file_lock(stream)
+    }
+
+    fn unlock(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _: usize = stream.pop()?;
+        self.file_unlock(stream)
+    }
+
     fn mkdir(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
@@ -708,4 +1006,20 @@ impl M3FSSession for FileSession {
         let _: usize = stream.pop()?;
         self.file_sync(stream)
     }
+
+    fn req_notify(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _: usize = stream.pop()?;
+        let events = FileEvent::from_bits_truncate(stream.pop()?);
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::req_notify(path={}, events={:?})",
+            self.session_id,
+            self.filename,
+            events
+        );
+
+        crate::watches_mut().request(self.session_id, events)?;
+        stream.reply_error(Code::Success)
+    }
 }
@@ -14,8 +14,9 @@
  * General Public License version 2 for more details.
  */
 
-use crate::data::ExtPos;
-use crate::ops::{dirs, inodes};
+use crate::data::{to_file_info, ExtPos};
+use crate::ops::perms::{self, Creds};
+use crate::ops::{dirs, fsck, inodes};
 use crate::sess::{FileSession, M3FSSession};
 
 use m3::{
@@ -28,7 +29,7 @@ use m3::{
     server::SessId,
     session::ServerSession,
     tcu::Label,
-    vfs::{FileMode, OpenFlags},
+    vfs::{FileEvent, FileMode, FsStats, OpenFlags},
 };
 
 static NEXT_PRIV_ID: StaticCell<SessId> = StaticCell::new(1);
@@ -43,6 +44,7 @@ pub struct MetaSession {
     priv_eps: Vec<Selector>,
     creator: usize,
     session_id: SessId,
+    creds: Creds,
 }
 
 impl MetaSession {
@@ -51,6 +53,7 @@ impl MetaSession {
         session_id: SessId,
         crt: usize,
         max_files: usize,
+        creds: Creds,
     ) -> Self {
         MetaSession {
             _server_session,
@@ -62,6 +65,7 @@ impl MetaSession {
             priv_eps: Vec::new(),
             creator: crt,
             session_id,
+            creds,
         }
     }
 
@@ -156,26 +160,24 @@ impl MetaSession {
             return Err(Error::new(Code::NoSpace));
         }
 
-        let ino = dirs::search(path, flags.contains(OpenFlags::CREATE))?;
+        let ino = dirs::search(&self.creds, path, flags.contains(OpenFlags::CREATE))?;
         let inode = inodes::get(ino)?;
-        let inode_mode = inode.mode;
 
-        if (flags.contains(OpenFlags::W) && !inode_mode.contains(FileMode::IWUSR))
-            || (flags.contains(OpenFlags::R) && !inode_mode.contains(FileMode::IRUSR))
-        {
+        if let Err(e) = perms::check(&self.creds, &inode, perms::access_for(flags)) {
             log!(
                 crate::LOG_SESSION,
                 "insufficient permissions: flags={:o}, mode={:o}",
                 flags,
                 inode.mode,
             );
-            return Err(Error::new(Code::NoPerm));
+            return Err(e);
         }
 
         // only determine the current size, if we're writing and the file isn't empty
         if flags.contains(OpenFlags::TRUNC) {
             inodes::truncate(&inode, &ExtPos::new(0, 0))?;
             // TODO revoke access, if necessary
+            dirs::notify_parent(path, FileEvent::MODIFY);
         }
 
         // for directories: ensure that we don't have a changed version in the cache
@@ -263,10 +265,10 @@ impl M3FSSession for MetaSession {
             path
         );
 
-        let ino = dirs::search(path, false)?;
+        let ino = dirs::search(&self.creds, path, false)?;
         let inode = inodes::get(ino)?;
 
-        let info = inode.to_file_info();
+        let info = to_file_info(&inode);
 
         let mut reply = m3::mem::MsgBuf::borrow_def();
         build_vmsg!(reply, Code::Success, info);
@@ -285,7 +287,7 @@ impl M3FSSession for MetaSession {
             mode
         );
 
-        dirs::create(path, mode)?;
+        dirs::create(&self.creds, path, mode)?;
 
         stream.reply_error(Code::Success)
     }
@@ -300,7 +302,7 @@ impl M3FSSession for MetaSession {
             path
         );
 
-        dirs::remove(path)?;
+        dirs::remove(&self.creds, path)?;
 
         stream.reply_error(Code::Success)
     }
@@ -317,11 +319,124 @@ impl M3FSSession for MetaSession {
             new_path
         );
 
-        dirs::link(old_path, new_path)?;
+        dirs::link(&self.creds, old_path, new_path)?;
+
+        stream.reply_error(Code::Success)
+    }
+
+    fn symlink(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let target: &str = stream.pop()?;
+        let path: &str = stream.pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] meta::symlink(target={}, path={})",
+            self.session_id,
+            target,
+            path
+        );
+
+        dirs::symlink(&self.creds, target, path)?;
+
+        stream.reply_error(Code::Success)
+    }
+
+    fn readlink(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let path: &str = stream.pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] meta::readlink(path={})",
+            self.session_id,
+            path
+        );
+
+        let target = dirs::readlink(&self.creds, path)?;
+
+        let mut reply = m3::mem::MsgBuf::borrow_def();
+        build_vmsg!(reply, Code::Success, target);
+        stream.reply(&reply)
+    }
+
+    fn chmod(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let path: &str = stream.pop()?;
+        let mode = FileMode::from_bits_truncate(stream.pop::<u16>()?);
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] meta::chmod(path={}, mode={:o})",
+            self.session_id,
+            path,
+            mode
+        );
+
+        perms::chmod(&self.creds, path, mode)?;
 
         stream.reply_error(Code::Success)
     }
 
+    fn chown(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let path: &str = stream.pop()?;
+        let uid: u32 = stream.pop()?;
+        let gid: u32 = stream.pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] meta::chown(path={}, uid={}, gid={})",
+            self.session_id,
+            path,
+            uid,
+            gid
+        );
+
+        perms::chown(&self.creds, path, uid, gid)?;
+
+        stream.reply_error(Code::Success)
+    }
+
+    fn fsck(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let repair: bool = stream.pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] meta::fsck(repair={})",
+            self.session_id,
+            repair
+        );
+
+        // the check sees all files and repairing frees inodes and blocks
+        if !self.creds.is_root() {
+            return Err(Error::new(Code::NoPerm));
+        }
+        if fsck::running() {
+            return Err(Error::new(Code::Exists));
+        }
+
+        // the reply is sent as soon as the check is finished
+        fsck::start(stream.take_msg(), repair);
+        Ok(())
+    }
+
+    fn statfs(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        log!(crate::LOG_SESSION, "[{}] meta::statfs()", self.session_id);
+
+        let stats = {
+            let sb = crate::superblock();
+            let checksums = crate::checksums_mut();
+            FsStats {
+                block_size: sb.block_size,
+                total_blocks: sb.total_blocks,
+                free_blocks: crate::blocks_mut().free_count(),
+                total_inodes: sb.total_inodes,
+                free_inodes: crate::inodes_mut().free_count(),
+                journal: crate::journal_mut().enabled(),
+                checksums: checksums.enabled(),
+                checksum_errors: checksums.errors(),
+            }
+        };
+        reply_vmsg!(stream, Code::Success, stats)
+    }
+
     fn unlink(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
         let path: &str = stream.pop()?;
 
@@ -332,7 +447,7 @@ impl M3FSSession for MetaSession {
             path
         );
 
-        dirs::unlink(path, true)?;
+        dirs::unlink(&self.creds, path, true)?;
 
         stream.reply_error(Code::Success)
     }
@@ -349,7 +464,7 @@ impl M3FSSession for MetaSession {
             new_path
         );
 
-        dirs::rename(old_path, new_path)?;
+        dirs::rename(&self.creds, old_path, new_path)?;
 
         stream.reply_error(Code::Success)
     }
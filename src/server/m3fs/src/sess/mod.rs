@@ -17,10 +17,12 @@
 mod file_session;
 mod meta_session;
 mod open_files;
+mod watches;
 
 pub use file_session::FileSession;
 pub use meta_session::MetaSession;
 pub use open_files::OpenFiles;
+pub use watches::Watches;
 
 use m3::com::GateIStream;
 use m3::errors::{Code, Error};
@@ -95,6 +97,34 @@ impl M3FSSession for FSSession {
         }
     }
 
+    fn allocate(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.allocate(stream),
+            FSSession::File(f) => f.allocate(stream),
+        }
+    }
+
+    fn punch_hole(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.punch_hole(stream),
+            FSSession::File(f) => f.punch_hole(stream),
+        }
+    }
+
+    fn lock(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.lock(stream),
+            FSSession::File(f) => f.lock(stream),
+        }
+    }
+
+    fn unlock(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.unlock(stream),
+            FSSession::File(f) => f.unlock(stream),
+        }
+    }
+
     fn mkdir(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
         match self {
             FSSession::Meta(m) => m.mkdir(stream),
@@ -116,6 +146,55 @@ impl M3FSSession for FSSession {
         }
     }
 
+    fn symlink(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.symlink(stream),
+            FSSession::File(f) => f.symlink(stream),
+        }
+    }
+
+    fn readlink(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.readlink(stream),
+            FSSession::File(f) => f.readlink(stream),
+        }
+    }
+
+    fn req_notify(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.req_notify(stream),
+            FSSession::File(f) => f.req_notify(stream),
+        }
+    }
+
+    fn chmod(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.chmod(stream),
+            FSSession::File(f) => f.chmod(stream),
+        }
+    }
+
+    fn chown(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.chown(stream),
+            FSSession::File(f) => f.chown(stream),
+        }
+    }
+
+    fn fsck(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.fsck(stream),
+            FSSession::File(f) => f.fsck(stream),
+        }
+    }
+
+    fn statfs(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.statfs(stream),
+            FSSession::File(f) => f.statfs(stream),
+        }
+    }
+
     fn unlink(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
         match self {
             FSSession::Meta(m) => m.unlink(stream),
@@ -180,6 +259,18 @@ pub trait M3FSSession {
     fn truncate(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
+    fn allocate(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn punch_hole(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn lock(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn unlock(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
     fn mkdir(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
@@ -189,6 +280,27 @@ pub trait M3FSSession {
     fn link(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
+    fn symlink(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn readlink(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn req_notify(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn chmod(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn chown(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn fsck(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn statfs(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
     fn unlink(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
@@ -14,24 +14,46 @@
  * General Public License version 2 for more details.
  */
 
-use crate::data::InodeNo;
+use crate::data::{Extent, InodeNo};
 use crate::ops::inodes;
 
-use m3::col::Treap;
-use m3::errors::Error;
+use m3::col::{Treap, Vec};
+use m3::errors::{Code, Error};
+use m3::server::SessId;
+
+/// An advisory lock of a session on the bytes `start`..`end` of a file
+#[derive(Copy, Clone)]
+struct FileLock {
+    sess: SessId,
+    start: usize,
+    end: usize,
+    exclusive: bool,
+}
+
+impl FileLock {
+    fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.start < end && start < self.end
+    }
+}
 
 pub struct OpenFile {
     appending: bool,
+    append_ext: Option<Extent>,
     deleted: bool,
     refs: usize,
+    layout: u64,
+    locks: Vec<FileLock>,
 }
 
 impl OpenFile {
     pub fn new() -> Self {
         OpenFile {
             appending: false,
+            append_ext: None,
             deleted: false,
             refs: 1,
+            layout: 0,
+            locks: Vec::new(),
         }
     }
 
@@ -42,6 +64,77 @@ impl OpenFile {
     pub fn set_appending(&mut self, new: bool) {
         self.appending = new;
     }
+
+    /// Returns the extent that has been allocated for the current append, but is not yet part of
+    /// the inode
+    pub fn append_ext(&self) -> Option<Extent> {
+        self.append_ext
+    }
+
+    pub fn set_append_ext(&mut self, ext: Option<Extent>) {
+        self.append_ext = ext;
+    }
+
+    /// Returns the generation of the file's extent layout, which changes whenever existing extents
+    /// are split, merged, or shortened. Sessions use it to notice that their extent positions are
+    /// outdated.
+    pub fn layout(&self) -> u64 {
+        self.layout
+    }
+
+    pub fn layout_changed(&mut self) {
+        self.layout += 1;
+    }
+
+    /// Acquires a shared or exclusive lock for session `sess` on the bytes `start`..`end`.
+    ///
+    /// Locks of `sess` within this range are replaced by the new lock. Fails with
+    /// [`Code::WouldBlock`] if another session holds a conflicting lock.
+    pub fn lock(
+        &mut self,
+        sess: SessId,
+        start: usize,
+        end: usize,
+        exclusive: bool,
+    ) -> Result<(), Error> {
+        let conflict = self
+            .locks
+            .iter()
+            .any(|l| l.sess != sess && l.overlaps(start, end) && (exclusive || l.exclusive));
+        if conflict {
+            return Err(Error::new(Code::WouldBlock));
+        }
+
+        self.unlock(sess, start, end);
+        self.locks.push(FileLock {
+            sess,
+            start,
+            end,
+            exclusive,
+        });
+        Ok(())
+    }
+
+    /// Releases the locks of session `sess` on the bytes `start`..`end`.
+    ///
+    /// Locks that only partially overlap with this range are shrunk or split accordingly.
+    pub fn unlock(&mut self, sess: SessId, start: usize, end: usize) {
+        let mut locks = Vec::with_capacity(self.locks.len());
+        for l in self.locks.drain(..) {
+            if l.sess != sess || !l.overlaps(start, end) {
+                locks.push(l);
+                continue;
+            }
+
+            if l.start < start {
+                locks.push(FileLock { end: start, ..l });
+            }
+            if l.end > end {
+                locks.push(FileLock { start: end, ..l });
+            }
+        }
+        self.locks = locks;
+    }
 }
 
 pub struct OpenFiles {
@@ -55,6 +148,10 @@ impl OpenFiles {
         }
     }
 
+    pub fn get_file(&self, ino: InodeNo) -> Option<&OpenFile> {
+        self.files.get(&ino)
+    }
+
     pub fn get_file_mut(&mut self, ino: InodeNo) -> Option<&mut OpenFile> {
         self.files.get_mut(&ino)
     }
@@ -80,9 +177,12 @@ impl OpenFiles {
         }
     }
 
-    pub fn remove_session(&mut self, ino: InodeNo) -> Result<(), Error> {
+    pub fn remove_session(&mut self, ino: InodeNo, sess: SessId) -> Result<(), Error> {
         let file = self.get_file_mut(ino).unwrap();
 
+        // locks are released as soon as the session is closed
+        file.unlock(sess, 0, usize::MAX);
+
         // dereference OpenFile instance
         assert!(file.refs > 0);
         file.refs -= 1;
@@ -0,0 +1,131 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::data::InodeNo;
+
+use m3::cap::Selector;
+use m3::col::Vec;
+use m3::com::{RGateArgs, RecvGate, SendGate};
+use m3::errors::{Code, Error};
+use m3::server::SessId;
+use m3::vfs::FileEvent;
+
+/// A watch of a client on a directory.
+///
+/// Events are recorded as soon as the watch exists, but only sent to the client after it requested
+/// them. Thus, events that occur while the client processes the previous notification are not lost.
+struct Watch {
+    sess: SessId,
+    ino: InodeNo,
+    rgate: RecvGate,
+    sgate: SendGate,
+    requested: FileEvent,
+    pending: FileEvent,
+}
+
+impl Watch {
+    fn send_events(&mut self) {
+        let events = self.pending & self.requested;
+        if !events.is_empty() && self.sgate.credits().unwrap() > 0 {
+            log!(
+                crate::LOG_WATCH,
+                "[{}] watch::notify(ino={}, events={:?})",
+                self.sess,
+                self.ino,
+                events
+            );
+            // ignore errors
+            send_vmsg!(&self.sgate, &self.rgate, events.bits()).ok();
+            self.requested &= !events;
+            self.pending &= !events;
+        }
+    }
+}
+
+/// All watches on directories, which are notified about changes by the file system operations.
+pub struct Watches {
+    watches: Vec<Watch>,
+}
+
+impl Watches {
+    pub const fn new() -> Self {
+        Watches {
+            watches: Vec::new(),
+        }
+    }
+
+    /// Returns true if there are no watches, allowing callers to skip collecting events
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+
+    /// Adds a watch for session `sess` on the directory `ino` that notifies the client via the
+    /// send gate `sgate`
+    pub fn add(&mut self, sess: SessId, ino: InodeNo, sgate: Selector) -> Result<(), Error> {
+        if self.watches.iter().any(|w| w.sess == sess) {
+            return Err(Error::new(Code::Exists));
+        }
+
+        let rgate = RecvGate::new_with(RGateArgs::default().order(6).msg_order(6))?;
+        rgate.activate()?;
+
+        log!(crate::LOG_WATCH, "[{}] watch::add(ino={})", sess, ino);
+        self.watches.push(Watch {
+            sess,
+            ino,
+            rgate,
+            sgate: SendGate::new_bind(sgate),
+            requested: FileEvent::empty(),
+            pending: FileEvent::empty(),
+        });
+        Ok(())
+    }
+
+    /// Removes the watch of session `sess`, if any
+    pub fn remove(&mut self, sess: SessId) {
+        self.watches.retain(|w| w.sess != sess);
+    }
+
+    /// Requests a notification for the given events for session `sess`
+    pub fn request(&mut self, sess: SessId, events: FileEvent) -> Result<(), Error> {
+        let watch = self
+            .watches
+            .iter_mut()
+            .find(|w| w.sess == sess)
+            .ok_or_else(|| Error::new(Code::NotSup))?;
+        watch.requested |= events;
+        watch.send_events();
+        Ok(())
+    }
+
+    /// Records `event` for all watches on the directory `ino`
+    pub fn notify(&mut self, ino: InodeNo, event: FileEvent) {
+        for w in self.watches.iter_mut().filter(|w| w.ino == ino) {
+            w.pending |= event;
+            w.send_events();
+        }
+    }
+
+    /// Fetches the acknowledgements of clients and sends the events that have been held back due
+    /// to missing credits
+    pub fn receive_acks(&mut self) {
+        for w in &mut self.watches {
+            if let Ok(msg) = w.rgate.fetch() {
+                w.rgate.ack_msg(msg).unwrap();
+                w.send_events();
+            }
+        }
+    }
+}
@@ -24,16 +24,22 @@ use m3::log;
 use m3::reply_vmsg;
 use m3::serialize::M3Deserializer;
 use m3::server::SessId;
-use m3::session::{MapFlags, ServerSession};
+use m3::session::{Advice, FaultStats, MapFlags, PagerStats, ServerSession, StatsResult};
 use m3::tcu::Label;
 use m3::tiles::Activity;
 use m3::util::math;
 use resmng::childs;
 
-use crate::dataspace::DataSpace;
+use crate::dataspace::{DataSpace, Fault};
+use crate::swap;
 
 const MAX_VIRT_ADDR: goff = cfg::MEM_CAP_END as goff - 1;
 
+/// Returns true if `e` reports that the child's memory quota or the memory pool is exhausted
+fn is_out_of_mem(e: &Error) -> bool {
+    matches!(e.code(), Code::NoSpace | Code::OutOfMem)
+}
+
 pub struct AddrSpace {
     crt: usize,
     parent: Option<SessId>,
@@ -42,6 +48,7 @@ pub struct AddrSpace {
     owner: Option<Selector>,
     sgates: Vec<SendGate>,
     ds: Vec<DataSpace>,
+    faults: FaultStats,
 }
 
 impl AddrSpace {
@@ -59,6 +66,7 @@ impl AddrSpace {
             owner: None,
             sgates: Vec::new(),
             ds: Vec::new(),
+            faults: FaultStats::default(),
         }
     }
 
@@ -189,7 +197,8 @@ impl AddrSpace {
         virt: goff,
         access: Perm,
     ) -> Result<(), Error> {
-        if let Some(ds) = self.find_ds_mut(virt) {
+        if let Some(idx) = self.find_ds_idx(virt) {
+            let ds = &self.ds[idx];
             if (ds.perm() & access) != access {
                 log!(
                     crate::LOG_DEF,
@@ -201,7 +210,27 @@ impl AddrSpace {
                 return Err(Error::new(Code::InvArgs));
             }
 
-            ds.handle_pf(childs, virt)
+            let kind = loop {
+                match self.ds[idx].handle_pf(childs, virt, access) {
+                    // if we are out of memory, make room by swapping out cold regions
+                    Err(e) if is_out_of_mem(&e) && swap::enabled() => {
+                        if !self.swap_out_coldest(childs)? {
+                            return Err(e);
+                        }
+                    },
+                    res => break res?,
+                }
+            };
+
+            let counter = match kind {
+                Fault::Anon => &mut self.faults.anon,
+                Fault::File => &mut self.faults.file,
+                Fault::Cow => &mut self.faults.cow,
+                Fault::Swap => &mut self.faults.swap,
+                Fault::Minor => &mut self.faults.minor,
+            };
+            *counter += 1;
+            Ok(())
         }
         else {
             log!(crate::LOG_DEF, "No dataspace at {:#x}", virt);
@@ -209,6 +238,22 @@ impl AddrSpace {
         }
     }
 
+    /// Swaps out the least recently used region of this address space. Returns false if there is
+    /// no region that can be swapped out.
+    fn swap_out_coldest(&mut self, childs: &mut childs::ChildManager) -> Result<bool, Error> {
+        let coldest = self
+            .ds
+            .iter()
+            .enumerate()
+            .filter_map(|(i, ds)| ds.coldest_region().map(|(r, age)| (i, r, age)))
+            .min_by_key(|(_, _, age)| *age);
+
+        match coldest {
+            Some((ds, reg, _)) => self.ds[ds].swap_out(childs, reg).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
     pub fn map_ds(&mut self, args: &mut M3Deserializer<'_>) -> Result<(Selector, goff), Error> {
         if !self.has_owner() {
             return Err(Error::new(Code::InvArgs));
@@ -358,7 +403,21 @@ impl AddrSpace {
         );
 
         if let Some(idx) = self.find_ds_idx(virt) {
-            self.ds.remove(idx);
+            // remove all dataspaces that have been split from the same mapping
+            let base = self.ds[idx].base();
+            while let Some(idx) = self.ds.iter().position(|ds| ds.base() == base) {
+                let mut ds = self.ds.remove(idx);
+                // the mapping is removed anyway; thus, don't let a failed write back prevent that
+                if let Err(e) = ds.sync() {
+                    log!(
+                        crate::LOG_DEF,
+                        "Unable to write back {:#x}..{:#x}: {}",
+                        ds.virt(),
+                        ds.virt() + ds.size() - 1,
+                        e
+                    );
+                }
+            }
         }
         else {
             log!(crate::LOG_DEF, "No dataspace at {:#x}", virt);
@@ -368,6 +427,163 @@ impl AddrSpace {
         is.reply_error(Code::Success)
     }
 
+    pub fn msync(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let virt: goff = is.pop()?;
+        let len: goff = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::msync(virt={:#x}, len={:#x})",
+            self.id(),
+            virt,
+            len,
+        );
+
+        if !self.overlaps(virt, len) {
+            log!(
+                crate::LOG_DEF,
+                "No dataspace at {:#x}..{:#x}",
+                virt,
+                virt + len
+            );
+            return Err(Error::new(Code::NotFound));
+        }
+
+        for ds in &mut self.ds {
+            if math::overlaps(ds.virt(), ds.virt() + ds.size(), virt, virt + len) {
+                ds.sync_range(virt, len)?;
+            }
+        }
+
+        is.reply_error(Code::Success)
+    }
+
+    pub fn protect(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let virt: goff = is.pop()?;
+        let len: goff = is.pop()?;
+        let perm = Perm::from_bits_truncate(is.pop::<u32>()?);
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::protect(virt={:#x}, len={:#x}, perm={:?})",
+            self.id(),
+            virt,
+            len,
+            perm,
+        );
+
+        let len = self.check_range(virt, len)?;
+        // don't allow more than the mappings have been created with
+        if self.ds.iter().any(|ds| {
+            math::overlaps(ds.virt(), ds.virt() + ds.size(), virt, virt + len)
+                && !ds.max_perms().contains(perm)
+        }) {
+            return Err(Error::new(Code::NoPerm));
+        }
+
+        self.split_range(virt, len);
+        for ds in &mut self.ds {
+            if ds.virt() >= virt && ds.virt() + ds.size() <= virt + len {
+                ds.protect(perm)?;
+            }
+        }
+
+        is.reply_error(Code::Success)
+    }
+
+    pub fn advise(
+        &mut self,
+        childs: &mut childs::ChildManager,
+        is: &mut GateIStream<'_>,
+    ) -> Result<(), Error> {
+        let virt: goff = is.pop()?;
+        let len: goff = is.pop()?;
+        let advice: Advice = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::advise(virt={:#x}, len={:#x}, advice={})",
+            self.id(),
+            virt,
+            len,
+            advice,
+        );
+
+        let len = self.check_range(virt, len)?;
+        match advice {
+            Advice::NORMAL | Advice::SEQUENTIAL => {
+                self.split_range(virt, len);
+                for ds in &mut self.ds {
+                    if ds.virt() >= virt && ds.virt() + ds.size() <= virt + len {
+                        ds.set_sequential(advice == Advice::SEQUENTIAL);
+                    }
+                }
+            },
+
+            Advice::WILLNEED => {
+                for ds in &mut self.ds {
+                    if math::overlaps(ds.virt(), ds.virt() + ds.size(), virt, virt + len) {
+                        match ds.prefetch(childs, virt, len) {
+                            // it's just a hint; thus, stop if we are out of memory
+                            Err(e) if is_out_of_mem(&e) => break,
+                            res => res?,
+                        }
+                    }
+                }
+            },
+
+            Advice::DONTNEED => {
+                for ds in &mut self.ds {
+                    if math::overlaps(ds.virt(), ds.virt() + ds.size(), virt, virt + len) {
+                        ds.discard(childs, virt, len)?;
+                    }
+                }
+            },
+
+            _ => return Err(Error::new(Code::InvArgs)),
+        }
+
+        is.reply_error(Code::Success)
+    }
+
+    pub fn stats(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let idx: usize = is.pop()?;
+
+        log!(crate::LOG_DEF, "[{}] pager::stats(idx={})", self.id(), idx);
+
+        let res = match idx {
+            usize::MAX => {
+                let mut stats = PagerStats {
+                    dataspaces: self.ds.len(),
+                    faults: self.faults.clone(),
+                    ..Default::default()
+                };
+                for ds in &self.ds {
+                    let info = ds.info();
+                    stats.virt_size += info.size;
+                    stats.resident += info.resident;
+                    stats.cow += info.cow;
+                    stats.swapped += info.swapped;
+                    if info.file {
+                        stats.file += info.resident;
+                    }
+                    else {
+                        stats.anon += info.resident;
+                    }
+                }
+                StatsResult::Stats(stats)
+            },
+            n => StatsResult::DataSpace(
+                self.ds
+                    .get(n)
+                    .ok_or_else(|| Error::new(Code::NotFound))?
+                    .info(),
+            ),
+        };
+
+        reply_vmsg!(is, Code::Success, res)
+    }
+
     pub fn close(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
         log!(crate::LOG_DEF, "[{}] pager::close()", self.id());
 
@@ -391,8 +607,45 @@ impl AddrSpace {
         Ok(())
     }
 
-    fn find_ds_mut(&mut self, virt: goff) -> Option<&mut DataSpace> {
-        self.find_ds_idx(virt).map(move |idx| &mut self.ds[idx])
+    /// Checks whether `virt`..`virt + len` is completely covered by dataspaces and returns the
+    /// length rounded up to pages
+    fn check_range(&self, virt: goff, len: goff) -> Result<goff, Error> {
+        if !self.has_owner() || !math::is_aligned(virt, cfg::PAGE_SIZE as goff) || len == 0 {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let len = math::round_up(len, cfg::PAGE_SIZE as goff);
+        let covered: goff = self
+            .ds
+            .iter()
+            .map(|ds| {
+                let start = virt.max(ds.virt());
+                let end = (virt + len).min(ds.virt() + ds.size());
+                end.saturating_sub(start)
+            })
+            .sum();
+        if covered != len {
+            log!(
+                crate::LOG_DEF,
+                "No dataspace at {:#x}..{:#x}",
+                virt,
+                virt + len
+            );
+            return Err(Error::new(Code::NotFound));
+        }
+        Ok(len)
+    }
+
+    /// Splits the dataspaces at the borders of `virt`..`virt + len`
+    fn split_range(&mut self, virt: goff, len: goff) {
+        for at in [virt, virt + len] {
+            if let Some(idx) = self.find_ds_idx(at) {
+                if self.ds[idx].virt() != at {
+                    let nds = self.ds[idx].split(at);
+                    self.ds.push(nds);
+                }
+            }
+        }
     }
 
     fn find_ds_idx(&self, virt: goff) -> Option<usize> {
@@ -420,6 +673,16 @@ impl Drop for AddrSpace {
         // the activity is destroyed anyway, therefore we don't need to do that.
         for ds in &mut self.ds {
             ds.kill();
+            // write back modified file mappings, which works without the activity
+            if let Err(e) = ds.sync() {
+                log!(
+                    crate::LOG_DEF,
+                    "Unable to write back {:#x}..{:#x}: {}",
+                    ds.virt(),
+                    ds.virt() + ds.size() - 1,
+                    e
+                );
+            }
         }
     }
 }
@@ -13,6 +13,7 @@
  * General Public License version 2 for more details.
  */
 
+use core::cmp;
 use m3::cap::Selector;
 use m3::cell::{RefCell, StaticCell};
 use m3::cfg;
@@ -22,7 +23,7 @@ use m3::goff;
 use m3::kif;
 use m3::log;
 use m3::rc::Rc;
-use m3::session::{ClientSession, MapFlags, M3FS};
+use m3::session::{ClientSession, DataSpaceInfo, MapFlags, M3FS};
 use m3::util::math;
 use resmng::childs;
 
@@ -31,6 +32,23 @@ use crate::regions::RegionList;
 
 const MAX_ANON_PAGES: usize = 4;
 const MAX_EXT_PAGES: usize = 8;
+// the number of pages to fault in at once for sequential accesses
+const MAX_SEQ_PAGES: usize = 64;
+
+/// The kinds of page faults
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Fault {
+    /// Anonymous memory has been allocated
+    Anon,
+    /// Memory has been obtained from the file
+    File,
+    /// Copy-on-write has been resolved
+    Cow,
+    /// Memory has been read back from swap
+    Swap,
+    /// Present memory has been (re)mapped
+    Minor,
+}
 
 static NEXT_ID: StaticCell<u64> = StaticCell::new(0);
 
@@ -69,10 +87,13 @@ pub struct DataSpace {
     virt: goff,
     size: goff,
     perms: kif::Perm,
+    // the permissions the dataspace has been created with
+    max_perms: kif::Perm,
     flags: MapFlags,
     regions: RegionList,
     owner: Selector,
     file: Option<FileMapping>,
+    sequential: bool,
 }
 
 impl DataSpace {
@@ -93,10 +114,12 @@ impl DataSpace {
             virt,
             size,
             perms,
+            max_perms: perms,
             flags,
             owner,
             regions: RegionList::new(owner, child, virt, size),
             file: Some(FileMapping::new(sel, off)),
+            sequential: false,
         }
     }
 
@@ -114,10 +137,12 @@ impl DataSpace {
             virt,
             size,
             perms,
+            max_perms: perms,
             flags,
             owner,
             regions: RegionList::new(owner, child, virt, size),
             file: None,
+            sequential: false,
         }
     }
 
@@ -128,10 +153,12 @@ impl DataSpace {
             virt: self.virt,
             size: self.size,
             perms: self.perms,
+            max_perms: self.max_perms,
             flags: self.flags,
             owner,
-            regions: RegionList::new(owner, self.child, self.virt, self.size),
+            regions: self.regions.new_like(owner),
             file: self.file.clone(),
+            sequential: self.sequential,
         }
     }
 
@@ -147,19 +174,51 @@ impl DataSpace {
         self.size
     }
 
+    /// Returns the start of the mapping this dataspace has been created for, which differs from
+    /// `virt` if it has been split
+    pub fn base(&self) -> goff {
+        self.regions.base()
+    }
+
     pub fn perm(&self) -> kif::Perm {
         self.perms
     }
 
+    pub fn info(&self) -> DataSpaceInfo {
+        let mut info = DataSpaceInfo {
+            virt: self.virt,
+            size: self.size,
+            perm: self.perms,
+            flags: self.flags,
+            file: self.file.is_some(),
+            resident: 0,
+            cow: 0,
+            swapped: 0,
+        };
+        for reg in self.regions.iter() {
+            let pages = (reg.size() / cfg::PAGE_SIZE as goff) as usize;
+            if reg.has_mem() {
+                info.resident += pages;
+                if reg.is_cow() {
+                    info.cow += pages;
+                }
+            }
+            else if reg.is_swapped() {
+                info.swapped += pages;
+            }
+        }
+        info
+    }
+
     pub fn inherit(&mut self, ds: &mut DataSpace) -> Result<(), Error> {
         self.id = ds.id;
 
         // if it's not writable, but we have already regions, we can simply keep them
-        if !ds.perms.contains(kif::Perm::W) && !self.regions.is_empty() {
+        let ds_perm = ds.cow_perm();
+        if !ds_perm.contains(kif::Perm::W) && !self.regions.is_empty() {
             return Ok(());
         }
 
-        let ds_perm = ds.perm();
         self.regions.clone(&mut ds.regions, ds_perm)
     }
 
@@ -167,23 +226,71 @@ impl DataSpace {
         self.regions.populate(sel);
     }
 
+    /// Returns true if modifications are written back to the file, which requires a shared and
+    /// writable file mapping
+    fn writes_back(&self) -> bool {
+        self.file.is_some()
+            && self.flags.contains(MapFlags::SHARED)
+            && self.perms.contains(kif::Perm::W)
+    }
+
+    /// Returns the permissions that determine whether regions are copied on write. Shared
+    /// memory is never copied.
+    fn cow_perm(&self) -> kif::Perm {
+        if self.flags.contains(MapFlags::SHARED) {
+            self.perms & !kif::Perm::W
+        }
+        else {
+            self.perms
+        }
+    }
+
     pub fn handle_pf(
         &mut self,
         childs: &mut childs::ChildManager,
         virt: goff,
-    ) -> Result<(), Error> {
-        let pf_off = math::round_dn(virt - self.virt, cfg::PAGE_SIZE as goff);
+        access: kif::Perm,
+    ) -> Result<Fault, Error> {
+        self.fault(childs, virt, access, None).map(|(_, kind)| kind)
+    }
+
+    /// Resolves a fault at `virt` for `access`. If `window` is given, the region is limited to
+    /// the offsets `window.0`..`window.1` instead of the default number of pages.
+    ///
+    /// Returns the end of the region that contains `virt` and the kind of fault.
+    fn fault(
+        &mut self,
+        childs: &mut childs::ChildManager,
+        virt: goff,
+        access: kif::Perm,
+        window: Option<(goff, goff)>,
+    ) -> Result<(goff, Fault), Error> {
+        let base = self.base();
+        let pf_off = math::round_dn(virt - base, cfg::PAGE_SIZE as goff);
+        let writes_back = self.writes_back();
+        let cow_perm = self.cow_perm();
         let reg = self.regions.pagefault(pf_off);
+        reg.touch();
 
+        let mut kind = Fault::Minor;
         // if it isn't backed with memory yet, allocate memory for it
         if !reg.has_mem() {
-            if let Some(ref f) = self.file {
+            // if it has been swapped out, read it back
+            if reg.is_swapped() {
+                reg.swap_in(childs)?;
+                kind = Fault::Swap;
+            }
+            else if let Some(ref f) = self.file {
                 // get memory cap for the region
                 // TODO add a cache for that; we request the same caps over and over again
                 let (off, len, sel) = M3FS::get_mem(&f.sess, f.offset + pf_off)?;
 
                 // first, resize the region to not be too large
-                reg.limit_to(pf_off, MAX_EXT_PAGES as goff);
+                match window {
+                    Some((start, end)) => reg.limit_range(start, end),
+                    None if self.sequential => reg.limit_to(pf_off, MAX_SEQ_PAGES as goff),
+                    None => reg.limit_to(pf_off, MAX_EXT_PAGES as goff),
+                }
 
                 // now, align the region with the memory capability that we got
                 let cap_begin = f.offset + pf_off - off;
@@ -203,21 +310,27 @@ impl DataSpace {
                     reg.set_size(math::round_up(len - reg.mem_off(), cfg::PAGE_SIZE as goff));
                 }
 
-                // if it's writable and should not be shared, create a copy
-                if !self.flags.contains(MapFlags::SHARED) && self.perms.contains(kif::Perm::W) {
+                // if it can become writable and should not be shared, create a copy. Note that we
+                // need to consider the maximum permissions here, because the region might be
+                // made writable later via protect, which would then write into the file.
+                if !self.flags.contains(MapFlags::SHARED) && self.max_perms.contains(kif::Perm::W) {
                     let src = MemGate::new_owned_bind(sel);
                     let child = childs
                         .child_by_id_mut(self.child)
                         .ok_or_else(|| Error::new(Code::ActivityGone))?;
-                    let mgate = child.alloc_local(reg.size(), kif::Perm::RWX)?;
-                    let mem = Rc::new(RefCell::new(PhysMem::new((self.owner, self.virt), mgate)?));
+                    let (mgate, alloc) = child.alloc_local(reg.size(), kif::Perm::RWX)?;
+                    let mem = Rc::new(RefCell::new(PhysMem::new(
+                        (self.owner, base),
+                        mgate,
+                        alloc,
+                    )?));
                     reg.set_mem(mem);
                     reg.copy_from(&src);
                     reg.set_mem_off(0);
                 }
                 else {
                     reg.set_mem(Rc::new(RefCell::new(PhysMem::new_bind(
-                        (self.owner, self.virt),
+                        (self.owner, base),
                         sel,
                     ))));
                 }
@@ -228,6 +341,7 @@ impl DataSpace {
                     reg.virt(),
                     reg.virt() + reg.size() - 1
                 );
+                kind = Fault::File;
             }
             else {
                 let max = if !self.flags.contains(MapFlags::NOLPAGE)
@@ -236,12 +350,18 @@ impl DataSpace {
                 {
                     cfg::LPAGE_SIZE / cfg::PAGE_SIZE
                 }
+                else if self.sequential {
+                    MAX_SEQ_PAGES
+                }
                 else {
                     MAX_ANON_PAGES
                 };
 
                 // don't allocate too much at once
-                reg.limit_to(pf_off, max as goff);
+                match window {
+                    Some((start, end)) => reg.limit_range(start, end),
+                    None => reg.limit_to(pf_off, max as goff),
+                }
 
                 log!(
                     crate::LOG_DEF,
@@ -253,28 +373,179 @@ impl DataSpace {
                 let child = childs
                     .child_by_id_mut(self.child)
                     .ok_or_else(|| Error::new(Code::ActivityGone))?;
-                let mgate = child.alloc_local(reg.size(), kif::Perm::RWX)?;
+                let (mgate, alloc) = child.alloc_local(reg.size(), kif::Perm::RWX)?;
                 reg.set_mem(Rc::new(RefCell::new(PhysMem::new(
-                    (self.owner, self.virt),
+                    (self.owner, base),
                     mgate,
+                    alloc,
                 )?)));
 
                 if !self.flags.contains(MapFlags::UNINIT) {
                     // zero the memory
                     reg.clear();
                 }
+                kind = Fault::Anon;
             }
         }
         // if we have memory, but COW is in progress
         else if reg.is_cow() {
-            reg.handle_cow(childs, self.perms)?;
+            reg.handle_cow(childs, cow_perm)?;
+            kind = Fault::Cow;
+        }
+        // nothing to do, unless it's a write to a region that we need to write back
+        else if reg.is_mapped() && !(writes_back && access.contains(kif::Perm::W)) {
+            return Ok((base + reg.offset() + reg.size(), kind));
+        }
+
+        // map regions that are written back read-only until the first write to track modifications
+        if writes_back && access.contains(kif::Perm::W) {
+            reg.set_dirty();
+        }
+        reg.map(reg.map_perm(self.perms, writes_back))?;
+        Ok((base + reg.offset() + reg.size(), kind))
+    }
+
+    /// Writes all modified regions back to the file
+    pub fn sync(&mut self) -> Result<(), Error> {
+        self.sync_range(self.virt, self.size)
+    }
+
+    /// Writes the modified regions within `virt`..`virt + len` back to the file
+    pub fn sync_range(&mut self, virt: goff, len: goff) -> Result<(), Error> {
+        if !self.writes_back() {
+            return Ok(());
+        }
+
+        let f = self.file.as_ref().unwrap();
+        let ro_perm = self.perms & !kif::Perm::W;
+        for reg in self.regions.iter_mut().filter(|r| {
+            r.is_dirty() && math::overlaps(r.virt(), r.virt() + r.size(), virt, virt + len)
+        }) {
+            log!(
+                crate::LOG_DEF,
+                "Writing back {:#x}..{:#x}",
+                reg.virt(),
+                reg.virt() + reg.size() - 1
+            );
+
+            // write-protect it first so that we notice writes during the write back
+            reg.clean(ro_perm)?;
+            if let Err(e) = M3FS::sync_mem(&f.sess, f.offset + reg.offset(), reg.size()) {
+                reg.set_dirty();
+                return Err(e);
+            }
         }
-        else if reg.is_mapped() {
-            // nothing to do
+        Ok(())
+    }
+
+    /// Splits this dataspace at `virt`, keeping the part before `virt` and returning the rest
+    pub fn split(&mut self, virt: goff) -> DataSpace {
+        let regions = self.regions.split(virt - self.base());
+        let nds = DataSpace {
+            id: alloc_id(),
+            child: self.child,
+            virt,
+            size: self.virt + self.size - virt,
+            perms: self.perms,
+            max_perms: self.max_perms,
+            flags: self.flags,
+            owner: self.owner,
+            regions,
+            file: self.file.clone(),
+            sequential: self.sequential,
+        };
+
+        // both parts are different dataspaces now, also for the clones of this address space
+        self.id = alloc_id();
+        self.size = virt - self.virt;
+        nds
+    }
+
+    /// Returns the permissions this dataspace has been created with, which are the upper bound
+    /// for the permissions that can be set via `protect`
+    pub fn max_perms(&self) -> kif::Perm {
+        self.max_perms
+    }
+
+    /// Changes the permissions of this dataspace to `perm` and remaps the present regions
+    pub fn protect(&mut self, perm: kif::Perm) -> Result<(), Error> {
+        // we will no longer notice writes without write permission; thus write back now
+        if !perm.contains(kif::Perm::W) {
+            self.sync()?;
+        }
+
+        self.perms = perm;
+        let writes_back = self.writes_back();
+        for reg in self.regions.iter_mut().filter(|r| r.is_mapped()) {
+            if perm.is_empty() {
+                reg.unmap();
+            }
+            else {
+                reg.map(reg.map_perm(perm, writes_back))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_sequential(&mut self, seq: bool) {
+        self.sequential = seq;
+    }
+
+    /// Faults in all pages within `virt`..`virt + len`
+    pub fn prefetch(
+        &mut self,
+        childs: &mut childs::ChildManager,
+        virt: goff,
+        len: goff,
+    ) -> Result<(), Error> {
+        // without permissions, there is nothing to map
+        if self.perms.is_empty() {
             return Ok(());
         }
 
-        reg.map(self.perms)
+        let base = self.base();
+        let start = math::round_dn(cmp::max(virt, self.virt), cfg::PAGE_SIZE as goff);
+        let end = math::round_up(
+            cmp::min(virt + len, self.virt + self.size),
+            cfg::PAGE_SIZE as goff,
+        );
+        let window = (start - base, end - base);
+
+        let mut cur = start;
+        while cur < end {
+            cur = self.fault(childs, cur, kif::Perm::empty(), Some(window))?.0;
+        }
+        Ok(())
+    }
+
+    /// Frees the pages within `virt`..`virt + len`, so that they are zeroed or loaded from the
+    /// file again on the next access
+    pub fn discard(
+        &mut self,
+        childs: &mut childs::ChildManager,
+        virt: goff,
+        len: goff,
+    ) -> Result<(), Error> {
+        // don't lose modifications of shared file mappings
+        self.sync_range(virt, len)?;
+
+        let base = self.base();
+        let start = cmp::max(virt, self.virt);
+        let end = cmp::min(virt + len, self.virt + self.size);
+        self.regions.discard(childs, start - base, end - base)
+    }
+
+    /// Returns the index and age of the least recently used region that can be swapped out
+    pub fn coldest_region(&self) -> Option<(usize, u64)> {
+        // only anonymous memory is swapped; file mappings are backed by the file system
+        match self.file {
+            Some(_) => None,
+            None => self.regions.coldest(),
+        }
+    }
+
+    pub fn swap_out(&mut self, childs: &mut childs::ChildManager, idx: usize) -> Result<(), Error> {
+        self.regions.swap_out(childs, idx)
     }
 
     pub fn kill(&mut self) {
@@ -20,6 +20,7 @@ mod dataspace;
 mod mapper;
 mod physmem;
 mod regions;
+mod swap;
 
 use core::ops::DerefMut;
 
@@ -36,7 +37,7 @@ use m3::server::{CapExchange, Handler, RequestHandler, Server, SessId, SessionCo
 use m3::session::{ClientSession, Pager, PagerOp, ResMng, M3FS};
 use m3::tcu::Label;
 use m3::tiles::{Activity, ActivityArgs, ChildActivity};
-use m3::util::math;
+use m3::util::{math, parse};
 use m3::vfs;
 
 use addrspace::AddrSpace;
@@ -49,6 +50,10 @@ use resmng::sendqueue;
 use resmng::subsys;
 
 pub const LOG_DEF: bool = false;
+pub const LOG_SWAP: bool = false;
+
+// the default size of the swap space
+const DEF_SWAP_SIZE: usize = 16 * 1024 * 1024;
 
 static PGHDL: LazyStaticRefCell<PagerReqHandler> = LazyStaticRefCell::default();
 static REQHDL: LazyReadOnlyCell<RequestHandler> = LazyReadOnlyCell::default();
@@ -166,6 +171,26 @@ fn get_mount(name: &str) -> Result<String, VerboseError> {
     Ok(our_path)
 }
 
+fn init_swap(subsys: &subsys::Subsystem) {
+    let mut cfg = None;
+    let mut size = DEF_SWAP_SIZE;
+    for arg in subsys.cfg().args() {
+        if let Some(path) = arg.strip_prefix("swapfile=") {
+            cfg = Some(swap::SwapConfig::File(path));
+        }
+        else if let Some(sess) = arg.strip_prefix("swapdisk=") {
+            cfg = Some(swap::SwapConfig::Disk(sess));
+        }
+        else if let Some(s) = arg.strip_prefix("swapsize=") {
+            size = parse::size(s).expect("Failed to parse swap size");
+        }
+    }
+
+    if let Some(cfg) = cfg {
+        swap::init(cfg, size).expect("Unable to initialize swap space");
+    }
+}
+
 struct PagedChildStarter {}
 
 impl subsys::ChildStarter for PagedChildStarter {
@@ -204,7 +229,8 @@ impl subsys::ChildStarter for PagedChildStarter {
             ActivityArgs::new(child.name())
                 .resmng(ResMng::new(resmng_sgate))
                 .pager(Pager::new(sess, pager_sgate, child_sgate)?)
-                .kmem(child.kmem().unwrap()),
+                .kmem(child.kmem().unwrap())
+                .prio(child.cfg().prio().unwrap_or(kif::Priority::NORMAL)),
         )?;
 
         // pass subsystem info to child, if it's a subsystem
@@ -285,6 +311,10 @@ fn handle_request(
             PagerOp::PAGEFAULT => aspace.pagefault(childs, is),
             PagerOp::MAP_ANON => aspace.map_anon(is),
             PagerOp::UNMAP => aspace.unmap(is),
+            PagerOp::MSYNC => aspace.msync(is),
+            PagerOp::PROTECT => aspace.protect(is),
+            PagerOp::ADVISE => aspace.advise(childs, is),
+            PagerOp::STATS => aspace.stats(is),
             PagerOp::CLOSE => aspace
                 .close(is)
                 .map(|_| hdl.close_sess(0, is.label() as SessId, is.rgate())),
@@ -348,6 +378,9 @@ pub fn main() -> Result<(), Error> {
         .borrow_mut()
         .push(("m3fs".to_string(), "/".to_string()));
 
+    // swap out anonymous memory if requested and the childs run out of memory
+    init_swap(&subsys);
+
     // create server
     let mut hdl = PagerReqHandler {
         sel: 0,
@@ -20,6 +20,7 @@ use m3::com::MemGate;
 use m3::errors::Error;
 use m3::goff;
 use m3::mem;
+use resmng::resources::memory::Allocation;
 
 static ZEROS: mem::AlignedBuf<{ cfg::PAGE_SIZE }> = mem::AlignedBuf::new_zeroed();
 static BUF: StaticRefCell<mem::AlignedBuf<{ cfg::PAGE_SIZE }>> =
@@ -44,20 +45,32 @@ fn clear_block(mem: &MemGate, size: goff) {
 
 pub struct PhysMem {
     mgate: MemGate,
+    // the allocation from the child's memory pool, if we allocated the memory
+    alloc: Option<Allocation>,
     owner_mem: Option<(Selector, goff)>,
 }
 
 impl PhysMem {
-    pub fn new(owner_mem: (Selector, goff), mem: MemGate) -> Result<Self, Error> {
+    pub fn new(
+        owner_mem: (Selector, goff),
+        mem: MemGate,
+        alloc: Allocation,
+    ) -> Result<Self, Error> {
         Ok(PhysMem {
             mgate: mem,
+            alloc: Some(alloc),
             owner_mem: Some(owner_mem),
         })
     }
 
-    pub fn new_with_mem(owner_mem: (Selector, goff), mem: MemGate) -> Self {
+    pub fn new_with_mem(
+        owner_mem: (Selector, goff),
+        mem: MemGate,
+        alloc: Option<Allocation>,
+    ) -> Self {
         PhysMem {
             mgate: mem,
+            alloc,
             owner_mem: Some(owner_mem),
         }
     }
@@ -65,6 +78,7 @@ impl PhysMem {
     pub fn new_bind(owner_mem: (Selector, goff), sel: Selector) -> Self {
         PhysMem {
             mgate: MemGate::new_bind(sel),
+            alloc: None,
             owner_mem: Some(owner_mem),
         }
     }
@@ -77,8 +91,19 @@ impl PhysMem {
         self.mgate.deactivate();
     }
 
-    pub fn replace_gate(&mut self, mem: MemGate) -> MemGate {
-        mem::replace(&mut self.mgate, mem)
+    pub fn replace_gate(
+        &mut self,
+        mem: MemGate,
+        alloc: Option<Allocation>,
+    ) -> (MemGate, Option<Allocation>) {
+        (
+            mem::replace(&mut self.mgate, mem),
+            mem::replace(&mut self.alloc, alloc),
+        )
+    }
+
+    pub fn alloc(&self) -> Option<Allocation> {
+        self.alloc
     }
 
     pub fn owner_mem(&self) -> Option<(Selector, goff)> {
@@ -18,7 +18,7 @@ use core::cmp;
 use core::fmt;
 use m3::boxed::Box;
 use m3::cap::Selector;
-use m3::cell::RefCell;
+use m3::cell::{RefCell, StaticCell};
 use m3::cfg;
 use m3::col::Vec;
 use m3::com::MemGate;
@@ -31,11 +31,18 @@ use m3::syscalls;
 use resmng::childs;
 
 use crate::physmem::{copy_block, PhysMem};
+use crate::swap::SwapSlots;
+
+// the clock for the ages of regions; ticks on every page fault
+static CLOCK: StaticCell<u64> = StaticCell::new(0);
 
 bitflags! {
     struct RegionFlags : u64 {
         const MAPPED = 0x1;
         const COW    = 0x2;
+        const DIRTY  = 0x4;
+        // the region covers only a part of its memory or swap slots
+        const PARTIAL = 0x8;
     }
 }
 
@@ -49,6 +56,9 @@ pub struct Region {
     size: goff,
     perm: Perm,
     flags: RegionFlags,
+    // the time of the last page fault in this region
+    age: u64,
+    swapped: Option<Rc<SwapSlots>>,
 }
 
 impl Region {
@@ -63,6 +73,8 @@ impl Region {
             size,
             perm: Perm::empty(),
             flags: RegionFlags::empty(),
+            age: 0,
+            swapped: None,
         }
     }
 
@@ -77,6 +89,8 @@ impl Region {
             size: self.size,
             perm: self.perm,
             flags: self.flags,
+            age: self.age,
+            swapped: self.swapped.clone(),
         }
     }
 
@@ -124,6 +138,129 @@ impl Region {
         self.flags.contains(RegionFlags::COW)
     }
 
+    pub fn is_dirty(&self) -> bool {
+        self.flags.contains(RegionFlags::DIRTY)
+    }
+
+    pub fn set_dirty(&mut self) {
+        self.flags.insert(RegionFlags::DIRTY);
+    }
+
+    /// Returns the permissions to map this region with, given the permissions `ds_perm` of its
+    /// dataspace. Write permission is withheld if we need to notice the next write to it.
+    pub fn map_perm(&self, ds_perm: Perm, track_writes: bool) -> Perm {
+        if self.is_cow() || (track_writes && !self.is_dirty()) {
+            ds_perm & !Perm::W
+        }
+        else {
+            ds_perm
+        }
+    }
+
+    /// Clears the dirty state and maps the region with `perm`, which should not contain write
+    /// permission, so that the next write is noticed again
+    pub fn clean(&mut self, perm: Perm) -> Result<(), Error> {
+        if self.is_mapped() {
+            self.map(perm)?;
+        }
+        self.flags.remove(RegionFlags::DIRTY);
+        Ok(())
+    }
+
+    pub fn is_swapped(&self) -> bool {
+        self.swapped.is_some()
+    }
+
+    pub fn age(&self) -> u64 {
+        self.age
+    }
+
+    pub fn touch(&mut self) {
+        let now = CLOCK.get() + 1;
+        CLOCK.set(now);
+        self.age = now;
+    }
+
+    /// Returns true if the memory of this region can be swapped out, which requires that we have
+    /// allocated the memory and nobody else uses it.
+    pub fn is_evictable(&self) -> bool {
+        match self.mem {
+            Some(ref mem) => {
+                !self.is_cow() && Rc::strong_count(mem) == 1 && mem.borrow().alloc().is_some()
+            },
+            None => false,
+        }
+    }
+
+    /// Writes the memory of this region to the swap space and gives the memory back to the child
+    pub fn swap_out(&mut self, childs: &mut childs::ChildManager) -> Result<(), Error> {
+        log!(
+            crate::LOG_SWAP,
+            "Swapping out {:#x}..{:#x} (age {})",
+            self.virt(),
+            self.virt() + self.size - 1,
+            self.age
+        );
+
+        // unmap it first to prevent changes while we write it to the swap space
+        self.unmap();
+
+        let slots = {
+            let mem = self.mem.as_ref().unwrap().borrow();
+            SwapSlots::swap_out(
+                mem.gate(),
+                self.mem_off,
+                (self.size >> cfg::PAGE_BITS) as usize,
+            )?
+        };
+
+        let alloc = self.mem.take().unwrap().borrow().alloc().unwrap();
+        self.mem_off = 0;
+        self.swapped = Some(Rc::new(slots));
+
+        let child = childs
+            .child_by_id_mut(self.child)
+            .ok_or_else(|| Error::new(Code::ActivityGone))?;
+        child.free_local(alloc)
+    }
+
+    /// Allocates new memory for this region and reads its content back from the swap space
+    pub fn swap_in(&mut self, childs: &mut childs::ChildManager) -> Result<(), Error> {
+        log!(
+            crate::LOG_SWAP,
+            "Swapping in {:#x}..{:#x}",
+            self.virt(),
+            self.virt() + self.size - 1
+        );
+
+        let child = childs
+            .child_by_id_mut(self.child)
+            .ok_or_else(|| Error::new(Code::ActivityGone))?;
+        let (mgate, alloc) = child.alloc_local(self.size, Perm::RWX)?;
+
+        let mut mem = PhysMem::new_with_mem((self.owner, self.ds_off), mgate, Some(alloc));
+        // for swapped regions, the memory offset refers to the swap slots
+        let res = self.swapped.as_ref().unwrap().swap_in(
+            mem.gate(),
+            (self.mem_off >> cfg::PAGE_BITS) as usize,
+            (self.size >> cfg::PAGE_BITS) as usize,
+        );
+        if let Err(e) = res {
+            drop(mem);
+            child.free_local(alloc).ok();
+            return Err(e);
+        }
+        // see below
+        mem.deactivate();
+
+        self.mem = Some(Rc::new(RefCell::new(mem)));
+        self.mem_off = 0;
+        // the memory is our own copy now; the slots are free'd as soon as nobody uses them anymore
+        self.swapped = None;
+        self.flags.remove(RegionFlags::COW | RegionFlags::PARTIAL);
+        Ok(())
+    }
+
     pub fn handle_cow(
         &mut self,
         childs: &mut childs::ChildManager,
@@ -157,7 +294,14 @@ impl Region {
                 let child = childs
                     .child_by_id_mut(self.child)
                     .ok_or_else(|| Error::new(Code::ActivityGone))?;
-                let mut ngate = child.alloc_local(self.size, Perm::RWX)?;
+                let (mut ngate, nalloc) = match child.alloc_local(self.size, Perm::RWX) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        // try again on the next page fault
+                        self.flags.insert(RegionFlags::COW);
+                        return Err(e);
+                    },
+                };
 
                 log!(
                     crate::LOG_DEF,
@@ -186,13 +330,13 @@ impl Region {
                     copy_block(&omem, &ngate, 0, self.size);
                 }
 
-                // are we the owner?
-                if self.owner == osel {
+                // are we the owner? if we cover only a part of the memory, the others keep it
+                if self.owner == osel && !self.flags.contains(RegionFlags::PARTIAL) {
                     // deactivate the MemGate, because we'll probably not need it again
                     ngate.deactivate();
 
                     // give the others the new memory gate
-                    let old = mem.replace_gate(ngate);
+                    let (old, old_alloc) = mem.replace_gate(ngate, Some(nalloc));
                     let owner_virt = mem.owner_mem().unwrap().1;
                     // there is no owner anymore
                     mem.remove_owner();
@@ -200,13 +344,21 @@ impl Region {
                     Rc::new(RefCell::new(PhysMem::new_with_mem(
                         (self.owner, owner_virt),
                         old,
+                        old_alloc,
                     )))
                 }
                 else {
-                    // the others keep the old mem; we take the new one
+                    // the others keep the old mem; we take the new one. if we were the owner, they
+                    // can no longer copy from our memory.
+                    if self.owner == osel {
+                        mem.remove_owner();
+                    }
+                    self.mem_off = 0;
+                    self.flags.remove(RegionFlags::PARTIAL);
                     Rc::new(RefCell::new(PhysMem::new_with_mem(
                         (self.owner, self.ds_off),
                         ngate,
+                        Some(nalloc),
                     )))
                 }
             };
@@ -219,6 +371,49 @@ impl Region {
         Ok(())
     }
 
+    /// Splits this region at offset `off`, keeping the part before `off` and returning the rest.
+    /// Both parts share the memory or the swap slots.
+    pub fn split(&mut self, off: goff) -> Box<Region> {
+        // mapping capabilities cannot be split; the parts are mapped again on the next page fault
+        self.unmap();
+
+        let diff = off - self.off;
+        let nreg = Box::new(Region {
+            owner: self.owner,
+            child: self.child,
+            mem: self.mem.clone(),
+            mem_off: self.mem_off + diff,
+            ds_off: self.ds_off,
+            off,
+            size: self.size - diff,
+            perm: self.perm,
+            flags: self.flags | RegionFlags::PARTIAL,
+            age: self.age,
+            swapped: self.swapped.clone(),
+        });
+
+        self.size = diff;
+        self.flags.insert(RegionFlags::PARTIAL);
+        nreg
+    }
+
+    /// Removes this region and gives its memory back to the child, if nobody else uses it
+    pub fn free(mut self, childs: &mut childs::ChildManager) -> Result<(), Error> {
+        self.unmap();
+
+        let alloc = match self.mem.take().map(Rc::try_unwrap) {
+            Some(Ok(mem)) => mem.into_inner().alloc(),
+            _ => None,
+        };
+        match alloc {
+            Some(alloc) => childs
+                .child_by_id_mut(self.child)
+                .ok_or_else(|| Error::new(Code::ActivityGone))?
+                .free_local(alloc),
+            None => Ok(()),
+        }
+    }
+
     pub fn limit_to(&mut self, pos: goff, pages: goff) {
         if self.size > pages * cfg::PAGE_SIZE as goff {
             let end = self.off + self.size;
@@ -229,6 +424,13 @@ impl Region {
         }
     }
 
+    /// Limits the region to the offsets `start`..`end`
+    pub fn limit_range(&mut self, start: goff, end: goff) {
+        let end = cmp::min(self.off + self.size, end);
+        self.off = cmp::max(self.off, start);
+        self.size = end - self.off;
+    }
+
     pub fn copy_from(&self, src: &MemGate) {
         if let Some(ref mem) = self.mem {
             copy_block(src, mem.borrow().gate(), self.mem_off, self.size());
@@ -264,10 +466,8 @@ impl Region {
         // don't revoke the mapping caps, if the address space got destroyed
         self.flags.remove(RegionFlags::MAPPED);
     }
-}
 
-impl Drop for Region {
-    fn drop(&mut self) {
+    pub fn unmap(&mut self) {
         if self.mem.is_some() && self.flags.contains(RegionFlags::MAPPED) {
             syscalls::revoke(
                 self.owner,
@@ -279,10 +479,17 @@ impl Drop for Region {
                 true,
             )
             .ok();
+            self.flags.remove(RegionFlags::MAPPED);
         }
     }
 }
 
+impl Drop for Region {
+    fn drop(&mut self) {
+        self.unmap();
+    }
+}
+
 impl fmt::Debug for Region {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
@@ -299,7 +506,9 @@ pub struct RegionList {
     owner: Selector,
     child: childs::Id,
     ds_off: goff,
-    size: goff,
+    // the range of offsets (relative to ds_off) that is covered by this list
+    start: goff,
+    end: goff,
     // put regions in Boxes to cheaply move them around
     #[allow(clippy::vec_box)]
     regs: Vec<Box<Region>>,
@@ -311,11 +520,29 @@ impl RegionList {
             owner,
             child,
             ds_off,
-            size,
+            start: 0,
+            end: size,
             regs: Vec::new(),
         }
     }
 
+    /// Creates an empty list for `owner` that covers the same range as this list
+    pub fn new_like(&self, owner: Selector) -> Self {
+        RegionList {
+            owner,
+            child: self.child,
+            ds_off: self.ds_off,
+            start: self.start,
+            end: self.end,
+            regs: Vec::new(),
+        }
+    }
+
+    /// Returns the virtual address that all offsets are relative to
+    pub fn base(&self) -> goff {
+        self.ds_off
+    }
+
     pub fn is_empty(&self) -> bool {
         self.regs.is_empty()
     }
@@ -356,8 +583,8 @@ impl RegionList {
             self.owner,
             self.child,
             self.ds_off,
-            0,
-            self.size,
+            self.start,
+            self.end - self.start,
         ));
         r.set_mem(Rc::new(RefCell::new(PhysMem::new_bind(
             (self.owner, self.ds_off),
@@ -377,6 +604,85 @@ impl RegionList {
         }
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regs.iter().map(|r| &**r)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Region> {
+        self.regs.iter_mut().map(|r| &mut **r)
+    }
+
+    /// Splits this list at offset `off` and returns the list of all regions behind `off`
+    pub fn split(&mut self, off: goff) -> RegionList {
+        self.split_region(off);
+
+        let idx = self
+            .regs
+            .iter()
+            .position(|r| r.off >= off)
+            .unwrap_or(self.regs.len());
+        let res = RegionList {
+            owner: self.owner,
+            child: self.child,
+            ds_off: self.ds_off,
+            start: off,
+            end: self.end,
+            regs: self.regs.split_off(idx),
+        };
+        self.end = off;
+        res
+    }
+
+    /// Removes all regions within the offsets `start`..`end` and gives their memory back to the
+    /// child, if nobody else uses it
+    pub fn discard(
+        &mut self,
+        childs: &mut childs::ChildManager,
+        start: goff,
+        end: goff,
+    ) -> Result<(), Error> {
+        self.split_region(start);
+        self.split_region(end);
+
+        let mut idx = 0;
+        while idx < self.regs.len() {
+            let r = &self.regs[idx];
+            if r.off >= start && r.off + r.size <= end {
+                (*self.regs.remove(idx)).free(childs)?;
+            }
+            else {
+                idx += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits the region that contains `off`, if `off` is not at its beginning
+    fn split_region(&mut self, off: goff) {
+        if let Some(idx) = self
+            .regs
+            .iter()
+            .position(|r| off > r.off && off < r.off + r.size)
+        {
+            let nreg = self.regs[idx].split(off);
+            self.regs.insert(idx + 1, nreg);
+        }
+    }
+
+    /// Returns the index and age of the least recently used region that can be swapped out
+    pub fn coldest(&self) -> Option<(usize, u64)> {
+        self.regs
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.is_evictable())
+            .map(|(i, r)| (i, r.age()))
+            .min_by_key(|(_, age)| *age)
+    }
+
+    pub fn swap_out(&mut self, childs: &mut childs::ChildManager, idx: usize) -> Result<(), Error> {
+        self.regs[idx].swap_out(childs)
+    }
+
     fn do_pagefault(&mut self, off: goff) -> usize {
         // search for the region that contains `off` or is behind `off`
         let mut last = None;
@@ -402,10 +708,10 @@ impl RegionList {
             self.regs[l].off + self.regs[l].size
         }
         else {
-            0
+            self.start
         };
         let end = if idx == self.regs.len() {
-            self.size
+            self.end
         }
         else {
             self.regs[idx].off
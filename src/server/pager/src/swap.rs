@@ -0,0 +1,208 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Swapping of anonymous memory to a file or a disk partition.
+//!
+//! The swap space is divided into page-sized slots. Evicted regions store the slots that hold
+//! their pages and read them back on the next page fault.
+
+use m3::boxed::Box;
+use m3::cell::{LazyStaticRefCell, StaticRefCell};
+use m3::cfg;
+use m3::col::Vec;
+use m3::com::MemGate;
+use m3::errors::{Code, Error};
+use m3::goff;
+use m3::io::{Read, Write};
+use m3::kif::Perm;
+use m3::log;
+use m3::mem;
+use m3::session::{BlockRange, Disk};
+use m3::vfs::{File, FileRef, GenericFile, OpenFlags, Seek, SeekMode, VFS};
+
+pub type SlotNo = u32;
+
+static SWAP: LazyStaticRefCell<Swap> = LazyStaticRefCell::default();
+static BUF: StaticRefCell<mem::AlignedBuf<{ cfg::PAGE_SIZE }>> =
+    StaticRefCell::new(mem::AlignedBuf::new_zeroed());
+
+/// The location of the swap space
+pub enum SwapConfig<'s> {
+    /// A file in the file system with given path
+    File(&'s str),
+    /// A partition of the disk, accessed via the session with given name
+    Disk(&'s str),
+}
+
+enum Backend {
+    File(FileRef<GenericFile>),
+    Disk {
+        disk: Box<Disk>,
+        // the bounce buffer for the disk server
+        buf: MemGate,
+    },
+}
+
+impl Backend {
+    fn read_page(&mut self, slot: SlotNo, dst: &MemGate, off: goff) -> Result<(), Error> {
+        let mut buf = BUF.borrow_mut();
+        match self {
+            Self::File(file) => {
+                file.seek(slot as usize * cfg::PAGE_SIZE, SeekMode::SET)?;
+                file.read_exact(&mut buf[..])?;
+            },
+            Self::Disk { disk, buf: bounce } => {
+                disk.read(0, BlockRange::new(slot), cfg::PAGE_SIZE, None)?;
+                bounce.read(&mut buf[..], 0)?;
+            },
+        }
+        dst.write(&buf[..], off)
+    }
+
+    fn write_page(&mut self, slot: SlotNo, src: &MemGate, off: goff) -> Result<(), Error> {
+        let mut buf = BUF.borrow_mut();
+        src.read(&mut buf[..], off)?;
+        match self {
+            Self::File(file) => {
+                file.seek(slot as usize * cfg::PAGE_SIZE, SeekMode::SET)?;
+                file.write_all(&buf[..])
+            },
+            Self::Disk { disk, buf: bounce } => {
+                bounce.write(&buf[..], 0)?;
+                disk.write(0, BlockRange::new(slot), cfg::PAGE_SIZE, None)
+            },
+        }
+    }
+}
+
+struct Swap {
+    backend: Backend,
+    // slots that have been used before and are free again
+    free: Vec<SlotNo>,
+    // all slots from this one on have never been used
+    next: SlotNo,
+    count: SlotNo,
+}
+
+impl Swap {
+    fn alloc(&mut self) -> Option<SlotNo> {
+        if let Some(slot) = self.free.pop() {
+            Some(slot)
+        }
+        else if self.next < self.count {
+            self.next += 1;
+            Some(self.next - 1)
+        }
+        else {
+            None
+        }
+    }
+
+    fn free_slots(&mut self) -> usize {
+        self.free.len() + (self.count - self.next) as usize
+    }
+}
+
+/// Initializes the swap space with given configuration and size in bytes
+pub fn init(cfg: SwapConfig<'_>, size: usize) -> Result<(), Error> {
+    let count = (size / cfg::PAGE_SIZE) as SlotNo;
+    let backend = match cfg {
+        SwapConfig::File(path) => {
+            let mut file = VFS::open(path, OpenFlags::RW | OpenFlags::CREATE | OpenFlags::TRUNC)?;
+            // allocate the space upfront so that swapping does not fail due to a full file system
+            file.allocate(0, count as usize * cfg::PAGE_SIZE)?;
+            Backend::File(file)
+        },
+        SwapConfig::Disk(name) => {
+            let disk = Box::new(Disk::new(name)?);
+            let buf = MemGate::new(cfg::PAGE_SIZE, Perm::RW)?;
+            disk.delegate_mem(&buf, BlockRange::new_range(0, count))?;
+            Backend::Disk { disk, buf }
+        },
+    };
+
+    log!(crate::LOG_SWAP, "Using swap space with {} pages", count);
+
+    SWAP.set(Swap {
+        backend,
+        free: Vec::new(),
+        next: 0,
+        count,
+    });
+    Ok(())
+}
+
+/// Returns true if swapping is enabled
+pub fn enabled() -> bool {
+    SWAP.is_some()
+}
+
+/// The swap slots that hold the pages of an evicted region. The slots are free'd on drop.
+pub struct SwapSlots {
+    slots: Vec<SlotNo>,
+}
+
+impl SwapSlots {
+    /// Writes `pages` pages from `src`, starting at `off`, to newly allocated swap slots
+    pub fn swap_out(src: &MemGate, off: goff, pages: usize) -> Result<Self, Error> {
+        let mut swap = SWAP.borrow_mut();
+        if swap.free_slots() < pages {
+            return Err(Error::new(Code::NoSpace));
+        }
+
+        let mut slots = Vec::with_capacity(pages);
+        for i in 0..pages {
+            let slot = swap.alloc().unwrap();
+            slots.push(slot);
+            let page_off = off + (i * cfg::PAGE_SIZE) as goff;
+            if let Err(e) = swap.backend.write_page(slot, src, page_off) {
+                swap.free.extend_from_slice(&slots);
+                return Err(e);
+            }
+        }
+
+        log!(
+            crate::LOG_SWAP,
+            "Swapped out {} pages to {:?}",
+            pages,
+            slots
+        );
+        Ok(Self { slots })
+    }
+
+    /// Reads `pages` pages, starting with page `first`, back into `dst`
+    pub fn swap_in(&self, dst: &MemGate, first: usize, pages: usize) -> Result<(), Error> {
+        let mut swap = SWAP.borrow_mut();
+        let slots = &self.slots[first..first + pages];
+        for (i, slot) in slots.iter().enumerate() {
+            let page_off = (i * cfg::PAGE_SIZE) as goff;
+            swap.backend.read_page(*slot, dst, page_off)?;
+        }
+
+        log!(
+            crate::LOG_SWAP,
+            "Swapped in {} pages from {:?}",
+            pages,
+            slots
+        );
+        Ok(())
+    }
+}
+
+impl Drop for SwapSlots {
+    fn drop(&mut self) {
+        SWAP.borrow_mut().free.extend_from_slice(&self.slots);
+    }
+}
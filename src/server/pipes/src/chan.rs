@@ -37,7 +37,6 @@ pub enum ChanType {
 pub struct Channel {
     ty: ChanType,
     id: SessId,
-    pipe: SessId,
     state: Rc<RefCell<State>>,
     mem: Option<MemGate>,
     sgate: SendGate,
@@ -50,7 +49,6 @@ impl Channel {
         id: SessId,
         sel: Selector,
         ty: ChanType,
-        pipe: SessId,
         state: Rc<RefCell<State>>,
         rgate: &RecvGate,
     ) -> Result<Self, Error> {
@@ -63,7 +61,6 @@ impl Channel {
         Ok(Channel {
             ty,
             id,
-            pipe,
             state,
             mem: None,
             sgate,
@@ -72,16 +69,8 @@ impl Channel {
         })
     }
 
-    pub fn id(&self) -> SessId {
-        self.id
-    }
-
-    pub fn ty(&self) -> ChanType {
-        self.ty
-    }
-
-    pub fn pipe(&self) -> SessId {
-        self.pipe
+    pub fn attach(&self) {
+        self.state.borrow_mut().attach(self.id, self.ty);
     }
 
     pub fn crd(&self) -> kif::CapRngDesc {
@@ -89,7 +78,7 @@ impl Channel {
     }
 
     pub fn clone(&self, id: SessId, sel: Selector, rgate: &RecvGate) -> Result<Channel, Error> {
-        Channel::new(id, sel, self.ty, self.pipe, self.state.clone(), rgate)
+        Channel::new(id, sel, self.ty, self.state.clone(), rgate)
     }
 
     pub fn set_ep(&mut self, ep: Selector) {
@@ -132,21 +121,28 @@ impl Channel {
             ChanType::WRITE => Err(Error::new(Code::InvArgs)),
         };
 
-        self.state.borrow_mut().handle_pending_writes(is.rgate());
+        self.handle_pending(is.rgate());
         res
     }
 
     pub fn next_out(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
         let _: usize = is.pop()?;
+        // clients that know about packet pipes pass the size of the packet they want to write
+        let size: usize = is.pop().unwrap_or(0);
 
-        log!(crate::LOG_DEF, "[{}] pipes::next_out()", self.id);
+        log!(
+            crate::LOG_DEF,
+            "[{}] pipes::next_out(size={})",
+            self.id,
+            size
+        );
 
         let res = match self.ty {
             ChanType::READ => Err(Error::new(Code::InvArgs)),
-            ChanType::WRITE => self.write(is, 0),
+            ChanType::WRITE => self.write(is, 0, size),
         };
 
-        self.state.borrow_mut().handle_pending_reads(is.rgate());
+        self.handle_pending(is.rgate());
         res
     }
 
@@ -163,7 +159,7 @@ impl Channel {
 
         let res = match self.ty {
             ChanType::READ => self.read(is, nbytes),
-            ChanType::WRITE => self.write(is, nbytes),
+            ChanType::WRITE => self.write(is, nbytes, 0),
         };
 
         self.handle_pending(is.rgate());
@@ -192,10 +188,10 @@ impl Channel {
     }
 
     fn handle_pending(&mut self, rgate: &RecvGate) {
-        match self.ty {
-            ChanType::READ => self.state.borrow_mut().handle_pending_writes(rgate),
-            ChanType::WRITE => self.state.borrow_mut().handle_pending_reads(rgate),
-        }
+        // besides the other side, other clients on our side might be able to continue as well
+        let mut state = self.state.borrow_mut();
+        state.handle_pending_reads(rgate);
+        state.handle_pending_writes(rgate);
     }
 
     fn read(&mut self, is: &mut GateIStream<'_>, commit: usize) -> Result<(), Error> {
@@ -210,14 +206,14 @@ impl Channel {
                 if commit > 0 {
                     return Err(Error::new(Code::InvArgs));
                 }
-                state.append_request(self.id, is, true);
+                state.append_request(self.id, is, true, 0);
                 return Ok(());
             }
 
             // this client is the current reader, so commit the read by pulling it from the ringbuf
             let amount = if commit == 0 { last_amount } else { commit };
             log!(crate::LOG_DEF, "[{}] pipes::read_pull({})", self.id, amount);
-            state.rbuf.pull(amount);
+            state.pull(amount);
             state.last_read = None;
         }
 
@@ -230,7 +226,7 @@ impl Channel {
         if state.has_pending_reads() {
             // only queue the request if we still have writers
             if !state.flags().contains(Flags::WRITE_EOF) {
-                state.append_request(self.id, is, true);
+                state.append_request(self.id, is, true, 0);
                 return Ok(());
             }
         }
@@ -266,13 +262,13 @@ impl Channel {
                 }
 
                 // otherwise queue the request
-                state.append_request(self.id, is, true);
+                state.append_request(self.id, is, true, 0);
                 Ok(())
             }
         }
     }
 
-    fn write(&mut self, is: &mut GateIStream<'_>, commit: usize) -> Result<(), Error> {
+    fn write(&mut self, is: &mut GateIStream<'_>, commit: usize, size: usize) -> Result<(), Error> {
         self.activate()?;
 
         // if there are no readers left, report EOF
@@ -290,7 +286,7 @@ impl Channel {
                 if commit > 0 {
                     return Err(Error::new(Code::InvArgs));
                 }
-                state.append_request(self.id, is, false);
+                state.append_request(self.id, is, false, size);
                 return Ok(());
             }
 
@@ -302,7 +298,7 @@ impl Channel {
                 self.id,
                 amount
             );
-            state.rbuf.push(last_amount, amount);
+            state.push(last_amount, amount);
             state.last_write = None;
         }
 
@@ -313,13 +309,13 @@ impl Channel {
 
         // if there are already queued write requests, just append this request
         if state.has_pending_writes() {
-            state.append_request(self.id, is, false);
+            state.append_request(self.id, is, false, size);
             return Ok(());
         }
 
         // request new write position
-        let amount = state.get_write_size();
-        if let Some(pos) = state.rbuf.get_write_pos(amount) {
+        let amount = state.get_write_size(size);
+        if let Some(pos) = state.get_write_pos(amount) {
             // there is space to write; give client the position and size
             state.last_write = Some((self.id, amount));
             // okay, input is available, so we fulfilled our promise
@@ -342,7 +338,7 @@ impl Channel {
             }
 
             // nothing to write, so queue the request
-            state.append_request(self.id, is, false);
+            state.append_request(self.id, is, false, size);
             Ok(())
         }
     }
@@ -0,0 +1,123 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::cap::Selector;
+use m3::cell::RefCell;
+use m3::col::{BTreeMap, String, ToString};
+use m3::com::{MemGate, RecvGate};
+use m3::errors::{Code, Error};
+use m3::kif::Perm;
+use m3::rc::Rc;
+use m3::server::SessId;
+
+use crate::chan::{ChanType, Channel};
+use crate::pipe::{Flags, State};
+
+/// A named pipe, which exists independently of the clients using it
+pub struct Fifo {
+    ino: usize,
+    state: Rc<RefCell<State>>,
+}
+
+impl Fifo {
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    pub fn new_chan(
+        &self,
+        sid: SessId,
+        sel: Selector,
+        ty: ChanType,
+        rgate: &RecvGate,
+    ) -> Result<Channel, Error> {
+        // in contrast to anonymous pipes, we provide the memory ourself, because the pipe should
+        // not depend on the lifetime of the client that created it. To not waste memory for pipes
+        // that are never used, we allocate it on the first open.
+        let mut state = self.state.borrow_mut();
+        if !state.has_mem() {
+            let mem = MemGate::new(state.mem_size(), Perm::RW)?;
+            state.set_mem(mem);
+        }
+
+        // named pipes can be opened again after all readers or writers have left. writers can
+        // fill the buffer until the next reader arrives.
+        match ty {
+            ChanType::READ => state.remove_flags(Flags::READ_EOF),
+            ChanType::WRITE => {
+                state.remove_flags(Flags::WRITE_EOF);
+                if !state.has_readers() {
+                    state.remove_flags(Flags::READ_EOF);
+                }
+            },
+        }
+        drop(state);
+
+        Channel::new(sid, sel, ty, self.state.clone(), rgate)
+    }
+}
+
+/// The namespace of all named pipes
+pub struct Fifos {
+    fifos: BTreeMap<String, Fifo>,
+    max_fifos: usize,
+    mem_size: usize,
+    next_ino: usize,
+}
+
+impl Fifos {
+    pub fn new(max_fifos: usize, mem_size: usize) -> Self {
+        Fifos {
+            fifos: BTreeMap::new(),
+            max_fifos,
+            mem_size,
+            // inode 0 is the root directory
+            next_ino: 1,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Fifo, Error> {
+        self.fifos
+            .get(name)
+            .ok_or_else(|| Error::new(Code::NoSuchFile))
+    }
+
+    pub fn create(&mut self, name: &str) -> Result<&Fifo, Error> {
+        if name.is_empty() || name.contains('/') {
+            return Err(Error::new(Code::InvArgs));
+        }
+        if self.fifos.contains_key(name) {
+            return Err(Error::new(Code::Exists));
+        }
+        if self.fifos.len() >= self.max_fifos {
+            return Err(Error::new(Code::NoSpace));
+        }
+
+        let fifo = Fifo {
+            ino: self.next_ino,
+            state: Rc::new(RefCell::new(State::new(self.mem_size, false))),
+        };
+        self.next_ino += 1;
+        Ok(self.fifos.entry(name.to_string()).or_insert(fifo))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), Error> {
+        // existing channels keep the pipe alive
+        self.fifos
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| Error::new(Code::NoSuchFile))
+    }
+}
@@ -32,10 +32,11 @@ impl Meta {
         sel: Selector,
         sid: SessId,
         mem_size: usize,
+        packet: bool,
         rgate: &RecvGate,
     ) -> Result<Pipe, Error> {
         self.pipes.push(sid);
-        Pipe::new(sel, sid, mem_size, rgate)
+        Pipe::new(sel, sid, mem_size, packet, rgate)
     }
 
     pub fn close(&mut self, sids: &mut Vec<SessId>) -> Result<(), Error> {
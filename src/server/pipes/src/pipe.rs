@@ -16,7 +16,7 @@
 use bitflags::bitflags;
 use m3::cap::Selector;
 use m3::cell::{Cell, RefCell};
-use m3::col::{VarRingBuf, Vec};
+use m3::col::{VarRingBuf, Vec, VecDeque};
 use m3::com::{GateIStream, MemGate, RGateArgs, RecvGate, SGateArgs, SendGate, EP};
 use m3::errors::{Code, Error};
 use m3::kif;
@@ -91,11 +91,13 @@ bitflags! {
 pub struct PendingRequest {
     chan: SessId,
     msg: &'static Message,
+    // the requested number of bytes for writes to packet pipes
+    size: usize,
 }
 
 impl PendingRequest {
-    fn new(chan: SessId, msg: &'static Message) -> Self {
-        PendingRequest { chan, msg }
+    fn new(chan: SessId, msg: &'static Message, size: usize) -> Self {
+        PendingRequest { chan, msg, size }
     }
 }
 
@@ -103,6 +105,8 @@ pub struct State {
     flags: Flags,
     mem: Option<MemGate>,
     mem_size: usize,
+    // the lengths of the packets in the ring buffer, if it's a packet pipe
+    packets: Option<VecDeque<usize>>,
     pub rbuf: VarRingBuf,
     pub last_read: Option<(SessId, usize)>,
     pub last_write: Option<(SessId, usize)>,
@@ -114,11 +118,12 @@ pub struct State {
 }
 
 impl State {
-    fn new(mem_size: usize) -> Self {
+    pub fn new(mem_size: usize, packet: bool) -> Self {
         State {
             flags: Flags::empty(),
             mem: None,
             mem_size,
+            packets: if packet { Some(VecDeque::new()) } else { None },
             rbuf: VarRingBuf::new(mem_size),
             last_read: None,
             last_write: None,
@@ -138,6 +143,33 @@ impl State {
         self.flags |= flags;
     }
 
+    pub fn has_mem(&self) -> bool {
+        self.mem.is_some()
+    }
+
+    pub fn mem_size(&self) -> usize {
+        self.mem_size
+    }
+
+    pub fn set_mem(&mut self, mem: MemGate) {
+        self.mem = Some(mem);
+    }
+
+    pub fn remove_flags(&mut self, flags: Flags) {
+        self.flags &= !flags;
+    }
+
+    pub fn has_readers(&self) -> bool {
+        !self.reader.is_empty()
+    }
+
+    pub fn attach(&mut self, id: SessId, ty: ChanType) {
+        match ty {
+            ChanType::READ => self.reader.push(id),
+            ChanType::WRITE => self.writer.push(id),
+        }
+    }
+
     pub fn has_pending_reads(&self) -> bool {
         !self.pending_reads.is_empty()
     }
@@ -146,14 +178,59 @@ impl State {
         !self.pending_writes.is_empty()
     }
 
+    pub fn is_packet(&self) -> bool {
+        self.packets.is_some()
+    }
+
     pub fn get_read_size(&self) -> usize {
         assert!(!self.reader.is_empty());
-        self.rbuf.size() / (4 * self.reader.len())
+        match &self.packets {
+            // packets are always read at once
+            Some(packets) => packets.front().copied().unwrap_or(0),
+            None => self.rbuf.size() / (4 * self.reader.len()),
+        }
     }
 
-    pub fn get_write_size(&self) -> usize {
+    pub fn get_write_size(&self, size: usize) -> usize {
         assert!(!self.writer.is_empty());
-        self.rbuf.size() / (4 * self.writer.len())
+        if self.is_packet() && size > 0 {
+            size.min(self.rbuf.size())
+        }
+        else {
+            self.rbuf.size() / (4 * self.writer.len())
+        }
+    }
+
+    /// Determines the write position for `size` bytes
+    pub fn get_write_pos(&mut self, size: usize) -> Option<usize> {
+        // packets need to be contiguous. to be able to write packets up to the size of the ring
+        // buffer, start at the beginning again if nobody is using the ring buffer
+        if self.is_packet() && self.rbuf.empty() && self.last_read.is_none() {
+            self.rbuf = VarRingBuf::new(self.rbuf.size());
+        }
+        self.rbuf.get_write_pos(size)
+    }
+
+    /// Advances the read position by `size`, or by the current packet for packet pipes
+    pub fn pull(&mut self, size: usize) {
+        match &mut self.packets {
+            Some(packets) => {
+                // the rest of the packet is discarded
+                let len = packets.pop_front().unwrap();
+                self.rbuf.pull(len);
+            },
+            None => self.rbuf.pull(size),
+        }
+    }
+
+    /// Advances the write position by `size`, adding a packet for packet pipes
+    pub fn push(&mut self, req_size: usize, size: usize) {
+        self.rbuf.push(req_size, size);
+        if let Some(packets) = &mut self.packets {
+            if size > 0 {
+                packets.push_back(size);
+            }
+        }
     }
 
     pub fn get_notify_gate(&mut self, sess: SessId) -> Option<&mut NotifyGate> {
@@ -244,8 +321,14 @@ impl State {
         }
     }
 
-    pub fn append_request(&mut self, id: SessId, is: &mut GateIStream<'_>, read: bool) {
-        let req = PendingRequest::new(id, is.take_msg());
+    pub fn append_request(
+        &mut self,
+        id: SessId,
+        is: &mut GateIStream<'_>,
+        read: bool,
+        size: usize,
+    ) {
+        let req = PendingRequest::new(id, is.take_msg(), size);
         if read {
             log!(crate::LOG_DEF, "[{}] pipes::read_wait()", id);
             self.pending_reads.insert(0, req);
@@ -323,18 +406,19 @@ impl State {
         // is there a pending write request?
         else if let Some(req) = self.pending_writes.last() {
             // try to find a place to write
-            let amount = self.get_write_size();
-            if let Some(pos) = self.rbuf.get_write_pos(amount) {
+            let (chan, msg) = (req.chan, req.msg);
+            let amount = self.get_write_size(req.size);
+            if let Some(pos) = self.get_write_pos(amount) {
                 // start writing
-                self.last_write = Some((req.chan, amount));
+                self.last_write = Some((chan, amount));
                 log!(
                     crate::LOG_DEF,
                     "[{}] pipes::late_write(): {} @ {}",
-                    req.chan,
+                    chan,
                     amount,
                     pos
                 );
-                reply_vmsg_late!(rgate, req.msg, Code::Success, pos, amount).ok();
+                reply_vmsg_late!(rgate, msg, Code::Success, pos, amount).ok();
 
                 // remove write request
                 self.pending_writes.pop();
@@ -371,7 +455,6 @@ impl State {
 }
 
 pub struct Pipe {
-    id: SessId,
     _sgate: SendGate,
     state: Rc<RefCell<State>>,
 }
@@ -381,6 +464,7 @@ impl Pipe {
         sel: Selector,
         id: SessId,
         mem_size: usize,
+        packet: bool,
         rgate: &RecvGate,
     ) -> Result<Self, Error> {
         let sgate = SendGate::new_with(
@@ -390,9 +474,8 @@ impl Pipe {
                 .sel(sel + 1),
         )?;
         Ok(Pipe {
-            id,
             _sgate: sgate,
-            state: Rc::new(RefCell::new(State::new(mem_size))),
+            state: Rc::new(RefCell::new(State::new(mem_size, packet))),
         })
     }
 
@@ -401,7 +484,7 @@ impl Pipe {
     }
 
     pub fn set_mem(&mut self, sel: Selector) {
-        self.state.borrow_mut().mem = Some(MemGate::new_bind(sel));
+        self.state.borrow_mut().set_mem(MemGate::new_bind(sel));
     }
 
     pub fn new_chan(
@@ -411,15 +494,7 @@ impl Pipe {
         ty: ChanType,
         rgate: &RecvGate,
     ) -> Result<Channel, Error> {
-        Channel::new(sid, sel, ty, self.id, self.state.clone(), rgate)
-    }
-
-    pub fn attach(&mut self, chan: &Channel) {
-        assert!(chan.pipe() == self.id);
-        match chan.ty() {
-            ChanType::READ => self.state.borrow_mut().reader.push(chan.id()),
-            ChanType::WRITE => self.state.borrow_mut().writer.push(chan.id()),
-        }
+        Channel::new(sid, sel, ty, self.state.clone(), rgate)
     }
 
     pub fn close(&mut self, sids: &mut Vec<SessId>) -> Result<(), Error> {
@@ -16,6 +16,7 @@
 #![no_std]
 
 mod chan;
+mod fifo;
 mod meta;
 mod pipe;
 mod sess;
@@ -38,9 +39,10 @@ use m3::session::{PipeOperation, ServerSession};
 use m3::tcu::Label;
 use m3::tiles::{Activity, OwnActivity};
 use m3::vec;
-use m3::vfs::GenFileOp;
+use m3::vfs::{GenFileOp, OpenFlags};
 
 use chan::{ChanType, Channel};
+use fifo::Fifos;
 use meta::Meta;
 use sess::{PipesSession, SessionData};
 
@@ -66,12 +68,17 @@ int_enum! {
         const OPEN_CHAN     = PipeOperation::OPEN_CHAN.val;
         const SET_MEM       = PipeOperation::SET_MEM.val;
         const CLOSE_PIPE    = PipeOperation::CLOSE_PIPE.val;
+        const OPEN_FIFO     = PipeOperation::OPEN_FIFO.val;
+        const MKFIFO        = PipeOperation::MKFIFO.val;
+        const UNLINK_FIFO   = PipeOperation::UNLINK_FIFO.val;
+        const STAT_FIFO     = PipeOperation::STAT_FIFO.val;
     }
 }
 
 struct PipesHandler {
     sel: Selector,
     sessions: SessionContainer<PipesSession>,
+    fifos: Fifos,
 }
 
 impl PipesHandler {
@@ -114,6 +121,37 @@ impl PipesHandler {
         Ok(())
     }
 
+    fn fifo_op(
+        &mut self,
+        sid: SessId,
+        op: Operation,
+        xchg: &mut CapExchange<'_>,
+    ) -> Result<(), Error> {
+        let sess = self.sessions.get(sid).unwrap();
+        if xchg.in_caps() != 0 || !matches!(sess.data(), SessionData::Meta(_)) {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let path: String = xchg.in_args().pop()?;
+        log!(
+            crate::LOG_DEF,
+            "[{}] pipes::fifo_op(op={}, path={})",
+            sid,
+            op,
+            path
+        );
+
+        match op {
+            Operation::MKFIFO => self.fifos.create(&path).map(|_| ()),
+            Operation::UNLINK_FIFO => self.fifos.remove(&path),
+            _ => {
+                let ino = self.fifos.get(&path)?.ino();
+                xchg.out_args().push(ino);
+                Ok(())
+            },
+        }
+    }
+
     fn with_chan<F, R>(&mut self, is: &mut GateIStream<'_>, func: F) -> Result<R, Error>
     where
         F: Fn(&mut Channel, &mut GateIStream<'_>) -> Result<R, Error>,
@@ -157,6 +195,11 @@ impl Handler<PipesSession> for PipesHandler {
             op
         );
 
+        // named pipes are created, removed, and inspected without creating a new session
+        if let Operation::MKFIFO | Operation::UNLINK_FIFO | Operation::STAT_FIFO = op {
+            return self.fifo_op(sid, op, xchg);
+        }
+
         if xchg.in_caps() != 2 {
             return Err(Error::new(Code::InvArgs));
         }
@@ -168,25 +211,64 @@ impl Handler<PipesSession> for PipesHandler {
             let nsid = self.sessions.next_id()?;
             let osess = self.sessions.get_mut(sid).unwrap();
             match &mut osess.data_mut() {
-                // meta sessions allow to create new pipes
-                SessionData::Meta(ref mut m) => {
-                    if op != Operation::OPEN_PIPE {
-                        return Err(Error::new(Code::InvArgs));
-                    }
-
-                    let sel = Activity::own().alloc_sels(2);
-                    let msize: usize = xchg.in_args().pop()?;
-                    log!(
-                        crate::LOG_DEF,
-                        "[{}] pipes::open_pipe(sid={}, sel={}, size={:#x})",
-                        sid,
-                        nsid,
-                        sel,
-                        msize
-                    );
-                    let pipe = m.create_pipe(sel, nsid, msize, REQHDL.get().recv_gate())?;
-                    let nsess = self.new_sub_sess(crt, sel, nsid, SessionData::Pipe(pipe))?;
-                    Ok((nsid, nsess, false))
+                // meta sessions allow to create new pipes and to open named pipes
+                SessionData::Meta(ref mut m) => match op {
+                    Operation::OPEN_PIPE => {
+                        let sel = Activity::own().alloc_sels(2);
+                        let msize: usize = xchg.in_args().pop()?;
+                        let packet: bool = xchg.in_args().pop()?;
+                        log!(
+                            crate::LOG_DEF,
+                            "[{}] pipes::open_pipe(sid={}, sel={}, size={:#x}, packet={})",
+                            sid,
+                            nsid,
+                            sel,
+                            msize,
+                            packet
+                        );
+                        let pipe =
+                            m.create_pipe(sel, nsid, msize, packet, REQHDL.get().recv_gate())?;
+                        let nsess = self.new_sub_sess(crt, sel, nsid, SessionData::Pipe(pipe))?;
+                        Ok((nsid, nsess))
+                    },
+
+                    Operation::OPEN_FIFO => {
+                        let sel = Activity::own().alloc_sels(2);
+                        let flags: OpenFlags = xchg.in_args().pop()?;
+                        let path: &str = xchg.in_args().pop()?;
+                        log!(
+                            crate::LOG_DEF,
+                            "[{}] pipes::open_fifo(sid={}, sel={}, path={}, flags={:?})",
+                            sid,
+                            nsid,
+                            sel,
+                            path,
+                            flags
+                        );
+
+                        // named pipes have either a read-end or a write-end
+                        let ty = match flags & OpenFlags::RW {
+                            OpenFlags::R => ChanType::READ,
+                            OpenFlags::W => ChanType::WRITE,
+                            _ => return Err(Error::new(Code::InvArgs)),
+                        };
+
+                        if flags.contains(OpenFlags::CREATE) && self.fifos.get(path).is_err() {
+                            self.fifos.create(path)?;
+                        }
+
+                        let chan = self.fifos.get(path)?.new_chan(
+                            nsid,
+                            sel,
+                            ty,
+                            REQHDL.get().recv_gate(),
+                        )?;
+                        chan.attach();
+                        let nsess = self.new_sub_sess(crt, sel, nsid, SessionData::Chan(chan))?;
+                        Ok((nsid, nsess))
+                    },
+
+                    _ => Err(Error::new(Code::InvArgs)),
                 },
 
                 // pipe sessions allow to create new channels
@@ -209,9 +291,9 @@ impl Handler<PipesSession> for PipesHandler {
                         ty
                     );
                     let chan = p.new_chan(nsid, sel, ty, REQHDL.get().recv_gate())?;
-                    p.attach(&chan);
+                    chan.attach();
                     let nsess = self.new_sub_sess(crt, sel, nsid, SessionData::Chan(chan))?;
-                    Ok((nsid, nsess, false))
+                    Ok((nsid, nsess))
                 },
 
                 // channel sessions can be cloned
@@ -230,22 +312,15 @@ impl Handler<PipesSession> for PipesHandler {
                     );
 
                     let chan = c.clone(nsid, sel, REQHDL.get().recv_gate())?;
+                    chan.attach();
                     let nsess = self.new_sub_sess(crt, sel, nsid, SessionData::Chan(chan))?;
-                    Ok((nsid, nsess, true))
+                    Ok((nsid, nsess))
                 },
             }
         };
-        let (nsid, nsess, attach_pipe) = res?;
+        let (nsid, nsess) = res?;
 
         let crd = if let SessionData::Chan(ref c) = nsess.data() {
-            // workaround because we cannot borrow self.sessions again inside the above match
-            if attach_pipe {
-                let psess = self.sessions.get_mut(c.pipe()).unwrap();
-                if let SessionData::Pipe(ref mut p) = psess.data_mut() {
-                    p.attach(c);
-                }
-            }
-
             c.crd()
         }
         else if let SessionData::Pipe(_) = nsess.data() {
@@ -338,20 +413,29 @@ impl Handler<PipesSession> for PipesHandler {
 #[derive(Clone, Debug)]
 pub struct PipesSettings {
     max_clients: usize,
+    max_fifos: usize,
+    fifo_size: usize,
 }
 
 impl Default for PipesSettings {
     fn default() -> Self {
         PipesSettings {
             max_clients: DEF_MAX_CLIENTS,
+            max_fifos: 32,
+            fifo_size: 64 * 1024,
         }
     }
 }
 
 fn usage() -> ! {
-    println!("Usage: {} [-m <clients>]", env::args().next().unwrap());
+    println!(
+        "Usage: {} [-m <clients>] [-f <fifos>] [-s <fifosize>]",
+        env::args().next().unwrap()
+    );
     println!();
     println!("  -m: the maximum number of clients (receive slots)");
+    println!("  -f: the maximum number of named pipes");
+    println!("  -s: the buffer size of named pipes in bytes");
     OwnActivity::exit_with(Code::InvArgs);
 }
 
@@ -368,6 +452,18 @@ fn parse_args() -> Result<PipesSettings, String> {
                     .map_err(|_| String::from("Failed to parse client count"))?;
                 i += 1;
             },
+            "-f" => {
+                settings.max_fifos = args[i + 1]
+                    .parse::<usize>()
+                    .map_err(|_| String::from("Failed to parse FIFO count"))?;
+                i += 1;
+            },
+            "-s" => {
+                settings.fifo_size = args[i + 1]
+                    .parse::<usize>()
+                    .map_err(|_| String::from("Failed to parse FIFO size"))?;
+                i += 1;
+            },
             _ => break,
         }
         i += 1;
@@ -385,6 +481,7 @@ pub fn main() -> Result<(), Error> {
     let mut hdl = PipesHandler {
         sel: 0,
         sessions: SessionContainer::new(settings.max_clients),
+        fifos: Fifos::new(settings.max_fifos, settings.fifo_size),
     };
     let s = Server::new("pipes", &mut hdl).expect("Unable to create service 'pipes'");
     hdl.sel = s.sel();
@@ -121,7 +121,8 @@ impl resmng::subsys::ChildStarter for RootChildStarter {
             child.child_tile().unwrap().tile_obj().clone(),
             ActivityArgs::new(child.name())
                 .resmng(ResMng::new(sgate))
-                .kmem(child.kmem().unwrap()),
+                .kmem(child.kmem().unwrap())
+                .prio(child.cfg().prio().unwrap_or(kif::Priority::NORMAL)),
         )
         .map_err(|e| VerboseError::new(e.code(), "Unable to create Activity".to_string()))?;
 
@@ -0,0 +1,96 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::{Vec, VecDeque};
+use m3::io::{Serial, Write};
+use m3::server::SessId;
+use vterm_util::line::{History, LineEditor, SpecialChars};
+
+use crate::Mode;
+
+/// The number of output bytes that are kept per console to redraw it
+pub const SCROLLBACK_SIZE: usize = 4096;
+
+/// A virtual console that is multiplexed over the serial line
+#[derive(Debug)]
+pub struct Console {
+    pub input: Vec<u8>,
+    pub eof: bool,
+    pub mode: Mode,
+    pub editor: LineEditor,
+    /// the meta session that received the last input
+    pub reader: Option<SessId>,
+    /// the history for lines that cannot be attributed to a session
+    pub history: History,
+    scrollback: VecDeque<u8>,
+}
+
+impl Console {
+    pub fn new(chars: SpecialChars) -> Self {
+        Self {
+            input: Vec::new(),
+            eof: false,
+            mode: Mode::COOKED,
+            editor: LineEditor::new(chars),
+            reader: None,
+            history: History::default(),
+            scrollback: VecDeque::with_capacity(SCROLLBACK_SIZE),
+        }
+    }
+
+    /// Writes the given output to the console, which is only visible on the serial line if the
+    /// console is `active`.
+    pub fn write(&mut self, data: &[u8], active: bool) {
+        self.record(data);
+
+        if active {
+            // ignore errors
+            Serial::new().write(data).ok();
+        }
+    }
+
+    /// Writes the echo of the line editor to the serial line.
+    ///
+    /// The echo is not recorded, because it contains the intermediate states of the line. Instead,
+    /// completed lines are recorded via `record` and the line that is currently edited is redrawn
+    /// by `redraw`.
+    pub fn echo(&self, data: &[u8]) {
+        Serial::new().write(data).ok();
+    }
+
+    /// Records the given output to be able to redraw the console later
+    pub fn record(&mut self, data: &[u8]) {
+        let kept = &data[data.len().saturating_sub(SCROLLBACK_SIZE)..];
+        let excess = (self.scrollback.len() + kept.len()).saturating_sub(SCROLLBACK_SIZE);
+        self.scrollback.drain(..excess);
+        self.scrollback.extend(kept);
+    }
+
+    /// Clears the terminal and restores the recent output of this console including the line that
+    /// is currently edited
+    pub fn redraw(&self) {
+        let mut serial = Serial::new();
+        let (first, second) = self.scrollback.as_slices();
+        serial.write(b"\x1b[H\x1b[2J").ok();
+        serial.write(first).ok();
+        serial.write(second).ok();
+
+        if self.mode == Mode::COOKED {
+            let mut line = Vec::new();
+            self.editor.redraw(&mut line);
+            serial.write(&line).ok();
+        }
+    }
+}
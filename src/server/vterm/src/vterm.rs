@@ -15,15 +15,18 @@
 
 #![no_std]
 
+mod console;
+
 use m3::cap::Selector;
-use m3::cell::{LazyReadOnlyCell, RefMut, StaticCell, StaticRefCell};
+use m3::cell::{LazyReadOnlyCell, StaticCell, StaticRefCell};
 use m3::col::Vec;
 use m3::com::{GateIStream, MemGate, Perm, RGateArgs, RecvGate, SGateArgs, SendGate, EP};
+use m3::env;
 use m3::errors::{Code, Error};
 use m3::int_enum;
-use m3::io::{Serial, Write};
 use m3::kif;
 use m3::log;
+use m3::println;
 use m3::rc::Rc;
 use m3::reply_vmsg;
 use m3::server::{
@@ -32,28 +35,33 @@ use m3::server::{
 };
 use m3::session::ServerSession;
 use m3::tcu::{Label, Message};
-use m3::tiles::Activity;
+use m3::tiles::{Activity, OwnActivity};
 use m3::vec;
 use m3::vfs::{FileEvent, FileInfo, FileMode, GenFileOp};
 use m3::{build_vmsg, goff, send_vmsg};
+use vterm_util::args::{self, MAX_CONSOLES};
+use vterm_util::line::{Event, History};
+
+use console::Console;
 
 pub const LOG_DEF: bool = false;
 pub const LOG_INOUT: bool = false;
 
 const BUF_SIZE: usize = 256;
 
+/// The hotkey to switch between consoles (^A), followed by the console number
+const SWITCH_KEY: u8 = 0x01;
+
 int_enum! {
-    struct Mode : u64 {
+    pub struct Mode : u64 {
         const RAW       = 0;
         const COOKED    = 1;
     }
 }
 
 static REQHDL: LazyReadOnlyCell<RequestHandler> = LazyReadOnlyCell::default();
-static BUFFER: StaticRefCell<Vec<u8>> = StaticRefCell::new(Vec::new());
-static INPUT: StaticRefCell<Vec<u8>> = StaticRefCell::new(Vec::new());
-static EOF: StaticCell<bool> = StaticCell::new(false);
-static MODE: StaticCell<Mode> = StaticCell::new(Mode::COOKED);
+static CONSOLES: StaticRefCell<Vec<Console>> = StaticRefCell::new(Vec::new());
+static ACTIVE: StaticCell<usize> = StaticCell::new(0);
 static TMP_BUF: StaticRefCell<[u8; BUF_SIZE]> = StaticRefCell::new([0u8; BUF_SIZE]);
 
 macro_rules! reply_vmsg_late {
@@ -68,6 +76,7 @@ macro_rules! reply_vmsg_late {
 struct VTermSession {
     crt: usize,
     sess: ServerSession,
+    console: usize,
     data: SessionData,
     parent: Option<SessId>,
     childs: Vec<SessId>,
@@ -76,13 +85,16 @@ struct VTermSession {
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 enum SessionData {
-    Meta,
+    Meta(History),
     Chan(Channel),
 }
 
 #[derive(Debug)]
 struct Channel {
     id: SessId,
+    // the meta session this channel has been cloned from
+    meta: SessId,
+    console: usize,
     active: bool,
     writing: bool,
     ep: Option<Selector>,
@@ -103,7 +115,14 @@ fn mem_off(id: SessId) -> goff {
 }
 
 impl Channel {
-    fn new(id: SessId, mem: Rc<MemGate>, caps: Selector, writing: bool) -> Result<Self, Error> {
+    fn new(
+        id: SessId,
+        meta: SessId,
+        console: usize,
+        mem: Rc<MemGate>,
+        caps: Selector,
+        writing: bool,
+    ) -> Result<Self, Error> {
         let sgate = SendGate::new_with(
             SGateArgs::new(REQHDL.get().recv_gate())
                 .label(id as Label)
@@ -114,6 +133,8 @@ impl Channel {
 
         Ok(Channel {
             id,
+            meta,
+            console,
             active: false,
             writing,
             ep: None,
@@ -144,7 +165,7 @@ impl Channel {
 
         log!(crate::LOG_DEF, "[{}] vterm::get_tmode()", self.id,);
 
-        reply_vmsg!(is, Code::Success, MODE.get())
+        reply_vmsg!(is, Code::Success, CONSOLES.borrow()[self.console].mode)
     }
 
     fn set_tmode(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
@@ -157,8 +178,10 @@ impl Channel {
             self.id,
             mode
         );
-        MODE.set(mode);
-        INPUT.borrow_mut().clear();
+        let mut consoles = CONSOLES.borrow_mut();
+        let con = &mut consoles[self.console];
+        con.mode = mode;
+        con.input.clear();
 
         is.reply_error(Code::Success)
     }
@@ -177,8 +200,9 @@ impl Channel {
         self.activate()?;
 
         if self.pos == self.len {
-            let mut input = INPUT.borrow_mut();
-            if !EOF.get() && input.is_empty() {
+            let mut consoles = CONSOLES.borrow_mut();
+            let con = &mut consoles[self.console];
+            if !con.eof && con.input.is_empty() {
                 // if we promised the client that input would be available, report WouldBlock
                 // instead of delaying the response.
                 if self.promised_events.contains(FileEvent::INPUT) {
@@ -190,18 +214,20 @@ impl Channel {
                 return Ok(());
             }
 
-            self.fetch_input(&mut input)?;
+            self.fetch_input(con)?;
         }
 
         reply_vmsg!(is, Code::Success, self.pos, self.len - self.pos)
     }
 
-    fn fetch_input(&mut self, input: &mut RefMut<'_, Vec<u8>>) -> Result<(), Error> {
+    fn fetch_input(&mut self, con: &mut Console) -> Result<(), Error> {
         // okay, input is available, so we fulfilled our promise
         self.promised_events &= !FileEvent::INPUT;
-        self.our_mem.write(input, mem_off(self.id))?;
-        self.len = input.len();
+        self.our_mem.write(&con.input, mem_off(self.id))?;
+        self.len = con.input.len();
         self.pos = 0;
+        // lines that are entered while nobody waits for input will probably go to the same reader
+        con.reader = Some(self.meta);
 
         log!(
             crate::LOG_INOUT,
@@ -211,8 +237,8 @@ impl Channel {
             self.len - self.pos
         );
 
-        EOF.set(false);
-        input.clear();
+        con.eof = false;
+        con.input.clear();
 
         Ok(())
     }
@@ -275,7 +301,8 @@ impl Channel {
         if nbytes > 0 {
             self.our_mem
                 .read(&mut TMP_BUF.borrow_mut()[0..nbytes], mem_off(self.id))?;
-            Serial::new().write(&TMP_BUF.borrow()[0..nbytes])?;
+            CONSOLES.borrow_mut()[self.console]
+                .write(&TMP_BUF.borrow()[0..nbytes], self.console == ACTIVE.get());
         }
         self.len = 0;
         Ok(())
@@ -300,7 +327,7 @@ impl Channel {
         // remove from promised events, because we need to notify the client about them again first
         self.promised_events &= !events;
         // check whether input is available already
-        if events.contains(FileEvent::INPUT) && !INPUT.borrow().is_empty() {
+        if events.contains(FileEvent::INPUT) && !CONSOLES.borrow()[self.console].input.is_empty() {
             self.pending_events |= FileEvent::INPUT;
         }
         // output is always possible
@@ -355,15 +382,22 @@ struct VTermHandler {
     sel: Selector,
     sessions: SessionContainer<VTermSession>,
     mem: Rc<MemGate>,
+    switching: bool,
 }
 
 impl VTermHandler {
-    fn new_sess(crt: usize, sess: ServerSession) -> VTermSession {
-        log!(crate::LOG_DEF, "[{}] vterm::new_meta()", sess.ident());
+    fn new_sess(crt: usize, sess: ServerSession, console: usize) -> VTermSession {
+        log!(
+            crate::LOG_DEF,
+            "[{}] vterm::new_meta(console={})",
+            sess.ident(),
+            console + 1
+        );
         VTermSession {
             crt,
             sess,
-            data: SessionData::Meta,
+            console,
+            data: SessionData::Meta(History::default()),
             parent: None,
             childs: Vec::new(),
         }
@@ -372,6 +406,8 @@ impl VTermHandler {
     fn new_chan(
         &self,
         parent: SessId,
+        meta: SessId,
+        console: usize,
         crt: usize,
         sid: SessId,
         writing: bool,
@@ -381,7 +417,15 @@ impl VTermHandler {
         Ok(VTermSession {
             crt,
             sess: ServerSession::new_with_sel(self.sel, sels, crt, sid as u64, false)?,
-            data: SessionData::Chan(Channel::new(sid, self.mem.clone(), sels, writing)?),
+            console,
+            data: SessionData::Chan(Channel::new(
+                sid,
+                meta,
+                console,
+                self.mem.clone(),
+                sels,
+                writing,
+            )?),
             parent: Some(parent),
             childs: Vec::new(),
         })
@@ -402,6 +446,13 @@ impl VTermHandler {
                 let crt = sess.crt;
                 self.sessions.remove(crt, id);
 
+                // don't record lines in the history of a future session with the same id
+                for con in CONSOLES.borrow_mut().iter_mut() {
+                    if con.reader == Some(id) {
+                        con.reader = None;
+                    }
+                }
+
                 // remove us from parent
                 if let Some(pid) = parent {
                     if let Some(p) = self.sessions.get_mut(pid) {
@@ -422,7 +473,7 @@ impl VTermHandler {
     {
         let sess = self.sessions.get_mut(is.label() as SessId).unwrap();
         match &mut sess.data {
-            SessionData::Meta => Err(Error::new(Code::InvArgs)),
+            SessionData::Meta(_) => Err(Error::new(Code::InvArgs)),
             SessionData::Chan(c) => func(c, is),
         }
     }
@@ -437,10 +488,13 @@ impl Handler<VTermSession> for VTermHandler {
         &mut self,
         crt: usize,
         srv_sel: Selector,
-        _arg: &str,
+        arg: &str,
     ) -> Result<(Selector, SessId), Error> {
-        self.sessions
-            .add_next(crt, srv_sel, false, |sess| Ok(Self::new_sess(crt, sess)))
+        let console = args::parse_session_arg(arg, CONSOLES.borrow().len())?;
+
+        self.sessions.add_next(crt, srv_sel, false, |sess| {
+            Ok(Self::new_sess(crt, sess, console))
+        })
     }
 
     fn obtain(&mut self, crt: usize, sid: SessId, xchg: &mut CapExchange<'_>) -> Result<(), Error> {
@@ -459,15 +513,24 @@ impl Handler<VTermSession> for VTermHandler {
             let nsid = sessions.next_id()?;
             let sess = sessions.get(sid).unwrap();
             match &sess.data {
-                SessionData::Meta => match op {
+                SessionData::Meta(_) => match op {
                     GenFileOp::CLONE => self
-                        .new_chan(sid, crt, nsid, xchg.in_args().pop::<i32>()? == 1)
+                        .new_chan(
+                            sid,
+                            sid,
+                            sess.console,
+                            crt,
+                            nsid,
+                            xchg.in_args().pop::<i32>()? == 1,
+                        )
                         .map(|s| (nsid, s)),
                     _ => Err(Error::new(Code::InvArgs)),
                 },
 
                 SessionData::Chan(c) => match op {
-                    GenFileOp::CLONE => self.new_chan(sid, crt, nsid, c.writing).map(|s| (nsid, s)),
+                    GenFileOp::CLONE => self
+                        .new_chan(sid, c.meta, sess.console, crt, nsid, c.writing)
+                        .map(|s| (nsid, s)),
                     _ => Err(Error::new(Code::InvArgs)),
                 },
             }
@@ -498,7 +561,7 @@ impl Handler<VTermSession> for VTermHandler {
         let sessions = &mut self.sessions;
         let sess = sessions.get_mut(sid).unwrap();
         match &mut sess.data {
-            SessionData::Meta => Err(Error::new(Code::InvArgs)),
+            SessionData::Meta(_) => Err(Error::new(Code::InvArgs)),
             SessionData::Chan(c) => match op {
                 GenFileOp::SET_DEST => {
                     let sel = Activity::own().alloc_sel();
@@ -528,24 +591,34 @@ impl Handler<VTermSession> for VTermHandler {
     }
 }
 
-fn add_signal(hdl: &mut VTermHandler) {
+fn add_signal(hdl: &mut VTermHandler, console: usize) {
     hdl.sessions.for_each(|s| match &mut s.data {
-        SessionData::Chan(c) => {
+        SessionData::Chan(c) if c.console == console => {
             c.add_event(FileEvent::SIGNAL);
         },
-        SessionData::Meta => {},
+        _ => {},
     });
 }
 
-fn add_input(hdl: &mut VTermHandler, eof: bool, mut flush: bool, input: &mut RefMut<'_, Vec<u8>>) {
-    // pass to first session that wants input
-    EOF.set(eof);
+fn add_input(
+    hdl: &mut VTermHandler,
+    console: usize,
+    con: &mut Console,
+    eof: bool,
+    mut flush: bool,
+) {
+    // pass to first session of the console that wants input
+    con.eof = eof;
 
     hdl.sessions.for_each(|s| {
-        if flush || !input.is_empty() {
+        if flush || !con.input.is_empty() {
             if let SessionData::Chan(c) = &mut s.data {
+                if c.console != console {
+                    return;
+                }
+
                 if let Some(msg) = c.pending_nextin.take() {
-                    c.fetch_input(input).unwrap();
+                    c.fetch_input(con).unwrap();
                     reply_vmsg_late!(msg, Code::Success, c.pos, c.len - c.pos).unwrap();
                     flush = false;
                 }
@@ -568,74 +641,177 @@ fn receive_acks(hdl: &mut VTermHandler) {
                 }
             }
         },
-        SessionData::Meta => {},
+        SessionData::Meta(_) => {},
     });
 }
 
-fn handle_input(hdl: &mut VTermHandler, msg: &'static Message) {
-    let mut input = INPUT.borrow_mut();
-    let mut buffer = BUFFER.borrow_mut();
+/// Determines the meta session of the channel that receives the next input of given console
+fn input_session(sessions: &mut SessionContainer<VTermSession>, console: usize) -> Option<SessId> {
+    // like add_input, use the first session that wants input
+    let mut reader = None;
+    sessions.for_each(|s| {
+        if let SessionData::Chan(c) = &s.data {
+            let wants_input = c.console == console
+                && (c.pending_nextin.is_some() || c.notify_events.contains(FileEvent::INPUT));
+            if reader.is_none() && wants_input {
+                reader = Some(c.id);
+            }
+        }
+    });
+
+    let mut sid = reader?;
+    loop {
+        let sess = sessions.get(sid)?;
+        match sess.data {
+            SessionData::Meta(_) => break Some(sid),
+            SessionData::Chan(_) => sid = sess.parent?,
+        }
+    }
+}
+
+fn history(sessions: &mut SessionContainer<VTermSession>, sid: SessId) -> Option<&mut History> {
+    match &mut sessions.get_mut(sid)?.data {
+        SessionData::Meta(h) => Some(h),
+        SessionData::Chan(_) => None,
+    }
+}
+
+fn feed_input(hdl: &mut VTermHandler, bytes: &[u8]) {
+    let console = ACTIVE.get();
+    let mut consoles = CONSOLES.borrow_mut();
+    let con = &mut consoles[console];
 
-    let bytes = unsafe { core::slice::from_raw_parts(msg.data.as_ptr(), msg.header.length()) };
     let mut flush = false;
     let mut eof = false;
-    if MODE.get() == Mode::RAW {
-        input.extend_from_slice(bytes);
+    if con.mode == Mode::RAW {
+        con.input.extend_from_slice(bytes);
     }
     else {
+        // completed lines are recorded in the history of the session that will receive them. if
+        // nobody waits for input yet (e.g., because the previous command is still running), we
+        // assume that the session that received the last input will also receive this line.
+        // without such a session, the console's own history is used.
+        let hist_sess = input_session(&mut hdl.sessions, console).or(con.reader);
+
+        let sessions = &mut hdl.sessions;
+        let mut signal = false;
         let mut output = vec![];
         for b in bytes {
-            match b {
-                // ^D
-                0x04 => eof = true,
-                // ^C
-                0x03 => add_signal(hdl),
-                // backspace
-                0x7f => {
-                    output.push(0x08);
-                    output.push(b' ');
-                    output.push(0x08);
-                    buffer.pop();
+            let hist = match hist_sess.and_then(|sid| history(sessions, sid)) {
+                Some(h) => h,
+                None => &mut con.history,
+            };
+            match con.editor.handle(*b, Some(hist), &mut output) {
+                Some(Event::Line) => {
+                    let line = con.editor.take_line();
+                    con.record(&line);
+                    con.input.extend_from_slice(&line);
+                    flush = true;
                 },
-                b => {
-                    if *b == 27 {
-                        buffer.push(b'^');
-                        output.push(b'^');
-                    }
-                    else if *b == b'\n' {
-                        flush = true;
-                    }
-                    if *b == b'\n' || !b.is_ascii_control() {
-                        buffer.push(*b);
-                    }
+                Some(Event::Eof) => {
+                    let line = con.editor.take_line();
+                    con.record(&line);
+                    con.input.extend_from_slice(&line);
+                    eof = true;
                 },
+                Some(Event::Signal) => signal = true,
+                None => {},
             }
+        }
 
-            if *b == b'\n' || !b.is_ascii_control() {
-                output.push(*b);
-            }
+        con.echo(&output);
+
+        if signal {
+            add_signal(hdl, console);
         }
+    }
+
+    add_input(hdl, console, con, eof, eof || flush);
+}
+
+fn switch_console(console: usize) {
+    if console != ACTIVE.get() {
+        log!(crate::LOG_DEF, "vterm::switch(console={})", console + 1);
+        ACTIVE.set(console);
+        CONSOLES.borrow()[console].redraw();
+    }
+}
+
+fn handle_input(hdl: &mut VTermHandler, msg: &'static Message) {
+    let bytes = unsafe { core::slice::from_raw_parts(msg.data.as_ptr(), msg.header.length()) };
+
+    // the input up to the hotkey belongs to the current console, the input afterwards to the
+    // console that is selected with the character following the hotkey
+    let mut start = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        if hdl.switching {
+            hdl.switching = false;
+            // the hotkey twice sends the hotkey itself
+            if *b == SWITCH_KEY {
+                start = i;
+                continue;
+            }
 
-        if eof || flush {
-            input.extend_from_slice(&buffer);
-            buffer.clear();
+            let count = CONSOLES.borrow().len();
+            match (*b as char).to_digit(10) {
+                Some(n) if n >= 1 && n as usize <= count => switch_console(n as usize - 1),
+                _ => {},
+            }
+            start = i + 1;
+        }
+        else if *b == SWITCH_KEY {
+            if i > start {
+                feed_input(hdl, &bytes[start..i]);
+            }
+            hdl.switching = true;
         }
-        Serial::new().write(&output).unwrap();
     }
 
-    add_input(hdl, eof, eof || flush, &mut input);
+    if !hdl.switching && start < bytes.len() {
+        feed_input(hdl, &bytes[start..]);
+    }
+}
+
+fn usage() -> ! {
+    println!(
+        "Usage: {} [-n <consoles>] [-c <name>=<char>]...",
+        env::args().next().unwrap()
+    );
+    println!();
+    println!(
+        "  -n: the number of virtual consoles (1-{}, default 1).",
+        MAX_CONSOLES
+    );
+    println!("      The consoles have no names, but are numbered from 1 to <consoles>.");
+    println!("      ^A followed by the console number switches to that console.");
+    println!("      Clients choose their console with the session argument console=<n>.");
+    println!("  -c: sets the special character <name> of the cooked mode to <char>.");
+    println!("      <name> is eof, intr, erase, kill, or werase.");
+    println!("      <char> is a number or uses the caret notation (e.g., ^U).");
+    OwnActivity::exit_with(Code::InvArgs);
 }
 
 #[no_mangle]
 pub fn main() -> Result<(), Error> {
+    let args: Vec<&str> = env::args().collect();
+    let settings = args::parse_args(&args).unwrap_or_else(|e| {
+        println!("Invalid arguments: {}", e);
+        usage();
+    });
+
     let mut hdl = VTermHandler {
         sel: 0,
         sessions: SessionContainer::new(DEF_MAX_CLIENTS),
         mem: Rc::new(
             MemGate::new(DEF_MAX_CLIENTS * BUF_SIZE, Perm::RW).expect("Unable to alloc memory"),
         ),
+        switching: false,
     };
 
+    for _ in 0..settings.consoles {
+        CONSOLES.borrow_mut().push(Console::new(settings.chars));
+    }
+
     let s = Server::new("vterm", &mut hdl).expect("Unable to create service 'vterm'");
     hdl.sel = s.sel();
 
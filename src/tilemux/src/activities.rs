@@ -131,29 +131,56 @@ pub enum Event {
     Start,
 }
 
+/// The state of an activity that needs to be saved while it is not running.
+///
+/// This is kept in one place so that it can be transferred to another tile on migrations.
+#[repr(C)]
+struct ExecState {
+    user: arch::State,
+    #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
+    fpu: arch::FPUState,
+    cmd: helper::TCUCmdState,
+    wait: WaitState,
+}
+
+/// What a suspended activity waits for; only used to transfer it to another tile on migrations.
+#[repr(C)]
+#[derive(Default)]
+struct WaitState {
+    // the activity is made ready on resume
+    wakeup: bool,
+    // the activity is blocked until this EP has a message
+    ep: Option<tcu::EpId>,
+}
+
 pub struct Activity {
     state: ActState,
     prev: Option<NonNull<Activity>>,
     next: Option<NonNull<Activity>>,
     aspace: Option<paging::AddrSpace<PTAllocator>>,
     frames: Vec<Phys>,
-    #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
-    fpu_state: arch::FPUState,
-    user_state: arch::State,
+    exec: ExecState,
     user_state_addr: usize,
     scheduled: TimeInstant,
+    prio: kif::Priority,
     time_quota: Rc<TimeQuota>,
     cpu_time: TimeDuration,
     ctxsws: u64,
+    blocked: TimeInstant,
+    blocked_time: TimeDuration,
+    page_faults: u64,
+    desched_msgs: u64,
     wait_timeout: bool,
     wait_irq: Option<tmif::IRQId>,
     wait_ep: Option<tcu::EpId>,
     irq_mask: u32,
     act_reg: tcu::Reg,
     eps_start: tcu::EpId,
-    cmd: helper::TCUCmdState,
     pf_state: Option<PfState>,
     cont: Option<fn(&mut Activity) -> ContResult>,
+    suspended: bool,
+    wakeup: bool,
+    restored: bool,
     has_refs: bool,
 }
 
@@ -204,11 +231,16 @@ static IDLE: LazyStaticUnsafeCell<Box<Activity>> = LazyStaticUnsafeCell::default
 static OUR: LazyStaticUnsafeCell<Box<Activity>> = LazyStaticUnsafeCell::default();
 static CUR: StaticUnsafeCell<Option<Box<Activity>>> = StaticUnsafeCell::new(None);
 
-static RDY: StaticRefCell<BoxList<Activity>> = StaticRefCell::new(BoxList::new());
+// one ready list per priority class
+const EMPTY_LIST: BoxList<Activity> = BoxList::new();
+static RDY: StaticRefCell<[BoxList<Activity>; kif::PRIO_COUNT]> =
+    StaticRefCell::new([EMPTY_LIST; kif::PRIO_COUNT]);
 static BLK: StaticRefCell<BoxList<Activity>> = StaticRefCell::new(BoxList::new());
 
 static BOOTSTRAP: StaticCell<bool> = StaticCell::new(true);
 static PTS: StaticRefCell<Vec<Phys>> = StaticRefCell::new(Vec::new());
+// the stats of activities that exited on their own until the kernel fetched them
+static EXITED: StaticRefCell<Vec<(Id, kif::ActivityStats)>> = StaticRefCell::new(Vec::new());
 
 pub fn init() {
     extern "C" {
@@ -258,6 +290,7 @@ pub fn init() {
             kif::tilemux::IDLE_ID,
             idle_quota,
             quota::get_pt(quota::IDLE_ID).unwrap(),
+            kif::Priority::LOW,
             0,
             root_pt,
         )));
@@ -265,6 +298,7 @@ pub fn init() {
             kif::tilemux::ACT_ID,
             our_quota,
             quota::get_pt(quota::IDLE_ID).unwrap(),
+            kif::Priority::LOW,
             0,
             root_pt,
         )));
@@ -291,9 +325,19 @@ pub fn add(
     id: Id,
     time_quota: quota::Id,
     pt_quota: quota::Id,
+    prio: kif::Priority,
     eps_start: tcu::EpId,
 ) -> Result<(), Error> {
-    log!(crate::LOG_ACTS, "Created Activity {}", id);
+    log!(
+        crate::LOG_ACTS,
+        "Created Activity {} with priority {}",
+        id,
+        prio
+    );
+
+    if prio.val as usize >= kif::PRIO_COUNT {
+        return Err(Error::new(Code::InvArgs));
+    }
 
     let time_quota = quota::get_time(time_quota).unwrap();
     if time_quota.total() == 0 {
@@ -317,7 +361,9 @@ pub fn add(
         (0, None)
     };
 
-    let mut act = Box::new(Activity::new(id, time_quota, pt_quota, eps_start, root_pt));
+    let mut act = Box::new(Activity::new(
+        id, time_quota, pt_quota, prio, eps_start, root_pt,
+    ));
 
     if pex_env().tile_desc.has_virtmem() {
         act.frames.push(frame);
@@ -329,10 +375,24 @@ pub fn add(
         ACTIVITIES.get_mut()[id as usize] = Some(NonNull::new_unchecked(act.as_mut()));
     }
 
+    // forget the stats of a previous activity with the same id that has not been fetched
+    EXITED.borrow_mut().retain(|(aid, _)| *aid != id);
+
     make_blocked(act);
     Ok(())
 }
 
+pub fn stats(id: Id) -> Option<kif::ActivityStats> {
+    if let Some(act) = get_mut(id) {
+        return Some(act.stats());
+    }
+
+    // the stats of exited activities can only be fetched once
+    let mut exited = EXITED.borrow_mut();
+    let idx = exited.iter().position(|(aid, _)| *aid == id)?;
+    Some(exited.remove(idx).1)
+}
+
 pub fn get_mut(id: Id) -> Option<ActivityRef<'static>> {
     if id == kif::tilemux::ACT_ID {
         Some(our())
@@ -369,7 +429,22 @@ pub fn cur() -> ActivityRef<'static> {
 }
 
 pub fn has_ready() -> bool {
-    !RDY.borrow().is_empty()
+    RDY.borrow().iter().any(|l| !l.is_empty())
+}
+
+/// Returns true if there is a ready activity with at least the given priority
+pub fn has_ready_at_least(prio: kif::Priority) -> bool {
+    RDY.borrow()[prio.val as usize..]
+        .iter()
+        .any(|l| !l.is_empty())
+}
+
+fn pop_ready() -> Option<Box<Activity>> {
+    // always prefer activities of higher classes
+    RDY.borrow_mut()
+        .iter_mut()
+        .rev()
+        .find_map(|l| l.pop_front())
 }
 
 pub fn schedule(mut action: ScheduleAction) -> usize {
@@ -408,30 +483,45 @@ pub fn schedule(mut action: ScheduleAction) -> usize {
 
 fn do_schedule(mut action: ScheduleAction) -> usize {
     let now = TimeInstant::now();
-    let mut next = RDY
-        .borrow_mut()
-        .pop_front()
+
+    // if only activities of lower classes are ready, continue with the current activity, unless it
+    // blocks, is suspended, or is killed
+    if matches!(action, ScheduleAction::Yield | ScheduleAction::Preempt) && has_ready() {
+        if let Some(mut old) = try_cur() {
+            if old.id() != kif::tilemux::IDLE_ID && !old.suspended && !has_ready_at_least(old.prio)
+            {
+                old.charge_budget(now);
+                // refill the budget as we would do when switching to it
+                old.refill_budget();
+                return old.continue_running(now);
+            }
+        }
+    }
+
+    let mut next = pop_ready()
         // safety: we know that idle is stored in a Box
         .unwrap_or_else(|| unsafe { Box::from_raw(IDLE.get_mut().as_mut()) });
 
     let old_time = if let Some(mut old) = try_cur() {
         // reduce budget now in case we decide not to switch below
-        old.time_quota.set_left(
-            old.time_quota
-                .left()
-                .saturating_sub((now - old.scheduled).as_nanos() as u64),
-        );
+        old.charge_budget(now);
 
         // save TCU command registers; do that first while still running with that activity
-        old.cmd.save();
+        old.exec.cmd.save();
 
         // now change activity
         let old_id = tcu::TCU::xchg_activity(next.activity_reg()).unwrap();
 
         // are there messages left we care about?
-        if action == ScheduleAction::Block && !old.can_block((old_id >> 16) as u16) {
-            // if the activity has budget left (or there is no one else ready), continue with it
-            if old.time_quota.left() > 0 || next.id() == kif::tilemux::IDLE_ID {
+        if action == ScheduleAction::Block
+            && !old.suspended
+            && !old.can_block((old_id >> 16) as u16)
+        {
+            // if the activity has budget left and no activity of a higher class is ready (or there
+            // is no one else ready), continue with it
+            if (old.time_quota.left() > 0 && next.prio.val <= old.prio.val)
+                || next.id() == kif::tilemux::IDLE_ID
+            {
                 let next_id = tcu::TCU::xchg_activity(old_id).unwrap();
                 next.set_activity_reg(next_id);
                 if next.id() != kif::tilemux::IDLE_ID {
@@ -441,10 +531,7 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
                 else {
                     Box::into_raw(next);
                 }
-                let last_sched = old.scheduled;
-                old.cpu_time += now - last_sched;
-                old.scheduled = now;
-                return old.user_state_addr;
+                return old.continue_running(now);
             }
             // otherwise, preempt it
             else {
@@ -474,17 +561,11 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
 
     next.scheduled = now;
     // budget is immediately refilled but we prefer other activities while a budget is 0 (see make_ready)
-    if next.time_quota.left() == 0 {
-        // to keep it simple, we divide the time slice by the number of users to ensure that activities
-        // that share a time slice don't receive more than their share in total. the better approach
-        // might be to actually schedule quotas and not activities, but that seems like overkill here.
-        next.time_quota
-            .set_left(next.time_quota.total() / next.time_quota.users());
-    }
+    next.refill_budget();
     let next_budget = next.time_quota.left();
 
     // restore TCU command registers
-    next.cmd.restore();
+    next.exec.cmd.restore();
 
     // exchange CUR
     // safety: we do no longer hold a reference to `own`
@@ -505,12 +586,6 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
         if old.id() != kif::tilemux::IDLE_ID {
             // block, preempt or kill activity
             match action {
-                ScheduleAction::Block => {
-                    make_blocked(old);
-                },
-                ScheduleAction::Preempt | ScheduleAction::Yield => {
-                    make_ready(old, old_time);
-                },
                 ScheduleAction::Kill => {
                     let old_id = old.id();
                     // safety: we do not access `old` afterwards
@@ -518,6 +593,16 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
                         ACTIVITIES.get_mut()[old_id as usize] = None;
                     }
                 },
+                // suspended activities stay blocked until they are resumed
+                _ if old.suspended => {
+                    make_blocked(old);
+                },
+                ScheduleAction::Block => {
+                    make_blocked(old);
+                },
+                ScheduleAction::Preempt | ScheduleAction::Yield => {
+                    make_ready(old, old_time);
+                },
             }
         }
         else {
@@ -540,17 +625,20 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
 
 fn make_blocked(mut act: Box<Activity>) {
     act.state = ActState::Blocked;
+    act.blocked = TimeInstant::now();
     BLK.borrow_mut().push_back(act);
 }
 
 fn make_ready(mut act: Box<Activity>, budget: TimeDuration) {
     act.state = ActState::Ready;
+    let mut rdy = RDY.borrow_mut();
+    let list = &mut rdy[act.prio.val as usize];
     // prefer activities with budget
     if !budget.is_zero() {
-        RDY.borrow_mut().push_front(act);
+        list.push_front(act);
     }
     else {
-        RDY.borrow_mut().push_back(act);
+        list.push_back(act);
     }
 }
 
@@ -566,7 +654,13 @@ pub fn remove(id: Id, status: Code, notify: bool, sched: bool) {
         let old = match unsafe { &v.as_ref().state } {
             // safety: we don't access `v` afterwards
             ActState::Running => unsafe { CUR.set(None).unwrap() },
-            ActState::Ready => RDY.borrow_mut().remove_if(|v| v.id() == id).unwrap(),
+            ActState::Ready => {
+                // safety: see above
+                let prio = unsafe { v.as_ref().prio };
+                RDY.borrow_mut()[prio.val as usize]
+                    .remove_if(|v| v.id() == id)
+                    .unwrap()
+            },
             ActState::Blocked => BLK.borrow_mut().remove_if(|v| v.id() == id).unwrap(),
         };
         // we now can't access `v` anymore
@@ -588,6 +682,9 @@ pub fn remove(id: Id, status: Code, notify: bool, sched: bool) {
         helper::flush_cache();
 
         if notify {
+            // the kernel fetches the final stats after it received the exit message
+            EXITED.borrow_mut().push((old.id(), old.stats()));
+
             // change to our activity (no need to save old act_reg; activity is dead)
             let pex_is_running = (tcu::TCU::get_cur_activity() & 0xFFFF) == kif::tilemux::ACT_ID;
             if !pex_is_running {
@@ -619,6 +716,7 @@ impl Activity {
         id: Id,
         time_quota: Rc<Quota<u64>>,
         pt_quota: Rc<PTQuota>,
+        prio: kif::Priority,
         eps_start: tcu::EpId,
         root_pt: Option<GlobAddr>,
     ) -> Self {
@@ -636,22 +734,33 @@ impl Activity {
             frames: Vec::new(),
             act_reg: id,
             state: ActState::Blocked,
-            #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
-            fpu_state: arch::FPUState::default(),
-            user_state: arch::State::default(),
+            exec: ExecState {
+                user: arch::State::default(),
+                #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
+                fpu: arch::FPUState::default(),
+                cmd: helper::TCUCmdState::new(),
+                wait: WaitState::default(),
+            },
             user_state_addr: 0,
+            prio,
             time_quota,
             cpu_time: TimeDuration::ZERO,
             ctxsws: 0,
             scheduled: TimeInstant::now(),
+            blocked: TimeInstant::now(),
+            blocked_time: TimeDuration::ZERO,
+            page_faults: 0,
+            desched_msgs: 0,
             wait_timeout: false,
             wait_irq: None,
             wait_ep: None,
             irq_mask: 0,
             eps_start,
-            cmd: helper::TCUCmdState::new(),
             pf_state: None,
             cont: None,
+            suspended: false,
+            wakeup: false,
+            restored: false,
             has_refs: false,
         }
     }
@@ -681,6 +790,10 @@ impl Activity {
         self.state
     }
 
+    pub fn prio(&self) -> kif::Priority {
+        self.prio
+    }
+
     pub fn activity_reg(&self) -> tcu::Reg {
         self.act_reg
     }
@@ -691,7 +804,7 @@ impl Activity {
 
     #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
     pub fn fpu_state(&mut self) -> &mut arch::FPUState {
-        &mut self.fpu_state
+        &mut self.exec.fpu
     }
 
     pub fn eps_start(&self) -> tcu::EpId {
@@ -708,6 +821,7 @@ impl Activity {
 
     pub fn add_msg(&mut self) {
         self.act_reg += 1 << 16;
+        self.desched_msgs += 1;
     }
 
     pub fn rem_msgs(&mut self, count: u16) {
@@ -719,8 +833,36 @@ impl Activity {
         TimeDuration::from_nanos(self.time_quota.left())
     }
 
+    /// Subtracts the time since the activity has been scheduled from its budget
+    fn charge_budget(&mut self, now: TimeInstant) {
+        self.time_quota.set_left(
+            self.time_quota
+                .left()
+                .saturating_sub((now - self.scheduled).as_nanos() as u64),
+        );
+    }
+
+    /// Refills the budget if it has been used up
+    fn refill_budget(&mut self) {
+        if self.time_quota.left() == 0 {
+            // to keep it simple, we divide the time slice by the number of users to ensure that
+            // activities that share a time slice don't receive more than their share in total. the
+            // better approach might be to actually schedule quotas and not activities, but that
+            // seems like overkill here.
+            self.time_quota
+                .set_left(self.time_quota.total() / self.time_quota.users());
+        }
+    }
+
+    /// Continues with this activity without switching and returns its state address
+    fn continue_running(&mut self, now: TimeInstant) -> usize {
+        self.cpu_time += now - self.scheduled;
+        self.scheduled = now;
+        self.user_state_addr
+    }
+
     pub fn user_state(&mut self) -> &mut arch::State {
-        &mut self.user_state
+        &mut self.exec.user
     }
 
     pub fn reset_stats(&mut self) -> TimeDuration {
@@ -744,6 +886,25 @@ impl Activity {
         old_time
     }
 
+    pub fn stats(&self) -> kif::ActivityStats {
+        let now = TimeInstant::now();
+        let mut cpu_time = self.cpu_time;
+        let mut blocked_time = self.blocked_time;
+        match self.state {
+            ActState::Running => cpu_time += now - self.scheduled,
+            ActState::Blocked => blocked_time += now - self.blocked,
+            ActState::Ready => {},
+        }
+
+        kif::ActivityStats {
+            cpu_time: cpu_time.as_nanos() as u64,
+            blocked_time: blocked_time.as_nanos() as u64,
+            ctxsws: self.ctxsws,
+            page_faults: self.page_faults,
+            desched_msgs: self.desched_msgs,
+        }
+    }
+
     pub fn irq_mask(&self) -> u32 {
         self.irq_mask
     }
@@ -829,8 +990,19 @@ impl Activity {
             return false;
         }
 
+        // remember the event for suspended activities and make them ready on resume
+        if self.suspended {
+            if !matches!(event, Event::Timeout) && self.wait_timeout {
+                timer::remove(self.id());
+                self.wait_timeout = false;
+            }
+            self.wakeup = true;
+            return true;
+        }
+
         if self.state == ActState::Blocked {
             let mut act = BLK.borrow_mut().remove_if(|v| v.id() == self.id()).unwrap();
+            act.blocked_time += TimeInstant::now() - act.blocked;
             if !matches!(event, Event::Timeout) && act.wait_timeout {
                 timer::remove(act.id());
                 act.wait_timeout = false;
@@ -844,6 +1016,118 @@ impl Activity {
         true
     }
 
+    pub fn suspend(&mut self) {
+        log!(crate::LOG_ACTS, "Suspending Activity {}", self.id());
+
+        if self.suspended {
+            return;
+        }
+        self.suspended = true;
+
+        match self.state {
+            // we cannot block the current activity here; do that via scheduling
+            ActState::Running => {
+                self.wakeup = true;
+                crate::reg_scheduling(ScheduleAction::Yield);
+            },
+            ActState::Ready => {
+                self.wakeup = true;
+                let act = RDY.borrow_mut()[self.prio.val as usize]
+                    .remove_if(|v| v.id() == self.id())
+                    .unwrap();
+                make_blocked(act);
+            },
+            // blocked activities are only made ready on resume if they are unblocked meanwhile
+            ActState::Blocked => {
+                self.wakeup = false;
+            },
+        }
+    }
+
+    pub fn resume(&mut self) {
+        log!(crate::LOG_ACTS, "Resuming Activity {}", self.id());
+
+        if !self.suspended {
+            return;
+        }
+        self.suspended = false;
+
+        // the wait state has been copied from another tile after restore_state. Messages that
+        // arrived since then were checked against the wrong wait state; thus check them again.
+        if self.restored {
+            self.restored = false;
+            self.wait_ep = self.exec.wait.ep;
+            self.wakeup = self.exec.wait.wakeup || !self.can_block(self.msgs());
+        }
+
+        if self.wakeup {
+            self.wakeup = false;
+            if self.state == ActState::Blocked {
+                let mut act = BLK.borrow_mut().remove_if(|v| v.id() == self.id()).unwrap();
+                act.blocked_time += TimeInstant::now() - act.blocked;
+                let budget = TimeDuration::from_nanos(act.time_quota.left());
+                make_ready(act, budget);
+            }
+            if self.state != ActState::Running {
+                crate::reg_scheduling(ScheduleAction::Yield);
+            }
+        }
+    }
+
+    /// Prepares the execution state of this activity for the migration to another tile and returns
+    /// the global address and size of the state.
+    ///
+    /// The activity needs to be suspended and must not wait for the pager or interrupts.
+    pub fn save_state(&mut self) -> Result<(GlobAddr, usize), Error> {
+        if !self.suspended || self.state == ActState::Running || self.user_state_addr == 0 {
+            return Err(Error::new(Code::InvState));
+        }
+        if self.pf_state.is_some() || self.cont.is_some() || self.irq_mask != 0 {
+            return Err(Error::new(Code::InvState));
+        }
+
+        arch::flush_fpu(self);
+
+        // waits with timeout end on the destination tile, which looks like a spurious wakeup
+        self.exec.wait = WaitState {
+            wakeup: self.wakeup || self.wait_timeout,
+            ep: self.wait_ep,
+        };
+
+        // write back all cachelines so that the memory of the activity is up to date in DRAM
+        helper::flush_cache();
+
+        Ok((Self::state_addr(&self.exec), size_of::<ExecState>()))
+    }
+
+    /// Prepares this freshly initialized activity to receive the execution state from another tile
+    /// and returns the global address and size of the state.
+    ///
+    /// The activity stays suspended until it is resumed, which continues to wait for what it
+    /// waited for on the other tile (see `resume`).
+    pub fn restore_state(&mut self) -> Result<(GlobAddr, usize), Error> {
+        if self.user_state_addr != 0 {
+            return Err(Error::new(Code::InvState));
+        }
+
+        if let Some(ref aspace) = self.aspace {
+            aspace.flush_tlb();
+        }
+        self.user_state_addr = &self.exec.user as *const _ as usize;
+        self.suspended = true;
+        self.restored = true;
+        self.blocked = TimeInstant::now();
+
+        Ok((Self::state_addr(&self.exec), size_of::<ExecState>()))
+    }
+
+    fn state_addr(state: &ExecState) -> GlobAddr {
+        // our data is mapped 1:1 to our memory region (see init)
+        let (mem_tile, mem_base, _, _) = tcu::TCU::unpack_mem_ep(0).unwrap();
+        let virt = state as *const _ as usize;
+        GlobAddr::new_with(mem_tile, mem_base) + (virt - cfg::MEM_OFFSET) as goff
+    }
+
     pub fn consume_time(&mut self) {
         let now = TimeInstant::now();
         let duration = now - self.scheduled;
@@ -852,13 +1136,14 @@ impl Activity {
                 .left()
                 .saturating_sub(duration.as_nanos() as u64),
         );
-        if self.time_quota.left() == 0 && has_ready() {
+        if self.time_quota.left() == 0 && has_ready_at_least(self.prio) {
             crate::reg_scheduling(ScheduleAction::Preempt);
         }
     }
 
     pub fn start_pf(&mut self, pf_state: PfState) {
         self.pf_state = Some(pf_state);
+        self.page_faults += 1;
     }
 
     pub fn finish_pf(&mut self) -> PfState {
@@ -876,12 +1161,14 @@ impl Activity {
         crate::app_env().boot.platform = pex_env().platform;
         if self.id() != kif::tilemux::IDLE_ID {
             arch::init_state(
-                &mut self.user_state,
+                &mut self.exec.user,
                 crate::app_env().entry as usize,
                 crate::app_env().sp as usize,
             );
         }
-        self.user_state_addr = &self.user_state as *const _ as usize;
+        self.user_state_addr = &self.exec.user as *const _ as usize;
+        // the time until the start does not count as blocked time
+        self.blocked = TimeInstant::now();
     }
 
     pub fn switch_to(&self) {
@@ -32,6 +32,10 @@ pub fn forget_fpu(_act_id: activities::Id) {
     // no FPU support
 }
 
+pub fn flush_fpu(_act: &mut activities::Activity) {
+    // no FPU support
+}
+
 pub fn disable_fpu() {
     // no FPU support
 }
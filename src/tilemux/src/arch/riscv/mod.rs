@@ -83,6 +83,15 @@ pub fn forget_fpu(act_id: activities::Id) {
     }
 }
 
+pub fn flush_fpu(act: &mut activities::Activity) {
+    // if the activity owns the FPU, its registers are not saved yet
+    if FPU_OWNER.get() == act.id() {
+        write_csr!("sstatus", set_fpu_mode(read_csr!("sstatus"), FSMode::CLEAN));
+        unsafe { save_fpu(act.fpu_state()) };
+        FPU_OWNER.set(tilemux::ACT_ID);
+    }
+}
+
 pub fn disable_fpu() {
     let mut cur = activities::cur();
     if cur.id() != FPU_OWNER.get() {
@@ -66,6 +66,24 @@ pub fn forget_fpu(act_id: activities::Id) {
     }
 }
 
+pub fn flush_fpu(act: &mut activities::Activity) {
+    // if the activity owns the FPU, its registers are not saved yet
+    if FPU_OWNER.get() == act.id() {
+        let cr0 = read_csr!("cr0");
+        write_csr!("cr0", cr0 & !CR0_TASK_SWITCHED);
+        let fpu_state = act.fpu_state();
+        unsafe {
+            asm!(
+                "fxsave [{0}]",
+                in(reg) &fpu_state.data,
+                options(nostack),
+            )
+        };
+        write_csr!("cr0", cr0);
+        FPU_OWNER.set(tilemux::ACT_ID);
+    }
+}
+
 pub fn disable_fpu() {
     if activities::cur().id() != FPU_OWNER.get() {
         write_csr!("cr0", read_csr!("cr0") | CR0_TASK_SWITCHED);
@@ -45,14 +45,15 @@ fn activity_init(msg: &'static tcu::Message) -> Result<(), Error> {
 
     log!(
         crate::LOG_SIDECALLS,
-        "sidecall::activity_init(act={}, time={}, pt={}, eps_start={})",
+        "sidecall::activity_init(act={}, time={}, pt={}, prio={}, eps_start={})",
         r.act_id,
         r.time_quota,
         r.pt_quota,
+        r.prio,
         r.eps_start
     );
 
-    activities::add(r.act_id, r.time_quota, r.pt_quota, r.eps_start)
+    activities::add(r.act_id, r.time_quota, r.pt_quota, r.prio, r.eps_start)
 }
 
 fn activity_ctrl(msg: &'static tcu::Message) -> Result<(), Error> {
@@ -79,6 +80,18 @@ fn activity_ctrl(msg: &'static tcu::Message) -> Result<(), Error> {
             Ok(())
         },
 
+        kif::tilemux::ActivityOp::SUSPEND => {
+            let mut act = activities::get_mut(r.act_id).ok_or_else(|| Error::new(Code::InvArgs))?;
+            act.suspend();
+            Ok(())
+        },
+
+        kif::tilemux::ActivityOp::RESUME => {
+            let mut act = activities::get_mut(r.act_id).ok_or_else(|| Error::new(Code::InvArgs))?;
+            act.resume();
+            Ok(())
+        },
+
         _ => {
             // we cannot remove the current activity here; remove it via scheduling
             match activities::try_cur() {
@@ -263,6 +276,50 @@ fn reset_stats(_msg: &'static tcu::Message) -> Result<(), Error> {
     Ok(())
 }
 
+fn activity_stats(msg: &'static tcu::Message) -> Result<kif::ActivityStats, Error> {
+    let r: kif::tilemux::ActStats = get_request(msg)?;
+
+    log!(
+        crate::LOG_SIDECALLS,
+        "sidecall::activity_stats(act={})",
+        r.act_id
+    );
+
+    activities::stats(r.act_id).ok_or_else(|| Error::new(Code::InvArgs))
+}
+
+fn activity_save(msg: &'static tcu::Message) -> Result<(GlobAddr, usize), Error> {
+    let r: kif::tilemux::ActSave = get_request(msg)?;
+
+    log!(
+        crate::LOG_SIDECALLS,
+        "sidecall::activity_save(act={})",
+        r.act_id
+    );
+
+    let mut act = activities::get_mut(r.act_id).ok_or_else(|| Error::new(Code::InvArgs))?;
+    act.save_state()
+}
+
+fn activity_restore(msg: &'static tcu::Message) -> Result<(GlobAddr, usize), Error> {
+    let r: kif::tilemux::ActRestore = get_request(msg)?;
+
+    log!(
+        crate::LOG_SIDECALLS,
+        "sidecall::activity_restore(act={})",
+        r.act_id
+    );
+
+    let cur = activities::cur();
+    assert!(cur.id() != r.act_id);
+    let mut act = activities::get_mut(r.act_id).ok_or_else(|| Error::new(Code::InvArgs))?;
+    // temporary switch to the activity to flush its TLB entries (see ActivityOp::START)
+    act.switch_to();
+    let res = act.restore_state();
+    cur.switch_to();
+    res
+}
+
 fn shutdown(_msg: &'static tcu::Message) -> Result<(), Error> {
     log!(crate::LOG_SIDECALLS, "sidecall::shutdown()",);
 
@@ -277,6 +334,21 @@ fn handle_sidecall(msg: &'static tcu::Message) {
     let mut val1 = 0;
     let mut val2 = 0;
     let op: kif::tilemux::Sidecalls = de.pop().unwrap();
+
+    // the stats don't fit into the default response
+    if op == kif::tilemux::Sidecalls::ACT_STATS {
+        let mut reply_buf = MsgBuf::borrow_def();
+        match activity_stats(msg) {
+            Ok(stats) => base::build_vmsg!(reply_buf, Code::Success, stats),
+            Err(e) => {
+                log!(crate::LOG_SIDECALLS, "sidecall {} failed: {}", op, e);
+                base::build_vmsg!(reply_buf, e.code())
+            },
+        }
+        reply_msg(msg, &reply_buf);
+        return;
+    }
+
     let res = match op {
         kif::tilemux::Sidecalls::ACT_INIT => activity_init(msg),
         kif::tilemux::Sidecalls::ACT_CTRL => activity_ctrl(msg),
@@ -297,6 +369,14 @@ fn handle_sidecall(msg: &'static tcu::Message) {
         kif::tilemux::Sidecalls::SET_QUOTA => set_quota(msg),
         kif::tilemux::Sidecalls::REMOVE_QUOTAS => remove_quotas(msg),
         kif::tilemux::Sidecalls::RESET_STATS => reset_stats(msg),
+        kif::tilemux::Sidecalls::ACT_SAVE => activity_save(msg).map(|(addr, size)| {
+            val1 = addr.raw();
+            val2 = size as u64;
+        }),
+        kif::tilemux::Sidecalls::ACT_RESTORE => activity_restore(msg).map(|(addr, size)| {
+            val1 = addr.raw();
+            val2 = size as u64;
+        }),
         kif::tilemux::Sidecalls::SHUTDOWN => shutdown(msg),
         _ => Err(Error::new(Code::NotSup)),
     };
@@ -67,8 +67,9 @@ pub fn remove(act: activities::Id) {
 pub fn reprogram() {
     // determine the remaining budget of the current activity, if there is any
     let budget = activities::try_cur().and_then(|cur| {
-        // don't use a budget if there is no ready activity or we're idling
-        if activities::has_ready() && cur.id() != kif::tilemux::IDLE_ID {
+        // don't use a budget if there is no ready activity of at least the same class or we're
+        // idling
+        if activities::has_ready_at_least(cur.prio()) && cur.id() != kif::tilemux::IDLE_ID {
             Some(cur.budget_left())
         }
         else {
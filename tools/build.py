@@ -1,15 +1,12 @@
 dirs = [
     'elf2hex',
-    'exm3fs',
     'gem52otf',
     'gem5log',
     'hwitrace',
     'ignoreint',
-    'm3fsck',
-    'mkm3fs',
+    'm3fstool',
     'netdbg',
     'setpgrp',
-    'shm3fs',
 ]
 
 
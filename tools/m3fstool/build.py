@@ -0,0 +1,8 @@
+from ninjapie import SourcePath
+
+
+def build(gen, env):
+    # the on-disk layout is shared with m3fs and therefore lives outside of this directory
+    deps = env.glob(gen, SourcePath('src/libs/rust/m3fs_layout/**/*.rs'))
+    bin = env.rust_exe(gen, out='m3fstool', deps=deps)
+    env.install(gen, env['TOOLDIR'], bin)
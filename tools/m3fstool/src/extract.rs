@@ -0,0 +1,54 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+use m3fs_layout::InodeNo;
+
+use crate::error::Error;
+use crate::image::{is_dir, is_link, Image};
+
+/// Copies the file, link, or directory tree at `src` in the image to `dst` on the host.
+///
+/// `dst` must not exist.
+pub fn extract(img: &Image, src: &str, dst: &Path) -> Result<(), Error> {
+    let ino = img.lookup(src)?;
+    extract_rec(img, ino, dst)
+}
+
+fn extract_rec(img: &Image, ino: InodeNo, dst: &Path) -> Result<(), Error> {
+    let inode = img.inode(ino);
+    if is_dir(inode.mode) {
+        fs::create_dir(dst)?;
+        for (name, child) in img.dir_entries(&inode)? {
+            if name != "." && name != ".." {
+                extract_rec(img, child, &dst.join(name))?;
+            }
+        }
+    }
+    else if is_link(inode.mode) {
+        let target = img.read_file(&inode);
+        symlink(String::from_utf8_lossy(&target).as_ref(), dst)?;
+    }
+    else {
+        if dst.exists() {
+            return Err(Error::Exists(dst.display().to_string()));
+        }
+        fs::write(dst, img.read_file(&inode))?;
+    }
+    Ok(())
+}
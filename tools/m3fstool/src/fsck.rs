@@ -0,0 +1,339 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3fs_layout::{
+    block_checksum, BlockNo, Extent, INode, InodeNo, JournalHeader, INODE_DIR_COUNT, NO_CHECKSUM,
+};
+
+use crate::image::{is_dir, is_link, is_reg, Image};
+
+struct Checker<'i> {
+    img: &'i Image,
+    blocks: Vec<bool>,
+    inodes: Vec<bool>,
+    links: Vec<u32>,
+    errors: Vec<String>,
+}
+
+impl<'i> Checker<'i> {
+    fn error(&mut self, msg: String) {
+        self.errors.push(msg);
+    }
+
+    fn valid_block(&self, bno: BlockNo) -> bool {
+        let sb = self.img.superblock();
+        bno >= sb.first_data_block() && bno < sb.total_blocks
+    }
+
+    fn use_block(&mut self, ino: InodeNo, bno: BlockNo) -> bool {
+        if !self.valid_block(bno) {
+            self.error(format!("Inode {} refers to invalid block {}", ino, bno));
+            false
+        }
+        else if self.blocks[bno as usize] {
+            self.error(format!("Block {} is used (at least) twice", bno));
+            false
+        }
+        else {
+            self.blocks[bno as usize] = true;
+            true
+        }
+    }
+
+    /// Checks the indirect blocks of the given inode and returns false if the extents cannot be
+    /// accessed safely
+    fn check_indirect(&mut self, ino: InodeNo, inode: &INode) -> bool {
+        let epb = self.img.superblock().extents_per_block() as u32;
+        let dir_count = INODE_DIR_COUNT as u32;
+
+        if inode.extents > self.img.max_extents() {
+            self.error(format!(
+                "Inode {} has too many extents ({})",
+                ino, inode.extents
+            ));
+            return false;
+        }
+
+        if inode.extents > dir_count {
+            if inode.indirect == 0 {
+                self.error(format!(
+                    "Inode {} has {} extents, but indirect pointer is 0",
+                    ino, inode.extents
+                ));
+                return false;
+            }
+            if !self.use_block(ino, inode.indirect) {
+                return false;
+            }
+        }
+        else if inode.indirect != 0 {
+            self.error(format!(
+                "Inode {} has {} extents, but indirect pointer is NOT 0",
+                ino, inode.extents
+            ));
+        }
+
+        if inode.extents > dir_count + epb {
+            if inode.dindirect == 0 {
+                self.error(format!(
+                    "Inode {} has {} extents, but double-indirect pointer is 0",
+                    ino, inode.extents
+                ));
+                return false;
+            }
+            if !self.use_block(ino, inode.dindirect) {
+                return false;
+            }
+
+            let count = (inode.extents - (dir_count + epb) + epb - 1) / epb;
+            for i in 0..epb {
+                let ext = self.img.block_extent(inode.dindirect, i);
+                if i < count {
+                    if ext.start == 0 || ext.length != 1 {
+                        self.error(format!(
+                            "Double-indirect entry {} of inode {} is invalid ({:?})",
+                            i, ino, ext
+                        ));
+                        return false;
+                    }
+                    if !self.use_block(ino, ext.start) {
+                        return false;
+                    }
+                }
+                else if ext != Extent::default() {
+                    self.error(format!(
+                        "Double-indirect entry {} of inode {} is NOT empty",
+                        i, ino
+                    ));
+                }
+            }
+        }
+        else if inode.dindirect != 0 {
+            self.error(format!(
+                "Inode {} has {} extents, but double-indirect pointer is NOT 0",
+                ino, inode.extents
+            ));
+        }
+        true
+    }
+
+    fn check_inode(&mut self, ino: InodeNo, pending: &mut Vec<InodeNo>) {
+        let inode = self.img.inode(ino);
+        if inode.inode != ino {
+            self.error(format!(
+                "Inode {} says that its inode number is {}",
+                ino, inode.inode
+            ));
+        }
+        if !is_dir(inode.mode) && !is_reg(inode.mode) && !is_link(inode.mode) {
+            self.error(format!("Inode {} has invalid mode {:o}", ino, inode.mode));
+            return;
+        }
+
+        if !self.check_indirect(ino, &inode) {
+            return;
+        }
+
+        let total_blocks = self.img.superblock().total_blocks as u64;
+        let mut count = 0u64;
+        let mut blocks_ok = true;
+        for i in 0..inode.extents {
+            let ext = self.img.extent(&inode, i);
+            // holes do not use any blocks, but are not allowed in directories
+            if ext.is_hole() && !is_dir(inode.mode) {
+                count += ext.length as u64;
+                continue;
+            }
+
+            let end = ext.start as u64 + ext.length as u64;
+            if ext.length == 0 || !self.valid_block(ext.start) || end > total_blocks {
+                self.error(format!(
+                    "Extent {} of inode {} is invalid ({:?})",
+                    i, ino, ext
+                ));
+                blocks_ok = false;
+                continue;
+            }
+            for bno in ext.block_range() {
+                blocks_ok &= self.use_block(ino, bno);
+            }
+            count += ext.length as u64;
+        }
+
+        let bs = self.img.superblock().block_size as u64;
+        if count * bs < inode.size {
+            self.error(format!(
+                "Inode {} has a size of {} bytes, but only {} blocks",
+                ino, inode.size, count
+            ));
+            return;
+        }
+
+        if is_dir(inode.mode) && blocks_ok {
+            self.check_dir(ino, &inode, pending);
+        }
+    }
+
+    fn check_dir(&mut self, ino: InodeNo, inode: &INode, pending: &mut Vec<InodeNo>) {
+        let total = self.img.superblock().total_inodes;
+        for bno in self.img.blocks(inode) {
+            let entries = match self.img.block_entries(bno) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.error(format!("Directory {} is corrupt: {:?}", ino, e));
+                    continue;
+                },
+            };
+
+            for (name, child) in entries {
+                if child >= total {
+                    self.error(format!(
+                        "Found invalid inode number {} in directory {}, entry '{}'",
+                        child, ino, name
+                    ));
+                    continue;
+                }
+
+                self.links[child as usize] += 1;
+                if name != "." && name != ".." && !self.inodes[child as usize] {
+                    self.inodes[child as usize] = true;
+                    pending.push(child);
+                }
+            }
+        }
+    }
+
+    fn check_bitmap<F>(&mut self, name: &str, used: &[bool], free: u32, first_free: u32, marked: F)
+    where
+        F: Fn(&Image, u32) -> bool,
+    {
+        let mut count = 0;
+        let mut first = None;
+        for (i, used) in used.iter().enumerate() {
+            let i = i as u32;
+            let is_marked = marked(self.img, i);
+            if !is_marked {
+                count += 1;
+            }
+            if !*used && first.is_none() {
+                first = Some(i);
+            }
+
+            if *used && !is_marked {
+                self.error(format!("{} {} is in use, but NOT marked used", name, i));
+            }
+            else if !*used && is_marked {
+                self.error(format!("{} {} is NOT in use, but marked used", name, i));
+            }
+        }
+
+        if count != free {
+            self.error(format!(
+                "Superblock says {} free {}s, but bitmap has {} free {}s",
+                free, name, count, name
+            ));
+        }
+        let first = first.unwrap_or(used.len() as u32);
+        if first_free > first {
+            self.error(format!(
+                "First free {} in superblock is wrong (is {}, should be at most {})",
+                name, first_free, first
+            ));
+        }
+    }
+}
+
+/// Checks the consistency of the given image and returns the found errors.
+///
+/// The check walks the directory tree starting at the root directory. It verifies the extents of
+/// all reachable inodes, the directory entries, the link counts, and compares the used inodes and
+/// blocks with the bitmaps and the superblock. Finally, it verifies the checksums of all used
+/// blocks, if the image has checksums.
+pub fn check(img: &Image) -> Vec<String> {
+    let sb = img.superblock();
+    let mut chk = Checker {
+        img,
+        blocks: vec![false; sb.total_blocks as usize],
+        inodes: vec![false; sb.total_inodes as usize],
+        links: vec![0; sb.total_inodes as usize],
+        errors: Vec::new(),
+    };
+
+    // a committed transaction in the journal means that the bitmaps and inodes are not up to date
+    if sb.journal_blocks > 0 {
+        if let Some((hd, _)) = JournalHeader::load(img.block(sb.first_journal_block())) {
+            chk.error(format!(
+                "Journal contains a pending transaction ({} blocks); mount with m3fs first",
+                hd.count
+            ));
+            return chk.errors;
+        }
+    }
+
+    // superblock, bitmaps, inodes, and journal are always used
+    for bno in 0..sb.first_data_block() {
+        chk.blocks[bno as usize] = true;
+    }
+
+    // collect all inodes and blocks from the directory tree
+    chk.inodes[0] = true;
+    let mut pending = vec![0];
+    while let Some(ino) = pending.pop() {
+        chk.check_inode(ino, &mut pending);
+    }
+
+    for ino in 0..sb.total_inodes {
+        if chk.inodes[ino as usize] {
+            let links = img.inode(ino).links as u32;
+            if links != chk.links[ino as usize] {
+                chk.error(format!(
+                    "Inode {} has {} links, but {} directory entries",
+                    ino, links, chk.links[ino as usize]
+                ));
+            }
+        }
+    }
+
+    let inodes = std::mem::take(&mut chk.inodes);
+    chk.check_bitmap(
+        "inode",
+        &inodes,
+        sb.free_inodes,
+        sb.first_free_inode,
+        |img, i| img.inode_used(i),
+    );
+    let blocks = std::mem::take(&mut chk.blocks);
+    chk.check_bitmap(
+        "block",
+        &blocks,
+        sb.free_blocks,
+        sb.first_free_block,
+        |img, i| img.block_used(i),
+    );
+
+    // blocks without checksum have not been written since the checksum area was created
+    for bno in (0..sb.total_blocks).filter(|b| blocks[*b as usize] && sb.has_checksum(*b)) {
+        let sum = img.checksum(bno);
+        let expected = block_checksum(img.block(bno));
+        if sum != NO_CHECKSUM && sum != expected {
+            chk.error(format!(
+                "Checksum of block {} is invalid (is {:#010x}, should be {:#010x})",
+                bno, sum, expected
+            ));
+        }
+    }
+
+    chk.errors
+}
@@ -0,0 +1,665 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3fs_layout::{
+    block_checksum, BlockNo, DirEntry, Extent, INode, InodeNo, SuperBlock, DIR_ENTRY_LEN,
+    INODE_DIR_COUNT, MAX_BLOCK_SIZE, MAX_LINK_LEN, NO_CHECKSUM, NUM_EXT_BYTES, NUM_INODE_BYTES,
+    SUPERBLOCK_MAGIC,
+};
+
+use std::fs;
+use std::mem::size_of;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::ptr;
+
+use crate::error::Error;
+
+pub const BLOCK_SIZE: u32 = MAX_BLOCK_SIZE;
+pub const MAX_BLOCKS: u32 = 1024 * 1024;
+pub const MAX_INODES: u32 = 4096;
+// one header block plus one block for each entry in the meta buffer of m3fs
+pub const JOURNAL_BLOCKS: u32 = 1 + 128;
+
+pub const S_IFMT: u16 = 0o170000;
+pub const S_IFDIR: u16 = 0o040000;
+pub const S_IFREG: u16 = 0o100000;
+pub const S_IFLNK: u16 = 0o120000;
+
+pub fn is_dir(mode: u16) -> bool {
+    mode & S_IFMT == S_IFDIR
+}
+
+pub fn is_reg(mode: u16) -> bool {
+    mode & S_IFMT == S_IFREG
+}
+
+pub fn is_link(mode: u16) -> bool {
+    mode & S_IFMT == S_IFLNK
+}
+
+/// Splits the given path into the path of the parent directory and the last component
+pub fn split_path(path: &str) -> (&str, &str) {
+    let path = path.trim_end_matches('/');
+    match path.rfind('/') {
+        Some(idx) => (&path[..idx], &path[idx + 1..]),
+        None => ("", path),
+    }
+}
+
+/// An m3fs image that is held in memory.
+///
+/// All modifications are done in memory and written to disk via [`Image::store`].
+pub struct Image {
+    data: Vec<u8>,
+    sb: SuperBlock,
+    blocks_per_extent: u32,
+}
+
+impl Image {
+    /// Creates a new and empty image with the given number of blocks and inodes.
+    ///
+    /// If `checksums` is true, the image stores a checksum for every used block, which is updated
+    /// whenever the image is written. If `journal` is true, the image reserves space for the
+    /// metadata journal of m3fs. The image does not contain a root directory yet.
+    pub fn new(
+        total_blocks: u32,
+        total_inodes: u32,
+        checksums: bool,
+        journal: bool,
+    ) -> Result<Self, Error> {
+        if total_blocks > MAX_BLOCKS {
+            return Err(Error::TooLarge(format!(
+                "Number of blocks (max: {})",
+                MAX_BLOCKS
+            )));
+        }
+        if total_inodes == 0 || total_inodes > MAX_INODES {
+            return Err(Error::TooLarge(format!(
+                "Number of inodes (max: {})",
+                MAX_INODES
+            )));
+        }
+
+        let mut sb = SuperBlock {
+            magic: SUPERBLOCK_MAGIC,
+            block_size: BLOCK_SIZE,
+            total_inodes,
+            total_blocks,
+            free_inodes: total_inodes,
+            free_blocks: total_blocks,
+            first_free_inode: 0,
+            first_free_block: 0,
+            journal_blocks: if journal { JOURNAL_BLOCKS } else { 0 },
+            checksum_blocks: 0,
+            checksum: 0,
+        };
+        if checksums {
+            sb.checksum_blocks = sb.needed_checksum_blocks();
+        }
+        if sb.first_data_block() > total_blocks {
+            return Err(Error::NoSpace("blocks"));
+        }
+
+        // mark superblock, inode and block bitmap, inode blocks, journal, and checksums as occupied
+        let first_data = sb.first_data_block();
+        sb.free_blocks -= first_data;
+        sb.first_free_block = first_data;
+
+        let mut img = Image {
+            data: vec![0; total_blocks as usize * BLOCK_SIZE as usize],
+            sb,
+            blocks_per_extent: 0,
+        };
+        for bno in 0..first_data {
+            img.set_bit(img.sb.first_blockbm_block(), bno, true);
+        }
+        Ok(img)
+    }
+
+    /// Creates an image from the given bytes and checks the superblock
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        if data.len() < size_of::<SuperBlock>() {
+            return Err(Error::InvalidImage("no superblock".to_string()));
+        }
+
+        // safety: the superblock consists of integers only and the data is large enough
+        let sb = unsafe { ptr::read_unaligned(data.as_ptr() as *const SuperBlock) };
+        if sb.magic != SUPERBLOCK_MAGIC {
+            return Err(Error::InvalidImage(format!(
+                "superblock magic is invalid (is {:#010x}, should be {:#010x})",
+                sb.magic, SUPERBLOCK_MAGIC
+            )));
+        }
+        if sb.checksum != sb.get_checksum() {
+            return Err(Error::InvalidImage(format!(
+                "superblock checksum is invalid (is {:#010x}, should be {:#010x})",
+                sb.checksum,
+                sb.get_checksum()
+            )));
+        }
+        if sb.total_blocks == 0 || sb.total_inodes == 0 {
+            return Err(Error::InvalidImage("no blocks or inodes".to_string()));
+        }
+        if sb.block_size == 0
+            || !sb.block_size.is_power_of_two()
+            || sb.block_size > MAX_BLOCK_SIZE
+            || (sb.block_size as usize) < size_of::<SuperBlock>()
+        {
+            return Err(Error::InvalidImage(format!(
+                "invalid block size {}",
+                sb.block_size
+            )));
+        }
+        if sb.first_data_block() > sb.total_blocks {
+            return Err(Error::InvalidImage("metadata exceeds image".to_string()));
+        }
+        if sb.checksum_blocks > 0 && sb.checksum_blocks < sb.needed_checksum_blocks() {
+            return Err(Error::InvalidImage(format!(
+                "checksum area has {} blocks, but {} are needed",
+                sb.checksum_blocks,
+                sb.needed_checksum_blocks()
+            )));
+        }
+        if data.len() < sb.total_blocks as usize * sb.block_size as usize {
+            return Err(Error::InvalidImage(format!(
+                "image has {} bytes, but superblock says {} blocks",
+                data.len(),
+                sb.total_blocks
+            )));
+        }
+        if sb.free_blocks > sb.total_blocks || sb.free_inodes > sb.total_inodes {
+            return Err(Error::InvalidImage(
+                "more free blocks or inodes than total".to_string(),
+            ));
+        }
+
+        Ok(Image {
+            data,
+            sb,
+            blocks_per_extent: 0,
+        })
+    }
+
+    /// Loads the image from the given file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// Writes the image to the given file
+    pub fn store(&mut self, path: &Path) -> Result<(), Error> {
+        self.write_checksums();
+        self.write_superblock();
+        fs::write(path, &self.data)?;
+        Ok(())
+    }
+
+    /// Returns the bytes of the image, including the up-to-date superblock and checksums
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        self.write_checksums();
+        self.write_superblock();
+        self.data
+    }
+
+    pub fn superblock(&self) -> &SuperBlock {
+        &self.sb
+    }
+
+    /// Sets the maximum number of blocks per extent for new files (0 = unlimited)
+    pub fn set_blocks_per_extent(&mut self, blocks: u32) {
+        self.blocks_per_extent = blocks;
+    }
+
+    fn write_superblock(&mut self) {
+        self.sb.checksum = self.sb.get_checksum();
+        let sb = SuperBlock { ..self.sb };
+        self.write(0, sb);
+    }
+
+    /// Returns the checksum that is stored for the given block
+    pub fn checksum(&self, bno: BlockNo) -> u32 {
+        let (block, off) = self.sb.checksum_location(bno);
+        self.read(block as usize * self.sb.block_size as usize + off)
+    }
+
+    fn write_checksums(&mut self) {
+        // free blocks have no checksum, because m3fs does not load them before writing them
+        for bno in 0..self.sb.total_blocks {
+            if !self.sb.has_checksum(bno) {
+                continue;
+            }
+            let sum = if self.block_used(bno) {
+                block_checksum(self.block(bno))
+            }
+            else {
+                NO_CHECKSUM
+            };
+            let (block, off) = self.sb.checksum_location(bno);
+            self.write(block as usize * self.sb.block_size as usize + off, sum);
+        }
+    }
+
+    pub fn block(&self, bno: BlockNo) -> &[u8] {
+        let bs = self.sb.block_size as usize;
+        &self.data[bno as usize * bs..(bno as usize + 1) * bs]
+    }
+
+    pub fn block_mut(&mut self, bno: BlockNo) -> &mut [u8] {
+        let bs = self.sb.block_size as usize;
+        &mut self.data[bno as usize * bs..(bno as usize + 1) * bs]
+    }
+
+    fn read<T>(&self, off: usize) -> T {
+        assert!(off + size_of::<T>() <= self.data.len());
+        // safety: the range is within the image and T is a plain on-disk struct
+        unsafe { ptr::read_unaligned(self.data.as_ptr().add(off) as *const T) }
+    }
+
+    fn write<T>(&mut self, off: usize, val: T) {
+        assert!(off + size_of::<T>() <= self.data.len());
+        // safety: see above
+        unsafe { ptr::write_unaligned(self.data.as_mut_ptr().add(off) as *mut T, val) }
+    }
+
+    fn bit(&self, first: BlockNo, idx: u32) -> bool {
+        let off = first as usize * self.sb.block_size as usize + idx as usize / 8;
+        self.data[off] & (1 << (idx % 8)) != 0
+    }
+
+    fn set_bit(&mut self, first: BlockNo, idx: u32, val: bool) {
+        let off = first as usize * self.sb.block_size as usize + idx as usize / 8;
+        if val {
+            self.data[off] |= 1 << (idx % 8);
+        }
+        else {
+            self.data[off] &= !(1 << (idx % 8));
+        }
+    }
+
+    /// Returns true if the given inode is marked used in the inode bitmap
+    pub fn inode_used(&self, ino: InodeNo) -> bool {
+        self.bit(self.sb.first_inodebm_block(), ino)
+    }
+
+    /// Returns true if the given block is marked used in the block bitmap
+    pub fn block_used(&self, bno: BlockNo) -> bool {
+        self.bit(self.sb.first_blockbm_block(), bno)
+    }
+
+    /// Allocates a new inode and returns its number
+    pub fn alloc_inode(&mut self) -> Result<InodeNo, Error> {
+        let ino = (self.sb.first_free_inode..self.sb.total_inodes)
+            .find(|i| !self.inode_used(*i))
+            .ok_or(Error::NoSpace("inodes"))?;
+        self.set_bit(self.sb.first_inodebm_block(), ino, true);
+        self.sb.free_inodes -= 1;
+        self.sb.first_free_inode = ino + 1;
+        Ok(ino)
+    }
+
+    /// Allocates a new and zeroed block and returns its number
+    pub fn alloc_block(&mut self) -> Result<BlockNo, Error> {
+        let start = self.sb.first_free_block.max(self.sb.first_data_block());
+        let bno = (start..self.sb.total_blocks)
+            .find(|b| !self.block_used(*b))
+            .ok_or(Error::NoSpace("blocks"))?;
+        self.set_bit(self.sb.first_blockbm_block(), bno, true);
+        self.sb.free_blocks -= 1;
+        self.sb.first_free_block = bno + 1;
+        self.block_mut(bno).fill(0);
+        Ok(bno)
+    }
+
+    fn inode_offset(&self, ino: InodeNo) -> usize {
+        self.sb.first_inode_block() as usize * self.sb.block_size as usize
+            + ino as usize * NUM_INODE_BYTES
+    }
+
+    /// Returns a copy of the given inode
+    pub fn inode(&self, ino: InodeNo) -> INode {
+        self.read(self.inode_offset(ino))
+    }
+
+    /// Writes back the given inode
+    pub fn write_inode(&mut self, inode: &INode) {
+        self.write(self.inode_offset(inode.inode), inode.clone());
+    }
+
+    /// Returns the maximum number of extents per inode
+    pub fn max_extents(&self) -> u32 {
+        let epb = self.sb.extents_per_block() as u32;
+        INODE_DIR_COUNT as u32 + epb + epb * epb
+    }
+
+    /// Returns the location (block and offset) of the indirect extent with the given index, or
+    /// `None` if the required indirect block does not exist
+    fn ext_location(&self, inode: &INode, idx: u32) -> Option<(BlockNo, usize)> {
+        let epb = self.sb.extents_per_block() as u32;
+        let idx = idx - INODE_DIR_COUNT as u32;
+        let (block, idx) = if idx < epb {
+            (inode.indirect, idx)
+        }
+        else {
+            if inode.dindirect == 0 {
+                return None;
+            }
+            let idx = idx - epb;
+            let dext = self.block_extent(inode.dindirect, idx / epb);
+            (dext.start, idx % epb)
+        };
+        if block == 0 {
+            None
+        }
+        else {
+            Some((block, self.ext_offset(block, idx)))
+        }
+    }
+
+    fn ext_offset(&self, block: BlockNo, idx: u32) -> usize {
+        block as usize * self.sb.block_size as usize + idx as usize * NUM_EXT_BYTES
+    }
+
+    /// Returns the extent with given index in the indirect block `block`
+    pub fn block_extent(&self, block: BlockNo, idx: u32) -> Extent {
+        self.read(self.ext_offset(block, idx))
+    }
+
+    /// Returns the extent with the given index of `inode`
+    pub fn extent(&self, inode: &INode, idx: u32) -> Extent {
+        if (idx as usize) < INODE_DIR_COUNT {
+            inode.direct[idx as usize]
+        }
+        else {
+            match self.ext_location(inode, idx) {
+                Some((_, off)) => self.read(off),
+                None => Extent::default(),
+            }
+        }
+    }
+
+    fn set_extent(&mut self, inode: &mut INode, idx: u32, ext: Extent) -> Result<(), Error> {
+        if (idx as usize) < INODE_DIR_COUNT {
+            inode.direct[idx as usize] = ext;
+            return Ok(());
+        }
+
+        // create the indirect blocks on demand
+        let epb = self.sb.extents_per_block() as u32;
+        let rel = idx - INODE_DIR_COUNT as u32;
+        if rel < epb {
+            if inode.indirect == 0 {
+                inode.indirect = self.alloc_block()?;
+            }
+        }
+        else {
+            if inode.dindirect == 0 {
+                inode.dindirect = self.alloc_block()?;
+            }
+            let doff = self.ext_offset(inode.dindirect, (rel - epb) / epb);
+            let dext: Extent = self.read(doff);
+            if dext.start == 0 {
+                let ind = self.alloc_block()?;
+                self.write(doff, Extent::new(ind, 1));
+            }
+        }
+
+        let (_, off) = self.ext_location(inode, idx).unwrap();
+        self.write(off, ext);
+        Ok(())
+    }
+
+    /// Returns the data blocks of the given inode in file order, skipping holes
+    pub fn blocks(&self, inode: &INode) -> Vec<BlockNo> {
+        let mut res = Vec::new();
+        for i in 0..inode.extents {
+            let ext = self.extent(inode, i);
+            if !ext.is_hole() {
+                res.extend(ext.block_range());
+            }
+        }
+        res
+    }
+
+    /// Allocates a new data block and appends it to the given inode.
+    ///
+    /// A new extent is started if the maximum number of blocks per extent is reached, if `new_ext`
+    /// is true, or if the block is not contiguous to the last extent. The size of the inode is not
+    /// changed.
+    pub fn append_block(&mut self, inode: &mut INode, new_ext: bool) -> Result<BlockNo, Error> {
+        let count = (0..inode.extents)
+            .map(|i| self.extent(inode, i).length)
+            .sum::<u32>();
+        let new_ext =
+            new_ext || (self.blocks_per_extent > 0 && count % self.blocks_per_extent == 0);
+
+        let bno = self.alloc_block()?;
+        if inode.extents > 0 && !new_ext {
+            let idx = inode.extents - 1;
+            let mut last = self.extent(inode, idx);
+            if last.start + last.length == bno {
+                last.length += 1;
+                self.set_extent(inode, idx, last)?;
+                return Ok(bno);
+            }
+        }
+
+        if inode.extents >= self.max_extents() {
+            return Err(Error::TooLarge(format!("Inode {}", inode.inode)));
+        }
+        self.set_extent(inode, inode.extents, Extent::new(bno, 1))?;
+        inode.extents += 1;
+        Ok(bno)
+    }
+
+    /// Returns the entries (name and inode number) of the given directory block, or an error if
+    /// the block does not contain valid entries
+    pub fn block_entries(&self, bno: BlockNo) -> Result<Vec<(String, InodeNo)>, Error> {
+        let data = self.block(bno);
+        let mut res = Vec::new();
+        let mut off = 0;
+        while off < data.len() {
+            if off + DIR_ENTRY_LEN > data.len() {
+                return Err(Error::InvalidImage(format!(
+                    "truncated entry in block {}",
+                    bno
+                )));
+            }
+            let entry = DirEntry::from_buffer(data, off);
+            let next = entry.next as usize;
+            if next < DIR_ENTRY_LEN + entry.name_length as usize || off + next > data.len() {
+                return Err(Error::InvalidImage(format!(
+                    "invalid entry at {}+{}",
+                    bno, off
+                )));
+            }
+            let name = &data[off + DIR_ENTRY_LEN..off + DIR_ENTRY_LEN + entry.name_length as usize];
+            res.push((String::from_utf8_lossy(name).into_owned(), entry.nodeno));
+            off += next;
+        }
+        Ok(res)
+    }
+
+    /// Returns the entries (name and inode number) of the given directory
+    pub fn dir_entries(&self, dir: &INode) -> Result<Vec<(String, InodeNo)>, Error> {
+        let mut res = Vec::new();
+        for bno in self.blocks(dir) {
+            res.extend(self.block_entries(bno)?);
+        }
+        Ok(res)
+    }
+
+    /// Adds an entry with given name for inode `ino` to the directory `dir`
+    pub fn add_entry(&mut self, dir: &mut INode, name: &str, ino: InodeNo) -> Result<(), Error> {
+        let bs = self.sb.block_size as usize;
+        let size = DirEntry::size_for(name.len());
+        if size > bs {
+            return Err(Error::TooLarge(format!("Name '{}'", name)));
+        }
+
+        // try to use the unused space behind an existing entry
+        for bno in self.blocks(dir) {
+            let mut off = 0;
+            while off < bs {
+                let (used, next) = {
+                    let entry = DirEntry::from_buffer(self.block(bno), off);
+                    (entry.size(), entry.next as usize)
+                };
+                if next >= used + size {
+                    let data = self.block_mut(bno);
+                    DirEntry::from_buffer_mut(data, off).next = used as u32;
+                    Self::write_entry(data, off + used, name, ino, next - used);
+                    return Ok(());
+                }
+                off += next;
+            }
+        }
+
+        // no space left; append a new block
+        let bno = self.append_block(dir, false)?;
+        dir.size += bs as u64;
+        Self::write_entry(self.block_mut(bno), 0, name, ino, bs);
+        Ok(())
+    }
+
+    fn write_entry(data: &mut [u8], off: usize, name: &str, ino: InodeNo, next: usize) {
+        data[off..off + DIR_ENTRY_LEN].fill(0);
+        let entry = DirEntry::from_buffer_mut(data, off);
+        entry.nodeno = ino;
+        entry.next = next as u32;
+        entry.set_name(name);
+    }
+
+    /// Searches for the entry with given name in the directory `dir`
+    pub fn find_entry(&self, dir: &INode, name: &str) -> Result<Option<InodeNo>, Error> {
+        Ok(self
+            .dir_entries(dir)?
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, ino)| ino))
+    }
+
+    /// Resolves the given absolute path to an inode number. Symbolic links are not followed.
+    pub fn lookup(&self, path: &str) -> Result<InodeNo, Error> {
+        let mut ino = 0;
+        for comp in path.split('/').filter(|c| !c.is_empty()) {
+            let inode = self.inode(ino);
+            if !is_dir(inode.mode) {
+                return Err(Error::NotDir(path.to_string()));
+            }
+            ino = self
+                .find_entry(&inode, comp)?
+                .ok_or_else(|| Error::NotFound(path.to_string()))?;
+        }
+        Ok(ino)
+    }
+
+    /// Returns the content of the given file, where holes read as zeros
+    pub fn read_file(&self, inode: &INode) -> Vec<u8> {
+        let bs = self.sb.block_size as usize;
+        let mut res = Vec::with_capacity(inode.size as usize);
+        for i in 0..inode.extents {
+            let ext = self.extent(inode, i);
+            for bno in ext.block_range() {
+                let len = (inode.size as usize - res.len()).min(bs);
+                if len == 0 {
+                    return res;
+                }
+                if ext.is_hole() {
+                    res.resize(res.len() + len, 0);
+                }
+                else {
+                    res.extend_from_slice(&self.block(bno)[..len]);
+                }
+            }
+        }
+        res
+    }
+
+    /// Copies the file, link, or directory tree at `src` on the host into the image.
+    ///
+    /// The entries of directories refer to `parent` via "..". Returns the inode number of the new
+    /// inode or `None` if `src` has been ignored, because it is of an unsupported type.
+    pub fn import(&mut self, src: &Path, parent: InodeNo) -> Result<Option<InodeNo>, Error> {
+        let meta = fs::symlink_metadata(src)?;
+        let mode = meta.mode() as u16;
+        if !is_dir(mode) && !is_reg(mode) && !is_link(mode) {
+            return Ok(None);
+        }
+
+        let ino = self.alloc_inode()?;
+        let mut inode = INode::default();
+        inode.inode = ino;
+        inode.mode = mode;
+        // all files on the host belong to root
+        inode.uid = 0;
+        inode.gid = 0;
+        inode.links = 1;
+        inode.lastaccess = meta.atime() as u32;
+        inode.lastmod = meta.mtime() as u32;
+
+        let bs = self.sb.block_size as usize;
+        if is_reg(mode) {
+            let content = fs::read(src)?;
+            for chunk in content.chunks(bs) {
+                let bno = self.append_block(&mut inode, false)?;
+                self.block_mut(bno)[..chunk.len()].copy_from_slice(chunk);
+            }
+            inode.size = content.len() as u64;
+        }
+        else if is_link(mode) {
+            // the target is stored in the first and only block of the inode
+            let target = fs::read_link(src)?;
+            let target = target.to_string_lossy();
+            if target.is_empty() || target.len() > MAX_LINK_LEN.min(bs) {
+                return Err(Error::TooLarge(format!(
+                    "Target of link '{}'",
+                    src.display()
+                )));
+            }
+            let bno = self.append_block(&mut inode, true)?;
+            self.block_mut(bno)[..target.len()].copy_from_slice(target.as_bytes());
+            inode.size = target.len() as u64;
+        }
+        else {
+            // "." and the entry in the parent directory
+            inode.links = 2;
+            self.add_entry(&mut inode, ".", ino)?;
+            self.add_entry(&mut inode, "..", parent)?;
+
+            let mut children = fs::read_dir(src)?.collect::<Result<Vec<_>, _>>()?;
+            children.sort_by_key(|e| e.file_name());
+            for child in children {
+                let name = child.file_name();
+                let name = name.to_string_lossy();
+                match self.import(&child.path(), ino)? {
+                    Some(child_ino) => {
+                        self.add_entry(&mut inode, &name, child_ino)?;
+                        // the ".." entry of subdirectories refers to us
+                        if is_dir(self.inode(child_ino).mode) {
+                            inode.links += 1;
+                        }
+                    },
+                    None => eprintln!(
+                        "Warning: ignored file '{}' (no regular file, directory, or link)",
+                        child.path().display()
+                    ),
+                }
+            }
+        }
+
+        self.write_inode(&inode);
+        Ok(Some(ino))
+    }
+}
@@ -0,0 +1,49 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use std::path::Path;
+
+use crate::error::Error;
+use crate::image::{is_dir, split_path, Image};
+
+/// Copies the file, link, or directory tree at `src` on the host to `dst` in the image.
+///
+/// The parent directory of `dst` has to exist, whereas `dst` itself must not exist.
+pub fn insert(img: &mut Image, src: &Path, dst: &str) -> Result<(), Error> {
+    let (dir, name) = split_path(dst);
+    if name.is_empty() {
+        return Err(Error::Exists(dst.to_string()));
+    }
+
+    let parent_ino = img.lookup(dir)?;
+    let mut parent = img.inode(parent_ino);
+    if !is_dir(parent.mode) {
+        return Err(Error::NotDir(dir.to_string()));
+    }
+    if img.find_entry(&parent, name)?.is_some() {
+        return Err(Error::Exists(dst.to_string()));
+    }
+
+    let ino = img
+        .import(src, parent_ino)?
+        .ok_or_else(|| Error::Unsupported(src.display().to_string()))?;
+    img.add_entry(&mut parent, name, ino)?;
+    // the ".." entry of the new directory refers to the parent
+    if is_dir(img.inode(ino).mode) {
+        parent.links += 1;
+    }
+    img.write_inode(&parent);
+    Ok(())
+}
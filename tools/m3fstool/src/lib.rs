@@ -0,0 +1,30 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Reading and writing of m3fs images on the host.
+//!
+//! The on-disk structures are shared with the m3fs server via the `m3fs_layout` crate.
+
+pub mod error;
+pub mod extract;
+pub mod fsck;
+pub mod image;
+pub mod insert;
+pub mod list;
+pub mod mkfs;
+pub mod show;
+
+pub use error::Error;
+pub use image::Image;
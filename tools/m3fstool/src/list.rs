@@ -0,0 +1,49 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3fs_layout::INode;
+
+use crate::error::Error;
+use crate::image::{is_dir, split_path, Image};
+
+/// An entry of a directory listing
+pub struct ListEntry {
+    pub name: String,
+    pub inode: INode,
+}
+
+/// Lists the directory at `path` in the image, including "." and "..".
+///
+/// If `path` does not refer to a directory, the result consists of the file itself.
+pub fn list(img: &Image, path: &str) -> Result<Vec<ListEntry>, Error> {
+    let ino = img.lookup(path)?;
+    let inode = img.inode(ino);
+    if !is_dir(inode.mode) {
+        let (_, name) = split_path(path);
+        return Ok(vec![ListEntry {
+            name: name.to_string(),
+            inode,
+        }]);
+    }
+
+    Ok(img
+        .dir_entries(&inode)?
+        .into_iter()
+        .map(|(name, ino)| ListEntry {
+            name,
+            inode: img.inode(ino),
+        })
+        .collect())
+}
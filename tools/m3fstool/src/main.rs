@@ -0,0 +1,145 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3fstool::image::{is_dir, is_link};
+use m3fstool::{extract, fsck, insert, list, mkfs, show, Error, Image};
+
+use std::env;
+use std::path::Path;
+use std::process::exit;
+
+fn usage(prog: &str) -> ! {
+    eprintln!("Usage: {} <command> <fsimage> [<args>...]", prog);
+    eprintln!();
+    eprintln!("Commands:");
+    eprintln!("  mkfs <fsimage> <dir> <blocks> <inodes> <blksperext> [-csum] [-journal]");
+    eprintln!("      creates <fsimage> with the content of <dir> on the host;");
+    eprintln!("      <blksperext> is the max. number of blocks per extent (0 = unlimited);");
+    eprintln!("      -csum stores checksums for all metadata and data blocks (data checksums are");
+    eprintln!("       only kept up to date with the disk backend; the mem backend drops them for");
+    eprintln!("       blocks that clients can write to);");
+    eprintln!("      -journal reserves a metadata journal (only useful for disk images)");
+    eprintln!("  fsck <fsimage>");
+    eprintln!("      checks the consistency of <fsimage>");
+    eprintln!("  ls <fsimage> [<path>]");
+    eprintln!("      lists the directory <path> (default: /)");
+    eprintln!("  extract <fsimage> <dest> [<path>]");
+    eprintln!("      copies <path> (default: /) to <dest> on the host");
+    eprintln!("  insert <fsimage> <src> <path> [<blksperext>]");
+    eprintln!("      copies <src> on the host to <path> in <fsimage>");
+    eprintln!("  show <fsimage> (sb|ibm|bbm|inodes|ino <inode>)");
+    eprintln!("      shows the superblock, the inode or block bitmap, all inodes, or <inode>");
+    exit(1)
+}
+
+fn print_entry(img: &Image, entry: &list::ListEntry) {
+    let inode = &entry.inode;
+    let kind = if is_dir(inode.mode) {
+        'd'
+    }
+    else if is_link(inode.mode) {
+        'l'
+    }
+    else {
+        '-'
+    };
+    print!(
+        "{}{:03o} {:>3} {:>4} {:>4} {:>10} {:>4} {}",
+        kind,
+        inode.mode & 0o777,
+        inode.links,
+        inode.uid,
+        inode.gid,
+        inode.size,
+        inode.inode,
+        entry.name
+    );
+    if is_link(inode.mode) {
+        print!(" -> {}", String::from_utf8_lossy(&img.read_file(inode)));
+    }
+    println!();
+}
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        usage(&args[0]);
+    }
+
+    let image = Path::new(&args[2]);
+    match args[1].as_str() {
+        "mkfs" if args.len() >= 7 && args[7..].iter().all(|a| a == "-csum" || a == "-journal") => {
+            let mut img = mkfs::create(
+                Path::new(&args[3]),
+                args[4].parse()?,
+                args[5].parse()?,
+                args[6].parse()?,
+                args[7..].iter().any(|a| a == "-csum"),
+                args[7..].iter().any(|a| a == "-journal"),
+            )?;
+            img.store(image)?;
+        },
+
+        "fsck" if args.len() == 3 => {
+            let img = Image::load(image)?;
+            let errors = fsck::check(&img);
+            for e in &errors {
+                eprintln!("{}", e);
+            }
+            if !errors.is_empty() {
+                exit(1);
+            }
+        },
+
+        "ls" if args.len() == 3 || args.len() == 4 => {
+            let img = Image::load(image)?;
+            let path = args.get(3).map(|s| s.as_str()).unwrap_or("/");
+            for entry in list::list(&img, path)? {
+                print_entry(&img, &entry);
+            }
+        },
+
+        "extract" if args.len() == 4 || args.len() == 5 => {
+            let img = Image::load(image)?;
+            let path = args.get(4).map(|s| s.as_str()).unwrap_or("/");
+            extract::extract(&img, path, Path::new(&args[3]))?;
+        },
+
+        "insert" if args.len() == 5 || args.len() == 6 => {
+            let mut img = Image::load(image)?;
+            if let Some(bpe) = args.get(5) {
+                img.set_blocks_per_extent(bpe.parse()?);
+            }
+            insert::insert(&mut img, Path::new(&args[3]), &args[4])?;
+            img.store(image)?;
+        },
+
+        "show" if args.len() == 4 || (args.len() == 5 && args[3] == "ino") => {
+            let img = Image::load(image)?;
+            match args[3].as_str() {
+                "sb" => print!("{}", show::superblock(&img)),
+                "ibm" => print!("{}", show::inode_bitmap(&img)),
+                "bbm" => print!("{}", show::block_bitmap(&img)),
+                "inodes" => print!("{}", show::inodes(&img)),
+                "ino" => print!("{}", show::inode(&img, args[4].parse()?)?),
+                _ => usage(&args[0]),
+            }
+        },
+
+        _ => usage(&args[0]),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use std::path::Path;
+
+use crate::error::Error;
+use crate::image::Image;
+
+/// Creates a new image with the given number of blocks and inodes and copies the directory tree at
+/// `src` on the host into it.
+///
+/// `blocks_per_extent` limits the number of blocks per extent (0 = unlimited), which allows to
+/// create fragmented files for testing. If `checksums` is true, the image stores a checksum for
+/// every used block. If `journal` is true, the image contains a metadata journal, which is only
+/// useful for images that are used with the disk backend of m3fs.
+pub fn create(
+    src: &Path,
+    blocks: u32,
+    inodes: u32,
+    blocks_per_extent: u32,
+    checksums: bool,
+    journal: bool,
+) -> Result<Image, Error> {
+    if !std::fs::metadata(src)?.is_dir() {
+        return Err(Error::NotDir(src.display().to_string()));
+    }
+
+    let mut img = Image::new(blocks, inodes, checksums, journal)?;
+    img.set_blocks_per_extent(blocks_per_extent);
+
+    // the root directory is always inode 0 and its parent is itself
+    let root = img.import(src, 0)?;
+    assert_eq!(root, Some(0));
+    Ok(img)
+}
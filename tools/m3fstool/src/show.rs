@@ -0,0 +1,121 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use std::fmt::Write;
+
+use m3fs_layout::{Extent, InodeNo, INODE_DIR_COUNT};
+
+use crate::error::Error;
+use crate::image::Image;
+
+/// Describes the superblock of the image
+pub fn superblock(img: &Image) -> String {
+    let sb = img.superblock();
+    let mut res = String::new();
+    writeln!(res, "Superblock:").unwrap();
+    writeln!(res, "  magic: {:#010x}", sb.magic).unwrap();
+    writeln!(res, "  blocksize: {}", sb.block_size).unwrap();
+    writeln!(res, "  total_inodes: {}", sb.total_inodes).unwrap();
+    writeln!(res, "  total_blocks: {}", sb.total_blocks).unwrap();
+    writeln!(res, "  free_inodes: {}", sb.free_inodes).unwrap();
+    writeln!(res, "  free_blocks: {}", sb.free_blocks).unwrap();
+    writeln!(res, "  first_free_inode: {}", sb.first_free_inode).unwrap();
+    writeln!(res, "  first_free_block: {}", sb.first_free_block).unwrap();
+    writeln!(res, "  journal_blocks: {}", sb.journal_blocks).unwrap();
+    writeln!(res, "  checksum_blocks: {}", sb.checksum_blocks).unwrap();
+    res
+}
+
+fn bitmap(total: u32, is_set: impl Fn(u32) -> bool) -> String {
+    let mut res = String::new();
+    for i in 0..total {
+        if i % 64 == 0 {
+            if i > 0 {
+                res.push('\n');
+            }
+            write!(res, " {:5}..{:5}: ", i, i + 63).unwrap();
+        }
+        else if i % 8 == 0 {
+            res.push(' ');
+        }
+        res.push(if is_set(i) { '1' } else { '0' });
+    }
+    res.push('\n');
+    res
+}
+
+/// Describes the inode bitmap of the image
+pub fn inode_bitmap(img: &Image) -> String {
+    let total = img.superblock().total_inodes;
+    format!("INode bitmap:\n{}", bitmap(total, |i| img.inode_used(i)))
+}
+
+/// Describes the block bitmap of the image
+pub fn block_bitmap(img: &Image) -> String {
+    let total = img.superblock().total_blocks;
+    format!("Block bitmap:\n{}", bitmap(total, |b| img.block_used(b)))
+}
+
+fn extent(ext: &Extent) -> String {
+    let last = if ext.length > 0 {
+        ext.start + ext.length - 1
+    }
+    else {
+        0
+    };
+    format!("{:4} .. {:4} ({})", ext.start, last, ext.length)
+}
+
+/// Describes the given inode and all its extents
+pub fn inode(img: &Image, ino: InodeNo) -> Result<String, Error> {
+    if ino >= img.superblock().total_inodes {
+        return Err(Error::NotFound(format!("Inode {}", ino)));
+    }
+
+    let inode = img.inode(ino);
+    let mut res = String::new();
+    writeln!(res, "INode {}:", ino).unwrap();
+    writeln!(res, "  devno: {}", inode.devno).unwrap();
+    writeln!(res, "  inode: {}", inode.inode).unwrap();
+    writeln!(res, "  mode: {:#o}", inode.mode).unwrap();
+    writeln!(res, "  uid: {}", inode.uid).unwrap();
+    writeln!(res, "  gid: {}", inode.gid).unwrap();
+    writeln!(res, "  links: {}", inode.links).unwrap();
+    writeln!(res, "  size: {}", inode.size).unwrap();
+    writeln!(res, "  lastaccess: {}", inode.lastaccess).unwrap();
+    writeln!(res, "  lastmod: {}", inode.lastmod).unwrap();
+    writeln!(res, "  extents: {}", inode.extents).unwrap();
+    for i in 0..inode.extents {
+        let ext = img.extent(&inode, i);
+        let kind = if (i as usize) < INODE_DIR_COUNT {
+            "direct"
+        }
+        else {
+            "indirect"
+        };
+        writeln!(res, "    {:4} ({}): {}", i, kind, extent(&ext)).unwrap();
+    }
+    writeln!(res, "  indirect: {}", inode.indirect).unwrap();
+    writeln!(res, "  dindirect: {}", inode.dindirect).unwrap();
+    Ok(res)
+}
+
+/// Describes all used inodes of the image
+pub fn inodes(img: &Image) -> String {
+    (0..img.superblock().total_inodes)
+        .filter(|ino| img.inode_used(*ino))
+        .map(|ino| inode(img, ino).unwrap())
+        .collect()
+}
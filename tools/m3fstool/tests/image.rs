@@ -0,0 +1,345 @@
+/*
+ * Copyright (C) 2023 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3fs_layout::Extent;
+use m3fstool::image::{self, is_dir, is_link, is_reg};
+use m3fstool::{extract, fsck, insert, list, mkfs, show, Error, Image};
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("m3fstool-{}-{}", name, process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u8).wrapping_mul(7).wrapping_add(seed))
+        .collect()
+}
+
+/// Creates a small directory tree on the host
+fn create_tree(root: &Path) {
+    fs::write(root.join("empty"), []).unwrap();
+    fs::write(root.join("small.txt"), b"hello world").unwrap();
+    fs::write(root.join("large.bin"), pattern(5 * 4096 + 123, 1)).unwrap();
+    fs::create_dir(root.join("sub")).unwrap();
+    fs::create_dir(root.join("sub/deeper")).unwrap();
+    fs::write(root.join("sub/deeper/file"), pattern(4096, 2)).unwrap();
+    symlink("../small.txt", root.join("sub/link")).unwrap();
+}
+
+/// Compares the directory trees `a` and `b` on the host
+fn assert_same_tree(a: &Path, b: &Path) {
+    let meta = fs::symlink_metadata(a).unwrap();
+    if meta.file_type().is_symlink() {
+        assert_eq!(fs::read_link(a).unwrap(), fs::read_link(b).unwrap());
+    }
+    else if meta.is_dir() {
+        let mut names_a = fs::read_dir(a)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect::<Vec<_>>();
+        let mut names_b = fs::read_dir(b)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect::<Vec<_>>();
+        names_a.sort();
+        names_b.sort();
+        assert_eq!(names_a, names_b);
+        for n in names_a {
+            assert_same_tree(&a.join(&n), &b.join(&n));
+        }
+    }
+    else {
+        assert_eq!(fs::read(a).unwrap(), fs::read(b).unwrap(), "{:?}", a);
+    }
+}
+
+fn assert_clean(img: &Image) {
+    let errors = fsck::check(img);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn mkfs_fsck_extract() {
+    let dir = temp_dir("mkfs");
+    let src = dir.join("src");
+    fs::create_dir(&src).unwrap();
+    create_tree(&src);
+
+    let img = mkfs::create(&src, 1024, 64, 0, false, false).unwrap();
+    assert_clean(&img);
+    // the journal is optional
+    assert_eq!(img.superblock().journal_blocks, 0);
+
+    // go through the bytes to make sure that the superblock is stored
+    let bytes = img.into_bytes();
+    let img = Image::from_bytes(bytes.clone()).unwrap();
+    assert_clean(&img);
+
+    // images with a different layout version are rejected
+    let mut old = bytes;
+    old[0] ^= 0x01;
+    assert!(matches!(
+        Image::from_bytes(old),
+        Err(Error::InvalidImage(_))
+    ));
+
+    let root = img.inode(0);
+    assert!(is_dir(root.mode));
+    // ".", "..", and the ".." of "sub"
+    assert_eq!(root.links, 3);
+
+    let large = img.inode(img.lookup("/large.bin").unwrap());
+    assert!(is_reg(large.mode));
+    assert_eq!(large.size, 5 * 4096 + 123);
+    // blocks are allocated contiguously
+    assert_eq!(large.extents, 1);
+
+    let link = img.inode(img.lookup("/sub/link").unwrap());
+    assert!(is_link(link.mode));
+    assert_eq!(img.read_file(&link), b"../small.txt");
+
+    let dst = dir.join("dst");
+    extract::extract(&img, "/", &dst).unwrap();
+    assert_same_tree(&src, &dst);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn show_metadata() {
+    let dir = temp_dir("show");
+    create_tree(&dir);
+
+    let img = mkfs::create(&dir, 1024, 64, 2, false, false).unwrap();
+    let sb = show::superblock(&img);
+    assert!(sb.contains("total_blocks: 1024"));
+    assert!(sb.contains("total_inodes: 64"));
+
+    // the root directory and the seven entries of the tree are used
+    let ibm = show::inode_bitmap(&img);
+    assert!(ibm.contains("     0..   63: 11111111 00000000"));
+
+    // the large file has been split into extents with at most two blocks each; the third is
+    // split again by the indirect block that has been allocated in between
+    let large = img.lookup("/large.bin").unwrap();
+    let desc = show::inode(&img, large).unwrap();
+    assert!(desc.contains("extents: 4"));
+    assert!(desc.contains("2 (indirect)"));
+    assert!(matches!(show::inode(&img, 64), Err(Error::NotFound(_))));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fragmented_files() {
+    let dir = temp_dir("frag");
+    let src = dir.join("src");
+    fs::create_dir(&src).unwrap();
+    // one block per extent requires the indirect and the double-indirect block
+    let content = pattern(600 * 4096 + 1, 3);
+    fs::write(src.join("huge"), &content).unwrap();
+
+    let img = mkfs::create(&src, 2048, 16, 1, false, false).unwrap();
+    assert_clean(&img);
+
+    let huge = img.inode(img.lookup("/huge").unwrap());
+    assert_eq!(huge.extents, 601);
+    assert!(huge.indirect != 0 && huge.dindirect != 0);
+    assert_eq!(img.read_file(&huge), content);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn insert_and_list() {
+    let dir = temp_dir("insert");
+    let src = dir.join("src");
+    fs::create_dir(&src).unwrap();
+    create_tree(&src);
+
+    let mut img = mkfs::create(&src, 1024, 512, 0, false, false).unwrap();
+
+    // a directory with enough entries to require multiple blocks
+    let many = dir.join("many");
+    fs::create_dir(&many).unwrap();
+    for i in 0..300 {
+        fs::write(many.join(format!("file-with-a-long-name-{}", i)), [i as u8]).unwrap();
+    }
+    insert::insert(&mut img, &many, "/sub/many").unwrap();
+    insert::insert(&mut img, &src.join("small.txt"), "/copy.txt").unwrap();
+    assert_clean(&img);
+
+    let many_ino = img.inode(img.lookup("/sub/many").unwrap());
+    assert!(many_ino.size > 4096);
+    assert_eq!(img.inode(img.lookup("/sub").unwrap()).links, 4);
+
+    let names = list::list(&img, "/")
+        .unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, [
+        ".",
+        "..",
+        "empty",
+        "large.bin",
+        "small.txt",
+        "sub",
+        "copy.txt"
+    ]);
+    let file = list::list(&img, "/copy.txt").unwrap();
+    assert_eq!(file.len(), 1);
+    assert_eq!(file[0].inode.size, 11);
+
+    assert!(matches!(
+        insert::insert(&mut img, &src.join("small.txt"), "/copy.txt"),
+        Err(Error::Exists(_))
+    ));
+    assert!(matches!(
+        insert::insert(&mut img, &src.join("small.txt"), "/nodir/file"),
+        Err(Error::NotFound(_))
+    ));
+
+    let dst = dir.join("many-out");
+    extract::extract(&img, "/sub/many", &dst).unwrap();
+    assert_same_tree(&many, &dst);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fsck_detects_errors() {
+    let dir = temp_dir("fsck");
+    let src = dir.join("src");
+    fs::create_dir(&src).unwrap();
+    create_tree(&src);
+
+    let mut img = mkfs::create(&src, 1024, 64, 0, false, false).unwrap();
+    assert_clean(&img);
+
+    // mark a used data block as free
+    let small = img.inode(img.lookup("/small.txt").unwrap());
+    let bno = small.direct[0].start;
+    let bm = img.superblock().first_blockbm_block();
+    img.block_mut(bm)[bno as usize / 8] &= !(1 << (bno % 8));
+    assert!(!img.block_used(bno));
+
+    // and break a link count
+    let mut sub = img.inode(img.lookup("/sub").unwrap());
+    sub.links += 1;
+    img.write_inode(&sub);
+
+    let errors = fsck::check(&img);
+    assert!(
+        errors.iter().any(|e| e.contains("NOT marked used")),
+        "{:?}",
+        errors
+    );
+    assert!(errors.iter().any(|e| e.contains("links")), "{:?}", errors);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sparse_files() {
+    let dir = temp_dir("sparse");
+    let src = dir.join("src");
+    fs::create_dir(&src).unwrap();
+    create_tree(&src);
+
+    let mut img = mkfs::create(&src, 1024, 64, 0, false, false).unwrap();
+
+    // turn the empty file into two blocks of holes, followed by a data block
+    let mut file = img.inode(img.lookup("/empty").unwrap());
+    let bno = img.alloc_block().unwrap();
+    img.block_mut(bno)[..4].copy_from_slice(b"data");
+    file.direct[0] = Extent::hole(2);
+    file.direct[1] = Extent::new(bno, 1);
+    file.extents = 2;
+    file.size = 2 * 4096 + 4;
+    img.write_inode(&file);
+    assert_clean(&img);
+
+    let mut expected = vec![0; 2 * 4096];
+    expected.extend_from_slice(b"data");
+    assert_eq!(img.read_file(&file), expected);
+    assert_eq!(img.blocks(&file), [bno]);
+
+    // holes are not allowed in directories
+    let mut sub = img.inode(img.lookup("/sub").unwrap());
+    sub.direct[1] = Extent::hole(1);
+    sub.extents = 2;
+    img.write_inode(&sub);
+    assert!(!fsck::check(&img).is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn checksums() {
+    let dir = temp_dir("checksums");
+    let src = dir.join("src");
+    fs::create_dir(&src).unwrap();
+    create_tree(&src);
+
+    let img = mkfs::create(&src, 1024, 64, 0, true, true).unwrap();
+    {
+        let sb = img.superblock();
+        assert_eq!(sb.checksum_blocks, 1);
+        assert_eq!(sb.journal_blocks, image::JOURNAL_BLOCKS);
+        assert!(!sb.has_checksum(0));
+        assert!(!sb.has_checksum(sb.first_journal_block()));
+        assert!(sb.has_checksum(sb.first_data_block()));
+    }
+
+    // the checksums are written together with the image
+    let mut img = Image::from_bytes(img.into_bytes()).unwrap();
+    assert_clean(&img);
+    let small = img.inode(img.lookup("/small.txt").unwrap());
+    let bno = small.direct[0].start;
+    assert_eq!(
+        img.checksum(bno),
+        m3fs_layout::block_checksum(img.block(bno))
+    );
+    // free blocks have no checksum
+    assert_eq!(img.checksum(1023), m3fs_layout::NO_CHECKSUM);
+
+    // modifications update the checksums
+    let file = dir.join("file");
+    fs::write(&file, pattern(3 * 4096, 3)).unwrap();
+    insert::insert(&mut img, &file, "/sub/file").unwrap();
+    let mut img = Image::from_bytes(img.into_bytes()).unwrap();
+    assert_clean(&img);
+
+    // corrupt a data block and a metadata block behind the back of the image
+    img.block_mut(bno)[0] ^= 0xFF;
+    let ib = img.superblock().first_inode_block();
+    img.block_mut(ib)[8] ^= 0xFF;
+    let errors = fsck::check(&img);
+    let bad = errors.iter().filter(|e| e.contains("Checksum")).count();
+    assert_eq!(bad, 2, "{:?}", errors);
+
+    fs::remove_dir_all(&dir).unwrap();
+}